@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::ops::DerefMut;
+use std::sync::Arc;
 use std::time;
 use std::{borrow::BorrowMut, future::IntoFuture};
 
@@ -13,11 +14,55 @@ use tauri::{
     WindowUrl,
 };
 
-use crate::firefight::{audio, events::*, local_store::*, types::*};
+use crate::firefight::{
+    audio, backup, bundle,
+    consistency::{self, ConsistencyReport},
+    crew::DispatchReport,
+    errors::StoreError,
+    events::{self, EventBroadcaster, StoreSnapshot},
+    history::UndoHistory,
+    images,
+    operators::{Access, OperatorRegistry, OperatorSession},
+    qualifications::{self, QualificationExpiry},
+    recommend::{self, DispatchRecommendation},
+    roster,
+    shifts::{self, ShiftHandover},
+    timeline::{self, VehicleResponseTimes},
+    transaction::StoreOperation,
+    types::*,
+};
 use crate::polly;
 
 const VEHICLE_SPEECH: &str = "Veículo";
 const STAFF_SPEECH: &str = "Guarnição";
+const CHIEF_SPEECH: &str = "Chefe";
+const DRIVER_SPEECH: &str = "Condutor";
+const HANDOVER_SPEECH: &str = "Rendição de turno";
+const TRANSFER_SPEECH: &str = "Transferência";
+
+/// Typed store errors reach the frontend as objects, anything else stays a plain message
+#[derive(Debug, serde::Serialize)]
+#[serde(untagged)]
+pub enum CommandError {
+    Store(StoreError),
+    Message(String),
+}
+
+impl CommandError {
+    /// Errors from outside the store, such as windowing or speech synthesis
+    fn message(error: impl std::fmt::Display) -> Self {
+        CommandError::Message(error.to_string())
+    }
+}
+
+impl From<anyhow::Error> for CommandError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast_ref::<StoreError>() {
+            Some(store_error) => CommandError::Store(store_error.clone()),
+            None => CommandError::message(error),
+        }
+    }
+}
 
 #[tauri::command]
 pub async fn get_version(app_handle: AppHandle) -> String {
@@ -25,52 +70,174 @@ pub async fn get_version(app_handle: AppHandle) -> String {
 }
 
 #[tauri::command]
-pub async fn get_store(state: State<'_, Mutex<LocalStore>>) -> Result<DataStore, String> {
+pub async fn get_store(state: State<'_, Mutex<FirefightStore>>) -> Result<DataStore, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    state.get_data_store().map_err(CommandError::from)
+}
+
+/// Full store with the revision of the last event already reflected in it
+#[tauri::command]
+pub async fn resync(
+    state: State<'_, Mutex<FirefightStore>>,
+    broadcaster: State<'_, Arc<EventBroadcaster>>,
+) -> Result<StoreSnapshot, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    // Events are only emitted while the store is locked, so both reads agree
+    let revision = broadcaster.revision();
+    match state.get_data_store() {
+        Ok(data_store) => Ok(StoreSnapshot {
+            revision,
+            data_store,
+        }),
+        Err(load_error) => Err(CommandError::from(load_error)),
+    }
+}
+
+/**
+ * Operator Section
+ */
+
+#[tauri::command]
+pub async fn login(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+    secret: String,
+) -> Result<OperatorSession, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    let session = operators.login(state.as_ref(), &staff_id, &secret)?;
+    if let Err(emit_error) = app_handle.emit_all(events::OPERATOR_CHANGED, Some(&session)) {
+        println!("Failed to emit operator change: {:?}", emit_error);
+    }
+
+    Ok(session)
+}
+
+#[tauri::command]
+pub async fn logout(
+    app_handle: AppHandle,
+    operators: State<'_, Arc<OperatorRegistry>>,
+) -> Result<(), CommandError> {
+    if let Err(logout_error) = operators.logout() {
+        return Err(CommandError::from(logout_error));
+    }
+    if let Err(emit_error) = app_handle.emit_all(events::OPERATOR_CHANGED, None::<OperatorSession>)
+    {
+        println!("Failed to emit operator change: {:?}", emit_error);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_operator(
+    operators: State<'_, Arc<OperatorRegistry>>,
+) -> Result<Option<OperatorSession>, CommandError> {
+    operators.session().map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn set_operator_secret(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+    secret: String,
+) -> Result<(), CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    Ok(get_data_store(state))
+    if secret.is_empty() {
+        return Err(CommandError::Message(String::from(
+            "Operator secret can not be empty",
+        )));
+    }
+
+    operators
+        .set_credential(state.as_ref(), &staff_id, &secret)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn remove_operator_secret(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    operators
+        .remove_credential(&staff_id)
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
 pub async fn create_active_occurrence(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
     mut active_occurrence: ActiveOccurrence,
-) -> Result<(), String> {
+) -> Result<DispatchReport, CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
     active_occurrence.creation_time = Some(time::UNIX_EPOCH.elapsed().unwrap().as_millis());
-    let create_result = state.create_active_occurrence(active_occurrence);
-    if let Err(create_error) = create_result {
-        return Err(create_error.to_string());
-    }
+    let dispatch_report = state.create_active_occurrence(active_occurrence)?;
+    emit_resource_transfers(&app_handle, &dispatch_report);
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-    Ok(())
+    Ok(dispatch_report)
+}
+
+/// Resources taken from other occurrences are reported like explicit transfers
+fn emit_resource_transfers(app_handle: &AppHandle, dispatch_report: &DispatchReport) {
+    for transfer in dispatch_report.transfers.iter() {
+        if let Err(emit_error) = app_handle.emit_all(events::RESOURCES_TRANSFERRED, transfer) {
+            println!("Failed to emit resource transfer: {:?}", emit_error);
+        }
+    }
 }
 
 #[tauri::command]
 pub async fn create_occurrence(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    occurrence: Occurrence,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    mut occurrence: Occurrence,
+) -> Result<(), CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    occurrence.image = match images::intern_image(&app_handle, &occurrence.image) {
+        Ok(image_reference) => image_reference,
+        Err(image_error) => return Err(CommandError::from(image_error)),
+    };
+
     let audible_occurrence_label = occurrence.name.clone();
     let occurrence_id = state.create_occurrence(occurrence);
     if let Err(create_error) = occurrence_id {
-        return Err(create_error.to_string());
+        return Err(CommandError::from(create_error));
     }
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-
     let audio_synthesizer = polly::client::create_polly_client().await;
     let audio_resouce = polly::synthesize::synthesize(
         &audio_synthesizer,
@@ -78,7 +245,7 @@ pub async fn create_occurrence(
     )
     .await;
     if audio_resouce.is_err() {
-        return Err(audio_resouce.unwrap_err().to_string());
+        return Err(CommandError::message(audio_resouce.unwrap_err()));
     }
     let _ = audio::put_audio_cache(
         &app_handle,
@@ -92,21 +259,27 @@ pub async fn create_occurrence(
 #[tauri::command]
 pub async fn create_staff(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    staff: Staff,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    mut staff: Staff,
+) -> Result<(), CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    staff.image = match images::intern_image(&app_handle, &staff.image) {
+        Ok(image_reference) => image_reference,
+        Err(image_error) => return Err(CommandError::from(image_error)),
+    };
+
     let audible_staff_label = staff.label.clone();
     let staff_id = state.create_staff(staff);
     if staff_id.is_err() {
-        return Err(staff_id.unwrap_err().to_string());
+        return Err(CommandError::from(staff_id.unwrap_err()));
     }
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-
     let audio_synthesizer = polly::client::create_polly_client().await;
     let audio_resouce = polly::synthesize::synthesize(
         &audio_synthesizer,
@@ -114,7 +287,7 @@ pub async fn create_staff(
     )
     .await;
     if audio_resouce.is_err() {
-        return Err(audio_resouce.unwrap_err().to_string());
+        return Err(CommandError::message(audio_resouce.unwrap_err()));
     }
     let _ = audio::put_audio_cache(
         &app_handle,
@@ -128,21 +301,22 @@ pub async fn create_staff(
 #[tauri::command]
 pub async fn create_team(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
     team: Team,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
     let audible_team_label = team.label.clone();
     let team_id = state.create_team(team);
     if team_id.is_err() {
-        return Err(team_id.unwrap_err().to_string());
+        return Err(CommandError::from(team_id.unwrap_err()));
     }
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-
     let audio_synthesizer = polly::client::create_polly_client().await;
     let audio_resouce = polly::synthesize::synthesize(
         &audio_synthesizer,
@@ -150,7 +324,7 @@ pub async fn create_team(
     )
     .await;
     if audio_resouce.is_err() {
-        return Err(audio_resouce.unwrap_err().to_string());
+        return Err(CommandError::message(audio_resouce.unwrap_err()));
     }
     let _ = audio::put_audio_cache(
         &app_handle,
@@ -164,21 +338,27 @@ pub async fn create_team(
 #[tauri::command]
 pub async fn create_vehicle(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    vehicle: Vehicle,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    mut vehicle: Vehicle,
+) -> Result<(), CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    vehicle.image = match images::intern_image(&app_handle, &vehicle.image) {
+        Ok(image_reference) => image_reference,
+        Err(image_error) => return Err(CommandError::from(image_error)),
+    };
+
     let audible_vehicle_label = vehicle.label.clone();
     let vehicle_id = state.create_vehicle(vehicle);
     if let Err(create_error) = vehicle_id {
-        return Err(create_error.to_string());
+        return Err(CommandError::from(create_error));
     }
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-
     let audio_synthesizer = polly::client::create_polly_client().await;
     let audio_resouce = polly::synthesize::synthesize(
         &audio_synthesizer,
@@ -186,7 +366,7 @@ pub async fn create_vehicle(
     )
     .await;
     if audio_resouce.is_err() {
-        return Err(audio_resouce.unwrap_err().to_string());
+        return Err(CommandError::message(audio_resouce.unwrap_err()));
     }
     let _ = audio::put_audio_cache(
         &app_handle,
@@ -200,41 +380,47 @@ pub async fn create_vehicle(
 #[tauri::command]
 pub async fn update_active_occurrence(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
     active_occurrence: ActiveOccurrence,
-) -> Result<(), String> {
+) -> Result<DispatchReport, CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let update_result =
-        state.update_active_occurrence(&active_occurrence.internal_id.clone(), active_occurrence);
-    if let Err(update_error) = update_result {
-        return Err(update_error.to_string());
-    }
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-    Ok(())
+    let dispatch_report = state
+        .update_active_occurrence(&active_occurrence.internal_id.clone(), active_occurrence)?;
+    emit_resource_transfers(&app_handle, &dispatch_report);
+
+    Ok(dispatch_report)
 }
 
 #[tauri::command]
 pub async fn update_occurrence(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    occurrence: Occurrence,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    mut occurrence: Occurrence,
+) -> Result<(), CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    occurrence.image = match images::intern_image(&app_handle, &occurrence.image) {
+        Ok(image_reference) => image_reference,
+        Err(image_error) => return Err(CommandError::from(image_error)),
+    };
+
     let audible_occurrence_label = occurrence.name.clone();
     let update_result = state.update_occurrence(&occurrence.internal_id.clone(), occurrence);
     if let Err(update_error) = update_result {
-        return Err(update_error.to_string());
+        return Err(CommandError::from(update_error));
     }
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-
     if let Ok(Some(previous_occurrence)) = update_result {
         if audible_occurrence_label != previous_occurrence.name {
             let audio_synthesizer = polly::client::create_polly_client().await;
@@ -244,7 +430,7 @@ pub async fn update_occurrence(
             )
             .await;
             if audio_resouce.is_err() {
-                return Err(audio_resouce.unwrap_err().to_string());
+                return Err(CommandError::message(audio_resouce.unwrap_err()));
             }
             let _ = audio::put_audio_cache(
                 &app_handle,
@@ -254,271 +440,1066 @@ pub async fn update_occurrence(
         }
     }
 
-    Ok(())
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn update_staff(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff: Staff,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    let mut staff = operators.authorize_staff_update(state.as_ref(), staff)?;
+
+    staff.image = match images::intern_image(&app_handle, &staff.image) {
+        Ok(image_reference) => image_reference,
+        Err(image_error) => return Err(CommandError::from(image_error)),
+    };
+
+    let audible_staff_label = staff.label.clone();
+    let update_result = state.update_staff(&staff.internal_id.clone(), staff);
+    if let Err(update_error) = update_result {
+        return Err(CommandError::from(update_error));
+    }
+
+    if let Ok(Some(previous_staff)) = update_result {
+        if audible_staff_label != previous_staff.label {
+            let audio_synthesizer = polly::client::create_polly_client().await;
+            let audio_resouce = polly::synthesize::synthesize(
+                &audio_synthesizer,
+                &polly::synthesize::Synthesizable::Staff(audible_staff_label),
+            )
+            .await;
+            if audio_resouce.is_err() {
+                return Err(CommandError::message(audio_resouce.unwrap_err()));
+            }
+            let _ = audio::put_audio_cache(
+                &app_handle,
+                &previous_staff.internal_id,
+                audio_resouce.unwrap().to_vec().borrow_mut(),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn update_team(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    team: Team,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let audible_team_label = team.label.clone();
+    let update_result = state.update_team(&team.internal_id.clone(), team);
+    if let Err(update_error) = update_result {
+        return Err(CommandError::from(update_error));
+    }
+
+    if let Ok(Some(previous_team)) = update_result {
+        if audible_team_label != previous_team.label {
+            let audio_synthesizer = polly::client::create_polly_client().await;
+            let audio_resouce = polly::synthesize::synthesize(
+                &audio_synthesizer,
+                &polly::synthesize::Synthesizable::Staff(audible_team_label),
+            )
+            .await;
+            if audio_resouce.is_err() {
+                return Err(CommandError::message(audio_resouce.unwrap_err()));
+            }
+            let _ = audio::put_audio_cache(
+                &app_handle,
+                &previous_team.internal_id,
+                audio_resouce.unwrap().to_vec().borrow_mut(),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn update_vehicle(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    mut vehicle: Vehicle,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    vehicle.image = match images::intern_image(&app_handle, &vehicle.image) {
+        Ok(image_reference) => image_reference,
+        Err(image_error) => return Err(CommandError::from(image_error)),
+    };
+
+    let audible_vehicle_label = vehicle.label.clone();
+    let update_result = state.update_vehicle(&vehicle.internal_id.clone(), vehicle);
+    if let Err(update_error) = update_result {
+        return Err(CommandError::from(update_error));
+    }
+
+    if let Ok(Some(previous_vehicle)) = update_result {
+        if audible_vehicle_label != previous_vehicle.label {
+            let audio_synthesizer = polly::client::create_polly_client().await;
+            let audio_resouce = polly::synthesize::synthesize(
+                &audio_synthesizer,
+                &polly::synthesize::Synthesizable::Vehicle(audible_vehicle_label),
+            )
+            .await;
+            if audio_resouce.is_err() {
+                return Err(CommandError::message(audio_resouce.unwrap_err()));
+            }
+            let _ = audio::put_audio_cache(
+                &app_handle,
+                &previous_vehicle.internal_id,
+                audio_resouce.unwrap().to_vec().borrow_mut(),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_active_occurrence(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    active_occurrence_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
+    let delete_result = state.delete_active_occurrence(&active_occurrence_id);
+    if let Err(delete_error) = delete_result {
+        return Err(CommandError::from(delete_error));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn close_active_occurrence(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    active_occurrence_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
+    let end_time = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    let close_result = state.close_active_occurrence(&active_occurrence_id, end_time);
+    if let Err(close_error) = close_result {
+        return Err(CommandError::from(close_error));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_archived_occurrence(
+    state: State<'_, Mutex<FirefightStore>>,
+    archived_occurrence_id: String,
+) -> Result<ArchivedOccurrence, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    state
+        .get_archived_occurrence(&archived_occurrence_id)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn get_archived_occurrence_crews(
+    state: State<'_, Mutex<FirefightStore>>,
+    archived_occurrence_id: String,
+) -> Result<Vec<VehicleCrew>, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    state
+        .get_archived_occurrence(&archived_occurrence_id)
+        .map(|archived_occurrence| archived_occurrence.active_occurrence.vehicle_crews())
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn record_vehicle_status(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    active_occurrence_id: String,
+    vehicle_id: String,
+    status: VehicleStatus,
+    time: Option<u128>,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
+    let status_time = time.unwrap_or_else(|| time::UNIX_EPOCH.elapsed().unwrap().as_millis());
+    state.record_vehicle_status(&active_occurrence_id, &vehicle_id, status, status_time)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn release_resources(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    active_occurrence_id: String,
+    mut release: ResourceRelease,
+) -> Result<ResourceRelease, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
+    release.time = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    let release = state.release_resources(&active_occurrence_id, &release)?;
+
+    Ok(release)
+}
+
+#[tauri::command]
+pub async fn transfer_resources(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    mut transfer: ResourceTransfer,
+) -> Result<ResourceTransfer, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
+    transfer.time = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    let transfer = state.transfer_resources(&transfer)?;
+
+    // The transfer is done, failing to tell about it only gets logged
+    if let Err(emit_error) = app_handle.emit_all(events::RESOURCES_TRANSFERRED, &transfer) {
+        println!("Failed to emit resource transfer: {:?}", emit_error);
+    }
+
+    let vehicle_labels = transfer
+        .vehicle_ids
+        .iter()
+        .filter_map(|vehicle_id| state.get_vehicle_label(vehicle_id).ok())
+        .collect::<Vec<String>>();
+    let occurrence_name = state
+        .get_active_occurrence(&transfer.to_active_occurrence_id)
+        .and_then(|active_occurrence| state.get_occurrence_name(&active_occurrence.occurrence_id));
+
+    // The announcement plays until its end, the store is released before it starts
+    drop(state_mutex);
+
+    let announce_result = match occurrence_name {
+        Ok(occurrence_name) => {
+            announce_resource_transfer(&app_handle, &vehicle_labels, &occurrence_name).await
+        }
+        Err(load_error) => Err(load_error),
+    };
+    if let Err(announce_error) = announce_result {
+        println!("Failed to announce resource transfer: {:?}", announce_error);
+    }
+
+    Ok(transfer)
+}
+
+/// Response times of an active occurrence, or of an archived one once it is closed
+#[tauri::command]
+pub async fn get_vehicle_response_times(
+    state: State<'_, Mutex<FirefightStore>>,
+    active_occurrence_id: String,
+) -> Result<Vec<VehicleResponseTimes>, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    let active_occurrence = match state.get_active_occurrence(&active_occurrence_id) {
+        Ok(active_occurrence) => active_occurrence,
+        Err(_) => state
+            .get_archived_occurrence(&active_occurrence_id)
+            .map(|archived_occurrence| archived_occurrence.active_occurrence)
+            .map_err(CommandError::from)?,
+    };
+
+    Ok(timeline::response_times(&active_occurrence))
+}
+
+#[tauri::command]
+pub async fn get_archived_occurrence_list(
+    state: State<'_, Mutex<FirefightStore>>,
+    query: ArchiveQuery,
+) -> Result<Vec<ArchivedOccurrence>, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    state
+        .get_archived_occurrence_list(&query)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn delete_occurrence(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    occurrence_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let delete_result = state.delete_occurrence(&occurrence_id);
+    if let Err(delete_error) = delete_result {
+        return Err(CommandError::from(delete_error));
+    }
+
+    let _ = audio::delete_audio_cache(&app_handle, &occurrence_id);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_staff(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let delete_result = state.delete_staff(&staff_id);
+    if let Err(delete_error) = delete_result {
+        return Err(CommandError::from(delete_error));
+    }
+    if let Err(credential_error) = operators.remove_credential(&staff_id) {
+        println!(
+            "Failed to remove operator credential: {:?}",
+            credential_error
+        );
+    }
+
+    let _ = audio::delete_audio_cache(&app_handle, &staff_id);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_team(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    team_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let delete_result = state.delete_team(&team_id);
+    if let Err(delete_error) = delete_result {
+        return Err(CommandError::from(delete_error));
+    }
+
+    let _ = audio::delete_audio_cache(&app_handle, &team_id);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_vehicle(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    vehicle_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let delete_result = state.delete_vehicle(&vehicle_id);
+    if let Err(delete_error) = delete_result {
+        return Err(CommandError::from(delete_error));
+    }
+
+    let _ = audio::delete_audio_cache(&app_handle, &vehicle_id);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_staff_shift(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    available_staff: Vec<String>,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
+    let update_result = state.set_staff_shift(available_staff, HashMap::new());
+    if let Err(update_error) = update_result {
+        return Err(CommandError::from(update_error));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_shift_template(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    shift_template: ShiftTemplate,
+) -> Result<String, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let now = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    state
+        .set_shift_template(shift_template, now)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn delete_shift_template(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    shift_template_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let delete_result = state.delete_shift_template(&shift_template_id);
+    if let Err(delete_error) = delete_result {
+        return Err(CommandError::from(delete_error));
+    }
+
+    Ok(())
+}
+
+/// Hands over to a template right away, ahead of its schedule
+#[tauri::command]
+pub async fn apply_shift_template(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    shift_template_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
+    let now = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    state
+        .apply_shift_template(&shift_template_id, now)
+        .map_err(CommandError::from)
+}
+
+/// Scheduled handovers in the given period, the coming week by default
+#[tauri::command]
+pub async fn get_shift_calendar(
+    state: State<'_, Mutex<FirefightStore>>,
+    from_time: Option<u128>,
+    to_time: Option<u128>,
+) -> Result<Vec<ShiftHandover>, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    let data_store = match state.get_data_store() {
+        Ok(data_store) => data_store,
+        Err(load_error) => return Err(CommandError::from(load_error)),
+    };
+    let from_time = from_time.unwrap_or_else(|| time::UNIX_EPOCH.elapsed().unwrap().as_millis());
+    let to_time = to_time.unwrap_or(from_time + shifts::CALENDAR_WINDOW);
+    Ok(shifts::shift_calendar(&data_store, from_time, to_time))
+}
+
+#[tauri::command]
+pub async fn add_staff_qualification(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+    qualification: Qualification,
+) -> Result<String, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    state
+        .add_staff_qualification(&staff_id, qualification)
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub async fn update_staff_qualification(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+    qualification: Qualification,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let update_result = state.update_staff_qualification(&staff_id, qualification);
+    if let Err(update_error) = update_result {
+        return Err(CommandError::from(update_error));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_staff_qualification(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+    qualification_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let remove_result = state.remove_staff_qualification(&staff_id, &qualification_id);
+    if let Err(remove_error) = remove_result {
+        return Err(CommandError::from(remove_error));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_staff_leave(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+    leave_period: LeavePeriod,
+) -> Result<String, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let leave_period_id = state.add_staff_leave(&staff_id, leave_period)?;
+    // A leave starting now should not wait for the next scheduler run
+    let now = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    state.apply_staff_leave(now)?;
+
+    Ok(leave_period_id)
+}
+
+#[tauri::command]
+pub async fn update_staff_leave(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+    leave_period: LeavePeriod,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    state.update_staff_leave(&staff_id, leave_period)?;
+    let now = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    state.apply_staff_leave(now)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_staff_leave(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    staff_id: String,
+    leave_period_id: String,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let remove_result = state.remove_staff_leave(&staff_id, &leave_period_id);
+    if let Err(remove_error) = remove_result {
+        return Err(CommandError::from(remove_error));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_expiring_qualifications(
+    state: State<'_, Mutex<FirefightStore>>,
+) -> Result<Vec<QualificationExpiry>, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    let data_store = match state.get_data_store() {
+        Ok(data_store) => data_store,
+        Err(load_error) => return Err(CommandError::from(load_error)),
+    };
+    let now = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    Ok(qualifications::expiring_qualifications(
+        &data_store,
+        now,
+        qualifications::EXPIRY_WARNING_WINDOW,
+    ))
+}
+
+/// Staff ids currently holding a valid qualification of the given kind
+#[tauri::command]
+pub async fn get_qualified_staff(
+    state: State<'_, Mutex<FirefightStore>>,
+    kind: QualificationKind,
+) -> Result<Vec<String>, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    let data_store = match state.get_data_store() {
+        Ok(data_store) => data_store,
+        Err(load_error) => return Err(CommandError::from(load_error)),
+    };
+    let now = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    Ok(qualifications::qualified_staff(&data_store, kind, now))
 }
 
+/// Vehicles and crews proposed from the dispatch plan of an occurrence, nothing is dispatched
 #[tauri::command]
-pub async fn update_staff(
-    app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    staff: Staff,
-) -> Result<(), String> {
+pub async fn recommend_dispatch(
+    state: State<'_, Mutex<FirefightStore>>,
+    occurrence_id: String,
+) -> Result<DispatchRecommendation, CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let audible_staff_label = staff.label.clone();
-    let update_result = state.update_staff(&staff.internal_id.clone(), staff);
-    if let Err(update_error) = update_result {
-        return Err(update_error.to_string());
-    }
+    let data_store = state.get_data_store()?;
+    let now = time::UNIX_EPOCH.elapsed().unwrap().as_millis();
+    recommend::recommend_dispatch(&data_store, &occurrence_id, now).map_err(CommandError::Store)
+}
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
+#[tauri::command]
+pub async fn set_crew_requirement(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    crew_requirement: CrewRequirement,
+) -> Result<String, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
 
-    if let Ok(Some(previous_staff)) = update_result {
-        if audible_staff_label != previous_staff.label {
-            let audio_synthesizer = polly::client::create_polly_client().await;
-            let audio_resouce = polly::synthesize::synthesize(
-                &audio_synthesizer,
-                &polly::synthesize::Synthesizable::Staff(audible_staff_label),
-            )
-            .await;
-            if audio_resouce.is_err() {
-                return Err(audio_resouce.unwrap_err().to_string());
-            }
-            let _ = audio::put_audio_cache(
-                &app_handle,
-                &previous_staff.internal_id,
-                audio_resouce.unwrap().to_vec().borrow_mut(),
-            );
-        }
-    }
+    operators.authorize(state.as_ref(), Access::Manage)?;
 
-    Ok(())
+    state
+        .set_crew_requirement(crew_requirement)
+        .map_err(CommandError::from)
 }
 
-
 #[tauri::command]
-pub async fn update_team(
-    app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    team: Team,
-) -> Result<(), String> {
+pub async fn delete_crew_requirement(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    crew_requirement_id: String,
+) -> Result<(), CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let audible_team_label = team.label.clone();
-    let update_result = state.update_team(&team.internal_id.clone(), team);
-    if let Err(update_error) = update_result {
-        return Err(update_error.to_string());
-    }
-
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
+    operators.authorize(state.as_ref(), Access::Manage)?;
 
-    if let Ok(Some(previous_team)) = update_result {
-        if audible_team_label != previous_team.label {
-            let audio_synthesizer = polly::client::create_polly_client().await;
-            let audio_resouce = polly::synthesize::synthesize(
-                &audio_synthesizer,
-                &polly::synthesize::Synthesizable::Staff(audible_team_label),
-            )
-            .await;
-            if audio_resouce.is_err() {
-                return Err(audio_resouce.unwrap_err().to_string());
-            }
-            let _ = audio::put_audio_cache(
-                &app_handle,
-                &previous_team.internal_id,
-                audio_resouce.unwrap().to_vec().borrow_mut(),
-            );
-        }
+    let delete_result = state.delete_crew_requirement(&crew_requirement_id);
+    if let Err(delete_error) = delete_result {
+        return Err(CommandError::from(delete_error));
     }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn update_vehicle(
-    app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    vehicle: Vehicle,
-) -> Result<(), String> {
+pub async fn undo(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    history: State<'_, Arc<UndoHistory>>,
+) -> Result<StoreOperation, CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let audible_vehicle_label = vehicle.label.clone();
-    let update_result = state.update_vehicle(&vehicle.internal_id.clone(), vehicle);
-    if let Err(update_error) = update_result {
-        return Err(update_error.to_string());
-    }
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
+    history
+        .undo(&mut |changes| state.replay(StoreOperation::Undo, changes))
+        .map_err(CommandError::from)
+}
 
-    if let Ok(Some(previous_vehicle)) = update_result {
-        if audible_vehicle_label != previous_vehicle.label {
-            let audio_synthesizer = polly::client::create_polly_client().await;
-            let audio_resouce = polly::synthesize::synthesize(
-                &audio_synthesizer,
-                &polly::synthesize::Synthesizable::Vehicle(audible_vehicle_label),
-            )
-            .await;
-            if audio_resouce.is_err() {
-                return Err(audio_resouce.unwrap_err().to_string());
-            }
-            let _ = audio::put_audio_cache(
-                &app_handle,
-                &previous_vehicle.internal_id,
-                audio_resouce.unwrap().to_vec().borrow_mut(),
-            );
-        }
-    }
+#[tauri::command]
+pub async fn redo(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    history: State<'_, Arc<UndoHistory>>,
+) -> Result<StoreOperation, CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
 
-    Ok(())
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
+    history
+        .redo(&mut |changes| state.replay(StoreOperation::Redo, changes))
+        .map_err(CommandError::from)
 }
 
 #[tauri::command]
-pub async fn delete_active_occurrence(
-    app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    active_occurrence_id: String,
-) -> Result<(), String> {
+pub async fn export_staff_csv(
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+) -> Result<String, CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let delete_result = state.delete_active_occurrence(&active_occurrence_id);
-    if let Err(delete_error) = delete_result {
-        return Err(delete_error.to_string());
-    }
+    operators.authorize(state.as_ref(), Access::Manage)?;
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-    Ok(())
+    let staff_list = match state.get_staff_list() {
+        Ok(staff_list) => staff_list,
+        Err(list_error) => return Err(CommandError::from(list_error)),
+    };
+    roster::export_staff_csv(&staff_list).map_err(CommandError::from)
 }
 
 #[tauri::command]
-pub async fn delete_occurrence(
+pub async fn import_staff_csv(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    occurrence_id: String,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    csv_text: String,
+    mode: roster::StaffImportMode,
+    dry_run: bool,
+) -> Result<roster::StaffImportReport, CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let delete_result = state.delete_occurrence(&occurrence_id);
-    if let Err(delete_error) = delete_result {
-        return Err(delete_error.to_string());
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let data_store = match state.get_data_store() {
+        Ok(data_store) => data_store,
+        Err(store_error) => return Err(CommandError::from(store_error)),
+    };
+    let plan = match roster::plan_staff_import(&data_store, &csv_text, mode) {
+        Ok(plan) => plan,
+        Err(plan_error) => return Err(CommandError::from(plan_error)),
+    };
+
+    let mut report = plan.report;
+    report.dry_run = dry_run;
+    if dry_run || !report.errors.is_empty() {
+        return Ok(report);
     }
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-    let _ = audio::delete_audio_cache(&app_handle, &occurrence_id);
+    let staff_list = plan
+        .entries
+        .iter()
+        .map(|entry| entry.staff.clone())
+        .collect::<Vec<Staff>>();
+    let staff_ids = match state.import_staff(staff_list) {
+        Ok(staff_ids) => staff_ids,
+        Err(import_error) => return Err(CommandError::from(import_error)),
+    };
 
-    Ok(())
+    drop(state_mutex);
+
+    // Labels are synthesized together once the store is released
+    let audible_staff = staff_ids
+        .into_iter()
+        .zip(plan.entries.into_iter())
+        .filter(|(_, entry)| entry.previous_label.as_ref() != Some(&entry.staff.label))
+        .map(|(staff_id, entry)| (staff_id, entry.staff.label))
+        .collect::<Vec<(String, String)>>();
+    if audible_staff.is_empty() {
+        return Ok(report);
+    }
+
+    let audio_synthesizer = polly::client::create_polly_client().await;
+    let audio_synthesizer_ref = &audio_synthesizer;
+    let app_handle_ref = &app_handle;
+    let _staff_cache: Result<Vec<()>, ()> = try_join_all(audible_staff.into_iter().map(
+        |(staff_id, staff_label)| async move {
+            let audio_resouce = polly::synthesize::synthesize(
+                audio_synthesizer_ref,
+                &polly::synthesize::Synthesizable::Staff(staff_label),
+            )
+            .await;
+            if let Ok(audio_data) = audio_resouce {
+                let _ = audio::put_audio_cache(
+                    app_handle_ref,
+                    &staff_id,
+                    audio_data.to_vec().borrow_mut(),
+                );
+            }
+
+            Ok(())
+        },
+    ))
+    .await;
+
+    Ok(report)
 }
 
 #[tauri::command]
-pub async fn delete_staff(
+pub async fn export_station_bundle(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    staff_id: String,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    target_path: String,
+    settings: serde_json::Value,
+) -> Result<bundle::BundleManifest, CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let delete_result = state.delete_staff(&staff_id);
-    if let Err(delete_error) = delete_result {
-        return Err(delete_error.to_string());
-    }
-
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-    let _ = audio::delete_audio_cache(&app_handle, &staff_id);
+    operators.authorize(state.as_ref(), Access::Manage)?;
 
-    Ok(())
+    bundle::export_bundle(
+        &app_handle,
+        state.as_ref(),
+        &settings,
+        std::path::Path::new(&target_path),
+    )
+    .map_err(CommandError::from)
 }
 
+/// Returns the settings stored in the bundle, applying them is left to the frontend
 #[tauri::command]
-pub async fn delete_team(
+pub async fn import_station_bundle(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    team_id: String,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    source_path: String,
+) -> Result<serde_json::Value, CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let delete_result = state.delete_team(&team_id);
-    if let Err(delete_error) = delete_result {
-        return Err(delete_error.to_string());
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let station_bundle = match bundle::read_bundle(std::path::Path::new(&source_path)) {
+        Ok(station_bundle) => station_bundle,
+        Err(read_error) => return Err(CommandError::from(read_error)),
+    };
+
+    // The station being replaced can itself be restored later
+    if let Err(backup_error) =
+        backup::create_backup(&app_handle, state.as_ref(), backup::BackupKind::PreRestore)
+    {
+        return Err(CommandError::from(backup_error));
     }
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-    let _ = audio::delete_audio_cache(&app_handle, &team_id);
+    println!(
+        "Importing station bundle from {} (format {}, schema {})",
+        station_bundle.manifest.app_version,
+        station_bundle.manifest.format_version,
+        station_bundle.manifest.schema_version
+    );
 
-    Ok(())
+    // Images go first so the restored records never point at missing files
+    if let Err(image_error) = bundle::restore_images(&app_handle, &station_bundle.image_files) {
+        return Err(CommandError::from(image_error));
+    }
+    let snapshot = station_bundle.snapshot;
+    if let Err(restore_error) = state.restore_store(snapshot.data_store, snapshot.archive) {
+        return Err(CommandError::from(restore_error));
+    }
+    if let Err(audio_error) = bundle::restore_audio_cache(&app_handle, &station_bundle.audio_clips)
+    {
+        return Err(CommandError::from(audio_error));
+    }
+    if let Err(image_error) = images::externalize_images(&app_handle, state.as_mut()) {
+        return Err(CommandError::from(image_error));
+    }
+
+    Ok(station_bundle.settings)
 }
 
 #[tauri::command]
-pub async fn delete_vehicle(
+pub async fn verify_store(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    vehicle_id: String,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    repair: bool,
+) -> Result<ConsistencyReport, CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let delete_result = state.delete_vehicle(&vehicle_id);
-    if let Err(delete_error) = delete_result {
-        return Err(delete_error.to_string());
+    let data_store = match state.get_data_store() {
+        Ok(data_store) => data_store,
+        Err(store_error) => return Err(CommandError::from(store_error)),
+    };
+    let report = consistency::verify_store(&data_store);
+    if !repair || report.is_consistent() {
+        return Ok(report);
     }
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
-    let _ = audio::delete_audio_cache(&app_handle, &vehicle_id);
+    operators.authorize(state.as_ref(), Access::Manage)?;
 
-    Ok(())
+    if let Err(backup_error) =
+        backup::create_backup(&app_handle, state.as_ref(), backup::BackupKind::PreRepair)
+    {
+        return Err(CommandError::from(backup_error));
+    }
+    let report = match state.repair_store() {
+        Ok(report) => report,
+        Err(repair_error) => return Err(CommandError::from(repair_error)),
+    };
+    for (category, inconsistencies) in report.inconsistencies.iter() {
+        for inconsistency in inconsistencies.iter() {
+            println!(
+                "Repaired {:?} on {:?} {}: {} ({})",
+                category,
+                inconsistency.entity_kind,
+                inconsistency.entity_id,
+                inconsistency.message,
+                inconsistency.repair.as_deref().unwrap_or("left as is")
+            );
+        }
+    }
+
+    Ok(report)
+}
+
+#[tauri::command(async)]
+pub fn list_backups(app_handle: AppHandle) -> Result<Vec<backup::BackupInfo>, CommandError> {
+    backup::list_backups(&app_handle).map_err(CommandError::from)
 }
 
 #[tauri::command]
-pub async fn set_staff_shift(
+pub async fn restore_backup(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-    available_staff: Vec<String>,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+    backup_name: String,
+) -> Result<(), CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
-    let update_result = state.set_staff_shift(available_staff, HashMap::new());
-    if let Err(update_error) = update_result {
-        return Err(update_error.to_string());
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
+    let snapshot = match backup::read_backup(&app_handle, &backup_name) {
+        Ok(snapshot) => snapshot,
+        Err(read_error) => return Err(CommandError::from(read_error)),
+    };
+
+    // The state being replaced can itself be restored later
+    if let Err(backup_error) =
+        backup::create_backup(&app_handle, state.as_ref(), backup::BackupKind::PreRestore)
+    {
+        return Err(CommandError::from(backup_error));
+    }
+
+    let restore_result = state.restore_store(snapshot.data_store, snapshot.archive);
+    if let Err(restore_error) = restore_result {
+        return Err(CommandError::from(restore_error));
+    }
+    if let Err(image_error) = images::externalize_images(&app_handle, state.as_mut()) {
+        return Err(CommandError::from(image_error));
     }
 
-    let _ = app_handle.emit_all(STATE_UPDATED, get_data_store(state));
     Ok(())
 }
 
-#[tauri::command(async)]
-pub fn clear_audio_cache(app_handle: AppHandle) -> Result<(), String> {
+#[tauri::command]
+pub async fn clear_audio_cache(
+    app_handle: AppHandle,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+) -> Result<(), CommandError> {
+    let mut state_mutex = state.lock().into_future().await;
+    let state_mutex_ref = state_mutex.borrow_mut();
+    let state = state_mutex_ref.deref_mut();
+
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
     match audio::clear_audio_cache(&app_handle) {
         Ok(_) => Ok(()),
-        Err(err) => Err(err.to_string()),
+        Err(err) => Err(CommandError::from(err)),
     }
 }
 
 #[tauri::command]
 pub async fn rebuild_audio_cache(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
-) -> Result<(), String> {
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
+) -> Result<(), CommandError> {
     let mut state_mutex = state.lock().into_future().await;
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
+    operators.authorize(state.as_ref(), Access::Manage)?;
+
     if let Err(clear_error) = audio::clear_audio_cache(&app_handle) {
-        return Err(clear_error.to_string());
+        return Err(CommandError::from(clear_error));
     }
 
     let audio_synthesizer = polly::client::create_polly_client().await;
@@ -555,6 +1536,11 @@ pub async fn rebuild_audio_cache(
         );
     }
 
+    for role_str in [CHIEF_SPEECH, DRIVER_SPEECH] {
+        let _ =
+            synthesize_pattern(app_handle_ref, audio_synthesizer_ref, &role_str.to_string()).await;
+    }
+
     let occurrence_list = state.get_occurrence_list();
     if occurrence_list.is_ok() {
         let _occurrence_cache: Result<Vec<()>, ()> = try_join_all(
@@ -629,14 +1615,14 @@ pub async fn rebuild_audio_cache(
 }
 
 #[tauri::command(async)]
-pub fn open_fvp(app_handle: AppHandle) -> Result<(), String> {
+pub fn open_fvp(app_handle: AppHandle) -> Result<(), CommandError> {
     // Already open?
     if let Some(wnd) = app_handle.get_window("fvp") {
         if let Err(show_error) = wnd.show() {
-            return Err(show_error.to_string());
+            return Err(CommandError::message(show_error));
         }
         if let Err(focus_error) = wnd.set_focus() {
-            return Err(focus_error.to_string());
+            return Err(CommandError::message(focus_error));
         }
     // Else try create
     } else if let Err(err) = WindowBuilder::new(
@@ -654,27 +1640,27 @@ pub fn open_fvp(app_handle: AppHandle) -> Result<(), String> {
     .resizable(true)
     .build()
     {
-        return Err(err.to_string());
+        return Err(CommandError::message(err));
     }
 
     Ok(())
 }
 
 #[tauri::command(async)]
-pub fn open_settings(app_handle: AppHandle, left: f64, top: f64) -> Result<(), String> {
+pub fn open_settings(app_handle: AppHandle, left: f64, top: f64) -> Result<(), CommandError> {
     // Already open?
     if let Some(wnd) = app_handle.get_window("settings") {
         if let Err(position_error) = wnd.set_position(tauri::Position::Logical(LogicalPosition {
             x: left,
             y: top,
         })) {
-            return Err(position_error.to_string());
+            return Err(CommandError::message(position_error));
         }
         if let Err(show_error) = wnd.show() {
-            return Err(show_error.to_string());
+            return Err(CommandError::message(show_error));
         }
         if let Err(focus_error) = wnd.set_focus() {
-            return Err(focus_error.to_string());
+            return Err(CommandError::message(focus_error));
         }
     // Else try create
     } else if let Err(err) = WindowBuilder::new(
@@ -692,16 +1678,16 @@ pub fn open_settings(app_handle: AppHandle, left: f64, top: f64) -> Result<(), S
     .resizable(false)
     .build()
     {
-        return Err(err.to_string());
+        return Err(CommandError::message(err));
     }
 
     Ok(())
 }
 
 #[tauri::command(async)]
-pub fn set_fullscreen(window: Window, fullscreen: bool) -> Result<(), String> {
+pub fn set_fullscreen(window: Window, fullscreen: bool) -> Result<(), CommandError> {
     if let Err(err) = window.set_fullscreen(fullscreen) {
-        return Err(err.to_string());
+        return Err(CommandError::message(err));
     }
 
     Ok(())
@@ -736,13 +1722,113 @@ async fn synthesize_pattern(
     audio_data
 }
 
+async fn synthesize_staff(
+    app_handle: &AppHandle,
+    syntherizer: &aws_sdk_polly::Client,
+    state: &FirefightStore,
+    staff_id: &String,
+) -> anyhow::Result<File> {
+    let mut staff_audio_data = audio::get_audio_cache(app_handle, staff_id);
+    // Try to synthesize and cache
+    if staff_audio_data.is_err() {
+        let staff_label = state.get_staff_label(staff_id)?;
+
+        let audio_resouce = polly::synthesize::synthesize(
+            syntherizer,
+            &polly::synthesize::Synthesizable::Staff(staff_label),
+        )
+        .await?;
+        let _ = audio::put_audio_cache(app_handle, staff_id, &audio_resouce.to_vec());
+        staff_audio_data = audio::get_audio_cache(app_handle, staff_id)
+    }
+
+    staff_audio_data
+}
+
+/// Synthesizes the announcement of an optional crew role, `None` when nobody holds it
+async fn synthesize_role(
+    app_handle: &AppHandle,
+    syntherizer: &aws_sdk_polly::Client,
+    state: &FirefightStore,
+    staff_id: Option<String>,
+) -> anyhow::Result<Option<File>> {
+    match staff_id {
+        Some(staff_id) => Ok(Some(
+            synthesize_staff(app_handle, syntherizer, state, &staff_id).await?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Announces a scheduled shift handover, returns once it was played
+pub async fn announce_shift_handover(
+    app_handle: &AppHandle,
+    handover: &ShiftHandover,
+) -> anyhow::Result<()> {
+    let audio_synthesizer = polly::client::create_polly_client().await;
+    let handover_cue = synthesize_pattern(
+        app_handle,
+        &audio_synthesizer,
+        &String::from(HANDOVER_SPEECH),
+    )
+    .await?;
+    let shift_template_cue = synthesize_pattern(
+        app_handle,
+        &audio_synthesizer,
+        &handover.shift_template_name,
+    )
+    .await?;
+
+    let (_stream, stream_handle) = OutputStream::try_default()?;
+    let sink = rodio::Sink::try_new(&stream_handle)?;
+    sink.append(Decoder::new(BufReader::new(handover_cue))?);
+    sink.append(Decoder::new(BufReader::new(shift_template_cue))?);
+    sink.sleep_until_end();
+
+    Ok(())
+}
+
+/// Announces the vehicles moved to another occurrence, returns once it was played
+async fn announce_resource_transfer(
+    app_handle: &AppHandle,
+    vehicle_labels: &[String],
+    occurrence_name: &String,
+) -> anyhow::Result<()> {
+    let audio_synthesizer = polly::client::create_polly_client().await;
+    let transfer_cue = synthesize_pattern(
+        app_handle,
+        &audio_synthesizer,
+        &String::from(TRANSFER_SPEECH),
+    )
+    .await?;
+    let vehicle_cues =
+        try_join_all(vehicle_labels.iter().map(|vehicle_label| {
+            synthesize_pattern(app_handle, &audio_synthesizer, vehicle_label)
+        }))
+        .await?;
+    let occurrence_cue =
+        synthesize_pattern(app_handle, &audio_synthesizer, occurrence_name).await?;
+
+    let (_stream, stream_handle) = OutputStream::try_default()?;
+    let sink = rodio::Sink::try_new(&stream_handle)?;
+    sink.append(Decoder::new(BufReader::new(transfer_cue))?);
+    for vehicle_cue in vehicle_cues {
+        sink.append(Decoder::new(BufReader::new(vehicle_cue))?);
+    }
+    sink.append(Decoder::new(BufReader::new(occurrence_cue))?);
+    sink.sleep_until_end();
+
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn alert(
     app_handle: AppHandle,
-    state: State<'_, Mutex<LocalStore>>,
+    state: State<'_, Mutex<FirefightStore>>,
+    operators: State<'_, Arc<OperatorRegistry>>,
     occurrence_id: String,
-    vehicle_assignment_map: HashMap<String, Vec<String>>,
-) -> Result<(), String> {
+    vehicle_assignment_map: HashMap<String, Vec<CrewAssignment>>,
+) -> Result<(), CommandError> {
     println!(
         "Alert command received, vehicle_staff_assignment {:?}, occurrenceId: {}",
         vehicle_assignment_map, occurrence_id
@@ -752,6 +1838,8 @@ pub async fn alert(
     let state_mutex_ref = state_mutex.borrow_mut();
     let state = state_mutex_ref.deref_mut();
 
+    operators.authorize(state.as_ref(), Access::Dispatch)?;
+
     let audio_synthesizer_ref = &polly::client::create_polly_client().await;
     let state_ref = &state;
     let app_handle_ref = &app_handle;
@@ -760,10 +1848,11 @@ pub async fn alert(
     let mut vehicle_sets = vehicle_assignment_map
         .into_iter()
         .map(|entry| {
-            let (vehicle_id, mut staff_ids) = entry;
+            let (vehicle_id, vehicle_crew) = entry;
+            let mut vehicle_crew = VehicleCrew::from_assignments(&vehicle_id, &vehicle_crew);
 
             // Sort staff within while mapping
-            staff_ids.sort_by(|a, b| {
+            vehicle_crew.crew_ids.sort_by(|a, b| {
                 let a_value = state
                     .get_staff_label(a)
                     .unwrap_or_default()
@@ -782,11 +1871,13 @@ pub async fn alert(
                 }
             });
 
-            (vehicle_id, staff_ids)
+            vehicle_crew
         })
-        .collect::<Vec<(String, Vec<String>)>>();
+        .collect::<Vec<VehicleCrew>>();
 
-    vehicle_sets.sort_by(|(a, _sa), (b, _sb)| {
+    vehicle_sets.sort_by(|crew_a, crew_b| {
+        let a = &crew_a.vehicle_id;
+        let b = &crew_b.vehicle_id;
         let a_cap = state.get_vehicle_capacity(a).unwrap_or_default();
         let b_cap = state.get_vehicle_capacity(b).unwrap_or_default();
 
@@ -818,62 +1909,67 @@ pub async fn alert(
     });
 
     // Prepare audio audio
-    let audio_cues = try_join_all(vehicle_sets.into_iter().map(
-        |(vehicle_id, staff_ids)| async move {
-            let mut vehicle_audio_data = audio::get_audio_cache(app_handle_ref, &vehicle_id);
-            // Try to synthesize and cache
-            if vehicle_audio_data.is_err() {
-                let mut vehicle_label = state_ref.get_vehicle_label(&vehicle_id)?.clone();
-                vehicle_label.retain(|c| !c.is_whitespace());
-
-                let audio_resouce = polly::synthesize::synthesize(
-                    audio_synthesizer_ref,
-                    &polly::synthesize::Synthesizable::Vehicle(vehicle_label),
-                )
-                .await?;
-                let _ =
-                    audio::put_audio_cache(app_handle_ref, &vehicle_id, &audio_resouce.to_vec());
-                vehicle_audio_data = audio::get_audio_cache(app_handle_ref, &vehicle_id)
-            }
+    let audio_cues = try_join_all(vehicle_sets.into_iter().map(|vehicle_crew| async move {
+        let vehicle_id = vehicle_crew.vehicle_id;
+        let mut vehicle_audio_data = audio::get_audio_cache(app_handle_ref, &vehicle_id);
+        // Try to synthesize and cache
+        if vehicle_audio_data.is_err() {
+            let mut vehicle_label = state_ref.get_vehicle_label(&vehicle_id)?.clone();
+            vehicle_label.retain(|c| !c.is_whitespace());
 
-            // Still failed? Give up
-            if vehicle_audio_data.is_err() {
-                return Err(vehicle_audio_data.unwrap_err());
-            }
+            let audio_resouce = polly::synthesize::synthesize(
+                audio_synthesizer_ref,
+                &polly::synthesize::Synthesizable::Vehicle(vehicle_label),
+            )
+            .await?;
+            let _ = audio::put_audio_cache(app_handle_ref, &vehicle_id, &audio_resouce.to_vec());
+            vehicle_audio_data = audio::get_audio_cache(app_handle_ref, &vehicle_id)
+        }
 
-            let staff_audio_data = try_join_all(staff_ids.into_iter().map(|staff_id| async move {
-                let mut staff_audio_data = audio::get_audio_cache(app_handle_ref, &staff_id);
-                // Try to synthesize and cache
-                if staff_audio_data.is_err() {
-                    let staff_label = state_ref.get_staff_label(&staff_id)?;
+        // Still failed? Give up
+        if vehicle_audio_data.is_err() {
+            return Err(vehicle_audio_data.unwrap_err());
+        }
 
-                    let audio_resouce = polly::synthesize::synthesize(
-                        audio_synthesizer_ref,
-                        &polly::synthesize::Synthesizable::Staff(staff_label),
-                    )
-                    .await?;
-                    let _ =
-                        audio::put_audio_cache(app_handle_ref, &staff_id, &audio_resouce.to_vec());
-                    staff_audio_data = audio::get_audio_cache(app_handle_ref, &staff_id)
-                }
+        let chief_audio_data = synthesize_role(
+            app_handle_ref,
+            audio_synthesizer_ref,
+            state_ref,
+            vehicle_crew.chief_id,
+        )
+        .await?;
+        let driver_audio_data = synthesize_role(
+            app_handle_ref,
+            audio_synthesizer_ref,
+            state_ref,
+            vehicle_crew.driver_id,
+        )
+        .await?;
 
-                staff_audio_data
-            }))
-            .await;
+        let staff_audio_data = try_join_all(vehicle_crew.crew_ids.into_iter().map(
+            |staff_id| async move {
+                synthesize_staff(app_handle_ref, audio_synthesizer_ref, state_ref, &staff_id).await
+            },
+        ))
+        .await;
 
-            if staff_audio_data.is_err() {
-                return Err(staff_audio_data.unwrap_err());
-            }
+        if staff_audio_data.is_err() {
+            return Err(staff_audio_data.unwrap_err());
+        }
 
-            let vehicle_audio_data = vehicle_audio_data.unwrap();
-            let staff_audio_data = staff_audio_data.unwrap();
+        let vehicle_audio_data = vehicle_audio_data.unwrap();
+        let staff_audio_data = staff_audio_data.unwrap();
 
-            Ok((vehicle_audio_data, staff_audio_data))
-        },
-    ))
+        Ok((
+            vehicle_audio_data,
+            chief_audio_data,
+            driver_audio_data,
+            staff_audio_data,
+        ))
+    }))
     .await;
     if audio_cues.is_err() {
-        return Err(audio_cues.unwrap_err().to_string());
+        return Err(CommandError::message(audio_cues.unwrap_err()));
     }
     let audio_cues = audio_cues.unwrap();
 
@@ -882,7 +1978,7 @@ pub async fn alert(
     if occurrence_cue.is_err() {
         let occurrence_label = state.get_occurrence_name(&occurrence_id);
         if occurrence_label.is_err() {
-            return Err(occurrence_label.unwrap_err().to_string());
+            return Err(CommandError::from(occurrence_label.unwrap_err()));
         }
         let occurrence_entry = occurrence_label.unwrap();
 
@@ -892,7 +1988,7 @@ pub async fn alert(
         )
         .await;
         if audio_resource.is_err() {
-            return Err(audio_resource.unwrap_err().to_string());
+            return Err(CommandError::message(audio_resource.unwrap_err()));
         }
         let audio_resource = audio_resource.unwrap();
 
@@ -900,14 +1996,14 @@ pub async fn alert(
         occurrence_cue = audio::get_audio_cache(&app_handle, &occurrence_id)
     }
     if occurrence_cue.is_err() {
-        return Err(occurrence_cue.unwrap_err().to_string());
+        return Err(CommandError::message(occurrence_cue.unwrap_err()));
     }
     let occurrence_cue = occurrence_cue.unwrap();
 
     // Prepare linking audio
     let alert_cue = audio::get_audio_resource(&app_handle, "alert");
     if alert_cue.is_err() {
-        return Err(alert_cue.unwrap_err().to_string());
+        return Err(CommandError::message(alert_cue.unwrap_err()));
     }
     let alert_cue = alert_cue.unwrap();
 
@@ -918,7 +2014,7 @@ pub async fn alert(
     )
     .await;
     if which_vehicle_cue.is_err() {
-        return Err(which_vehicle_cue.unwrap_err().to_string());
+        return Err(CommandError::from(which_vehicle_cue.unwrap_err()));
     }
     let which_staff_cue = synthesize_pattern(
         app_handle_ref,
@@ -927,7 +2023,14 @@ pub async fn alert(
     )
     .await;
     if which_staff_cue.is_err() {
-        return Err(which_staff_cue.unwrap_err().to_string());
+        return Err(CommandError::from(which_staff_cue.unwrap_err()));
+    }
+    for role_str in [CHIEF_SPEECH, DRIVER_SPEECH] {
+        if let Err(role_error) =
+            synthesize_pattern(app_handle_ref, audio_synthesizer_ref, &role_str.to_string()).await
+        {
+            return Err(CommandError::from(role_error));
+        }
     }
 
     // Get a output stream handle to the default physical sound device
@@ -941,7 +2044,7 @@ pub async fn alert(
 
     audio_cues
         .into_iter()
-        .for_each(|(vehicle_cue, staff_cues)| {
+        .for_each(|(vehicle_cue, chief_cue, driver_cue, staff_cues)| {
             let vehicle_start = audio::get_audio_cache(
                 &app_handle,
                 &audio::get_string_hash(&String::from(VEHICLE_SPEECH)),
@@ -950,6 +2053,19 @@ pub async fn alert(
             sink.append(Decoder::new(BufReader::new(vehicle_start)).unwrap());
             sink.append(Decoder::new(BufReader::new(vehicle_cue)).unwrap());
 
+            // Chief and driver are called by role before the rest of the crew
+            for (role_str, role_cue) in [(CHIEF_SPEECH, chief_cue), (DRIVER_SPEECH, driver_cue)] {
+                if let Some(role_cue) = role_cue {
+                    let role_start = audio::get_audio_cache(
+                        &app_handle,
+                        &audio::get_string_hash(&String::from(role_str)),
+                    )
+                    .unwrap();
+                    sink.append(Decoder::new(BufReader::new(role_start)).unwrap());
+                    sink.append(Decoder::new(BufReader::new(role_cue)).unwrap());
+                }
+            }
+
             if !staff_cues.is_empty() {
                 let staff_start = audio::get_audio_cache(
                     &app_handle,
@@ -21,7 +21,7 @@ pub fn get_audio_resource(
     Ok(std::fs::File::open(audio_resource_path)?)
 }
 
-fn get_audio_cache_dir(app_handle: &tauri::AppHandle) -> anyhow::Result<std::path::PathBuf> {
+pub fn get_audio_cache_dir(app_handle: &tauri::AppHandle) -> anyhow::Result<std::path::PathBuf> {
     let audio_cache_path = app_handle
         .path_resolver()
         .app_local_data_dir()
@@ -0,0 +1,482 @@
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+use tauri::AppHandle;
+
+use super::migrations::{
+    self, ARCHIVE_KEY, CREW_REQUIREMENTS_KEY, SCHEMA_VERSION_KEY, SHIFT_TEMPLATES_KEY,
+};
+use super::types::{ArchiveQuery, ArchivedOccurrence, DataStore, FirefightDataManager};
+
+const BACKUP_PREFIX: &str = "data_store-";
+const BACKUP_EXTENSION: &str = ".dat";
+
+// Retention rules, periodic backups keep the most recent ones plus the newest of each day
+const RECENT_BACKUP_RETENTION: usize = 24;
+const DAILY_BACKUP_RETENTION: usize = 14;
+const EVENT_BACKUP_RETENTION: usize = 5;
+const DAY_MILLIS: u128 = 24 * 60 * 60 * 1000;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupKind {
+    Periodic,
+    PreMigration,
+    PreRepair,
+    PreRestore,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub name: String,
+    pub kind: BackupKind,
+    pub creation_time: u128,
+    pub size: u64,
+}
+
+pub struct BackupSnapshot {
+    pub data_store: DataStore,
+    pub archive: HashMap<String, ArchivedOccurrence>,
+}
+
+fn get_backup_dir(app_handle: &AppHandle) -> anyhow::Result<PathBuf> {
+    let backup_dir = app_handle
+        .path_resolver()
+        .app_local_data_dir()
+        .with_context(|| "Unable to resolve the app local data directory".to_string())?
+        .join("backups");
+    std::fs::create_dir_all(&backup_dir)
+        .with_context(|| format!("Failed to create backup directory {:?}", backup_dir))?;
+    Ok(backup_dir)
+}
+
+fn get_backup_name(kind: BackupKind, creation_time: u128) -> anyhow::Result<String> {
+    let kind_name = serde_json::to_value(kind)?
+        .as_str()
+        .map(String::from)
+        .with_context(|| "Failed to serialize backup kind".to_string())?;
+    Ok(format!(
+        "{}{}-{}{}",
+        BACKUP_PREFIX, kind_name, creation_time, BACKUP_EXTENSION
+    ))
+}
+
+fn parse_backup_name(name: &str) -> Option<(BackupKind, u128)> {
+    let (kind_name, creation_time) = name
+        .strip_prefix(BACKUP_PREFIX)?
+        .strip_suffix(BACKUP_EXTENSION)?
+        .rsplit_once('-')?;
+    let kind = serde_json::from_value(Value::String(String::from(kind_name))).ok()?;
+    Some((kind, creation_time.parse().ok()?))
+}
+
+fn read_entry<T: DeserializeOwned>(
+    entries: &Map<String, Value>,
+    key: &str,
+) -> anyhow::Result<HashMap<String, T>> {
+    let entry_value = entries
+        .get(key)
+        .with_context(|| format!("Unable to read {} from backup", key))?
+        .clone();
+    serde_json::from_value(entry_value).with_context(|| format!("Failed to deserialize {}", key))
+}
+
+pub fn list_backups(app_handle: &AppHandle) -> anyhow::Result<Vec<BackupInfo>> {
+    let backup_dir = get_backup_dir(app_handle)?;
+
+    let mut backup_list = vec![];
+    for dir_entry in std::fs::read_dir(&backup_dir)
+        .with_context(|| format!("Failed to read backup directory {:?}", backup_dir))?
+    {
+        let dir_entry = dir_entry?;
+        let name = dir_entry.file_name().to_string_lossy().to_string();
+        if let Some((kind, creation_time)) = parse_backup_name(&name) {
+            backup_list.push(BackupInfo {
+                name,
+                kind,
+                creation_time,
+                size: dir_entry.metadata()?.len(),
+            });
+        }
+    }
+
+    backup_list.sort_by_key(|backup| Reverse(backup.creation_time));
+    Ok(backup_list)
+}
+
+fn get_backup_path(app_handle: &AppHandle, name: &str) -> anyhow::Result<PathBuf> {
+    if parse_backup_name(name).is_none() || name.contains(['/', '\\']) {
+        anyhow::bail!("Invalid backup name: {}", name);
+    }
+
+    let backup_path = get_backup_dir(app_handle)?.join(name);
+    if !backup_path.exists() {
+        anyhow::bail!("No backup found with name: {}", name);
+    }
+    Ok(backup_path)
+}
+
+fn write_backup(
+    app_handle: &AppHandle,
+    kind: BackupKind,
+    contents: &[u8],
+) -> anyhow::Result<BackupInfo> {
+    let creation_time = std::time::UNIX_EPOCH.elapsed()?.as_millis();
+    let name = get_backup_name(kind, creation_time)?;
+    let backup_path = get_backup_dir(app_handle)?.join(&name);
+
+    // Written aside first so a crash never leaves a truncated backup behind
+    let partial_path = backup_path.with_extension("partial");
+    std::fs::write(&partial_path, contents)
+        .with_context(|| format!("Failed to write backup {:?}", partial_path))?;
+    std::fs::rename(&partial_path, &backup_path)
+        .with_context(|| format!("Failed to finish backup {:?}", backup_path))?;
+
+    apply_retention(app_handle)?;
+
+    Ok(BackupInfo {
+        name,
+        kind,
+        creation_time,
+        size: contents.len() as u64,
+    })
+}
+
+/// Copies an existing store file, used before it gets rewritten by a migration
+pub fn backup_file(
+    app_handle: &AppHandle,
+    source_path: &Path,
+    kind: BackupKind,
+) -> anyhow::Result<BackupInfo> {
+    let contents = std::fs::read(source_path)
+        .with_context(|| format!("Failed to read {:?} for backup", source_path))?;
+    write_backup(app_handle, kind, &contents)
+}
+
+/// Serializes the current contents of any store backend in the `data_store.dat` format
+pub fn serialize_snapshot(store: &dyn FirefightDataManager) -> anyhow::Result<Vec<u8>> {
+    let archive = store
+        .get_archived_occurrence_list(&ArchiveQuery::default())?
+        .into_iter()
+        .map(|archived_occurrence| {
+            (
+                archived_occurrence.active_occurrence.internal_id.clone(),
+                archived_occurrence,
+            )
+        })
+        .collect::<HashMap<String, ArchivedOccurrence>>();
+
+    serialize_store(&store.get_data_store()?, &archive)
+}
+
+fn serialize_store(
+    data_store: &DataStore,
+    archive: &HashMap<String, ArchivedOccurrence>,
+) -> anyhow::Result<Vec<u8>> {
+    let mut snapshot = Map::new();
+    snapshot.insert(
+        String::from("active_occurrences"),
+        json!(data_store.active_occurrences),
+    );
+    snapshot.insert(String::from("occurrences"), json!(data_store.occurrences));
+    snapshot.insert(String::from("staff"), json!(data_store.staff));
+    snapshot.insert(String::from("teams"), json!(data_store.teams));
+    snapshot.insert(String::from("vehicles"), json!(data_store.vehicles));
+    snapshot.insert(
+        String::from(CREW_REQUIREMENTS_KEY),
+        json!(data_store.crew_requirements),
+    );
+    snapshot.insert(
+        String::from(SHIFT_TEMPLATES_KEY),
+        json!(data_store.shift_templates),
+    );
+    snapshot.insert(String::from(ARCHIVE_KEY), json!(archive));
+    snapshot.insert(
+        String::from(SCHEMA_VERSION_KEY),
+        json!(migrations::current_schema_version()),
+    );
+    Ok(serde_json::to_vec(&snapshot)?)
+}
+
+pub fn create_backup(
+    app_handle: &AppHandle,
+    store: &dyn FirefightDataManager,
+    kind: BackupKind,
+) -> anyhow::Result<BackupInfo> {
+    write_backup(app_handle, kind, &serialize_snapshot(store)?)
+}
+
+/// Backs up contents read outside of a store backend, such as a database about to be migrated
+pub fn create_store_backup(
+    app_handle: &AppHandle,
+    data_store: &DataStore,
+    archive: &HashMap<String, ArchivedOccurrence>,
+    kind: BackupKind,
+) -> anyhow::Result<BackupInfo> {
+    write_backup(app_handle, kind, &serialize_store(data_store, archive)?)
+}
+
+/// Reads a snapshot written by `serialize_snapshot`, migrating it to the current schema
+pub fn parse_snapshot(snapshot_bytes: &[u8]) -> anyhow::Result<BackupSnapshot> {
+    let mut entries = serde_json::from_slice::<Map<String, Value>>(snapshot_bytes)
+        .with_context(|| "Failed to parse store snapshot".to_string())?;
+    migrations::migrate_store(&mut entries)?;
+
+    Ok(BackupSnapshot {
+        data_store: DataStore {
+            active_occurrences: read_entry(&entries, "active_occurrences")?,
+            occurrences: read_entry(&entries, "occurrences")?,
+            staff: read_entry(&entries, "staff")?,
+            teams: read_entry(&entries, "teams")?,
+            vehicles: read_entry(&entries, "vehicles")?,
+            crew_requirements: read_entry(&entries, CREW_REQUIREMENTS_KEY)?,
+            shift_templates: read_entry(&entries, SHIFT_TEMPLATES_KEY)?,
+        },
+        archive: read_entry(&entries, ARCHIVE_KEY)?,
+    })
+}
+
+pub fn read_backup(app_handle: &AppHandle, name: &str) -> anyhow::Result<BackupSnapshot> {
+    let backup_path = get_backup_path(app_handle, name)?;
+    let backup_bytes = std::fs::read(&backup_path)
+        .with_context(|| format!("Failed to read backup {:?}", backup_path))?;
+    parse_snapshot(&backup_bytes).with_context(|| format!("Failed to parse backup {}", name))
+}
+
+pub fn find_latest_valid_backup(app_handle: &AppHandle) -> Option<BackupInfo> {
+    list_backups(app_handle)
+        .ok()?
+        .into_iter()
+        .find(|backup| read_backup(app_handle, &backup.name).is_ok())
+}
+
+/// Puts a backup in place of a store file, the replaced file is kept next to it
+pub fn restore_backup_file(
+    app_handle: &AppHandle,
+    name: &str,
+    target_path: &Path,
+) -> anyhow::Result<()> {
+    read_backup(app_handle, name)?;
+    let backup_path = get_backup_path(app_handle, name)?;
+
+    if target_path.exists() {
+        let replaced_path = target_path.with_extension(format!(
+            "replaced-{}",
+            std::time::UNIX_EPOCH.elapsed()?.as_millis()
+        ));
+        std::fs::rename(target_path, &replaced_path)
+            .with_context(|| format!("Failed to move {:?} aside", target_path))?;
+    }
+    std::fs::copy(&backup_path, target_path)
+        .with_context(|| format!("Failed to restore backup {}", name))?;
+
+    Ok(())
+}
+
+/// Backups the retention rules drop, `backup_list` is expected newest first
+fn expired_backups(backup_list: &[BackupInfo]) -> Vec<&BackupInfo> {
+    let mut kept_names = HashSet::new();
+    let mut kept_days = HashSet::new();
+    let mut periodic_count = 0;
+    let mut event_counts = HashMap::new();
+    for backup in backup_list.iter() {
+        match backup.kind {
+            BackupKind::Periodic => {
+                // Newest first, so the first backup seen for a day is the one to keep
+                let day = backup.creation_time / DAY_MILLIS;
+                let keep_recent = periodic_count < RECENT_BACKUP_RETENTION;
+                let keep_daily =
+                    kept_days.len() < DAILY_BACKUP_RETENTION && !kept_days.contains(&day);
+                periodic_count += 1;
+
+                if keep_recent || keep_daily {
+                    kept_names.insert(backup.name.clone());
+                    kept_days.insert(day);
+                }
+            }
+            kind => {
+                let event_count = event_counts.entry(kind).or_insert(0);
+                if *event_count < EVENT_BACKUP_RETENTION {
+                    kept_names.insert(backup.name.clone());
+                }
+                *event_count += 1;
+            }
+        }
+    }
+
+    backup_list
+        .iter()
+        .filter(|backup| !kept_names.contains(&backup.name))
+        .collect()
+}
+
+fn apply_retention(app_handle: &AppHandle) -> anyhow::Result<()> {
+    let backup_list = list_backups(app_handle)?;
+    let backup_dir = get_backup_dir(app_handle)?;
+
+    for backup in expired_backups(&backup_list) {
+        if let Err(remove_error) = std::fs::remove_file(backup_dir.join(&backup.name)) {
+            println!("Failed to remove backup {}: {}", backup.name, remove_error);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::transaction::ChangeSet;
+    use crate::firefight::types::VehicleCategory;
+
+    const HOUR_MILLIS: u128 = 60 * 60 * 1000;
+
+    fn backup(kind: BackupKind, creation_time: u128) -> BackupInfo {
+        BackupInfo {
+            name: get_backup_name(kind, creation_time).unwrap(),
+            kind,
+            creation_time,
+            size: 0,
+        }
+    }
+
+    /// Newest first, as `list_backups` returns them
+    fn backup_list(mut backups: Vec<BackupInfo>) -> Vec<BackupInfo> {
+        backups.sort_by_key(|backup| Reverse(backup.creation_time));
+        backups
+    }
+
+    fn expired_times(backup_list: &[BackupInfo]) -> Vec<u128> {
+        expired_backups(backup_list)
+            .into_iter()
+            .map(|backup| backup.creation_time)
+            .collect()
+    }
+
+    #[test]
+    fn backup_names_round_trip() {
+        for kind in [
+            BackupKind::Periodic,
+            BackupKind::PreMigration,
+            BackupKind::PreRepair,
+            BackupKind::PreRestore,
+        ] {
+            let name = get_backup_name(kind, 1700000000000).unwrap();
+            assert_eq!(parse_backup_name(&name), Some((kind, 1700000000000)));
+        }
+        assert_eq!(
+            get_backup_name(BackupKind::PreMigration, 42).unwrap(),
+            "data_store-preMigration-42.dat"
+        );
+    }
+
+    #[test]
+    fn foreign_files_are_not_backups() {
+        for name in [
+            "data_store.dat",
+            "data_store-periodic-42.dat.partial",
+            "data_store-periodic-42.partial",
+            "data_store-hourly-42.dat",
+            "data_store-periodic-soon.dat",
+            "data_store-periodic--42.dat",
+            "backup-periodic-42.dat",
+        ] {
+            assert_eq!(parse_backup_name(name), None, "{} was parsed", name);
+        }
+    }
+
+    #[test]
+    fn snapshots_read_back_what_was_serialized() {
+        let mut data_store = fixtures::data_store(
+            vec![fixtures::occurrence("occurrence")],
+            vec![fixtures::staff("staff")],
+            vec![fixtures::team("team", &["staff"])],
+            vec![fixtures::vehicle(
+                "vehicle",
+                VehicleCategory::FireFight,
+                Some(5),
+            )],
+        );
+        let active_occurrence =
+            fixtures::active_occurrence("closed", "occurrence", &[("vehicle", &["staff"])]);
+        let archive = HashMap::from([(
+            String::from("closed"),
+            ArchivedOccurrence {
+                active_occurrence: active_occurrence.clone(),
+                end_time: 100,
+            },
+        )]);
+        data_store
+            .active_occurrences
+            .insert(String::from("active"), active_occurrence);
+
+        let snapshot = parse_snapshot(&serialize_store(&data_store, &archive).unwrap()).unwrap();
+        assert!(ChangeSet::diff(&data_store, &snapshot.data_store).is_empty());
+        assert_eq!(snapshot.archive["closed"].end_time, 100);
+        assert!(parse_snapshot(b"not a snapshot").is_err());
+    }
+
+    #[test]
+    fn recent_periodic_backups_are_kept() {
+        let backups = backup_list(
+            (0..RECENT_BACKUP_RETENTION as u128)
+                .map(|hour| backup(BackupKind::Periodic, hour * HOUR_MILLIS))
+                .collect(),
+        );
+        assert!(expired_backups(&backups).is_empty());
+    }
+
+    #[test]
+    fn older_periodic_backups_keep_the_newest_of_each_day() {
+        // Hourly backups over 20 days, the last day is entirely covered by recent retention
+        let last_hour = 20 * 24;
+        let backups = backup_list(
+            (0..last_hour)
+                .map(|hour| backup(BackupKind::Periodic, hour * HOUR_MILLIS))
+                .collect(),
+        );
+
+        let expired = expired_times(&backups);
+        let kept = backups
+            .iter()
+            .map(|backup| backup.creation_time)
+            .filter(|creation_time| !expired.contains(creation_time))
+            .collect::<Vec<u128>>();
+
+        let mut expected = (last_hour - RECENT_BACKUP_RETENTION as u128..last_hour)
+            .rev()
+            .map(|hour| hour * HOUR_MILLIS)
+            .collect::<Vec<u128>>();
+        expected.extend(
+            (20 - DAILY_BACKUP_RETENTION as u128..19)
+                .rev()
+                .map(|day| day * DAY_MILLIS + 23 * HOUR_MILLIS),
+        );
+        assert_eq!(kept, expected);
+    }
+
+    #[test]
+    fn event_backups_are_limited_per_kind() {
+        let mut backups = vec![];
+        for kind in [BackupKind::PreMigration, BackupKind::PreRestore] {
+            backups.extend(
+                (0..EVENT_BACKUP_RETENTION as u128 + 2).map(|minute| backup(kind, minute * 60000)),
+            );
+        }
+        backups.push(backup(BackupKind::Periodic, 0));
+        let backups = backup_list(backups);
+
+        let expired = expired_backups(&backups);
+        assert_eq!(expired.len(), 4);
+        assert!(expired
+            .iter()
+            .all(|backup| backup.kind != BackupKind::Periodic && backup.creation_time < 120000));
+    }
+}
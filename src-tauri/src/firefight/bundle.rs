@@ -0,0 +1,502 @@
+use anyhow::Context;
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use tauri::AppHandle;
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use super::audio;
+use super::backup::{self, BackupSnapshot};
+use super::images;
+use super::migrations;
+use super::types::FirefightDataManager;
+
+/// Bumped whenever the layout of the bundle itself changes, the store inside is migrated separately
+pub const BUNDLE_FORMAT_VERSION: u64 = 2;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const STORE_ENTRY: &str = "data_store.json";
+const SETTINGS_ENTRY: &str = "settings.json";
+const AUDIO_CACHE_PREFIX: &str = "audio_cache/";
+const AUDIO_CACHE_EXTENSION: &str = ".ogg";
+// Added in format version 2, older bundles carry their images inline in the store
+const IMAGE_PREFIX: &str = "images/";
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format_version: u64,
+    pub app_version: String,
+    pub schema_version: u64,
+    pub creation_time: u128,
+}
+
+// Files outside the store that travel with it
+struct BundleDirs {
+    audio_cache_dir: PathBuf,
+    image_dir: PathBuf,
+}
+
+pub struct StationBundle {
+    pub manifest: BundleManifest,
+    pub snapshot: BackupSnapshot,
+    pub settings: Value,
+    pub audio_clips: Vec<(String, Vec<u8>)>,
+    pub image_files: Vec<(String, Vec<u8>)>,
+}
+
+fn write_entry(writer: &mut ZipWriter<File>, name: &str, contents: &[u8]) -> anyhow::Result<()> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    writer
+        .start_file(name, options)
+        .with_context(|| format!("Failed to add {} to bundle", name))?;
+    writer
+        .write_all(contents)
+        .with_context(|| format!("Failed to write {} to bundle", name))?;
+    Ok(())
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> anyhow::Result<Vec<u8>> {
+    let mut entry = archive
+        .by_name(name)
+        .with_context(|| format!("Bundle is missing {}", name))?;
+    let mut contents = vec![];
+    entry
+        .read_to_end(&mut contents)
+        .with_context(|| format!("Failed to read {} from bundle", name))?;
+    Ok(contents)
+}
+
+/// Writes the whole station into a single archive
+pub fn export_bundle(
+    app_handle: &AppHandle,
+    store: &dyn FirefightDataManager,
+    settings: &Value,
+    target_path: &Path,
+) -> anyhow::Result<BundleManifest> {
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        app_version: app_handle.package_info().version.to_string(),
+        schema_version: migrations::current_schema_version(),
+        creation_time: std::time::UNIX_EPOCH.elapsed()?.as_millis(),
+    };
+    let bundle_dirs = BundleDirs {
+        audio_cache_dir: audio::get_audio_cache_dir(app_handle)?,
+        image_dir: images::get_image_dir(app_handle)?,
+    };
+
+    write_bundle(&manifest, &bundle_dirs, store, settings, target_path)?;
+    Ok(manifest)
+}
+
+fn write_bundle(
+    manifest: &BundleManifest,
+    bundle_dirs: &BundleDirs,
+    store: &dyn FirefightDataManager,
+    settings: &Value,
+    target_path: &Path,
+) -> anyhow::Result<()> {
+    // Written aside first so an interrupted export never looks like a valid bundle
+    let partial_path = target_path.with_extension("partial");
+    let partial_file = File::create(&partial_path)
+        .with_context(|| format!("Failed to create bundle {:?}", partial_path))?;
+    let mut writer = ZipWriter::new(partial_file);
+
+    write_entry(&mut writer, MANIFEST_ENTRY, &serde_json::to_vec(manifest)?)?;
+    write_entry(
+        &mut writer,
+        STORE_ENTRY,
+        &backup::serialize_snapshot(store)?,
+    )?;
+    write_entry(&mut writer, SETTINGS_ENTRY, &serde_json::to_vec(settings)?)?;
+
+    let audio_cache_dir = &bundle_dirs.audio_cache_dir;
+    for dir_entry in std::fs::read_dir(audio_cache_dir)
+        .with_context(|| format!("Failed to read audio cache {:?}", audio_cache_dir))?
+    {
+        let dir_entry = dir_entry?;
+        let file_name = dir_entry.file_name().to_string_lossy().to_string();
+        if !file_name.ends_with(AUDIO_CACHE_EXTENSION) {
+            continue;
+        }
+
+        let contents = std::fs::read(dir_entry.path())
+            .with_context(|| format!("Failed to read audio clip {}", file_name))?;
+        write_entry(
+            &mut writer,
+            &format!("{}{}", AUDIO_CACHE_PREFIX, file_name),
+            &contents,
+        )?;
+    }
+
+    let image_dir = &bundle_dirs.image_dir;
+    for dir_entry in std::fs::read_dir(image_dir)
+        .with_context(|| format!("Failed to read image directory {:?}", image_dir))?
+    {
+        let dir_entry = dir_entry?;
+        let file_name = dir_entry.file_name().to_string_lossy().to_string();
+        let contents = std::fs::read(dir_entry.path())
+            .with_context(|| format!("Failed to read image {}", file_name))?;
+        write_entry(
+            &mut writer,
+            &format!("{}{}", IMAGE_PREFIX, file_name),
+            &contents,
+        )?;
+    }
+
+    writer
+        .finish()
+        .with_context(|| "Failed to finish bundle".to_string())?;
+    std::fs::rename(&partial_path, target_path)
+        .with_context(|| format!("Failed to move bundle to {:?}", target_path))?;
+
+    Ok(())
+}
+
+/// Reads and validates a whole bundle without touching the current station
+pub fn read_bundle(source_path: &Path) -> anyhow::Result<StationBundle> {
+    let source_file = File::open(source_path)
+        .with_context(|| format!("Failed to open bundle {:?}", source_path))?;
+    let mut archive = ZipArchive::new(source_file)
+        .with_context(|| format!("{:?} is not a station bundle", source_path))?;
+
+    let manifest =
+        serde_json::from_slice::<BundleManifest>(&read_entry(&mut archive, MANIFEST_ENTRY)?)
+            .with_context(|| "Failed to parse bundle manifest".to_string())?;
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        anyhow::bail!(
+            "Bundle format version {} is newer than the supported version {}",
+            manifest.format_version,
+            BUNDLE_FORMAT_VERSION
+        );
+    }
+
+    let snapshot = backup::parse_snapshot(&read_entry(&mut archive, STORE_ENTRY)?)?;
+    let settings = serde_json::from_slice::<Value>(&read_entry(&mut archive, SETTINGS_ENTRY)?)
+        .with_context(|| "Failed to parse bundle settings".to_string())?;
+
+    let mut audio_clips = vec![];
+    let mut image_files = vec![];
+    for entry_index in 0..archive.len() {
+        let mut entry = archive.by_index(entry_index)?;
+        if let Some(file_name) = entry.name().strip_prefix(IMAGE_PREFIX) {
+            let file_name = file_name.to_string();
+            let reference = file_name.get(..64).unwrap_or_default();
+            if !images::is_image_reference(reference) || file_name.contains(['/', '\\']) {
+                anyhow::bail!("Invalid image in bundle: {}", file_name);
+            }
+
+            let mut contents = vec![];
+            entry
+                .read_to_end(&mut contents)
+                .with_context(|| format!("Failed to read image {}", file_name))?;
+            image_files.push((file_name, contents));
+            continue;
+        }
+
+        let resource_name = match entry
+            .name()
+            .strip_prefix(AUDIO_CACHE_PREFIX)
+            .and_then(|file_name| file_name.strip_suffix(AUDIO_CACHE_EXTENSION))
+        {
+            Some(resource_name) => resource_name.to_string(),
+            None => continue,
+        };
+        if resource_name.is_empty() || resource_name.contains(['/', '\\', '.']) {
+            anyhow::bail!("Invalid audio clip in bundle: {}", entry.name());
+        }
+
+        let mut contents = vec![];
+        entry
+            .read_to_end(&mut contents)
+            .with_context(|| format!("Failed to read audio clip {}", resource_name))?;
+        audio_clips.push((resource_name, contents));
+    }
+
+    Ok(StationBundle {
+        manifest,
+        snapshot,
+        settings,
+        audio_clips,
+        image_files,
+    })
+}
+
+/// Replaces the audio cache with the clips of an imported bundle
+pub fn restore_audio_cache(
+    app_handle: &AppHandle,
+    audio_clips: &[(String, Vec<u8>)],
+) -> anyhow::Result<()> {
+    audio::clear_audio_cache(app_handle)?;
+    for (resource_name, contents) in audio_clips.iter() {
+        audio::put_audio_cache(app_handle, resource_name, contents)?;
+    }
+
+    Ok(())
+}
+
+/// Adds the image files of an imported bundle, existing images are never overwritten
+pub fn restore_images(
+    app_handle: &AppHandle,
+    image_files: &[(String, Vec<u8>)],
+) -> anyhow::Result<()> {
+    write_image_files(&images::get_image_dir(app_handle)?, image_files)
+}
+
+fn write_image_files(image_dir: &Path, image_files: &[(String, Vec<u8>)]) -> anyhow::Result<()> {
+    for (file_name, contents) in image_files.iter() {
+        let image_path = image_dir.join(file_name);
+        if !image_path.exists() {
+            std::fs::write(&image_path, contents)
+                .with_context(|| format!("Failed to write image {}", file_name))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures::{self, MemoryStore};
+    use crate::firefight::types::{ArchivedOccurrence, DataStore};
+    use base64::Engine;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    const IMAGE_REFERENCE: &str =
+        "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    fn temp_dir() -> PathBuf {
+        let temp_dir = std::env::temp_dir().join(format!("bundle-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+        temp_dir
+    }
+
+    fn bundle_dirs(temp_dir: &Path) -> BundleDirs {
+        let bundle_dirs = BundleDirs {
+            audio_cache_dir: temp_dir.join("audio_cache"),
+            image_dir: temp_dir.join("images"),
+        };
+        std::fs::create_dir_all(&bundle_dirs.audio_cache_dir).unwrap();
+        std::fs::create_dir_all(&bundle_dirs.image_dir).unwrap();
+        bundle_dirs
+    }
+
+    fn manifest(format_version: u64) -> BundleManifest {
+        BundleManifest {
+            format_version,
+            app_version: String::from("1.0.0"),
+            schema_version: migrations::current_schema_version(),
+            creation_time: 100,
+        }
+    }
+
+    fn station() -> MemoryStore {
+        let mut staff = fixtures::staff("staff-1");
+        staff.image = String::from(IMAGE_REFERENCE);
+        let mut store = MemoryStore::new(fixtures::data_store(
+            vec![fixtures::occurrence("occurrence-1")],
+            vec![staff],
+            vec![],
+            vec![],
+        ));
+        store.archive.insert(
+            String::from("archived-1"),
+            ArchivedOccurrence {
+                active_occurrence: fixtures::active_occurrence("archived-1", "occurrence-1", &[]),
+                end_time: 100,
+            },
+        );
+        store
+    }
+
+    /// Bundle laid out by hand, the way older or newer versions would have written it
+    fn write_raw_bundle(
+        target_path: &Path,
+        manifest: &BundleManifest,
+        store_bytes: &[u8],
+        extra_entries: &[(&str, &[u8])],
+    ) {
+        let mut writer = ZipWriter::new(File::create(target_path).unwrap());
+        write_entry(
+            &mut writer,
+            MANIFEST_ENTRY,
+            &serde_json::to_vec(manifest).unwrap(),
+        )
+        .unwrap();
+        write_entry(&mut writer, STORE_ENTRY, store_bytes).unwrap();
+        write_entry(&mut writer, SETTINGS_ENTRY, b"{}").unwrap();
+        for (entry_name, contents) in extra_entries {
+            write_entry(&mut writer, entry_name, contents).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn png_data_url() -> String {
+        let mut png_bytes = std::io::Cursor::new(vec![]);
+        image::RgbImage::new(1, 1)
+            .write_to(&mut png_bytes, image::ImageFormat::Png)
+            .unwrap();
+        format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png_bytes.into_inner())
+        )
+    }
+
+    #[test]
+    fn bundles_carry_the_whole_station() {
+        let source_dir = temp_dir();
+        let source_dirs = bundle_dirs(&source_dir);
+        std::fs::write(source_dirs.audio_cache_dir.join("clip.ogg"), b"clip").unwrap();
+        std::fs::write(source_dirs.audio_cache_dir.join("notes.txt"), b"notes").unwrap();
+        let image_name = format!("{}.img", IMAGE_REFERENCE);
+        std::fs::write(source_dirs.image_dir.join(&image_name), b"image").unwrap();
+
+        let bundle_path = source_dir.join("station.zip");
+        let station = station();
+        let settings = json!({ "stationName": "Central" });
+        write_bundle(
+            &manifest(BUNDLE_FORMAT_VERSION),
+            &source_dirs,
+            &station,
+            &settings,
+            &bundle_path,
+        )
+        .unwrap();
+        assert!(!bundle_path.with_extension("partial").exists());
+
+        let station_bundle = read_bundle(&bundle_path).unwrap();
+        assert_eq!(
+            station_bundle.manifest.format_version,
+            BUNDLE_FORMAT_VERSION
+        );
+        assert_eq!(station_bundle.settings, settings);
+        assert_eq!(
+            station_bundle.audio_clips,
+            vec![(String::from("clip"), b"clip".to_vec())]
+        );
+        assert_eq!(
+            station_bundle.image_files,
+            vec![(image_name.clone(), b"image".to_vec())]
+        );
+
+        let target_dir = temp_dir();
+        let target_dirs = bundle_dirs(&target_dir);
+        let mut target = MemoryStore::new(DataStore::default());
+        write_image_files(&target_dirs.image_dir, &station_bundle.image_files).unwrap();
+        let snapshot = station_bundle.snapshot;
+        target
+            .restore_store(snapshot.data_store, snapshot.archive)
+            .unwrap();
+
+        assert_eq!(
+            json!(target.data.occurrences),
+            json!(station.data.occurrences)
+        );
+        assert_eq!(json!(target.data.staff), json!(station.data.staff));
+        assert_eq!(
+            target.archive.keys().collect::<Vec<_>>(),
+            vec!["archived-1"]
+        );
+        assert_eq!(
+            std::fs::read(target_dirs.image_dir.join(&image_name)).unwrap(),
+            b"image"
+        );
+        assert_eq!(
+            images::externalize_images_into(&target_dirs.image_dir, &mut target).unwrap(),
+            0
+        );
+
+        std::fs::remove_dir_all(source_dir).unwrap();
+        std::fs::remove_dir_all(target_dir).unwrap();
+    }
+
+    #[test]
+    fn format_version_1_bundles_have_their_inline_images_moved_to_disk() {
+        let temp_dir = temp_dir();
+        let target_dirs = bundle_dirs(&temp_dir);
+        let mut station = station();
+        let image = png_data_url();
+        station.data.staff.get_mut("staff-1").unwrap().image = image.clone();
+
+        let bundle_path = temp_dir.join("station.zip");
+        write_raw_bundle(
+            &bundle_path,
+            &manifest(1),
+            &backup::serialize_snapshot(&station).unwrap(),
+            &[],
+        );
+        let station_bundle = read_bundle(&bundle_path).unwrap();
+        assert!(station_bundle.image_files.is_empty());
+        assert_eq!(
+            station_bundle.snapshot.data_store.staff["staff-1"].image,
+            image
+        );
+
+        let mut target = MemoryStore::new(DataStore::default());
+        target
+            .restore_store(station_bundle.snapshot.data_store, HashMap::new())
+            .unwrap();
+        assert_eq!(
+            images::externalize_images_into(&target_dirs.image_dir, &mut target).unwrap(),
+            1
+        );
+
+        let reference = &target.data.staff["staff-1"].image;
+        assert!(images::is_image_reference(reference));
+        assert!(target_dirs
+            .image_dir
+            .join(format!("{}.img", reference))
+            .exists());
+
+        std::fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn newer_bundle_formats_are_refused() {
+        let temp_dir = temp_dir();
+        let bundle_path = temp_dir.join("station.zip");
+        write_raw_bundle(
+            &bundle_path,
+            &manifest(BUNDLE_FORMAT_VERSION + 1),
+            &backup::serialize_snapshot(&station()).unwrap(),
+            &[],
+        );
+
+        let read_error = read_bundle(&bundle_path).err().unwrap();
+        assert!(read_error.to_string().contains("newer than the supported"));
+
+        std::fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn bundle_entries_cannot_escape_their_directories() {
+        let temp_dir = temp_dir();
+        let bundle_path = temp_dir.join("station.zip");
+        let store_bytes = backup::serialize_snapshot(&station()).unwrap();
+
+        for entry_name in [
+            String::from("audio_cache/../clip.ogg"),
+            format!("images/{}/../../image.img", IMAGE_REFERENCE),
+            String::from("images/not-a-reference.img"),
+        ] {
+            write_raw_bundle(
+                &bundle_path,
+                &manifest(BUNDLE_FORMAT_VERSION),
+                &store_bytes,
+                &[(&entry_name, b"contents")],
+            );
+            assert!(
+                read_bundle(&bundle_path).is_err(),
+                "{} was read",
+                entry_name
+            );
+        }
+
+        std::fs::remove_dir_all(temp_dir).unwrap();
+    }
+}
@@ -0,0 +1,749 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use super::transaction::EntityKind;
+use super::types::{
+    ActiveOccurrence, CrewRequirementTarget, CrewRole, DataStore, StaffState, TeamState,
+    VehicleState, VehicleStatus,
+};
+
+#[derive(
+    Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum InconsistencyCategory {
+    UnknownOccurrence,
+    DanglingStaff,
+    DanglingTeam,
+    DanglingVehicle,
+    DanglingTeamMember,
+    UnassignedVehicleCrew,
+    UnassignedCrewMember,
+    DuplicateCrewRole,
+    DuplicateStaffDispatch,
+    DuplicateVehicleDispatch,
+    StaleStaffState,
+    StaleTeamState,
+    StaleVehicleState,
+    DanglingCrewRequirement,
+    DanglingShiftReference,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Inconsistency {
+    pub entity_kind: EntityKind,
+    pub entity_id: String,
+    pub message: String,
+    /// What a repair does about it, `None` when it has to be fixed by hand
+    pub repair: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsistencyReport {
+    pub repaired: bool,
+    pub inconsistencies: BTreeMap<InconsistencyCategory, Vec<Inconsistency>>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+
+    fn push(
+        &mut self,
+        category: InconsistencyCategory,
+        entity_kind: EntityKind,
+        entity_id: &str,
+        message: String,
+        repair: Option<String>,
+    ) {
+        self.inconsistencies
+            .entry(category)
+            .or_default()
+            .push(Inconsistency {
+                entity_kind,
+                entity_id: entity_id.to_string(),
+                message,
+                repair,
+            });
+    }
+}
+
+fn sorted_keys<T>(collection: &HashMap<String, T>) -> Vec<String> {
+    let mut keys = collection.keys().cloned().collect::<Vec<String>>();
+    keys.sort();
+    keys
+}
+
+// Oldest occurrences first, so they keep resources claimed by several occurrences
+fn sorted_active_occurrence_ids(data_store: &DataStore) -> Vec<String> {
+    let mut active_occurrences = data_store
+        .active_occurrences
+        .values()
+        .collect::<Vec<&ActiveOccurrence>>();
+    active_occurrences
+        .sort_by(|a, b| (a.creation_time, &a.internal_id).cmp(&(b.creation_time, &b.internal_id)));
+    active_occurrences
+        .into_iter()
+        .map(|active_occurrence| active_occurrence.internal_id.clone())
+        .collect()
+}
+
+fn check_team_members(data_store: &mut DataStore, report: &mut ConsistencyReport) {
+    for team_id in sorted_keys(&data_store.teams) {
+        let staff = &data_store.staff;
+        let team = data_store.teams.get_mut(&team_id).unwrap();
+        for member_id in team.member_ids.iter() {
+            if !staff.contains_key(member_id) {
+                report.push(
+                    InconsistencyCategory::DanglingTeamMember,
+                    EntityKind::Team,
+                    &team_id,
+                    format!("Team {} has unknown member {}", team.label, member_id),
+                    Some(String::from("Remove the member from the team")),
+                );
+            }
+        }
+        team.member_ids
+            .retain(|member_id| staff.contains_key(member_id));
+    }
+}
+
+fn check_crew_requirements(data_store: &mut DataStore, report: &mut ConsistencyReport) {
+    for crew_requirement_id in sorted_keys(&data_store.crew_requirements) {
+        let crew_requirement = &data_store.crew_requirements[&crew_requirement_id];
+        if let CrewRequirementTarget::Vehicle { vehicle_id } = &crew_requirement.target {
+            if !data_store.vehicles.contains_key(vehicle_id) {
+                report.push(
+                    InconsistencyCategory::DanglingCrewRequirement,
+                    EntityKind::CrewRequirement,
+                    &crew_requirement_id,
+                    format!("Crew requirement refers to unknown vehicle {}", vehicle_id),
+                    Some(String::from("Remove the crew requirement")),
+                );
+                data_store.crew_requirements.remove(&crew_requirement_id);
+            }
+        }
+    }
+}
+
+fn check_shift_templates(data_store: &mut DataStore, report: &mut ConsistencyReport) {
+    for shift_template_id in sorted_keys(&data_store.shift_templates) {
+        let staff = &data_store.staff;
+        let teams = &data_store.teams;
+        let shift_template = data_store
+            .shift_templates
+            .get_mut(&shift_template_id)
+            .unwrap();
+
+        let mut unknown_ids = shift_template
+            .available_staff
+            .iter()
+            .chain(shift_template.team_allocations.values().flatten())
+            .filter(|staff_id| !staff.contains_key(*staff_id))
+            .chain(
+                shift_template
+                    .team_allocations
+                    .keys()
+                    .filter(|team_id| !teams.contains_key(*team_id)),
+            )
+            .cloned()
+            .collect::<Vec<String>>();
+        unknown_ids.sort();
+        unknown_ids.dedup();
+        for unknown_id in unknown_ids {
+            report.push(
+                InconsistencyCategory::DanglingShiftReference,
+                EntityKind::ShiftTemplate,
+                &shift_template_id,
+                format!(
+                    "Shift template {} refers to unknown staff or team {}",
+                    shift_template.name, unknown_id
+                ),
+                Some(String::from("Remove the reference from the shift template")),
+            );
+        }
+
+        shift_template
+            .available_staff
+            .retain(|staff_id| staff.contains_key(staff_id));
+        shift_template
+            .team_allocations
+            .retain(|team_id, _| teams.contains_key(team_id));
+        shift_template
+            .team_allocations
+            .values_mut()
+            .for_each(|member_ids| member_ids.retain(|staff_id| staff.contains_key(staff_id)));
+    }
+}
+
+fn check_references(
+    data_store: &mut DataStore,
+    active_occurrence_id: &String,
+    report: &mut ConsistencyReport,
+) {
+    let DataStore {
+        active_occurrences,
+        occurrences,
+        staff,
+        teams,
+        vehicles,
+        ..
+    } = data_store;
+    let active_occurrence = active_occurrences.get_mut(active_occurrence_id).unwrap();
+
+    if !occurrences.contains_key(&active_occurrence.occurrence_id) {
+        report.push(
+            InconsistencyCategory::UnknownOccurrence,
+            EntityKind::ActiveOccurrence,
+            active_occurrence_id,
+            format!(
+                "Active occurrence refers to unknown occurrence {}",
+                active_occurrence.occurrence_id
+            ),
+            None,
+        );
+    }
+
+    for staff_id in active_occurrence.staff_ids.iter() {
+        if !staff.contains_key(staff_id) {
+            report.push(
+                InconsistencyCategory::DanglingStaff,
+                EntityKind::ActiveOccurrence,
+                active_occurrence_id,
+                format!("Active occurrence refers to unknown staff {}", staff_id),
+                Some(String::from("Remove the staff from the occurrence")),
+            );
+        }
+    }
+    active_occurrence
+        .staff_ids
+        .retain(|staff_id| staff.contains_key(staff_id));
+
+    for team_id in active_occurrence.team_ids.iter() {
+        if !teams.contains_key(team_id) {
+            report.push(
+                InconsistencyCategory::DanglingTeam,
+                EntityKind::ActiveOccurrence,
+                active_occurrence_id,
+                format!("Active occurrence refers to unknown team {}", team_id),
+                Some(String::from("Remove the team from the occurrence")),
+            );
+        }
+    }
+    active_occurrence
+        .team_ids
+        .retain(|team_id| teams.contains_key(team_id));
+
+    for vehicle_id in active_occurrence.vehicle_ids.iter() {
+        if !vehicles.contains_key(vehicle_id) {
+            report.push(
+                InconsistencyCategory::DanglingVehicle,
+                EntityKind::ActiveOccurrence,
+                active_occurrence_id,
+                format!("Active occurrence refers to unknown vehicle {}", vehicle_id),
+                Some(String::from("Remove the vehicle from the occurrence")),
+            );
+        }
+    }
+    active_occurrence
+        .vehicle_ids
+        .retain(|vehicle_id| vehicles.contains_key(vehicle_id));
+
+    check_assignments(active_occurrence, report);
+}
+
+fn check_assignments(active_occurrence: &mut ActiveOccurrence, report: &mut ConsistencyReport) {
+    let active_occurrence_id = active_occurrence.internal_id.clone();
+
+    for vehicle_id in sorted_keys(&active_occurrence.vehicle_assignment_map) {
+        if !active_occurrence.vehicle_ids.contains(&vehicle_id) {
+            report.push(
+                InconsistencyCategory::UnassignedVehicleCrew,
+                EntityKind::ActiveOccurrence,
+                &active_occurrence_id,
+                format!(
+                    "Crew assigned to vehicle {} which is not part of the occurrence",
+                    vehicle_id
+                ),
+                Some(String::from("Remove the crew assignment")),
+            );
+            active_occurrence.vehicle_assignment_map.remove(&vehicle_id);
+            continue;
+        }
+
+        let staff_ids = &active_occurrence.staff_ids;
+        let vehicle_crew = active_occurrence
+            .vehicle_assignment_map
+            .get_mut(&vehicle_id)
+            .unwrap();
+        for assignment in vehicle_crew.iter() {
+            if !staff_ids.contains(&assignment.staff_id) {
+                report.push(
+                    InconsistencyCategory::UnassignedCrewMember,
+                    EntityKind::ActiveOccurrence,
+                    &active_occurrence_id,
+                    format!(
+                        "Staff {} is crewing vehicle {} but is not part of the occurrence",
+                        assignment.staff_id, vehicle_id
+                    ),
+                    Some(String::from("Remove the staff from the crew")),
+                );
+            }
+        }
+        vehicle_crew.retain(|assignment| staff_ids.contains(&assignment.staff_id));
+
+        for role in [CrewRole::Chief, CrewRole::Driver] {
+            let mut role_assignments = vehicle_crew
+                .iter_mut()
+                .filter(|assignment| assignment.role == role)
+                .skip(1)
+                .peekable();
+            if role_assignments.peek().is_none() {
+                continue;
+            }
+
+            report.push(
+                InconsistencyCategory::DuplicateCrewRole,
+                EntityKind::ActiveOccurrence,
+                &active_occurrence_id,
+                format!(
+                    "Vehicle {} has more than one {:?} assigned",
+                    vehicle_id, role
+                ),
+                Some(String::from(
+                    "Keep the first one and assign the others as crew",
+                )),
+            );
+            role_assignments.for_each(|assignment| assignment.role = CrewRole::Crew);
+        }
+    }
+}
+
+fn check_duplicate_dispatch(
+    data_store: &mut DataStore,
+    active_occurrence_ids: &[String],
+    report: &mut ConsistencyReport,
+) {
+    let mut staff_claims = HashMap::new();
+    let mut vehicle_claims = HashMap::new();
+
+    for active_occurrence_id in active_occurrence_ids.iter() {
+        let active_occurrence = data_store
+            .active_occurrences
+            .get_mut(active_occurrence_id)
+            .unwrap();
+
+        let mut released_staff_ids = vec![];
+        for staff_id in active_occurrence.staff_ids.iter() {
+            match staff_claims.get(staff_id) {
+                Some(claiming_id) => {
+                    report.push(
+                        InconsistencyCategory::DuplicateStaffDispatch,
+                        EntityKind::Staff,
+                        staff_id,
+                        format!(
+                            "Staff is dispatched to {} and {}",
+                            claiming_id, active_occurrence_id
+                        ),
+                        Some(format!("Keep the staff in {} only", claiming_id)),
+                    );
+                    released_staff_ids.push(staff_id.clone());
+                }
+                None => {
+                    staff_claims.insert(staff_id.clone(), active_occurrence_id.clone());
+                }
+            }
+        }
+
+        let mut released_vehicle_ids = vec![];
+        for vehicle_id in active_occurrence.vehicle_ids.iter() {
+            match vehicle_claims.get(vehicle_id) {
+                Some(claiming_id) => {
+                    report.push(
+                        InconsistencyCategory::DuplicateVehicleDispatch,
+                        EntityKind::Vehicle,
+                        vehicle_id,
+                        format!(
+                            "Vehicle is dispatched to {} and {}",
+                            claiming_id, active_occurrence_id
+                        ),
+                        Some(format!("Keep the vehicle in {} only", claiming_id)),
+                    );
+                    released_vehicle_ids.push(vehicle_id.clone());
+                }
+                None => {
+                    vehicle_claims.insert(vehicle_id.clone(), active_occurrence_id.clone());
+                }
+            }
+        }
+
+        active_occurrence
+            .staff_ids
+            .retain(|staff_id| !released_staff_ids.contains(staff_id));
+        active_occurrence
+            .vehicle_ids
+            .retain(|vehicle_id| !released_vehicle_ids.contains(vehicle_id));
+        active_occurrence
+            .vehicle_assignment_map
+            .retain(|vehicle_id, _| !released_vehicle_ids.contains(vehicle_id));
+        active_occurrence
+            .vehicle_assignment_map
+            .values_mut()
+            .for_each(|vehicle_crew| {
+                vehicle_crew.retain(|assignment| !released_staff_ids.contains(&assignment.staff_id))
+            });
+    }
+}
+
+fn check_states(data_store: &mut DataStore, report: &mut ConsistencyReport) {
+    let mut dispatched_staff_ids = HashSet::new();
+    let mut dispatched_team_ids = HashSet::new();
+    let mut dispatched_vehicle_ids = HashSet::new();
+    let mut returned_vehicle_ids = HashSet::new();
+    for active_occurrence in data_store.active_occurrences.values() {
+        dispatched_staff_ids.extend(active_occurrence.staff_ids.iter().cloned());
+        dispatched_team_ids.extend(active_occurrence.team_ids.iter().cloned());
+        // Vehicles back at base stay listed on the occurrence but are available again
+        for vehicle_id in active_occurrence.vehicle_ids.iter() {
+            if active_occurrence.vehicle_status(vehicle_id) == Some(VehicleStatus::AtBase) {
+                returned_vehicle_ids.insert(vehicle_id.clone());
+            } else {
+                dispatched_vehicle_ids.insert(vehicle_id.clone());
+            }
+        }
+    }
+
+    for staff_id in sorted_keys(&data_store.staff) {
+        let staff = data_store.staff.get_mut(&staff_id).unwrap();
+        let is_dispatched = dispatched_staff_ids.contains(&staff_id);
+        if is_dispatched && staff.state != StaffState::Dispatched {
+            report.push(
+                InconsistencyCategory::StaleStaffState,
+                EntityKind::Staff,
+                &staff_id,
+                format!(
+                    "Staff {} is part of an active occurrence but is {:?}",
+                    staff.label, staff.state
+                ),
+                Some(String::from("Mark the staff as dispatched")),
+            );
+            staff.state = StaffState::Dispatched;
+        } else if !is_dispatched && staff.state == StaffState::Dispatched {
+            report.push(
+                InconsistencyCategory::StaleStaffState,
+                EntityKind::Staff,
+                &staff_id,
+                format!(
+                    "Staff {} is dispatched but no active occurrence holds it",
+                    staff.label
+                ),
+                Some(String::from("Mark the staff as available")),
+            );
+            staff.state = StaffState::Available;
+        }
+    }
+
+    for team_id in sorted_keys(&data_store.teams) {
+        let team = data_store.teams.get_mut(&team_id).unwrap();
+        let is_dispatched = dispatched_team_ids.contains(&team_id);
+        if is_dispatched && team.state != TeamState::Dispatched {
+            report.push(
+                InconsistencyCategory::StaleTeamState,
+                EntityKind::Team,
+                &team_id,
+                format!(
+                    "Team {} is part of an active occurrence but is {:?}",
+                    team.label, team.state
+                ),
+                Some(String::from("Mark the team as dispatched")),
+            );
+            team.state = TeamState::Dispatched;
+        } else if !is_dispatched && team.state == TeamState::Dispatched {
+            report.push(
+                InconsistencyCategory::StaleTeamState,
+                EntityKind::Team,
+                &team_id,
+                format!(
+                    "Team {} is dispatched but no active occurrence holds it",
+                    team.label
+                ),
+                Some(String::from("Mark the team as available")),
+            );
+            team.state = TeamState::Available;
+        }
+    }
+
+    for vehicle_id in sorted_keys(&data_store.vehicles) {
+        let vehicle = data_store.vehicles.get_mut(&vehicle_id).unwrap();
+        let is_dispatched = dispatched_vehicle_ids.contains(&vehicle_id);
+        if is_dispatched && vehicle.state != VehicleState::Dispatched {
+            report.push(
+                InconsistencyCategory::StaleVehicleState,
+                EntityKind::Vehicle,
+                &vehicle_id,
+                format!(
+                    "Vehicle {} is part of an active occurrence but is {:?}",
+                    vehicle.label, vehicle.state
+                ),
+                Some(String::from("Mark the vehicle as dispatched")),
+            );
+            vehicle.state = VehicleState::Dispatched;
+        } else if !is_dispatched && vehicle.state == VehicleState::Dispatched {
+            let description = if returned_vehicle_ids.contains(&vehicle_id) {
+                format!(
+                    "Vehicle {} is back at base but still dispatched",
+                    vehicle.label
+                )
+            } else {
+                format!(
+                    "Vehicle {} is dispatched but no active occurrence holds it",
+                    vehicle.label
+                )
+            };
+            report.push(
+                InconsistencyCategory::StaleVehicleState,
+                EntityKind::Vehicle,
+                &vehicle_id,
+                description,
+                Some(String::from("Mark the vehicle as available")),
+            );
+            vehicle.state = VehicleState::Available;
+        }
+    }
+}
+
+/// Fixes every repairable inconsistency in place, in a fixed order so the outcome only
+/// depends on the store contents
+pub fn repair_store(data_store: &mut DataStore) -> ConsistencyReport {
+    let mut report = ConsistencyReport::default();
+    let active_occurrence_ids = sorted_active_occurrence_ids(data_store);
+
+    check_team_members(data_store, &mut report);
+    check_crew_requirements(data_store, &mut report);
+    check_shift_templates(data_store, &mut report);
+    for active_occurrence_id in active_occurrence_ids.iter() {
+        check_references(data_store, active_occurrence_id, &mut report);
+    }
+    check_duplicate_dispatch(data_store, &active_occurrence_ids, &mut report);
+    check_states(data_store, &mut report);
+
+    report
+}
+
+/// Reports what `repair_store` would change without touching the store
+pub fn verify_store(data_store: &DataStore) -> ConsistencyReport {
+    repair_store(&mut data_store.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::operations;
+    use crate::firefight::transaction::ChangeSet;
+    use crate::firefight::types::VehicleCategory;
+
+    fn store() -> DataStore {
+        let mut data_store = fixtures::data_store(
+            vec![fixtures::occurrence("occurrence")],
+            vec![
+                fixtures::staff("staff-1"),
+                fixtures::staff("staff-2"),
+                fixtures::staff("staff-3"),
+            ],
+            vec![fixtures::team("team", &["staff-3"])],
+            vec![
+                fixtures::vehicle("vehicle-1", VehicleCategory::FireFight, Some(5)),
+                fixtures::vehicle("vehicle-2", VehicleCategory::Ambulances, Some(3)),
+            ],
+        );
+        let active_occurrence = fixtures::active_occurrence(
+            "active",
+            "occurrence",
+            &[("vehicle-1", &["staff-1", "staff-2"])],
+        );
+        data_store
+            .active_occurrences
+            .insert(String::from("active"), active_occurrence);
+        for staff_id in ["staff-1", "staff-2"] {
+            data_store.staff.get_mut(staff_id).unwrap().state = StaffState::Dispatched;
+        }
+        data_store.vehicles.get_mut("vehicle-1").unwrap().state = VehicleState::Dispatched;
+        data_store
+    }
+
+    fn categories(report: &ConsistencyReport) -> Vec<InconsistencyCategory> {
+        report.inconsistencies.keys().copied().collect()
+    }
+
+    #[test]
+    fn a_consistent_store_is_left_as_it_is() {
+        let mut data_store = store();
+        let report = repair_store(&mut data_store);
+
+        assert!(report.is_consistent());
+        assert!(ChangeSet::diff(&store(), &data_store).is_empty());
+    }
+
+    #[test]
+    fn dangling_references_are_removed() {
+        let mut data_store = store();
+        data_store.staff.remove("staff-2");
+        data_store.staff.remove("staff-3");
+
+        let report = repair_store(&mut data_store);
+        assert_eq!(
+            categories(&report),
+            vec![
+                InconsistencyCategory::DanglingStaff,
+                InconsistencyCategory::DanglingTeamMember,
+                InconsistencyCategory::UnassignedCrewMember,
+            ]
+        );
+
+        let active_occurrence = &data_store.active_occurrences["active"];
+        assert_eq!(active_occurrence.staff_ids, vec!["staff-1"]);
+        assert_eq!(
+            active_occurrence.vehicle_assignment_map["vehicle-1"].len(),
+            1
+        );
+        assert!(data_store.teams["team"].member_ids.is_empty());
+        assert!(repair_store(&mut data_store).is_consistent());
+    }
+
+    #[test]
+    fn stale_states_follow_the_active_occurrences() {
+        let mut data_store = store();
+        data_store.staff.get_mut("staff-1").unwrap().state = StaffState::Available;
+        data_store.vehicles.get_mut("vehicle-2").unwrap().state = VehicleState::Dispatched;
+
+        let report = repair_store(&mut data_store);
+        assert_eq!(
+            categories(&report),
+            vec![
+                InconsistencyCategory::StaleStaffState,
+                InconsistencyCategory::StaleVehicleState,
+            ]
+        );
+        assert_eq!(data_store.staff["staff-1"].state, StaffState::Dispatched);
+        assert_eq!(
+            data_store.vehicles["vehicle-2"].state,
+            VehicleState::Available
+        );
+    }
+
+    #[test]
+    fn resources_dispatched_twice_stay_with_the_oldest_occurrence() {
+        let mut data_store = store();
+        let mut newer_occurrence =
+            fixtures::active_occurrence("newer", "occurrence", &[("vehicle-1", &["staff-1"])]);
+        newer_occurrence.creation_time = Some(10);
+        data_store
+            .active_occurrences
+            .insert(String::from("newer"), newer_occurrence);
+
+        let report = repair_store(&mut data_store);
+        assert_eq!(
+            categories(&report),
+            vec![
+                InconsistencyCategory::DuplicateStaffDispatch,
+                InconsistencyCategory::DuplicateVehicleDispatch,
+            ]
+        );
+
+        let newer_occurrence = &data_store.active_occurrences["newer"];
+        assert!(newer_occurrence.staff_ids.is_empty());
+        assert!(newer_occurrence.vehicle_ids.is_empty());
+        assert!(newer_occurrence.vehicle_assignment_map.is_empty());
+        assert_eq!(
+            data_store.active_occurrences["active"].vehicle_ids,
+            vec!["vehicle-1"]
+        );
+    }
+
+    #[test]
+    fn duplicate_crew_roles_keep_the_first_assignment() {
+        let mut data_store = store();
+        data_store
+            .active_occurrences
+            .get_mut("active")
+            .unwrap()
+            .vehicle_assignment_map
+            .get_mut("vehicle-1")
+            .unwrap()
+            .iter_mut()
+            .for_each(|assignment| assignment.role = CrewRole::Driver);
+
+        let report = repair_store(&mut data_store);
+        assert_eq!(
+            categories(&report),
+            vec![InconsistencyCategory::DuplicateCrewRole]
+        );
+
+        let vehicle_crew =
+            &data_store.active_occurrences["active"].vehicle_assignment_map["vehicle-1"];
+        assert_eq!(vehicle_crew[0].role, CrewRole::Driver);
+        assert_eq!(vehicle_crew[1].role, CrewRole::Crew);
+    }
+
+    #[test]
+    fn vehicles_back_at_base_are_available() {
+        let mut data_store = store();
+        let active_occurrence_id = String::from("active");
+        let vehicle_id = String::from("vehicle-1");
+        for (status, time) in [(VehicleStatus::Alerted, 0), (VehicleStatus::AtBase, 10)] {
+            operations::record_vehicle_status(
+                &mut data_store,
+                &active_occurrence_id,
+                &vehicle_id,
+                status,
+                time,
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            data_store.vehicles["vehicle-1"].state,
+            VehicleState::Available
+        );
+        assert!(verify_store(&data_store).is_consistent());
+
+        data_store.vehicles.get_mut("vehicle-1").unwrap().state = VehicleState::Dispatched;
+        let report = repair_store(&mut data_store);
+        assert_eq!(
+            categories(&report),
+            vec![InconsistencyCategory::StaleVehicleState]
+        );
+        assert_eq!(
+            data_store.vehicles["vehicle-1"].state,
+            VehicleState::Available
+        );
+    }
+
+    #[test]
+    fn verifying_reports_without_changing_the_store() {
+        let mut data_store = store();
+        data_store.staff.get_mut("staff-3").unwrap().state = StaffState::Dispatched;
+
+        let report = verify_store(&data_store);
+        assert_eq!(
+            categories(&report),
+            vec![InconsistencyCategory::StaleStaffState]
+        );
+        assert!(!report.repaired);
+        assert_eq!(data_store.staff["staff-3"].state, StaffState::Dispatched);
+    }
+
+    #[test]
+    fn unknown_occurrences_are_reported_but_not_repaired() {
+        let mut data_store = store();
+        data_store.occurrences.clear();
+
+        let report = repair_store(&mut data_store);
+        let inconsistencies = &report.inconsistencies[&InconsistencyCategory::UnknownOccurrence];
+        assert_eq!(inconsistencies.len(), 1);
+        assert_eq!(inconsistencies[0].repair, None);
+        assert!(data_store.active_occurrences.contains_key("active"));
+    }
+}
@@ -0,0 +1,589 @@
+use super::errors::StoreError;
+use super::types::{
+    ActiveOccurrence, CrewAssignment, CrewRequirement, CrewRequirementTarget, CrewRole, DataStore,
+    QualificationKind, ResourceTransfer, Staff, StaffRank, Vehicle,
+};
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum CrewProblem {
+    TooFewCrew {
+        crew_count: usize,
+        min_crew: u8,
+    },
+    TooManyCrew {
+        crew_count: usize,
+        max_crew: u8,
+    },
+    MissingChief {
+        chief_rank: StaffRank,
+    },
+    MissingQualifications {
+        qualifications: Vec<QualificationKind>,
+    },
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrewViolation {
+    pub vehicle_id: String,
+    /// `None` when only the capacity of the vehicle was exceeded
+    pub requirement_id: Option<String>,
+    pub problem: CrewProblem,
+    pub blocking: bool,
+}
+
+/// Outcome of a dispatch, with the unmet requirements that did not block it
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DispatchReport {
+    pub active_occurrence_id: String,
+    pub crew_warnings: Vec<CrewViolation>,
+    /// Resources the occurrence took over from other active occurrences
+    pub transfers: Vec<ResourceTransfer>,
+}
+
+/// Requirement that applies to a vehicle, its own before the one of its category
+pub fn crew_requirement<'a>(
+    data_store: &'a DataStore,
+    vehicle: &Vehicle,
+) -> Option<&'a CrewRequirement> {
+    let vehicle_target = CrewRequirementTarget::Vehicle {
+        vehicle_id: vehicle.internal_id.clone(),
+    };
+    let category_target = CrewRequirementTarget::Category {
+        category: vehicle.category.clone(),
+    };
+
+    data_store
+        .crew_requirements
+        .get(&vehicle_target.requirement_id())
+        .or_else(|| {
+            data_store
+                .crew_requirements
+                .get(&category_target.requirement_id())
+        })
+}
+
+fn assign_slot(
+    slot: usize,
+    crew: &[&Staff],
+    qualifications: &[QualificationKind],
+    time: u128,
+    slot_by_member: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for (member_index, member) in crew.iter().enumerate() {
+        if visited[member_index] || !member.is_qualified(qualifications[slot], time) {
+            continue;
+        }
+        visited[member_index] = true;
+
+        let member_is_free = match slot_by_member[member_index] {
+            Some(other_slot) => assign_slot(
+                other_slot,
+                crew,
+                qualifications,
+                time,
+                slot_by_member,
+                visited,
+            ),
+            None => true,
+        };
+        if member_is_free {
+            slot_by_member[member_index] = Some(slot);
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Fills every required qualification with a different crew member, returns the ones left open.
+/// Crews are small, so trying to move members between slots one slot at a time is cheap.
+fn unfilled_qualifications(
+    crew: &[&Staff],
+    qualifications: &[QualificationKind],
+    time: u128,
+) -> Vec<QualificationKind> {
+    let mut slot_by_member = vec![None; crew.len()];
+    let mut unfilled_qualifications = vec![];
+    for slot in 0..qualifications.len() {
+        let mut visited = vec![false; crew.len()];
+        if !assign_slot(
+            slot,
+            crew,
+            qualifications,
+            time,
+            &mut slot_by_member,
+            &mut visited,
+        ) {
+            unfilled_qualifications.push(qualifications[slot]);
+        }
+    }
+
+    unfilled_qualifications
+}
+
+pub fn check_vehicle_crew(
+    data_store: &DataStore,
+    vehicle: &Vehicle,
+    vehicle_crew: &[CrewAssignment],
+    time: u128,
+) -> Vec<CrewViolation> {
+    let crew = vehicle_crew
+        .iter()
+        .filter_map(|assignment| data_store.staff.get(&assignment.staff_id))
+        .collect::<Vec<&Staff>>();
+    let requirement = crew_requirement(data_store, vehicle);
+
+    let mut violations = vec![];
+    let mut push_violation = |requirement: Option<&CrewRequirement>, problem: CrewProblem| {
+        violations.push(CrewViolation {
+            vehicle_id: vehicle.internal_id.clone(),
+            requirement_id: requirement.map(|requirement| requirement.internal_id.clone()),
+            problem,
+            blocking: requirement.is_some_and(|requirement| requirement.blocking),
+        })
+    };
+
+    if let Some(min_crew) = requirement.and_then(|requirement| requirement.min_crew) {
+        if crew.len() < min_crew as usize {
+            push_violation(
+                requirement,
+                CrewProblem::TooFewCrew {
+                    crew_count: crew.len(),
+                    min_crew,
+                },
+            );
+        }
+    }
+
+    // Without a maximum of its own the vehicle is only limited by its capacity, as a warning
+    match requirement.and_then(|requirement| requirement.max_crew) {
+        Some(max_crew) if crew.len() > max_crew as usize => push_violation(
+            requirement,
+            CrewProblem::TooManyCrew {
+                crew_count: crew.len(),
+                max_crew,
+            },
+        ),
+        Some(_) => {}
+        None => {
+            if let Some(capacity) = vehicle.capacity {
+                if crew.len() > capacity as usize {
+                    push_violation(
+                        None,
+                        CrewProblem::TooManyCrew {
+                            crew_count: crew.len(),
+                            max_crew: capacity,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    let requirement = match requirement {
+        Some(requirement) => requirement,
+        None => return violations,
+    };
+
+    if let Some(chief_seniority) = requirement
+        .chief_rank
+        .as_ref()
+        .and_then(StaffRank::seniority)
+    {
+        let is_senior_enough = |member: &Staff| {
+            member
+                .rank
+                .seniority()
+                .is_some_and(|seniority| seniority <= chief_seniority)
+        };
+        // An assigned chief has to hold the rank, otherwise anyone in the crew can command
+        let assigned_chief = vehicle_crew
+            .iter()
+            .find(|assignment| assignment.role == CrewRole::Chief);
+        let has_chief = match assigned_chief {
+            Some(assignment) => data_store
+                .staff
+                .get(&assignment.staff_id)
+                .is_some_and(is_senior_enough),
+            None => crew.iter().any(|member| is_senior_enough(member)),
+        };
+        if !has_chief {
+            push_violation(
+                Some(requirement),
+                CrewProblem::MissingChief {
+                    chief_rank: requirement.chief_rank.clone().unwrap(),
+                },
+            );
+        }
+    }
+
+    let qualifications = unfilled_qualifications(&crew, &requirement.qualifications, time);
+    if !qualifications.is_empty() {
+        push_violation(
+            Some(requirement),
+            CrewProblem::MissingQualifications { qualifications },
+        );
+    }
+
+    violations
+}
+
+/// Checks the crew of every vehicle of an active occurrence, in dispatch order
+pub fn check_crews(
+    data_store: &DataStore,
+    active_occurrence: &ActiveOccurrence,
+    time: u128,
+) -> Vec<CrewViolation> {
+    active_occurrence
+        .vehicle_ids
+        .iter()
+        .filter_map(|vehicle_id| data_store.vehicles.get(vehicle_id))
+        .flat_map(|vehicle| {
+            let vehicle_crew = active_occurrence
+                .vehicle_assignment_map
+                .get(&vehicle.internal_id)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            check_vehicle_crew(data_store, vehicle, vehicle_crew, time)
+        })
+        .collect()
+}
+
+/// Fails with the blocking violations and returns the others as warnings. Vehicles that keep
+/// the crew they already had only warn, so adding a requirement never locks an occurrence.
+pub fn enforce_crew_requirements(
+    data_store: &DataStore,
+    active_occurrence: &ActiveOccurrence,
+    previous_active_occurrence: Option<&ActiveOccurrence>,
+    time: u128,
+) -> Result<Vec<CrewViolation>, StoreError> {
+    let mut violations = check_crews(data_store, active_occurrence, time);
+    if let Some(previous_active_occurrence) = previous_active_occurrence {
+        for violation in violations.iter_mut() {
+            let crew_is_unchanged = previous_active_occurrence
+                .vehicle_ids
+                .contains(&violation.vehicle_id)
+                && previous_active_occurrence
+                    .vehicle_assignment_map
+                    .get(&violation.vehicle_id)
+                    == active_occurrence
+                        .vehicle_assignment_map
+                        .get(&violation.vehicle_id);
+            if crew_is_unchanged {
+                violation.blocking = false;
+            }
+        }
+    }
+
+    let (blocking_violations, warnings): (Vec<CrewViolation>, Vec<CrewViolation>) = violations
+        .into_iter()
+        .partition(|violation| violation.blocking);
+    if !blocking_violations.is_empty() {
+        return Err(StoreError::UnmetCrewRequirements {
+            violations: blocking_violations,
+        });
+    }
+
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::types::{Qualification, VehicleCategory, VehicleCrew};
+    use crate::firefight::{fixtures, operations};
+
+    const TIME: u128 = 1_000;
+
+    fn requirement(target: CrewRequirementTarget) -> CrewRequirement {
+        CrewRequirement {
+            internal_id: target.requirement_id(),
+            target,
+            min_crew: None,
+            max_crew: None,
+            chief_rank: None,
+            qualifications: vec![],
+            blocking: true,
+        }
+    }
+
+    fn category_requirement() -> CrewRequirement {
+        requirement(CrewRequirementTarget::Category {
+            category: VehicleCategory::FireFight,
+        })
+    }
+
+    fn qualified_staff(staff_id: &str, kinds: &[QualificationKind]) -> Staff {
+        let mut member = fixtures::staff(staff_id);
+        member.qualifications = kinds
+            .iter()
+            .map(|kind| Qualification {
+                internal_id: format!("{:?}", kind),
+                kind: *kind,
+                issue_time: 0,
+                expiry_time: None,
+            })
+            .collect();
+        member
+    }
+
+    fn store(staff: Vec<Staff>, crew_requirements: Vec<CrewRequirement>) -> DataStore {
+        let mut data_store = fixtures::data_store(
+            vec![],
+            staff,
+            vec![],
+            vec![fixtures::vehicle(
+                "vehicle",
+                VehicleCategory::FireFight,
+                Some(3),
+            )],
+        );
+        data_store.crew_requirements = crew_requirements
+            .into_iter()
+            .map(|crew_requirement| (crew_requirement.internal_id.clone(), crew_requirement))
+            .collect();
+        data_store
+    }
+
+    fn crew(staff_ids: &[&str]) -> Vec<CrewAssignment> {
+        staff_ids
+            .iter()
+            .map(|staff_id| CrewAssignment {
+                staff_id: staff_id.to_string(),
+                role: CrewRole::Crew,
+            })
+            .collect()
+    }
+
+    fn check(data_store: &DataStore, vehicle_crew: &[CrewAssignment]) -> Vec<CrewProblem> {
+        check_vehicle_crew(
+            data_store,
+            &data_store.vehicles["vehicle"],
+            vehicle_crew,
+            TIME,
+        )
+        .into_iter()
+        .map(|violation| violation.problem)
+        .collect()
+    }
+
+    #[test]
+    fn vehicle_requirements_replace_the_one_of_their_category() {
+        let mut vehicle_requirement = requirement(CrewRequirementTarget::Vehicle {
+            vehicle_id: String::from("vehicle"),
+        });
+        vehicle_requirement.min_crew = Some(1);
+        let mut category_requirement = category_requirement();
+        category_requirement.min_crew = Some(3);
+        let data_store = store(
+            vec![fixtures::staff("staff")],
+            vec![vehicle_requirement, category_requirement],
+        );
+
+        let requirement = crew_requirement(&data_store, &data_store.vehicles["vehicle"]).unwrap();
+        assert_eq!(requirement.internal_id, "vehicle:vehicle");
+        assert!(check(&data_store, &crew(&["staff"])).is_empty());
+    }
+
+    #[test]
+    fn crew_size_is_checked_against_the_requirement_and_the_capacity() {
+        let mut crew_requirement = category_requirement();
+        crew_requirement.min_crew = Some(2);
+        crew_requirement.max_crew = Some(2);
+        let staff = ["a", "b", "c", "d"].map(fixtures::staff).to_vec();
+        let data_store = store(staff.clone(), vec![crew_requirement]);
+
+        assert_eq!(
+            check(&data_store, &crew(&["a"])),
+            vec![CrewProblem::TooFewCrew {
+                crew_count: 1,
+                min_crew: 2
+            }]
+        );
+        assert_eq!(
+            check(&data_store, &crew(&["a", "b", "c"])),
+            vec![CrewProblem::TooManyCrew {
+                crew_count: 3,
+                max_crew: 2
+            }]
+        );
+
+        // Without a requirement only the capacity warns
+        let data_store = store(staff, vec![]);
+        let violations = check_vehicle_crew(
+            &data_store,
+            &data_store.vehicles["vehicle"],
+            &crew(&["a", "b", "c", "d"]),
+            TIME,
+        );
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].requirement_id, None);
+        assert!(!violations[0].blocking);
+    }
+
+    #[test]
+    fn an_assigned_chief_has_to_hold_the_rank() {
+        let mut crew_requirement = category_requirement();
+        crew_requirement.chief_rank = Some(StaffRank::Rank3);
+        let mut officer = fixtures::staff("officer");
+        officer.rank = StaffRank::Rank2;
+        let mut firefighter = fixtures::staff("firefighter");
+        firefighter.rank = StaffRank::Rank6;
+        let data_store = store(vec![officer, firefighter], vec![crew_requirement]);
+
+        assert!(check(&data_store, &crew(&["officer", "firefighter"])).is_empty());
+
+        let mut vehicle_crew = crew(&["officer", "firefighter"]);
+        vehicle_crew[1].role = CrewRole::Chief;
+        assert_eq!(
+            check(&data_store, &vehicle_crew),
+            vec![CrewProblem::MissingChief {
+                chief_rank: StaffRank::Rank3
+            }]
+        );
+    }
+
+    #[test]
+    fn each_required_qualification_needs_a_different_member() {
+        let mut crew_requirement = category_requirement();
+        crew_requirement.qualifications = vec![QualificationKind::Driver, QualificationKind::Tas];
+        let data_store = store(
+            vec![
+                qualified_staff("both", &[QualificationKind::Driver, QualificationKind::Tas]),
+                qualified_staff("driver", &[QualificationKind::Driver]),
+            ],
+            vec![crew_requirement],
+        );
+
+        // The member holding both has to cover Tas for the other to drive
+        assert!(check(&data_store, &crew(&["both", "driver"])).is_empty());
+        assert_eq!(
+            check(&data_store, &crew(&["driver"])),
+            vec![CrewProblem::MissingQualifications {
+                qualifications: vec![QualificationKind::Tas]
+            }]
+        );
+    }
+
+    #[test]
+    fn expired_qualifications_do_not_count() {
+        let mut crew_requirement = category_requirement();
+        crew_requirement.qualifications = vec![QualificationKind::Diver];
+        let mut diver = qualified_staff("diver", &[QualificationKind::Diver]);
+        diver.qualifications[0].expiry_time = Some(TIME);
+        let data_store = store(vec![diver], vec![crew_requirement]);
+
+        assert_eq!(
+            check(&data_store, &crew(&["diver"])),
+            vec![CrewProblem::MissingQualifications {
+                qualifications: vec![QualificationKind::Diver]
+            }]
+        );
+    }
+
+    #[test]
+    fn blocking_violations_fail_unless_the_crew_is_unchanged() {
+        let mut crew_requirement = category_requirement();
+        crew_requirement.min_crew = Some(2);
+        let data_store = store(vec![fixtures::staff("a")], vec![crew_requirement]);
+        let active_occurrence =
+            fixtures::active_occurrence("active", "occurrence", &[("vehicle", &["a"])]);
+
+        let error =
+            enforce_crew_requirements(&data_store, &active_occurrence, None, TIME).unwrap_err();
+        assert!(matches!(error, StoreError::UnmetCrewRequirements { .. }));
+
+        let warnings = enforce_crew_requirements(
+            &data_store,
+            &active_occurrence,
+            Some(&active_occurrence),
+            TIME,
+        )
+        .unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(!warnings[0].blocking);
+    }
+
+    #[test]
+    fn a_vehicle_has_at_most_one_chief_and_one_driver() {
+        let mut data_store = store(["a", "b", "c"].map(fixtures::staff).to_vec(), vec![]);
+        data_store.occurrences.insert(
+            String::from("occurrence"),
+            fixtures::occurrence("occurrence"),
+        );
+        let mut active_occurrence =
+            fixtures::active_occurrence("active", "occurrence", &[("vehicle", &["a", "b", "c"])]);
+        let vehicle_crew = active_occurrence
+            .vehicle_assignment_map
+            .get_mut("vehicle")
+            .unwrap();
+        vehicle_crew[0].role = CrewRole::Chief;
+        vehicle_crew[1].role = CrewRole::Driver;
+        assert!(operations::validate_active_occurrence(&data_store, &active_occurrence).is_ok());
+
+        for role in [CrewRole::Chief, CrewRole::Driver] {
+            let mut active_occurrence = active_occurrence.clone();
+            active_occurrence
+                .vehicle_assignment_map
+                .get_mut("vehicle")
+                .unwrap()[2]
+                .role = role;
+            assert_eq!(
+                operations::validate_active_occurrence(&data_store, &active_occurrence),
+                Err(StoreError::DuplicateCrewRole {
+                    vehicle_id: String::from("vehicle"),
+                    role,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn crews_are_read_by_role_keeping_the_first_chief_and_driver() {
+        let mut vehicle_crew = crew(&["a", "b", "c", "d"]);
+        vehicle_crew[1].role = CrewRole::Driver;
+        vehicle_crew[2].role = CrewRole::Chief;
+        vehicle_crew[3].role = CrewRole::Chief;
+
+        let crew = VehicleCrew::from_assignments(&String::from("vehicle"), &vehicle_crew);
+        assert_eq!(crew.chief_id.as_deref(), Some("c"));
+        assert_eq!(crew.driver_id.as_deref(), Some("b"));
+        assert_eq!(crew.crew_ids, vec!["a", "d"]);
+    }
+
+    #[test]
+    fn plain_staff_ids_are_read_as_crew_members() {
+        let vehicle_crew = serde_json::from_value::<Vec<CrewAssignment>>(serde_json::json!([
+            "a",
+            { "staffId": "b" },
+            { "staffId": "c", "role": "driver" },
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            vehicle_crew,
+            vec![
+                CrewAssignment {
+                    staff_id: String::from("a"),
+                    role: CrewRole::Crew,
+                },
+                CrewAssignment {
+                    staff_id: String::from("b"),
+                    role: CrewRole::Crew,
+                },
+                CrewAssignment {
+                    staff_id: String::from("c"),
+                    role: CrewRole::Driver,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use super::crew::CrewViolation;
+use super::types::{CrewRole, StaffPermission, VehicleStatus};
+
+/// Store failures the frontend can tell apart, carried inside anyhow errors and found by downcasting
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize, thiserror::Error)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum StoreError {
+    #[error("No active occurrence found with id: {active_occurrence_id}")]
+    UnknownActiveOccurrence { active_occurrence_id: String },
+    #[error("No occurrence found with id: {occurrence_id}")]
+    UnknownOccurrence { occurrence_id: String },
+    #[error("No staff found with id: {staff_id}")]
+    UnknownStaff { staff_id: String },
+    #[error("No team found with id: {team_id}")]
+    UnknownTeam { team_id: String },
+    #[error("No vehicle found with id: {vehicle_id}")]
+    UnknownVehicle { vehicle_id: String },
+    #[error("Vehicle {vehicle_id} has a crew assigned but is not part of the occurrence")]
+    UnassignedVehicle { vehicle_id: String },
+    #[error(
+        "Staff {staff_id} is assigned to vehicle {vehicle_id} but is not part of the occurrence"
+    )]
+    UnassignedStaff {
+        vehicle_id: String,
+        staff_id: String,
+    },
+    #[error("Vehicle {vehicle_id} has more than one {role:?} assigned")]
+    DuplicateCrewRole { vehicle_id: String, role: CrewRole },
+    #[error("Staff {staff_id} has no qualification with id: {qualification_id}")]
+    UnknownQualification {
+        staff_id: String,
+        qualification_id: String,
+    },
+    #[error("Qualification {qualification_id} expires before it is issued")]
+    InvalidQualificationPeriod { qualification_id: String },
+    #[error("Staff {staff_id} has no leave period with id: {leave_period_id}")]
+    UnknownLeavePeriod {
+        staff_id: String,
+        leave_period_id: String,
+    },
+    #[error("Leave period {leave_period_id} ends before it starts")]
+    InvalidLeavePeriod { leave_period_id: String },
+    #[error("Crew requirement {crew_requirement_id} has a minimum crew above its maximum")]
+    InvalidCrewRange { crew_requirement_id: String },
+    #[error("{} crew requirements are not met", violations.len())]
+    UnmetCrewRequirements { violations: Vec<CrewViolation> },
+    #[error("Vehicle {vehicle_id} is not part of active occurrence {active_occurrence_id}")]
+    VehicleNotInOccurrence {
+        active_occurrence_id: String,
+        vehicle_id: String,
+    },
+    #[error("Staff {staff_id} is not part of active occurrence {active_occurrence_id}")]
+    StaffNotInOccurrence {
+        active_occurrence_id: String,
+        staff_id: String,
+    },
+    #[error("Team {team_id} is not part of active occurrence {active_occurrence_id}")]
+    TeamNotInOccurrence {
+        active_occurrence_id: String,
+        team_id: String,
+    },
+    #[error("Nothing to release from active occurrence {active_occurrence_id}")]
+    EmptyResourceRelease { active_occurrence_id: String },
+    #[error("Nothing to transfer from {from_active_occurrence_id} to {to_active_occurrence_id}")]
+    InvalidResourceTransfer {
+        from_active_occurrence_id: String,
+        to_active_occurrence_id: String,
+    },
+    #[error("Vehicle {vehicle_id} cannot report {status:?} after its last status")]
+    InvalidVehicleStatus {
+        vehicle_id: String,
+        status: VehicleStatus,
+    },
+    #[error("{} vehicles are not back at base", vehicle_ids.len())]
+    VehiclesNotAtBase { vehicle_ids: Vec<String> },
+    #[error("No shift template found with id: {shift_template_id}")]
+    UnknownShiftTemplate { shift_template_id: String },
+    #[error("Shift template {shift_template_id} has a recurrence with a zero interval or ending before it starts")]
+    InvalidShiftSchedule { shift_template_id: String },
+    #[error("No operator is logged in")]
+    NotLoggedIn,
+    #[error("Operators with {permission:?} permission are not allowed to do this")]
+    PermissionDenied { permission: StaffPermission },
+    #[error("Unknown operator or wrong secret")]
+    InvalidCredentials,
+}
@@ -1 +1,415 @@
-pub const STATE_UPDATED: &str = "firefight://state_updated";
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Manager};
+
+use super::transaction::{ChangeObserver, ChangeSet, EntityChanges, StoreOperation};
+use super::types::{DataStore, Staff, Team};
+
+pub const ACTIVE_OCCURRENCE_CREATED: &str = "firefight://active_occurrence_created";
+pub const ACTIVE_OCCURRENCE_UPDATED: &str = "firefight://active_occurrence_updated";
+pub const ACTIVE_OCCURRENCE_DELETED: &str = "firefight://active_occurrence_deleted";
+pub const OCCURRENCE_CREATED: &str = "firefight://occurrence_created";
+pub const OCCURRENCE_UPDATED: &str = "firefight://occurrence_updated";
+pub const OCCURRENCE_DELETED: &str = "firefight://occurrence_deleted";
+pub const STAFF_CREATED: &str = "firefight://staff_created";
+pub const STAFF_UPDATED: &str = "firefight://staff_updated";
+pub const STAFF_DELETED: &str = "firefight://staff_deleted";
+pub const TEAM_CREATED: &str = "firefight://team_created";
+pub const TEAM_UPDATED: &str = "firefight://team_updated";
+pub const TEAM_DELETED: &str = "firefight://team_deleted";
+pub const VEHICLE_CREATED: &str = "firefight://vehicle_created";
+pub const VEHICLE_UPDATED: &str = "firefight://vehicle_updated";
+pub const VEHICLE_DELETED: &str = "firefight://vehicle_deleted";
+pub const CREW_REQUIREMENT_CREATED: &str = "firefight://crew_requirement_created";
+pub const CREW_REQUIREMENT_UPDATED: &str = "firefight://crew_requirement_updated";
+pub const CREW_REQUIREMENT_DELETED: &str = "firefight://crew_requirement_deleted";
+pub const SHIFT_TEMPLATE_CREATED: &str = "firefight://shift_template_created";
+pub const SHIFT_TEMPLATE_UPDATED: &str = "firefight://shift_template_updated";
+pub const SHIFT_TEMPLATE_DELETED: &str = "firefight://shift_template_deleted";
+pub const ARCHIVED_OCCURRENCE_CREATED: &str = "firefight://archived_occurrence_created";
+pub const ARCHIVED_OCCURRENCE_UPDATED: &str = "firefight://archived_occurrence_updated";
+pub const ARCHIVED_OCCURRENCE_DELETED: &str = "firefight://archived_occurrence_deleted";
+pub const SHIFT_CHANGED: &str = "firefight://shift_changed";
+// Not store changes, carry no revision
+pub const QUALIFICATIONS_EXPIRING: &str = "firefight://qualifications_expiring";
+pub const OPERATOR_CHANGED: &str = "firefight://operator_changed";
+pub const SHIFT_HANDOVER: &str = "firefight://shift_handover";
+pub const RESOURCES_TRANSFERRED: &str = "firefight://resources_transferred";
+
+struct CollectionEvents {
+    created: &'static str,
+    updated: &'static str,
+    deleted: &'static str,
+}
+
+const ACTIVE_OCCURRENCE_EVENTS: CollectionEvents = CollectionEvents {
+    created: ACTIVE_OCCURRENCE_CREATED,
+    updated: ACTIVE_OCCURRENCE_UPDATED,
+    deleted: ACTIVE_OCCURRENCE_DELETED,
+};
+const OCCURRENCE_EVENTS: CollectionEvents = CollectionEvents {
+    created: OCCURRENCE_CREATED,
+    updated: OCCURRENCE_UPDATED,
+    deleted: OCCURRENCE_DELETED,
+};
+const STAFF_EVENTS: CollectionEvents = CollectionEvents {
+    created: STAFF_CREATED,
+    updated: STAFF_UPDATED,
+    deleted: STAFF_DELETED,
+};
+const TEAM_EVENTS: CollectionEvents = CollectionEvents {
+    created: TEAM_CREATED,
+    updated: TEAM_UPDATED,
+    deleted: TEAM_DELETED,
+};
+const VEHICLE_EVENTS: CollectionEvents = CollectionEvents {
+    created: VEHICLE_CREATED,
+    updated: VEHICLE_UPDATED,
+    deleted: VEHICLE_DELETED,
+};
+const CREW_REQUIREMENT_EVENTS: CollectionEvents = CollectionEvents {
+    created: CREW_REQUIREMENT_CREATED,
+    updated: CREW_REQUIREMENT_UPDATED,
+    deleted: CREW_REQUIREMENT_DELETED,
+};
+const SHIFT_TEMPLATE_EVENTS: CollectionEvents = CollectionEvents {
+    created: SHIFT_TEMPLATE_CREATED,
+    updated: SHIFT_TEMPLATE_UPDATED,
+    deleted: SHIFT_TEMPLATE_DELETED,
+};
+const ARCHIVED_OCCURRENCE_EVENTS: CollectionEvents = CollectionEvents {
+    created: ARCHIVED_OCCURRENCE_CREATED,
+    updated: ARCHIVED_OCCURRENCE_UPDATED,
+    deleted: ARCHIVED_OCCURRENCE_DELETED,
+};
+
+/// Created or updated entities of one collection
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntitiesChanged<T> {
+    pub revision: u64,
+    pub operation: StoreOperation,
+    pub entities: Vec<T>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntitiesDeleted {
+    pub revision: u64,
+    pub operation: StoreOperation,
+    pub entity_ids: Vec<String>,
+}
+
+/// Staff and teams touched by a shift change, sent together so windows never see half a shift
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftChanged {
+    pub revision: u64,
+    pub staff: Vec<Staff>,
+    pub teams: Vec<Team>,
+}
+
+/// Full store at a given revision, for windows that missed an event
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreSnapshot {
+    pub revision: u64,
+    pub data_store: DataStore,
+}
+
+fn changed_entities<T: Clone>(changes: &EntityChanges<T>, created: bool) -> Vec<T> {
+    changes
+        .values()
+        .filter(|change| change.before.is_none() == created)
+        .filter_map(|change| change.after.clone())
+        .collect()
+}
+
+fn deleted_ids<T>(changes: &EntityChanges<T>) -> Vec<String> {
+    changes
+        .iter()
+        .filter(|(_, change)| change.after.is_none())
+        .map(|(entity_id, _)| entity_id.clone())
+        .collect()
+}
+
+/// Delivers an event with its serialized payload
+type EventSink = Box<dyn Fn(&str, Value) -> anyhow::Result<()> + Send + Sync>;
+
+/// Emits the changed entities of every commit to all windows, each event gets the next revision
+pub struct EventBroadcaster {
+    sink: EventSink,
+    revision: AtomicU64,
+}
+
+impl EventBroadcaster {
+    pub fn new(app_handle: AppHandle) -> Self {
+        EventBroadcaster::with_sink(Box::new(move |event, payload| {
+            app_handle
+                .emit_all(event, payload)
+                .map_err(|emit_error| anyhow::anyhow!("Failed to emit {}: {}", event, emit_error))
+        }))
+    }
+
+    fn with_sink(sink: EventSink) -> Self {
+        EventBroadcaster {
+            sink,
+            revision: AtomicU64::new(0),
+        }
+    }
+
+    /// Revision of the last emitted event, only stable while the store is locked
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::SeqCst)
+    }
+
+    fn emit<S: Serialize>(
+        &self,
+        event: &str,
+        payload: impl FnOnce(u64) -> S,
+    ) -> anyhow::Result<()> {
+        let revision = self.revision.fetch_add(1, Ordering::SeqCst) + 1;
+        (self.sink)(event, serde_json::to_value(payload(revision))?)
+    }
+
+    fn emit_collection<T: Serialize + Clone>(
+        &self,
+        events: &CollectionEvents,
+        operation: StoreOperation,
+        changes: &EntityChanges<T>,
+    ) -> anyhow::Result<()> {
+        let created = changed_entities(changes, true);
+        if !created.is_empty() {
+            self.emit(events.created, |revision| EntitiesChanged {
+                revision,
+                operation,
+                entities: created,
+            })?;
+        }
+
+        let updated = changed_entities(changes, false);
+        if !updated.is_empty() {
+            self.emit(events.updated, |revision| EntitiesChanged {
+                revision,
+                operation,
+                entities: updated,
+            })?;
+        }
+
+        let entity_ids = deleted_ids(changes);
+        if !entity_ids.is_empty() {
+            self.emit(events.deleted, |revision| EntitiesDeleted {
+                revision,
+                operation,
+                entity_ids,
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ChangeObserver for EventBroadcaster {
+    fn on_commit(&self, operation: StoreOperation, changes: &ChangeSet) -> anyhow::Result<()> {
+        if operation == StoreOperation::SetStaffShift {
+            return self.emit(SHIFT_CHANGED, |revision| ShiftChanged {
+                revision,
+                staff: changed_entities(&changes.staff, false),
+                teams: changed_entities(&changes.teams, false),
+            });
+        }
+        if operation == StoreOperation::ApplyShiftTemplate {
+            self.emit(SHIFT_CHANGED, |revision| ShiftChanged {
+                revision,
+                staff: changed_entities(&changes.staff, false),
+                teams: changed_entities(&changes.teams, false),
+            })?;
+            // Only the last handover of the template changed
+            return self.emit_collection(
+                &SHIFT_TEMPLATE_EVENTS,
+                operation,
+                &changes.shift_templates,
+            );
+        }
+
+        self.emit_collection(
+            &ACTIVE_OCCURRENCE_EVENTS,
+            operation,
+            &changes.active_occurrences,
+        )?;
+        self.emit_collection(&OCCURRENCE_EVENTS, operation, &changes.occurrences)?;
+        self.emit_collection(&STAFF_EVENTS, operation, &changes.staff)?;
+        self.emit_collection(&TEAM_EVENTS, operation, &changes.teams)?;
+        self.emit_collection(&VEHICLE_EVENTS, operation, &changes.vehicles)?;
+        self.emit_collection(
+            &CREW_REQUIREMENT_EVENTS,
+            operation,
+            &changes.crew_requirements,
+        )?;
+        self.emit_collection(&SHIFT_TEMPLATE_EVENTS, operation, &changes.shift_templates)?;
+        self.emit_collection(
+            &ARCHIVED_OCCURRENCE_EVENTS,
+            operation,
+            &changes.archived_occurrences,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::transaction::EntityChange;
+    use crate::firefight::types::{ArchivedOccurrence, StaffState};
+    use std::sync::{Arc, Mutex};
+
+    type Emitted = Arc<Mutex<Vec<(String, Value)>>>;
+
+    fn broadcaster() -> (EventBroadcaster, Emitted) {
+        let emitted = Emitted::default();
+        let sink_emitted = emitted.clone();
+        let broadcaster = EventBroadcaster::with_sink(Box::new(move |event, payload| {
+            sink_emitted
+                .lock()
+                .unwrap()
+                .push((event.to_string(), payload));
+            Ok(())
+        }));
+        (broadcaster, emitted)
+    }
+
+    fn events(emitted: &Emitted) -> Vec<(String, u64)> {
+        emitted
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(event, payload)| (event.clone(), payload["revision"].as_u64().unwrap()))
+            .collect()
+    }
+
+    fn staff_store(staff_ids: &[&str]) -> DataStore {
+        fixtures::data_store(
+            vec![],
+            staff_ids
+                .iter()
+                .map(|staff_id| fixtures::staff(staff_id))
+                .collect(),
+            vec![],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn created_updated_and_deleted_entities_get_their_own_revision() {
+        let (broadcaster, emitted) = broadcaster();
+        let mut after = staff_store(&["kept", "added"]);
+        after.staff.get_mut("kept").unwrap().label = String::from("K");
+        let changes = ChangeSet::diff(&staff_store(&["kept", "removed"]), &after);
+
+        broadcaster
+            .on_commit(StoreOperation::UpdateStaff, &changes)
+            .unwrap();
+        assert_eq!(
+            events(&emitted),
+            vec![
+                (String::from(STAFF_CREATED), 1),
+                (String::from(STAFF_UPDATED), 2),
+                (String::from(STAFF_DELETED), 3),
+            ]
+        );
+        assert_eq!(broadcaster.revision(), 3);
+
+        let emitted = emitted.lock().unwrap();
+        assert_eq!(emitted[0].1["entities"][0]["internalId"], "added");
+        assert_eq!(emitted[1].1["entities"][0]["label"], "K");
+        assert_eq!(emitted[2].1["entityIds"][0], "removed");
+        assert_eq!(emitted[2].1["operation"], "updateStaff");
+    }
+
+    #[test]
+    fn revisions_keep_counting_across_commits() {
+        let (broadcaster, emitted) = broadcaster();
+        for staff_id in ["first", "second"] {
+            let changes = ChangeSet::diff(&DataStore::default(), &staff_store(&[staff_id]));
+            broadcaster
+                .on_commit(StoreOperation::CreateStaff, &changes)
+                .unwrap();
+        }
+
+        assert_eq!(
+            events(&emitted),
+            vec![
+                (String::from(STAFF_CREATED), 1),
+                (String::from(STAFF_CREATED), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn shift_changes_are_sent_as_one_event() {
+        let (broadcaster, emitted) = broadcaster();
+        let mut after = staff_store(&["staff-1", "staff-2"]);
+        after
+            .staff
+            .values_mut()
+            .for_each(|staff| staff.state = StaffState::Unavailable);
+        let changes = ChangeSet::diff(&staff_store(&["staff-1", "staff-2"]), &after);
+
+        broadcaster
+            .on_commit(StoreOperation::SetStaffShift, &changes)
+            .unwrap();
+        assert_eq!(events(&emitted), vec![(String::from(SHIFT_CHANGED), 1)]);
+        assert_eq!(
+            emitted.lock().unwrap()[0].1["staff"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn closing_an_occurrence_announces_its_archive_entry() {
+        let (broadcaster, emitted) = broadcaster();
+        let active_occurrence = fixtures::active_occurrence("active", "occurrence", &[]);
+        let mut before = DataStore::default();
+        before
+            .active_occurrences
+            .insert(String::from("active"), active_occurrence.clone());
+        let mut changes = ChangeSet::diff(&before, &DataStore::default());
+        changes.archived_occurrences.insert(
+            String::from("active"),
+            EntityChange {
+                before: None,
+                after: Some(ArchivedOccurrence {
+                    active_occurrence,
+                    end_time: 100,
+                }),
+            },
+        );
+
+        broadcaster
+            .on_commit(StoreOperation::CloseActiveOccurrence, &changes)
+            .unwrap();
+        assert_eq!(
+            events(&emitted),
+            vec![
+                (String::from(ACTIVE_OCCURRENCE_DELETED), 1),
+                (String::from(ARCHIVED_OCCURRENCE_CREATED), 2),
+            ]
+        );
+
+        // Undoing the close takes the entry out of the archive again
+        broadcaster
+            .on_commit(StoreOperation::Undo, &changes.inverse())
+            .unwrap();
+        assert_eq!(
+            events(&emitted)[2..],
+            [
+                (String::from(ACTIVE_OCCURRENCE_CREATED), 3),
+                (String::from(ARCHIVED_OCCURRENCE_DELETED), 4),
+            ]
+        );
+    }
+}
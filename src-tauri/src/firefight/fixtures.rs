@@ -0,0 +1,316 @@
+use anyhow::Context;
+use std::{cmp::Reverse, collections::HashMap, sync::Arc};
+
+use super::transaction::{ChangeObserver, ChangeSet};
+use super::types::{
+    ActiveOccurrence, ArchiveQuery, ArchivedOccurrence, CrewAssignment, CrewRole, DataStore,
+    FirefightDataManager, Occurrence, ShiftTemplate, Staff, StaffPermission, StaffRank, StaffState,
+    Team, TeamState, Vehicle, VehicleCategory, VehicleState,
+};
+
+pub fn occurrence(occurrence_id: &str) -> Occurrence {
+    Occurrence {
+        internal_id: occurrence_id.to_string(),
+        image: String::new(),
+        name: format!("Occurrence {}", occurrence_id),
+        dispatch_plan: vec![],
+    }
+}
+
+pub fn staff(staff_id: &str) -> Staff {
+    Staff {
+        internal_id: staff_id.to_string(),
+        image: String::new(),
+        label: staff_id.to_string(),
+        name: format!("Staff {}", staff_id),
+        national_id: String::new(),
+        permission: StaffPermission::Own,
+        rank: StaffRank::Unknown,
+        state: StaffState::Available,
+        qualifications: vec![],
+        leave_periods: vec![],
+    }
+}
+
+pub fn team(team_id: &str, member_ids: &[&str]) -> Team {
+    Team {
+        internal_id: team_id.to_string(),
+        label: team_id.to_string(),
+        member_ids: member_ids
+            .iter()
+            .map(|member_id| member_id.to_string())
+            .collect(),
+        state: TeamState::Available,
+    }
+}
+
+pub fn vehicle(vehicle_id: &str, category: VehicleCategory, capacity: Option<u8>) -> Vehicle {
+    Vehicle {
+        internal_id: vehicle_id.to_string(),
+        capacity,
+        category,
+        image: String::new(),
+        label: vehicle_id.to_string(),
+        license_plate: None,
+        state: VehicleState::Available,
+    }
+}
+
+/// Active occurrence of `occurrence_id` crewing each vehicle with the given staff as regular crew
+pub fn active_occurrence(
+    active_occurrence_id: &str,
+    occurrence_id: &str,
+    vehicle_crews: &[(&str, &[&str])],
+) -> ActiveOccurrence {
+    let mut vehicle_assignment_map = HashMap::new();
+    let mut staff_ids = vec![];
+    for (vehicle_id, crew_ids) in vehicle_crews.iter() {
+        let vehicle_crew = crew_ids
+            .iter()
+            .map(|staff_id| CrewAssignment {
+                staff_id: staff_id.to_string(),
+                role: CrewRole::Crew,
+            })
+            .collect::<Vec<CrewAssignment>>();
+        vehicle_assignment_map.insert(vehicle_id.to_string(), vehicle_crew);
+        staff_ids.extend(crew_ids.iter().map(|staff_id| staff_id.to_string()));
+    }
+
+    ActiveOccurrence {
+        address: None,
+        codu_number: None,
+        creation_time: Some(0),
+        description: None,
+        internal_id: active_occurrence_id.to_string(),
+        location: None,
+        occurrence_id: occurrence_id.to_string(),
+        reference_point: None,
+        resource_releases: vec![],
+        resource_transfers: vec![],
+        staff_ids,
+        team_ids: vec![],
+        vehicle_assignment_map,
+        vehicle_ids: vehicle_crews
+            .iter()
+            .map(|(vehicle_id, _)| vehicle_id.to_string())
+            .collect(),
+        vehicle_timelines: HashMap::new(),
+        vmer_siv: None,
+    }
+}
+
+/// Store holding the given entities, keyed by their internal ids
+pub fn data_store(
+    occurrences: Vec<Occurrence>,
+    staff: Vec<Staff>,
+    teams: Vec<Team>,
+    vehicles: Vec<Vehicle>,
+) -> DataStore {
+    DataStore {
+        occurrences: occurrences
+            .into_iter()
+            .map(|occurrence| (occurrence.internal_id.clone(), occurrence))
+            .collect(),
+        staff: staff
+            .into_iter()
+            .map(|member| (member.internal_id.clone(), member))
+            .collect(),
+        teams: teams
+            .into_iter()
+            .map(|team| (team.internal_id.clone(), team))
+            .collect(),
+        vehicles: vehicles
+            .into_iter()
+            .map(|vehicle| (vehicle.internal_id.clone(), vehicle))
+            .collect(),
+        ..DataStore::default()
+    }
+}
+
+/// Store backend kept only in memory, commits apply straight to the data it holds
+#[derive(Default)]
+pub struct MemoryStore {
+    pub data: DataStore,
+    pub archive: HashMap<String, ArchivedOccurrence>,
+    observers: Vec<Arc<dyn ChangeObserver>>,
+}
+
+impl MemoryStore {
+    pub fn new(data: DataStore) -> MemoryStore {
+        MemoryStore {
+            data,
+            ..MemoryStore::default()
+        }
+    }
+}
+
+impl FirefightDataManager for MemoryStore {
+    fn get_active_occurrence(&self, occurrence_id: &String) -> anyhow::Result<ActiveOccurrence> {
+        self.data
+            .active_occurrences
+            .get(occurrence_id)
+            .cloned()
+            .with_context(|| format!("No active occurrence found with id: {}", occurrence_id))
+    }
+
+    fn get_active_occurrence_by_staff(
+        &self,
+        staff_id: &String,
+    ) -> anyhow::Result<ActiveOccurrence> {
+        self.data
+            .active_occurrences
+            .values()
+            .find(|active_occurrence| active_occurrence.staff_ids.contains(staff_id))
+            .cloned()
+            .with_context(|| format!("No active occurrence found with staff id: {}", staff_id))
+    }
+
+    fn get_active_occurrence_by_vehicle(
+        &self,
+        vehicle_id: &String,
+    ) -> anyhow::Result<ActiveOccurrence> {
+        self.data
+            .active_occurrences
+            .values()
+            .find(|active_occurrence| active_occurrence.vehicle_ids.contains(vehicle_id))
+            .cloned()
+            .with_context(|| format!("No active occurrence found with vehicle id: {}", vehicle_id))
+    }
+
+    fn get_active_occurrence_list(&self) -> anyhow::Result<Vec<ActiveOccurrence>> {
+        Ok(self.data.active_occurrences.values().cloned().collect())
+    }
+
+    fn get_active_occurrence_list_by_occurrence(
+        &self,
+        occurrence_id: &String,
+    ) -> anyhow::Result<Vec<ActiveOccurrence>> {
+        Ok(self
+            .data
+            .active_occurrences
+            .values()
+            .filter(|active_occurrence| active_occurrence.occurrence_id == *occurrence_id)
+            .cloned()
+            .collect())
+    }
+
+    fn get_occurrence(&self, occurrence_id: &String) -> anyhow::Result<Occurrence> {
+        self.data
+            .occurrences
+            .get(occurrence_id)
+            .cloned()
+            .with_context(|| format!("No occurrence found with id: {}", occurrence_id))
+    }
+
+    fn get_occurrence_name(&self, occurrence_id: &String) -> anyhow::Result<String> {
+        Ok(self.get_occurrence(occurrence_id)?.name)
+    }
+
+    fn get_occurrence_list(&self) -> anyhow::Result<Vec<Occurrence>> {
+        Ok(self.data.occurrences.values().cloned().collect())
+    }
+
+    fn get_staff(&self, staff_id: &String) -> anyhow::Result<Staff> {
+        self.data
+            .staff
+            .get(staff_id)
+            .cloned()
+            .with_context(|| format!("No staff found with id: {}", staff_id))
+    }
+
+    fn get_staff_label(&self, staff_id: &String) -> anyhow::Result<String> {
+        Ok(self.get_staff(staff_id)?.label)
+    }
+
+    fn get_staff_list(&self) -> anyhow::Result<Vec<Staff>> {
+        Ok(self.data.staff.values().cloned().collect())
+    }
+
+    fn get_team(&self, team_id: &String) -> anyhow::Result<Team> {
+        self.data
+            .teams
+            .get(team_id)
+            .cloned()
+            .with_context(|| format!("No team found with id: {}", team_id))
+    }
+
+    fn get_team_label(&self, team_id: &String) -> anyhow::Result<String> {
+        Ok(self.get_team(team_id)?.label)
+    }
+
+    fn get_team_list(&self) -> anyhow::Result<Vec<Team>> {
+        Ok(self.data.teams.values().cloned().collect())
+    }
+
+    fn get_vehicle(&self, vehicle_id: &String) -> anyhow::Result<Vehicle> {
+        self.data
+            .vehicles
+            .get(vehicle_id)
+            .cloned()
+            .with_context(|| format!("No vehicle found with id: {}", vehicle_id))
+    }
+
+    fn get_vehicle_capacity(&self, vehicle_id: &String) -> anyhow::Result<Option<u8>> {
+        Ok(self.get_vehicle(vehicle_id)?.capacity)
+    }
+
+    fn get_vehicle_label(&self, vehicle_id: &String) -> anyhow::Result<String> {
+        Ok(self.get_vehicle(vehicle_id)?.label)
+    }
+
+    fn get_vehicle_list(&self) -> anyhow::Result<Vec<Vehicle>> {
+        Ok(self.data.vehicles.values().cloned().collect())
+    }
+
+    fn get_shift_template_list(&self) -> anyhow::Result<Vec<ShiftTemplate>> {
+        Ok(self.data.shift_templates.values().cloned().collect())
+    }
+
+    fn get_data_store(&self) -> anyhow::Result<DataStore> {
+        Ok(self.data.clone())
+    }
+
+    fn get_archived_occurrence(
+        &self,
+        archived_occurrence_id: &String,
+    ) -> anyhow::Result<ArchivedOccurrence> {
+        self.archive
+            .get(archived_occurrence_id)
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "No archived occurrence found with id: {}",
+                    archived_occurrence_id
+                )
+            })
+    }
+
+    fn get_archived_occurrence_list(
+        &self,
+        query: &ArchiveQuery,
+    ) -> anyhow::Result<Vec<ArchivedOccurrence>> {
+        let mut found_values = self
+            .archive
+            .values()
+            .filter(|archived_occurrence| query.matches(archived_occurrence))
+            .cloned()
+            .collect::<Vec<ArchivedOccurrence>>();
+        found_values.sort_by_key(|archived_occurrence| Reverse(archived_occurrence.end_time));
+
+        Ok(found_values)
+    }
+
+    fn add_observer(&mut self, observer: Arc<dyn ChangeObserver>) {
+        self.observers.push(observer);
+    }
+
+    fn observers(&self) -> &[Arc<dyn ChangeObserver>] {
+        &self.observers
+    }
+
+    fn commit(&mut self, changes: &ChangeSet) -> anyhow::Result<()> {
+        changes.apply(&mut self.data);
+        changes.apply_archive(&mut self.archive);
+        Ok(())
+    }
+}
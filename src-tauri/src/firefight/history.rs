@@ -0,0 +1,287 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use super::transaction::{ChangeObserver, ChangeSet, StoreOperation};
+
+/// How many dispatch board operations can be undone
+pub const UNDO_HISTORY_LIMIT: usize = 50;
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub operation: StoreOperation,
+    pub changes: ChangeSet,
+}
+
+#[derive(Default)]
+struct HistoryStacks {
+    undo_stack: VecDeque<HistoryEntry>,
+    redo_stack: Vec<HistoryEntry>,
+}
+
+/// Records undoable commits, replaying an entry is left to the caller holding the store
+#[derive(Default)]
+pub struct UndoHistory {
+    stacks: Mutex<HistoryStacks>,
+}
+
+impl UndoHistory {
+    fn stacks(&self) -> anyhow::Result<std::sync::MutexGuard<'_, HistoryStacks>> {
+        self.stacks
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Undo history is poisoned"))
+    }
+
+    fn pop_undo(&self) -> anyhow::Result<Option<HistoryEntry>> {
+        Ok(self.stacks()?.undo_stack.pop_back())
+    }
+
+    fn pop_redo(&self) -> anyhow::Result<Option<HistoryEntry>> {
+        Ok(self.stacks()?.redo_stack.pop())
+    }
+
+    fn push_undo(&self, entry: HistoryEntry) -> anyhow::Result<()> {
+        let mut stacks = self.stacks()?;
+        stacks.undo_stack.push_back(entry);
+        if stacks.undo_stack.len() > UNDO_HISTORY_LIMIT {
+            stacks.undo_stack.pop_front();
+        }
+        Ok(())
+    }
+
+    fn push_redo(&self, entry: HistoryEntry) -> anyhow::Result<()> {
+        self.stacks()?.redo_stack.push(entry);
+        Ok(())
+    }
+
+    /// Reverts the latest entry through `replay`, which commits with the store held. A conflicting
+    /// entry can never be applied again, so it is dropped.
+    pub fn undo(
+        &self,
+        replay: &mut dyn FnMut(&ChangeSet) -> anyhow::Result<()>,
+    ) -> anyhow::Result<StoreOperation> {
+        let entry = self
+            .pop_undo()?
+            .ok_or_else(|| anyhow::anyhow!("Nothing to undo"))?;
+        replay(&entry.changes.inverse())?;
+        let operation = entry.operation;
+        self.push_redo(entry)?;
+        Ok(operation)
+    }
+
+    /// Applies the latest undone entry again through `replay`, dropped as well on conflicts
+    pub fn redo(
+        &self,
+        replay: &mut dyn FnMut(&ChangeSet) -> anyhow::Result<()>,
+    ) -> anyhow::Result<StoreOperation> {
+        let entry = self
+            .pop_redo()?
+            .ok_or_else(|| anyhow::anyhow!("Nothing to redo"))?;
+        replay(&entry.changes)?;
+        let operation = entry.operation;
+        self.push_undo(entry)?;
+        Ok(operation)
+    }
+}
+
+impl ChangeObserver for UndoHistory {
+    fn on_commit(&self, operation: StoreOperation, changes: &ChangeSet) -> anyhow::Result<()> {
+        if !operation.is_undoable() {
+            return Ok(());
+        }
+
+        let mut changes = changes.clone();
+        // Undoing a handover brings the previous roster back, the template keeps the handover as
+        // applied so the scheduler does not hand over again
+        if operation == StoreOperation::ApplyShiftTemplate {
+            changes.shift_templates.clear();
+        }
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        self.push_undo(HistoryEntry { operation, changes })?;
+        self.stacks()?.redo_stack.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::operations;
+    use crate::firefight::types::{DataStore, ShiftTemplate, StaffState};
+    use std::collections::HashMap;
+
+    fn store() -> DataStore {
+        let mut data_store = fixtures::data_store(
+            vec![fixtures::occurrence("occurrence")],
+            vec![fixtures::staff("staff-1"), fixtures::staff("staff-2")],
+            vec![],
+            vec![],
+        );
+        data_store.active_occurrences.insert(
+            String::from("active"),
+            fixtures::active_occurrence("active", "occurrence", &[]),
+        );
+        data_store
+    }
+
+    /// Stages and commits like a store backend, notifying the history afterwards
+    fn commit(
+        history: &UndoHistory,
+        data_store: &mut DataStore,
+        operation: StoreOperation,
+        stage: impl FnOnce(&mut DataStore),
+    ) {
+        let mut staged_store = data_store.clone();
+        stage(&mut staged_store);
+        let changes = ChangeSet::diff(data_store, &staged_store);
+        changes.apply(data_store);
+        history.on_commit(operation, &changes).unwrap();
+    }
+
+    fn describe(history: &UndoHistory, data_store: &mut DataStore, description: &str) {
+        commit(
+            history,
+            data_store,
+            StoreOperation::UpdateActiveOccurrence,
+            |data_store| {
+                data_store
+                    .active_occurrences
+                    .get_mut("active")
+                    .unwrap()
+                    .description = Some(String::from(description))
+            },
+        );
+    }
+
+    fn description(data_store: &DataStore) -> Option<String> {
+        data_store.active_occurrences["active"].description.clone()
+    }
+
+    fn replay(data_store: &mut DataStore) -> impl FnMut(&ChangeSet) -> anyhow::Result<()> + '_ {
+        |changes| {
+            changes.check_conflicts(data_store)?;
+            changes.apply(data_store);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn undone_operations_can_be_redone() {
+        let history = UndoHistory::default();
+        let mut data_store = store();
+        describe(&history, &mut data_store, "first");
+        describe(&history, &mut data_store, "second");
+
+        let operation = history.undo(&mut replay(&mut data_store)).unwrap();
+        assert_eq!(operation, StoreOperation::UpdateActiveOccurrence);
+        assert_eq!(description(&data_store).as_deref(), Some("first"));
+
+        history.redo(&mut replay(&mut data_store)).unwrap();
+        assert_eq!(description(&data_store).as_deref(), Some("second"));
+        assert!(history.redo(&mut replay(&mut data_store)).is_err());
+    }
+
+    #[test]
+    fn only_the_latest_operations_are_kept() {
+        let history = UndoHistory::default();
+        let mut data_store = store();
+        for index in 0..UNDO_HISTORY_LIMIT + 5 {
+            describe(&history, &mut data_store, &index.to_string());
+        }
+
+        for _ in 0..UNDO_HISTORY_LIMIT {
+            history.undo(&mut replay(&mut data_store)).unwrap();
+        }
+        assert_eq!(description(&data_store).as_deref(), Some("4"));
+        assert!(history.undo(&mut replay(&mut data_store)).is_err());
+    }
+
+    #[test]
+    fn new_operations_clear_the_redo_stack() {
+        let history = UndoHistory::default();
+        let mut data_store = store();
+        describe(&history, &mut data_store, "first");
+        history.undo(&mut replay(&mut data_store)).unwrap();
+
+        describe(&history, &mut data_store, "second");
+        assert!(history.redo(&mut replay(&mut data_store)).is_err());
+        assert_eq!(description(&data_store).as_deref(), Some("second"));
+    }
+
+    #[test]
+    fn other_operations_are_not_recorded() {
+        let history = UndoHistory::default();
+        let mut data_store = store();
+        commit(
+            &history,
+            &mut data_store,
+            StoreOperation::UpdateStaff,
+            |data_store| data_store.staff.get_mut("staff-1").unwrap().label = String::from("S1"),
+        );
+
+        assert!(history.undo(&mut replay(&mut data_store)).is_err());
+        assert_eq!(data_store.staff["staff-1"].label, "S1");
+    }
+
+    #[test]
+    fn conflicting_entries_are_dropped() {
+        let history = UndoHistory::default();
+        let mut data_store = store();
+        describe(&history, &mut data_store, "first");
+        describe(&history, &mut data_store, "second");
+        data_store
+            .active_occurrences
+            .get_mut("active")
+            .unwrap()
+            .description = Some(String::from("edited elsewhere"));
+
+        assert!(history.undo(&mut replay(&mut data_store)).is_err());
+        assert_eq!(
+            description(&data_store).as_deref(),
+            Some("edited elsewhere")
+        );
+        assert!(history.redo(&mut replay(&mut data_store)).is_err());
+
+        // The entry below the conflicting one stays available
+        data_store
+            .active_occurrences
+            .get_mut("active")
+            .unwrap()
+            .description = Some(String::from("first"));
+        history.undo(&mut replay(&mut data_store)).unwrap();
+        assert_eq!(description(&data_store), None);
+    }
+
+    #[test]
+    fn undoing_a_handover_keeps_it_applied() {
+        let history = UndoHistory::default();
+        let mut data_store = store();
+        data_store.shift_templates.insert(
+            String::from("night"),
+            ShiftTemplate {
+                internal_id: String::from("night"),
+                name: String::from("Night"),
+                available_staff: vec![String::from("staff-2")],
+                team_allocations: HashMap::new(),
+                schedule: vec![],
+                last_handover: None,
+            },
+        );
+        commit(
+            &history,
+            &mut data_store,
+            StoreOperation::ApplyShiftTemplate,
+            |data_store| {
+                operations::apply_shift_template(data_store, &String::from("night"), 100).unwrap()
+            },
+        );
+        assert_eq!(data_store.staff["staff-1"].state, StaffState::Unavailable);
+
+        let operation = history.undo(&mut replay(&mut data_store)).unwrap();
+        assert_eq!(operation, StoreOperation::ApplyShiftTemplate);
+        assert_eq!(data_store.staff["staff-1"].state, StaffState::Available);
+        assert_eq!(data_store.shift_templates["night"].last_handover, Some(100));
+    }
+}
@@ -0,0 +1,305 @@
+use anyhow::Context;
+use base64::Engine;
+use image::{imageops::FilterType, ImageFormat};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+use tauri::AppHandle;
+
+use super::transaction::StoreOperation;
+use super::types::FirefightDataManager;
+
+/// Scheme the webviews load images from, `firefight-image://localhost/<reference>_<size>`
+pub const IMAGE_PROTOCOL: &str = "firefight-image";
+
+const DATA_URL_PREFIX: &str = "data:";
+const ORIGINAL_IMAGE_EXTENSION: &str = "img";
+const THUMBNAIL_EXTENSION: &str = "png";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageSize {
+    Original,
+    Card,
+    Tag,
+}
+
+impl ImageSize {
+    const THUMBNAILS: [ImageSize; 2] = [ImageSize::Card, ImageSize::Tag];
+
+    fn from_name(name: &str) -> Option<ImageSize> {
+        ImageSize::THUMBNAILS
+            .into_iter()
+            .chain([ImageSize::Original])
+            .find(|size| size.name() == name)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ImageSize::Original => "original",
+            ImageSize::Card => "card",
+            ImageSize::Tag => "tag",
+        }
+    }
+
+    /// Longest side of the thumbnail in pixels
+    fn max_dimension(&self) -> Option<u32> {
+        match self {
+            ImageSize::Original => None,
+            ImageSize::Card => Some(256),
+            ImageSize::Tag => Some(64),
+        }
+    }
+}
+
+pub fn get_image_dir(app_handle: &AppHandle) -> anyhow::Result<PathBuf> {
+    let image_dir = app_handle
+        .path_resolver()
+        .app_local_data_dir()
+        .with_context(|| "Unable to resolve the app local data directory".to_string())?
+        .join("images");
+    std::fs::create_dir_all(&image_dir)
+        .with_context(|| format!("Failed to create image directory {:?}", image_dir))?;
+    Ok(image_dir)
+}
+
+/// References are the hex SHA-256 of the original image bytes
+pub fn is_image_reference(value: &str) -> bool {
+    value.len() == 64
+        && value
+            .chars()
+            .all(|value_char| value_char.is_ascii_hexdigit())
+}
+
+fn get_image_path(image_dir: &Path, reference: &str, size: ImageSize) -> anyhow::Result<PathBuf> {
+    if !is_image_reference(reference) {
+        anyhow::bail!("Invalid image reference: {}", reference);
+    }
+
+    let file_name = match size {
+        ImageSize::Original => format!("{}.{}", reference, ORIGINAL_IMAGE_EXTENSION),
+        thumbnail_size => format!(
+            "{}-{}.{}",
+            reference,
+            thumbnail_size.name(),
+            THUMBNAIL_EXTENSION
+        ),
+    };
+    Ok(image_dir.join(file_name))
+}
+
+/// Writes an image and its thumbnails once, storing the same bytes again is a no-op
+pub fn store_image(app_handle: &AppHandle, image_bytes: &[u8]) -> anyhow::Result<String> {
+    write_image(&get_image_dir(app_handle)?, image_bytes)
+}
+
+fn write_image(image_dir: &Path, image_bytes: &[u8]) -> anyhow::Result<String> {
+    let reference = format!("{:x}", Sha256::digest(image_bytes));
+    let original_path = get_image_path(image_dir, &reference, ImageSize::Original)?;
+    if original_path.exists() {
+        return Ok(reference);
+    }
+
+    let decoded_image =
+        image::load_from_memory(image_bytes).with_context(|| "Unsupported image".to_string())?;
+    for size in ImageSize::THUMBNAILS {
+        let max_dimension = size.max_dimension().unwrap_or(u32::MAX);
+        let mut thumbnail_bytes = Cursor::new(vec![]);
+        decoded_image
+            .resize(max_dimension, max_dimension, FilterType::Triangle)
+            .write_to(&mut thumbnail_bytes, ImageFormat::Png)
+            .with_context(|| format!("Failed to create {:?} thumbnail", size))?;
+        std::fs::write(
+            get_image_path(image_dir, &reference, size)?,
+            thumbnail_bytes.into_inner(),
+        )
+        .with_context(|| format!("Failed to write {:?} thumbnail", size))?;
+    }
+
+    // The original goes last, its presence marks a complete set of files
+    std::fs::write(&original_path, image_bytes)
+        .with_context(|| format!("Failed to write image {:?}", original_path))?;
+    Ok(reference)
+}
+
+pub fn read_image(
+    app_handle: &AppHandle,
+    reference: &str,
+    size: ImageSize,
+) -> anyhow::Result<Vec<u8>> {
+    let image_path = get_image_path(&get_image_dir(app_handle)?, reference, size)?;
+    std::fs::read(&image_path)
+        .with_context(|| format!("No image found with reference: {}", reference))
+}
+
+fn decode_data_url(data_url: &str) -> anyhow::Result<Vec<u8>> {
+    let (_, encoded_image) = data_url
+        .split_once(";base64,")
+        .with_context(|| "Image data URL is not base64 encoded".to_string())?;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded_image)
+        .with_context(|| "Failed to decode image data URL".to_string())
+}
+
+/// Turns whatever the frontend sent as an image into a reference, inline data URLs are
+/// stored on disk and existing references are kept
+pub fn intern_image(app_handle: &AppHandle, image: &str) -> anyhow::Result<String> {
+    if image.is_empty() || is_image_reference(image) {
+        return Ok(image.to_string());
+    }
+
+    store_inline_image(&get_image_dir(app_handle)?, image)
+}
+
+fn store_inline_image(image_dir: &Path, image: &str) -> anyhow::Result<String> {
+    if !image.starts_with(DATA_URL_PREFIX) {
+        anyhow::bail!("Unsupported image value");
+    }
+
+    write_image(image_dir, &decode_data_url(image)?)
+}
+
+/// Moves inline images left in the store by older versions, backups or bundles to disk
+pub fn externalize_images(
+    app_handle: &AppHandle,
+    store: &mut dyn FirefightDataManager,
+) -> anyhow::Result<usize> {
+    externalize_images_into(&get_image_dir(app_handle)?, store)
+}
+
+/// Same as `externalize_images`, writing into `image_dir`
+pub fn externalize_images_into(
+    image_dir: &Path,
+    store: &mut dyn FirefightDataManager,
+) -> anyhow::Result<usize> {
+    let data_store = store.get_data_store()?;
+
+    let mut references = HashMap::new();
+    let inline_images = data_store
+        .occurrences
+        .values()
+        .map(|occurrence| &occurrence.image)
+        .chain(data_store.staff.values().map(|staff| &staff.image))
+        .chain(data_store.vehicles.values().map(|vehicle| &vehicle.image))
+        .filter(|image| image.starts_with(DATA_URL_PREFIX));
+    for inline_image in inline_images {
+        if references.contains_key(inline_image) {
+            continue;
+        }
+        match store_inline_image(image_dir, inline_image) {
+            Ok(reference) => {
+                references.insert(inline_image.clone(), reference);
+            }
+            Err(intern_error) => println!("Failed to store inline image: {:?}", intern_error),
+        }
+    }
+    if references.is_empty() {
+        return Ok(0);
+    }
+
+    store.transaction(StoreOperation::ExternalizeImages, &mut |data_store| {
+        for occurrence in data_store.occurrences.values_mut() {
+            if let Some(reference) = references.get(&occurrence.image) {
+                occurrence.image = reference.clone();
+            }
+        }
+        for staff in data_store.staff.values_mut() {
+            if let Some(reference) = references.get(&staff.image) {
+                staff.image = reference.clone();
+            }
+        }
+        for vehicle in data_store.vehicles.values_mut() {
+            if let Some(reference) = references.get(&vehicle.image) {
+                vehicle.image = reference.clone();
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(references.len())
+}
+
+/// Parses the last path segment of a protocol request, `<reference>_<size>`
+pub fn parse_image_request(uri: &str) -> Option<(String, ImageSize)> {
+    let request_path = uri.split(['?', '#']).next()?;
+    let (reference, size_name) = request_path.rsplit('/').next()?.split_once('_')?;
+    if !is_image_reference(reference) {
+        return None;
+    }
+    Some((reference.to_string(), ImageSize::from_name(size_name)?))
+}
+
+pub fn image_mimetype(image_bytes: &[u8]) -> &'static str {
+    match image::guess_format(image_bytes) {
+        Ok(ImageFormat::Png) => "image/png",
+        Ok(ImageFormat::Jpeg) => "image/jpeg",
+        Ok(ImageFormat::Gif) => "image/gif",
+        Ok(ImageFormat::WebP) => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REFERENCE: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn image_requests_name_a_reference_and_a_size() {
+        for (uri, size) in [
+            (
+                format!("firefight-image://localhost/{}_card", REFERENCE),
+                ImageSize::Card,
+            ),
+            (
+                format!("https://firefight-image.localhost/{}_tag", REFERENCE),
+                ImageSize::Tag,
+            ),
+            (
+                format!("firefight-image://localhost/{}_original?v=1#top", REFERENCE),
+                ImageSize::Original,
+            ),
+        ] {
+            assert_eq!(
+                parse_image_request(&uri),
+                Some((REFERENCE.to_string(), size))
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_image_requests_are_refused() {
+        for uri in [
+            format!("firefight-image://localhost/{}", REFERENCE),
+            format!("firefight-image://localhost/{}_huge", REFERENCE),
+            format!("firefight-image://localhost/{}_card", &REFERENCE[1..]),
+            String::from("firefight-image://localhost/../secrets_card"),
+            format!("firefight-image://localhost/{}_card/", REFERENCE),
+        ] {
+            assert_eq!(parse_image_request(&uri), None, "{} was accepted", uri);
+        }
+    }
+
+    #[test]
+    fn data_urls_are_decoded() {
+        assert_eq!(
+            decode_data_url("data:image/png;base64,aGVsbG8=").unwrap(),
+            b"hello"
+        );
+        assert!(decode_data_url("data:image/png,hello").is_err());
+    }
+
+    #[test]
+    fn mimetypes_are_guessed_from_the_image_bytes() {
+        assert_eq!(
+            image_mimetype(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            "image/png"
+        );
+        assert_eq!(image_mimetype(b"\xff\xd8\xff\xe0\0\x10JFIF"), "image/jpeg");
+        assert_eq!(image_mimetype(b"hello"), "application/octet-stream");
+    }
+}
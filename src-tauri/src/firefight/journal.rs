@@ -0,0 +1,180 @@
+use anyhow::Context;
+use serde::Serialize;
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::{Arc, Mutex},
+};
+use tauri::AppHandle;
+
+use super::operators::OperatorRegistry;
+use super::transaction::{ChangeObserver, ChangeSet, EntityChanges, EntityKind, StoreOperation};
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub timestamp: u128,
+    pub operation: StoreOperation,
+    /// Staff id of the operator logged in when the change was committed
+    #[serde(default)]
+    pub operator_id: Option<String>,
+    pub entity_kind: EntityKind,
+    pub entity_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// Appends one line per changed entity to `audit_journal.jsonl`, entries are never rewritten
+pub struct AuditJournal {
+    file: Mutex<File>,
+    operators: Arc<OperatorRegistry>,
+}
+
+pub fn open_journal(
+    app_handle: &AppHandle,
+    operators: Arc<OperatorRegistry>,
+) -> anyhow::Result<AuditJournal> {
+    let data_dir = app_handle
+        .path_resolver()
+        .app_local_data_dir()
+        .with_context(|| "Unable to resolve the app local data directory".to_string())?;
+    std::fs::create_dir_all(&data_dir)
+        .with_context(|| format!("Failed to create data directory {:?}", data_dir))?;
+
+    let journal_path = data_dir.join("audit_journal.jsonl");
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&journal_path)
+        .with_context(|| format!("Failed to open audit journal {:?}", journal_path))?;
+
+    Ok(AuditJournal {
+        file: Mutex::new(file),
+        operators,
+    })
+}
+
+fn push_entries<T: Serialize>(
+    entries: &mut Vec<AuditEntry>,
+    timestamp: u128,
+    operation: StoreOperation,
+    operator_id: &Option<String>,
+    entity_kind: EntityKind,
+    changes: &EntityChanges<T>,
+) -> anyhow::Result<()> {
+    for (entity_id, change) in changes.iter() {
+        entries.push(AuditEntry {
+            timestamp,
+            operation,
+            operator_id: operator_id.clone(),
+            entity_kind,
+            entity_id: entity_id.clone(),
+            before: change
+                .before
+                .as_ref()
+                .map(serde_json::to_value)
+                .transpose()?,
+            after: change
+                .after
+                .as_ref()
+                .map(serde_json::to_value)
+                .transpose()?,
+        });
+    }
+
+    Ok(())
+}
+
+impl ChangeObserver for AuditJournal {
+    fn on_commit(&self, operation: StoreOperation, changes: &ChangeSet) -> anyhow::Result<()> {
+        let timestamp = std::time::UNIX_EPOCH.elapsed()?.as_millis();
+        let operator_id = self
+            .operators
+            .session()?
+            .map(|operator_session| operator_session.staff_id);
+
+        let mut entries = vec![];
+        push_entries(
+            &mut entries,
+            timestamp,
+            operation,
+            &operator_id,
+            EntityKind::ActiveOccurrence,
+            &changes.active_occurrences,
+        )?;
+        push_entries(
+            &mut entries,
+            timestamp,
+            operation,
+            &operator_id,
+            EntityKind::ArchivedOccurrence,
+            &changes.archived_occurrences,
+        )?;
+        push_entries(
+            &mut entries,
+            timestamp,
+            operation,
+            &operator_id,
+            EntityKind::Occurrence,
+            &changes.occurrences,
+        )?;
+        push_entries(
+            &mut entries,
+            timestamp,
+            operation,
+            &operator_id,
+            EntityKind::Staff,
+            &changes.staff,
+        )?;
+        push_entries(
+            &mut entries,
+            timestamp,
+            operation,
+            &operator_id,
+            EntityKind::Team,
+            &changes.teams,
+        )?;
+        push_entries(
+            &mut entries,
+            timestamp,
+            operation,
+            &operator_id,
+            EntityKind::Vehicle,
+            &changes.vehicles,
+        )?;
+        push_entries(
+            &mut entries,
+            timestamp,
+            operation,
+            &operator_id,
+            EntityKind::CrewRequirement,
+            &changes.crew_requirements,
+        )?;
+        push_entries(
+            &mut entries,
+            timestamp,
+            operation,
+            &operator_id,
+            EntityKind::ShiftTemplate,
+            &changes.shift_templates,
+        )?;
+
+        // A commit is written with a single call so its entries stay together
+        let mut lines = String::new();
+        for entry in entries.iter() {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Audit journal is poisoned"))?;
+        file.write_all(lines.as_bytes())
+            .with_context(|| "Failed to write audit journal".to_string())?;
+        file.sync_data()
+            .with_context(|| "Failed to sync audit journal".to_string())?;
+
+        Ok(())
+    }
+}
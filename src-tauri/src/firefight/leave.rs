@@ -0,0 +1,212 @@
+use super::types::{DataStore, LeaveReason, LeaveStatus, Staff, StaffState};
+
+/// State an ongoing leave keeps the staff member in, sick leave wins over any other reason
+fn leave_state(staff: &Staff) -> Option<StaffState> {
+    let mut ongoing_reasons = staff
+        .leave_periods
+        .iter()
+        .filter(|leave_period| leave_period.status == LeaveStatus::Ongoing)
+        .map(|leave_period| leave_period.reason)
+        .peekable();
+    ongoing_reasons.peek()?;
+
+    if ongoing_reasons.any(|reason| reason == LeaveReason::Sick) {
+        Some(StaffState::SickLeave)
+    } else {
+        Some(StaffState::Unavailable)
+    }
+}
+
+/// Moves leave periods to their status at `time`, returns whether an ongoing one stopped.
+/// Edited periods are moved back as well, so ending a leave early takes effect on the next run.
+fn advance_leave_periods(staff: &mut Staff, time: u128) -> bool {
+    let mut leave_stopped = false;
+    for leave_period in staff.leave_periods.iter_mut() {
+        let next_status = if time >= leave_period.end_time {
+            LeaveStatus::Ended
+        } else if time >= leave_period.start_time {
+            LeaveStatus::Ongoing
+        } else {
+            LeaveStatus::Scheduled
+        };
+
+        if leave_period.status == LeaveStatus::Ongoing && next_status != LeaveStatus::Ongoing {
+            leave_stopped = true;
+        }
+        leave_period.status = next_status;
+    }
+
+    leave_stopped
+}
+
+/// Moves a staff member into the state of their ongoing leave. Like a shift change, only staff
+/// in Available or Unavailable are moved, dispatched staff follow once they are back. Staff only
+/// leave SickLeave when a leave of their own stopped, and stay Unavailable until the next shift.
+pub fn settle_staff_state(staff: &mut Staff, leave_stopped: bool) {
+    match leave_state(staff) {
+        Some(next_state) => {
+            let movable = matches!(staff.state, StaffState::Available | StaffState::Unavailable)
+                || (staff.state == StaffState::SickLeave && leave_stopped);
+            if movable {
+                staff.state = next_state;
+            }
+        }
+        None => {
+            if staff.state == StaffState::SickLeave && leave_stopped {
+                staff.state = StaffState::Unavailable;
+            }
+        }
+    }
+}
+
+/// Starts and ends the leave periods due at `time` and moves their staff accordingly
+pub fn apply_leave_transitions(data_store: &mut DataStore, time: u128) {
+    for staff in data_store.staff.values_mut() {
+        let leave_stopped = advance_leave_periods(staff, time);
+        settle_staff_state(staff, leave_stopped);
+    }
+}
+
+/// Whether `apply_leave_transitions` would change any of the given staff at `time`
+pub fn has_due_transitions(staff_list: &[Staff], time: u128) -> bool {
+    staff_list.iter().any(|staff| {
+        let mut next_staff = staff.clone();
+        let leave_stopped = advance_leave_periods(&mut next_staff, time);
+        settle_staff_state(&mut next_staff, leave_stopped);
+        next_staff != *staff
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::types::LeavePeriod;
+
+    fn leave_period(
+        leave_period_id: &str,
+        reason: LeaveReason,
+        start_time: u128,
+        end_time: u128,
+    ) -> LeavePeriod {
+        LeavePeriod {
+            internal_id: leave_period_id.to_string(),
+            reason,
+            start_time,
+            end_time,
+            status: LeaveStatus::Scheduled,
+        }
+    }
+
+    fn store(state: StaffState, leave_periods: Vec<LeavePeriod>) -> DataStore {
+        let mut staff = fixtures::staff("staff");
+        staff.state = state;
+        staff.leave_periods = leave_periods;
+        fixtures::data_store(vec![], vec![staff], vec![], vec![])
+    }
+
+    fn statuses(data_store: &DataStore) -> Vec<LeaveStatus> {
+        data_store.staff["staff"]
+            .leave_periods
+            .iter()
+            .map(|leave_period| leave_period.status)
+            .collect()
+    }
+
+    #[test]
+    fn leave_periods_start_and_end_on_time() {
+        let mut data_store = store(
+            StaffState::Available,
+            vec![leave_period("vacation", LeaveReason::Vacation, 100, 200)],
+        );
+
+        apply_leave_transitions(&mut data_store, 99);
+        assert_eq!(statuses(&data_store), vec![LeaveStatus::Scheduled]);
+        assert_eq!(data_store.staff["staff"].state, StaffState::Available);
+
+        apply_leave_transitions(&mut data_store, 100);
+        assert_eq!(statuses(&data_store), vec![LeaveStatus::Ongoing]);
+        assert_eq!(data_store.staff["staff"].state, StaffState::Unavailable);
+
+        // Staff stay Unavailable until the next shift puts them back
+        apply_leave_transitions(&mut data_store, 200);
+        assert_eq!(statuses(&data_store), vec![LeaveStatus::Ended]);
+        assert_eq!(data_store.staff["staff"].state, StaffState::Unavailable);
+    }
+
+    #[test]
+    fn sick_leave_wins_over_other_reasons() {
+        let mut data_store = store(
+            StaffState::Available,
+            vec![
+                leave_period("vacation", LeaveReason::Vacation, 100, 300),
+                leave_period("sick", LeaveReason::Sick, 150, 200),
+            ],
+        );
+
+        apply_leave_transitions(&mut data_store, 150);
+        assert_eq!(data_store.staff["staff"].state, StaffState::SickLeave);
+
+        // The vacation still goes on once the sick leave stopped
+        apply_leave_transitions(&mut data_store, 200);
+        assert_eq!(data_store.staff["staff"].state, StaffState::Unavailable);
+    }
+
+    #[test]
+    fn sick_leave_is_only_left_when_a_leave_stops() {
+        let mut data_store = store(StaffState::SickLeave, vec![]);
+
+        apply_leave_transitions(&mut data_store, 100);
+        assert_eq!(data_store.staff["staff"].state, StaffState::SickLeave);
+
+        data_store.staff.get_mut("staff").unwrap().leave_periods =
+            vec![leave_period("sick", LeaveReason::Sick, 100, 200)];
+        apply_leave_transitions(&mut data_store, 100);
+        apply_leave_transitions(&mut data_store, 200);
+        assert_eq!(data_store.staff["staff"].state, StaffState::Unavailable);
+    }
+
+    #[test]
+    fn dispatched_and_inactive_staff_are_not_moved() {
+        for state in [StaffState::Dispatched, StaffState::Inactive] {
+            let mut data_store = store(
+                state.clone(),
+                vec![leave_period("sick", LeaveReason::Sick, 100, 200)],
+            );
+
+            apply_leave_transitions(&mut data_store, 150);
+            assert_eq!(statuses(&data_store), vec![LeaveStatus::Ongoing]);
+            assert_eq!(data_store.staff["staff"].state, state);
+        }
+    }
+
+    #[test]
+    fn edited_leave_periods_move_back() {
+        let mut data_store = store(
+            StaffState::Available,
+            vec![leave_period("vacation", LeaveReason::Vacation, 100, 200)],
+        );
+        apply_leave_transitions(&mut data_store, 150);
+
+        data_store.staff.get_mut("staff").unwrap().leave_periods[0].start_time = 160;
+        apply_leave_transitions(&mut data_store, 155);
+        assert_eq!(statuses(&data_store), vec![LeaveStatus::Scheduled]);
+    }
+
+    #[test]
+    fn transitions_are_only_due_when_a_period_moves() {
+        let mut data_store = store(
+            StaffState::Available,
+            vec![leave_period("vacation", LeaveReason::Vacation, 100, 200)],
+        );
+        let staff_list =
+            |data_store: &DataStore| data_store.staff.values().cloned().collect::<Vec<Staff>>();
+
+        assert!(!has_due_transitions(&staff_list(&data_store), 99));
+        assert!(has_due_transitions(&staff_list(&data_store), 100));
+
+        apply_leave_transitions(&mut data_store, 100);
+        assert!(!has_due_transitions(&staff_list(&data_store), 150));
+        assert!(has_due_transitions(&staff_list(&data_store), 200));
+    }
+}
@@ -1,88 +1,155 @@
 use anyhow::Context;
-use serde_json::json;
-use std::{borrow::Borrow, collections::HashMap};
+use serde::de::DeserializeOwned;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use tauri::AppHandle;
 use tauri_plugin_store::StoreBuilder;
 
+use super::backup::{self, BackupKind};
+use super::migrations::{
+    self, ARCHIVE_KEY, COLLECTION_KEYS, CREW_REQUIREMENTS_KEY, SCHEMA_VERSION_KEY,
+    SHIFT_TEMPLATES_KEY,
+};
+use super::transaction::{ChangeObserver, ChangeSet};
 use super::types::{
-    ActiveOccurrence, DataStore, FirefightDataManager, Occurrence, Staff, StaffState, Team,
-    TeamState, Vehicle, VehicleState,
+    ActiveOccurrence, ArchiveQuery, ArchivedOccurrence, DataStore, FirefightDataManager,
+    Occurrence, ShiftTemplate, Staff, Team, Vehicle,
 };
 
-pub type LocalStore = tauri_plugin_store::Store<tauri::Wry>;
+type PersistedStore = tauri_plugin_store::Store<tauri::Wry>;
+
+/// Keeps the whole data store in memory, writes reach `data_store.dat` on the next flush
+pub struct LocalStore {
+    data: DataStore,
+    archive: HashMap<String, ArchivedOccurrence>,
+    dirty: bool,
+    observers: Vec<Arc<dyn ChangeObserver>>,
+    persisted_store: PersistedStore,
+    read_only: bool,
+}
 
-pub fn create_store(app_handle: AppHandle) -> LocalStore {
-    let store_path = app_handle
+pub fn get_store_path(app_handle: &AppHandle) -> PathBuf {
+    app_handle
         .path_resolver()
         .app_local_data_dir()
         .unwrap()
-        .join("data_store.dat");
-    let mut firefight_store = StoreBuilder::new(app_handle, store_path).build();
+        .join("data_store.dat")
+}
 
-    if let Err(load_error) = firefight_store.load() {
-        println!("Store not found {:?} - Using default.", load_error);
+pub fn create_store(app_handle: AppHandle) -> anyhow::Result<LocalStore> {
+    let store_path = get_store_path(&app_handle);
+    let mut firefight_store = StoreBuilder::new(app_handle.clone(), store_path.clone()).build();
+
+    if store_path.exists() {
+        firefight_store
+            .load()
+            .with_context(|| format!("Failed to load store {:?}", store_path))?;
+    } else {
+        println!("Store not found {:?} - Using default.", store_path);
+    }
+
+    migrate_persisted_store(&app_handle, &store_path, &mut firefight_store)?;
+    let data = read_data_store(&firefight_store)?;
+    let archive = read_collection(&firefight_store, ARCHIVE_KEY)?;
+
+    Ok(LocalStore {
+        data,
+        archive,
+        dirty: false,
+        observers: vec![],
+        persisted_store: firefight_store,
+        read_only: false,
+    })
+}
 
-        let default_store = DataStore::default();
-        if let Err(update_error) = firefight_store.insert(
-            String::from("active_occurrences"),
-            serde_json::json!(default_store.active_occurrences),
-        ) {
-            println!("Failed to update store: {}", update_error);
-        }
-        if let Err(update_error) = firefight_store.insert(
-            String::from("occurrences"),
-            serde_json::json!(default_store.occurrences),
-        ) {
-            println!("Failed to update store: {}", update_error);
-        }
-        if let Err(update_error) = firefight_store.insert(
-            String::from("staff"),
-            serde_json::json!(default_store.staff),
-        ) {
-            println!("Failed to update store: {}", update_error);
-        }
-        if let Err(update_error) = firefight_store.insert(
-            String::from("vehicles"),
-            serde_json::json!(default_store.vehicles),
-        ) {
-            println!("Failed to update store: {}", update_error);
-        }
+/// Empty store that never writes, keeps the app usable while a damaged store file is recovered
+pub fn create_recovery_store(app_handle: AppHandle) -> LocalStore {
+    let store_path = get_store_path(&app_handle);
 
-        if let Err(save_error) = firefight_store.save() {
-            println!("Failed to save store: {}", save_error);
+    LocalStore {
+        data: DataStore::default(),
+        archive: HashMap::new(),
+        dirty: false,
+        observers: vec![],
+        persisted_store: StoreBuilder::new(app_handle, store_path).build(),
+        read_only: true,
+    }
+}
+
+fn migrate_persisted_store(
+    app_handle: &AppHandle,
+    store_path: &Path,
+    persisted_store: &mut PersistedStore,
+) -> anyhow::Result<()> {
+    let mut entries = serde_json::Map::new();
+    for key in COLLECTION_KEYS.into_iter().chain([
+        ARCHIVE_KEY,
+        CREW_REQUIREMENTS_KEY,
+        SHIFT_TEMPLATES_KEY,
+        SCHEMA_VERSION_KEY,
+    ]) {
+        if let Some(value) = persisted_store.get(key) {
+            entries.insert(String::from(key), value.clone());
         }
     }
 
-    firefight_store
-}
+    if store_path.exists()
+        && migrations::schema_version(&entries)? < migrations::current_schema_version()
+    {
+        backup::backup_file(app_handle, store_path, BackupKind::PreMigration)?;
+    }
 
-pub fn get_data_store(state: &mut LocalStore) -> DataStore {
-    let default_teams = serde_json::json!({});
-    let teams = state.get("teams").unwrap_or_else(|| &default_teams);
+    if !migrations::migrate_store(&mut entries)? {
+        return Ok(());
+    }
 
-    DataStore {
-        active_occurrences: serde_json::from_value(
-            state.get("active_occurrences").unwrap().clone(),
-        )
-        .unwrap(),
-        occurrences: serde_json::from_value(state.get("occurrences").unwrap().clone()).unwrap(),
-        staff: serde_json::from_value(state.get("staff").unwrap().clone()).unwrap(),
-        teams: serde_json::from_value(teams.clone()).unwrap(),
-        vehicles: serde_json::from_value(state.get("vehicles").unwrap().clone()).unwrap(),
+    // Nothing is saved unless every step succeeded
+    for (key, value) in entries {
+        persisted_store
+            .insert(key.clone(), value)
+            .with_context(|| format!("Failed to update {}", key))?;
     }
+    persisted_store
+        .save()
+        .with_context(|| "Failed to save migrated store".to_string())?;
+
+    Ok(())
+}
+
+fn read_collection<T: DeserializeOwned>(
+    persisted_store: &PersistedStore,
+    key: &str,
+) -> anyhow::Result<HashMap<String, T>> {
+    let collection_value = persisted_store
+        .get(key)
+        .with_context(|| format!("Unable to read {} from store", key))?
+        .clone();
+
+    serde_json::from_value(collection_value)
+        .with_context(|| format!("Failed to deserialize {}", key))
+}
+
+fn read_data_store(persisted_store: &PersistedStore) -> anyhow::Result<DataStore> {
+    Ok(DataStore {
+        active_occurrences: read_collection(persisted_store, "active_occurrences")?,
+        occurrences: read_collection(persisted_store, "occurrences")?,
+        staff: read_collection(persisted_store, "staff")?,
+        teams: read_collection(persisted_store, "teams")?,
+        vehicles: read_collection(persisted_store, "vehicles")?,
+        crew_requirements: read_collection(persisted_store, CREW_REQUIREMENTS_KEY)?,
+        shift_templates: read_collection(persisted_store, SHIFT_TEMPLATES_KEY)?,
+    })
 }
 
 impl FirefightDataManager for LocalStore {
     fn get_active_occurrence(&self, occurrence_id: &String) -> anyhow::Result<ActiveOccurrence> {
-        let active_occurrences_value = self
-            .get("active_occurrences")
-            .with_context(|| "Unable to read active occurrences from store".to_string())?
-            .clone();
-        let active_occurrence_store =
-            serde_json::from_value::<HashMap<String, ActiveOccurrence>>(active_occurrences_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-        let found_value = active_occurrence_store
+        let found_value = self
+            .data
+            .active_occurrences
             .get(occurrence_id)
             .cloned()
             .with_context(|| format!("No active occurrence found with id: {}", occurrence_id))?;
@@ -93,15 +160,9 @@ impl FirefightDataManager for LocalStore {
         &self,
         staff_id: &String,
     ) -> anyhow::Result<ActiveOccurrence> {
-        let active_occurrences_value = self
-            .get("active_occurrences")
-            .with_context(|| "Unable to read active occurrences from store".to_string())?
-            .clone();
-        let active_occurrence_store =
-            serde_json::from_value::<HashMap<String, ActiveOccurrence>>(active_occurrences_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-        let found_value = active_occurrence_store
+        let found_value = self
+            .data
+            .active_occurrences
             .values()
             .find(|active_occurrence| active_occurrence.staff_ids.contains(staff_id))
             .cloned()
@@ -114,15 +175,9 @@ impl FirefightDataManager for LocalStore {
         &self,
         vehicle_id: &String,
     ) -> anyhow::Result<ActiveOccurrence> {
-        let active_occurrences_value = self
-            .get("active_occurrences")
-            .with_context(|| "Unable to read active occurrences from store".to_string())?
-            .clone();
-        let active_occurrence_store =
-            serde_json::from_value::<HashMap<String, ActiveOccurrence>>(active_occurrences_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-        let found_value = active_occurrence_store
+        let found_value = self
+            .data
+            .active_occurrences
             .values()
             .find(|active_occurrence| active_occurrence.vehicle_ids.contains(vehicle_id))
             .cloned()
@@ -134,30 +189,16 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_active_occurrence_list(&self) -> anyhow::Result<Vec<ActiveOccurrence>> {
-        let active_occurrences_value = self
-            .get("active_occurrences")
-            .with_context(|| "Unable to read active occurrences from store".to_string())?
-            .clone();
-        let active_occurrence_store =
-            serde_json::from_value::<HashMap<String, ActiveOccurrence>>(active_occurrences_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-        Ok(active_occurrence_store.values().cloned().collect())
+        Ok(self.data.active_occurrences.values().cloned().collect())
     }
 
     fn get_active_occurrence_list_by_occurrence(
         &self,
         occurrence_id: &String,
     ) -> anyhow::Result<Vec<ActiveOccurrence>> {
-        let active_occurrences_value = self
-            .get("active_occurrences")
-            .with_context(|| "Unable to read active occurrences from store".to_string())?
-            .clone();
-        let active_occurrence_store =
-            serde_json::from_value::<HashMap<String, ActiveOccurrence>>(active_occurrences_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-        let found_values = active_occurrence_store
+        let found_values = self
+            .data
+            .active_occurrences
             .values()
             .filter(|active_occurrence| active_occurrence.occurrence_id == *occurrence_id)
             .cloned()
@@ -167,15 +208,9 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_occurrence(&self, occurrence_id: &String) -> anyhow::Result<Occurrence> {
-        let occurrences_value = self
-            .get("occurrences")
-            .with_context(|| "Unable to read occurrences from store".to_string())?
-            .clone();
-        let occurrence_store =
-            serde_json::from_value::<HashMap<String, Occurrence>>(occurrences_value)
-                .with_context(|| "Failed to deserialize occurrences".to_string())?;
-
-        let found_value = occurrence_store
+        let found_value = self
+            .data
+            .occurrences
             .get(occurrence_id)
             .cloned()
             .with_context(|| format!("No occurrence found with id: {}", occurrence_id))?;
@@ -183,15 +218,9 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_occurrence_name(&self, occurrence_id: &String) -> anyhow::Result<String> {
-        let occurrences_value = self
-            .get("occurrences")
-            .with_context(|| "Unable to read occurrences from store".to_string())?
-            .clone();
-        let occurrence_store =
-            serde_json::from_value::<HashMap<String, Occurrence>>(occurrences_value)
-                .with_context(|| "Failed to deserialize occurrences".to_string())?;
-
-        let found_value = occurrence_store
+        let found_value = self
+            .data
+            .occurrences
             .get(occurrence_id)
             .with_context(|| format!("No occurrence found with id: {}", occurrence_id))?
             .name
@@ -200,26 +229,13 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_occurrence_list(&self) -> anyhow::Result<Vec<Occurrence>> {
-        let occurrences_value = self
-            .get("occurrences")
-            .with_context(|| "Unable to read occurrences from store".to_string())?
-            .clone();
-        let occurrence_store =
-            serde_json::from_value::<HashMap<String, Occurrence>>(occurrences_value)
-                .with_context(|| "Failed to deserialize occurrences".to_string())?;
-
-        Ok(occurrence_store.values().cloned().collect())
+        Ok(self.data.occurrences.values().cloned().collect())
     }
 
     fn get_staff(&self, staff_id: &String) -> anyhow::Result<Staff> {
-        let staff_value = self
-            .get("staff")
-            .with_context(|| "Unable to read staff from store".to_string())?
-            .clone();
-        let staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-            .with_context(|| "Failed to deserialize staff".to_string())?;
-
-        let found_value = staff_store
+        let found_value = self
+            .data
+            .staff
             .get(staff_id)
             .cloned()
             .with_context(|| format!("No staff found with id: {}", staff_id))?;
@@ -227,14 +243,9 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_staff_label(&self, staff_id: &String) -> anyhow::Result<String> {
-        let staff_value = self
-            .get("staff")
-            .with_context(|| "Unable to read staff from store".to_string())?
-            .clone();
-        let staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-            .with_context(|| "Failed to deserialize staff".to_string())?;
-
-        let found_value = staff_store
+        let found_value = self
+            .data
+            .staff
             .get(staff_id)
             .with_context(|| format!("No staff found with id: {}", staff_id))
             .map(|staff| staff.label.clone())?;
@@ -242,25 +253,13 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_staff_list(&self) -> anyhow::Result<Vec<Staff>> {
-        let staff_value = self
-            .get("staff")
-            .with_context(|| "Unable to read staff from store".to_string())?
-            .clone();
-        let staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-            .with_context(|| "Failed to deserialize staff".to_string())?;
-
-        Ok(staff_store.values().cloned().collect())
+        Ok(self.data.staff.values().cloned().collect())
     }
 
     fn get_team(&self, team_id: &String) -> anyhow::Result<Team> {
-        let teams_value = self
-            .get("teams")
-            .with_context(|| "Unable to read teams from store".to_string())?
-            .clone();
-        let teams_store = serde_json::from_value::<HashMap<String, Team>>(teams_value)
-            .with_context(|| "Failed to deserialize teams".to_string())?;
-
-        let found_value = teams_store
+        let found_value = self
+            .data
+            .teams
             .get(team_id)
             .cloned()
             .with_context(|| format!("No team found with id: {}", team_id))?;
@@ -268,14 +267,9 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_team_label(&self, team_id: &String) -> anyhow::Result<String> {
-        let teams_value = self
-            .get("teams")
-            .with_context(|| "Unable to read teams from store".to_string())?
-            .clone();
-        let teams_store = serde_json::from_value::<HashMap<String, Team>>(teams_value)
-            .with_context(|| "Failed to deserialize teams".to_string())?;
-
-        let found_value = teams_store
+        let found_value = self
+            .data
+            .teams
             .get(team_id)
             .with_context(|| format!("No teams found with id: {}", team_id))
             .map(|team| team.label.clone())?;
@@ -283,36 +277,17 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_team_list(&self) -> anyhow::Result<Vec<Team>> {
-        let teams_value = self
-            .get("teams")
-            .with_context(|| "Unable to read teams from store".to_string())?
-            .clone();
-        let teams_store = serde_json::from_value::<HashMap<String, Team>>(teams_value)
-            .with_context(|| "Failed to deserialize teams".to_string())?;
-
-        Ok(teams_store.values().cloned().collect())
+        Ok(self.data.teams.values().cloned().collect())
     }
 
     fn get_vehicle_list(&self) -> anyhow::Result<Vec<Vehicle>> {
-        let vehicles_value = self
-            .get("vehicles")
-            .with_context(|| "Unable to read vehicles from store".to_string())?
-            .clone();
-        let vehicle_store = serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-            .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-        Ok(vehicle_store.values().cloned().collect())
+        Ok(self.data.vehicles.values().cloned().collect())
     }
 
     fn get_vehicle(&self, vehicle_id: &String) -> anyhow::Result<Vehicle> {
-        let vehicles_value = self
-            .get("vehicles")
-            .with_context(|| "Unable to read vehicles from store".to_string())?
-            .clone();
-        let vehicle_store = serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-            .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-        let found_value = vehicle_store
+        let found_value = self
+            .data
+            .vehicles
             .get(vehicle_id)
             .cloned()
             .with_context(|| format!("No vehicle found with id: {}", vehicle_id))?;
@@ -320,14 +295,9 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_vehicle_capacity(&self, vehicle_id: &String) -> anyhow::Result<Option<u8>> {
-        let vehicles_value = self
-            .get("vehicles")
-            .with_context(|| "Unable to read vehicles from store".to_string())?
-            .clone();
-        let vehicle_store = serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-            .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-        let found_value = vehicle_store
+        let found_value = self
+            .data
+            .vehicles
             .get(vehicle_id)
             .with_context(|| format!("No vehicle found with id: {}", vehicle_id))
             .map(|vehicle| vehicle.capacity)?;
@@ -335,745 +305,139 @@ impl FirefightDataManager for LocalStore {
     }
 
     fn get_vehicle_label(&self, vehicle_id: &String) -> anyhow::Result<String> {
-        let vehicles_value = self
-            .get("vehicles")
-            .with_context(|| "Unable to read vehicles from store".to_string())?
-            .clone();
-        let vehicle_store = serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-            .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-        let found_value = vehicle_store
+        let found_value = self
+            .data
+            .vehicles
             .get(vehicle_id)
             .with_context(|| format!("No vehicle found with id: {}", vehicle_id))
             .map(|vehicle| vehicle.label.clone())?;
         Ok(found_value)
     }
 
-    fn create_active_occurrence(
-        &mut self,
-        mut occurrence: ActiveOccurrence,
-    ) -> anyhow::Result<String> {
-        let active_occurrences_value = self
-            .get("active_occurrences")
-            .with_context(|| "Unable to read active occurrences from store".to_string())?
-            .clone();
-        let mut active_occurrence_store =
-            serde_json::from_value::<HashMap<String, ActiveOccurrence>>(active_occurrences_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-        // Validate other active ocurrences
-        active_occurrence_store
-            .iter_mut()
-            .for_each(|(_, active_occurrence)| {
-                active_occurrence
-                    .staff_ids
-                    .iter()
-                    .any(|staff_id| occurrence.staff_ids.contains(staff_id))
-                    .then(|| {
-                        active_occurrence
-                            .staff_ids
-                            .retain(|staff_id| !occurrence.staff_ids.contains(staff_id));
-                    });
-                active_occurrence
-                    .vehicle_ids
-                    .iter()
-                    .any(|vehicle_id| occurrence.vehicle_ids.contains(vehicle_id))
-                    .then(|| {
-                        active_occurrence
-                            .vehicle_ids
-                            .retain(|vehicle_id| !occurrence.vehicle_ids.contains(vehicle_id));
-                    });
-            });
-
-        // Create active ocurrence and update store
-        let active_occurrence_id = uuid::Uuid::new_v4().to_string();
-        occurrence.internal_id = active_occurrence_id.clone();
-        active_occurrence_store.insert(active_occurrence_id.clone(), occurrence.clone());
-
-        self.insert(
-            String::from("active_occurrences"),
-            serde_json::json!(active_occurrence_store),
-        )
-        .with_context(|| {
-            format!(
-                "Failed to create active occurrence {} with value {:?}",
-                &active_occurrence_id,
-                active_occurrence_store.get(&active_occurrence_id)
-            )
-        })?;
-
-        // Update vehicles
-        let vehicles_value = self
-            .get("vehicles")
-            .with_context(|| "Unable to read vehicles from store".to_string())?
-            .clone();
-        let mut vehicle_store = serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-            .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-        occurrence.vehicle_ids.iter().for_each(|vehicle_id| {
-            let vehicle = vehicle_store
-                .get_mut(vehicle_id)
-                .with_context(|| format!("Failed to get vehicle with id: {}", vehicle_id))
-                .unwrap();
-            vehicle.state = VehicleState::Dispatched;
-        });
-
-        self.insert(String::from("vehicles"), serde_json::json!(vehicle_store))
-            .with_context(|| format!("Failed to update vehicles with value {:?}", vehicle_store))?;
-
-        // Update staff
-        let staff_value = self
-            .get("staff")
-            .with_context(|| "Unable to read staff from store".to_string())?
-            .clone();
-        let mut staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-            .with_context(|| "Failed to deserialize staff".to_string())?;
-
-        occurrence.staff_ids.iter().for_each(|staff_id| {
-            let staff = staff_store
-                .get_mut(staff_id)
-                .with_context(|| format!("Failed to get staff with id: {}", staff_id))
-                .unwrap();
-            staff.state = StaffState::Dispatched;
-        });
-
-        self.insert(String::from("staff"), serde_json::json!(staff_store))
-            .with_context(|| format!("Failed to update staff with value {:?}", staff_store))?;
-
-        self.save()
-            .with_context(|| "Failed to save store while creating active occurrence".to_string())?;
-        Ok(active_occurrence_id)
+    fn get_shift_template_list(&self) -> anyhow::Result<Vec<ShiftTemplate>> {
+        Ok(self.data.shift_templates.values().cloned().collect())
     }
 
-    fn create_occurrence(&mut self, mut occurrence: Occurrence) -> anyhow::Result<String> {
-        let occurrences_value = self
-            .get("occurrences")
-            .with_context(|| "Unable to read occurrences from store".to_string())?
-            .clone();
-        let mut occurrence_store =
-            serde_json::from_value::<HashMap<String, Occurrence>>(occurrences_value)
-                .with_context(|| "Failed to deserialize occurrences".to_string())?;
-
-        let occurrence_id = uuid::Uuid::new_v4().to_string();
-        occurrence.internal_id = occurrence_id.clone();
-        occurrence_store.insert(occurrence_id.clone(), occurrence);
-
-        self.insert(
-            String::from("occurrences"),
-            serde_json::json!(occurrence_store),
-        )
-        .with_context(|| {
-            format!(
-                "Failed to create occurrence {} with value {:?}",
-                &occurrence_id,
-                occurrence_store.get(&occurrence_id)
-            )
-        })?;
-        self.save()
-            .with_context(|| "Failed to save store while creating occurrence".to_string())?;
-
-        Ok(occurrence_id)
+    fn get_data_store(&self) -> anyhow::Result<DataStore> {
+        Ok(self.data.clone())
     }
 
-    fn create_staff(&mut self, mut staff: Staff) -> anyhow::Result<String> {
-        let staff_value = self
-            .get("staff")
-            .with_context(|| "Unable to read staff from store".to_string())?
-            .clone();
-        let mut staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-            .with_context(|| "Failed to deserialize staff".to_string())?;
-
-        let staff_id = uuid::Uuid::new_v4().to_string();
-        staff.internal_id = staff_id.clone();
-        staff_store.insert(staff_id.clone(), staff);
-
-        self.insert(String::from("staff"), serde_json::json!(staff_store))
-            .with_context(|| {
-                format!(
-                    "Failed to create staff {} with value {:?}",
-                    &staff_id,
-                    staff_store.get(&staff_id)
-                )
-            })?;
-        self.save()
-            .with_context(|| "Failed to save store while creating staff".to_string())?;
-
-        Ok(staff_id)
-    }
-
-    fn create_team(&mut self, mut team: Team) -> anyhow::Result<String> {
-        let default_teams = serde_json::json!({});
-        let teams_value = self
-            .get("teams")
-            .with_context(|| "Unable to read teams from store".to_string())
-            .unwrap_or_else(|_| &default_teams)
-            .clone();
-        let mut teams_store = serde_json::from_value::<HashMap<String, Team>>(teams_value)
-            .with_context(|| "Failed to deserialize teams".to_string())?;
-
-        let team_id = uuid::Uuid::new_v4().to_string();
-        team.internal_id = team_id.clone();
-        teams_store.insert(team_id.clone(), team);
-
-        self.insert(String::from("teams"), serde_json::json!(teams_store))
-            .with_context(|| {
-                format!(
-                    "Failed to create teams {} with value {:?}",
-                    &team_id,
-                    teams_store.get(&team_id)
-                )
-            })?;
-        self.save()
-            .with_context(|| "Failed to save store while creating team".to_string())?;
-
-        Ok(team_id)
-    }
-
-    fn create_vehicle(&mut self, mut vehicle: Vehicle) -> anyhow::Result<String> {
-        let vehicles_value = self
-            .get("vehicles")
-            .with_context(|| "Unable to read vehicles from store".to_string())?
-            .clone();
-        let mut vehicle_store = serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-            .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-        let vehicle_id = uuid::Uuid::new_v4().to_string();
-        vehicle.internal_id = vehicle_id.clone();
-        vehicle_store.insert(vehicle_id.clone(), vehicle);
-
-        self.insert(String::from("vehicles"), serde_json::json!(vehicle_store))
-            .with_context(|| {
-                format!(
-                    "Failed to create vehicle {} with value {:?}",
-                    &vehicle_id,
-                    vehicle_store.get(&vehicle_id).unwrap()
-                )
-            })?;
-        self.save()
-            .with_context(|| "Failed to save store while creating vehicle".to_string())?;
-
-        Ok(vehicle_id)
-    }
-
-    fn update_active_occurrence(
-        &mut self,
-        active_occurrence_id: &String,
-        active_occurrence: ActiveOccurrence,
-    ) -> anyhow::Result<Option<ActiveOccurrence>> {
-        let active_occurrences_value = self
-            .get("active_occurrences")
-            .with_context(|| "Unable to read active occurrences from store".to_string())?
-            .clone();
-        let mut active_occurrence_store =
-            serde_json::from_value::<HashMap<String, ActiveOccurrence>>(active_occurrences_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-        let previous_active_occurrence = active_occurrence_store
-            .insert(active_occurrence_id.clone(), active_occurrence.clone())
-            .unwrap();
-        self.insert(
-            String::from("active_occurrences"),
-            serde_json::json!(active_occurrence_store),
-        )
-        .with_context(|| {
-            format!(
-                "Failed to update active occurrence {} with value {:?}",
-                active_occurrence_id,
-                active_occurrence_store.get(active_occurrence_id).unwrap()
-            )
-        })?;
-
-        let removed_vehicles = previous_active_occurrence
-            .vehicle_ids
-            .iter()
-            .filter(|vehicle_id| !active_occurrence.vehicle_ids.contains(vehicle_id))
-            .collect::<Vec<&String>>();
-        let removed_staff = previous_active_occurrence
-            .staff_ids
-            .iter()
-            .filter(|staff_id| !active_occurrence.staff_ids.contains(staff_id))
-            .collect::<Vec<&String>>();
-
-        // Update vehicles
-        let vehicles_value = self
-            .get("vehicles")
-            .with_context(|| "Unable to read vehicles from store".to_string())?
-            .clone();
-        let mut vehicle_store = serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-            .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-        removed_vehicles.iter().for_each(|vehicle_id| {
-            let vehicle = vehicle_store
-                .get_mut(*vehicle_id)
-                .with_context(|| format!("Failed to get vehicle with id: {}", vehicle_id))
-                .unwrap();
-            vehicle.state = VehicleState::Available;
-        });
-
-        active_occurrence.vehicle_ids.iter().for_each(|vehicle_id| {
-            let vehicle = vehicle_store
-                .get_mut(vehicle_id)
-                .with_context(|| format!("Failed to get vehicle with id: {}", vehicle_id))
-                .unwrap();
-            vehicle.state = VehicleState::Dispatched;
-        });
-
-        self.insert(String::from("vehicles"), serde_json::json!(vehicle_store))
-            .with_context(|| format!("Failed to update vehicles with value {:?}", vehicle_store))?;
-
-        // Update staff
-        let staff_value = self
-            .get("staff")
-            .with_context(|| "Unable to read staff from store".to_string())?
-            .clone();
-        let mut staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-            .with_context(|| "Failed to deserialize staff".to_string())?;
-
-        removed_staff.iter().for_each(|staff_id| {
-            let staff = staff_store
-                .get_mut(*staff_id)
-                .with_context(|| format!("Failed to get staff with id: {}", staff_id))
-                .unwrap();
-            staff.state = StaffState::Available;
-        });
-
-        active_occurrence.staff_ids.iter().for_each(|staff_id| {
-            let staff = staff_store
-                .get_mut(staff_id)
-                .with_context(|| format!("Failed to get staff with id: {}", staff_id))
-                .unwrap();
-            staff.state = StaffState::Dispatched;
-        });
-        self.insert(String::from("staff"), serde_json::json!(staff_store))
-            .with_context(|| format!("Failed to update staff with value {:?}", staff_store))?;
-
-        self.save()
-            .with_context(|| "Failed to save store while updating active occurrence".to_string())?;
-
-        Ok(Some(previous_active_occurrence))
-    }
-
-    fn update_occurrence(
-        &mut self,
-        occurrence_id: &String,
-        occurrence: Occurrence,
-    ) -> anyhow::Result<Option<Occurrence>> {
-        let occurrence_value = self
-            .get("occurrences")
-            .with_context(|| "Unable to read occurrences from store".to_string())?
-            .clone();
-        let mut occurrence_store =
-            serde_json::from_value::<HashMap<String, Occurrence>>(occurrence_value)
-                .with_context(|| "Failed to deserialize occurrences".to_string())?;
-
-        let prev_ocurrence = occurrence_store.insert(occurrence_id.clone(), occurrence);
-
-        self.insert(
-            String::from("occurrences"),
-            serde_json::json!(occurrence_store),
-        )
-        .with_context(|| {
-            format!(
-                "Failed to update occurrence {} with value {:?}",
-                occurrence_id,
-                occurrence_store.get(occurrence_id).unwrap()
-            )
-        })?;
-        self.save()
-            .with_context(|| "Failed to save store while updating occurrence".to_string())?;
-
-        Ok(prev_ocurrence)
-    }
-
-    fn update_staff(&mut self, staff_id: &String, staff: Staff) -> anyhow::Result<Option<Staff>> {
-        let staff_value = self
-            .get("staff")
-            .with_context(|| "Unable to read staff from store".to_string())?
-            .clone();
-        let mut staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-            .with_context(|| "Failed to deserialize staff".to_string())?;
-
-        let prev_staff = staff_store.insert(staff_id.clone(), staff);
-
-        self.insert(String::from("staff"), serde_json::json!(staff_store))
+    fn get_archived_occurrence(
+        &self,
+        archived_occurrence_id: &String,
+    ) -> anyhow::Result<ArchivedOccurrence> {
+        let found_value = self
+            .archive
+            .get(archived_occurrence_id)
+            .cloned()
             .with_context(|| {
                 format!(
-                    "Failed to update staff {} with value {:?}",
-                    staff_id,
-                    staff_store.get(staff_id).unwrap()
+                    "No archived occurrence found with id: {}",
+                    archived_occurrence_id
                 )
             })?;
-        self.save()
-            .with_context(|| "Failed to save store while updating staff".to_string())?;
-
-        Ok(prev_staff)
+        Ok(found_value)
     }
 
-    fn update_team(&mut self, team_id: &String, team: Team) -> anyhow::Result<Option<Team>> {
-        let teams_value = self
-            .get("teams")
-            .with_context(|| "Unable to read teams from store".to_string())?
-            .clone();
-        let mut teams_store = serde_json::from_value::<HashMap<String, Team>>(teams_value)
-            .with_context(|| "Failed to deserialize teams".to_string())?;
-
-        let prev_staff = teams_store.insert(team_id.clone(), team);
-
-        self.insert(String::from("teams"), serde_json::json!(teams_store))
-            .with_context(|| {
-                format!(
-                    "Failed to update teams {} with value {:?}",
-                    team_id,
-                    teams_store.get(team_id).unwrap()
-                )
-            })?;
-        self.save()
-            .with_context(|| "Failed to save store while updating team".to_string())?;
+    fn get_archived_occurrence_list(
+        &self,
+        query: &ArchiveQuery,
+    ) -> anyhow::Result<Vec<ArchivedOccurrence>> {
+        let mut found_values = self
+            .archive
+            .values()
+            .filter(|archived_occurrence| query.matches(archived_occurrence))
+            .cloned()
+            .collect::<Vec<ArchivedOccurrence>>();
+        found_values.sort_by_key(|archived_occurrence| Reverse(archived_occurrence.end_time));
 
-        Ok(prev_staff)
+        Ok(found_values)
     }
 
-    fn update_vehicle(
-        &mut self,
-        vehicle_id: &String,
-        vehicle: Vehicle,
-    ) -> anyhow::Result<Option<Vehicle>> {
-        let vehicles_value = self
-            .get("vehicles")
-            .with_context(|| "Unable to read vehicles from store".to_string())?
-            .clone();
-        let mut vehicle_store = serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-            .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-        let previous_vehicle = vehicle_store.insert(vehicle_id.clone(), vehicle);
-
-        self.insert(String::from("vehicles"), serde_json::json!(vehicle_store))
-            .with_context(|| {
-                format!(
-                    "Failed to update vehicle {} with value {:?}",
-                    vehicle_id,
-                    vehicle_store.get(vehicle_id)
-                )
-            })?;
-        self.save()
-            .with_context(|| "Failed to save store while updating vehicle".to_string())?;
-
-        Ok(previous_vehicle)
+    fn add_observer(&mut self, observer: Arc<dyn ChangeObserver>) {
+        self.observers.push(observer);
     }
 
-    fn delete_active_occurrence(&mut self, active_occurrence_id: &String) -> anyhow::Result<()> {
-        let active_occurrence_value = self
-            .get("active_occurrences")
-            .with_context(|| "Unable to read active occurrences from store".to_string())?
-            .clone();
-        let mut active_occurrence_store =
-            serde_json::from_value::<HashMap<String, ActiveOccurrence>>(active_occurrence_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-        if let Some(active_ocurrence) = active_occurrence_store.remove(active_occurrence_id) {
-            self.insert(
-                String::from("active_occurrences"),
-                serde_json::json!(active_occurrence_store),
-            )
-            .with_context(|| {
-                format!(
-                    "Failed to delete active occurrence {}",
-                    active_occurrence_id
-                )
-            })?;
-
-            // Update vehicles
-            let vehicles_value = self
-                .get("vehicles")
-                .with_context(|| "Unable to read vehicles from store".to_string())?
-                .clone();
-            let mut vehicle_store =
-                serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-                    .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-            active_ocurrence.vehicle_ids.iter().for_each(|vehicle_id| {
-                if let Some(vehicle) = vehicle_store.get_mut(vehicle_id) {
-                    vehicle.state = VehicleState::Available;
-                }
-            });
-
-            self.insert(String::from("vehicles"), serde_json::json!(vehicle_store))
-                .with_context(|| {
-                    format!("Failed to update vehicles with value {:?}", vehicle_store)
-                })?;
-
-            // Update staff
-            let staff_value = self
-                .get("staff")
-                .with_context(|| "Unable to read staff from store".to_string())?
-                .clone();
-            let mut staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-                .with_context(|| "Failed to deserialize staff".to_string())?;
-
-            active_ocurrence.staff_ids.iter().for_each(|staff_id| {
-                if let Some(staff) = staff_store.get_mut(staff_id) {
-                    staff.state = StaffState::Available;
-                }
-            });
-
-            self.insert(String::from("staff"), serde_json::json!(staff_store))
-                .with_context(|| format!("Failed to update staff with value {:?}", staff_store))?;
-
-            self.save().with_context(|| {
-                "Failed to save store while deleting active occurrence".to_string()
-            })?;
-        }
-
-        Ok(())
+    fn observers(&self) -> &[Arc<dyn ChangeObserver>] {
+        &self.observers
     }
 
-    fn delete_occurrence(&mut self, occurrence_id: &String) -> anyhow::Result<()> {
-        let occurrence_value = self
-            .get("occurrences")
-            .with_context(|| "Unable to read occurrences from store".to_string())?
-            .clone();
-        let mut occurrence_store =
-            serde_json::from_value::<HashMap<String, Occurrence>>(occurrence_value)
-                .with_context(|| "Failed to deserialize occurrences".to_string())?;
-
-        occurrence_store.remove(occurrence_id);
-
-        self.insert(
-            String::from("occurrences"),
-            serde_json::json!(occurrence_store),
-        )
-        .with_context(|| format!("Failed to delete occurrence {}", occurrence_id))?;
-        self.save()
-            .with_context(|| "Failed to save store while deleting occurrence".to_string())?;
+    // The live data is in memory, so the working copy is compared with it without a second copy
+    fn stage_changes(
+        &self,
+        stage: &mut dyn FnMut(&mut DataStore) -> anyhow::Result<()>,
+    ) -> anyhow::Result<ChangeSet> {
+        let mut staged_store = self.data.clone();
+        stage(&mut staged_store)?;
 
-        Ok(())
+        Ok(ChangeSet::diff(&self.data, &staged_store))
     }
 
-    fn delete_staff(&mut self, staff_id: &String) -> anyhow::Result<()> {
-        let staff_value = self
-            .get("staff")
-            .with_context(|| "Unable to read staff from store".to_string())?
-            .clone();
-        let mut staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-            .with_context(|| "Failed to deserialize staff".to_string())?;
-
-        let removed_entry = staff_store.remove(staff_id);
-        self.insert(String::from("staff"), serde_json::json!(staff_store))
-            .with_context(|| format!("Failed to delete staff {}", staff_id))?;
-
-        if let Some(remove_staff) = removed_entry {
-            if remove_staff.state == StaffState::Dispatched {
-                // Update active occurrence
-                let active_occurrence_value = self
-                    .get("active_occurrences")
-                    .with_context(|| "Unable to read active occurrences from store".to_string())?
-                    .clone();
-                let mut active_occurrence_store = serde_json::from_value::<
-                    HashMap<String, ActiveOccurrence>,
-                >(active_occurrence_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-                if let Some(active_occurrence) = active_occurrence_store
-                    .values_mut()
-                    .find(|active_occurrence| active_occurrence.staff_ids.contains(&staff_id))
-                {
-                    let staff_idx = active_occurrence
-                        .staff_ids
-                        .iter()
-                        .position(|id| id == staff_id);
-                    active_occurrence.staff_ids.swap_remove(staff_idx.unwrap());
-                    active_occurrence
-                        .vehicle_assignment_map
-                        .iter_mut()
-                        .for_each(|(_, vehicle_staff_ids)| {
-                            let vehicle_staff_idx =
-                                vehicle_staff_ids.iter().position(|id| id == staff_id);
-                            if let Some(vehicle_staff_idx) = vehicle_staff_idx {
-                                vehicle_staff_ids.swap_remove(vehicle_staff_idx);
-                            }
-                        });
-                }
-
-                self.insert(
-                    String::from("active_occurrences"),
-                    serde_json::json!(active_occurrence_store),
-                )
-                .with_context(|| {
-                    format!(
-                        "Failed to update active occurrences with value {:?}",
-                        active_occurrence_store
-                    )
-                })?;
-            }
-        }
-
-        self.save()
-            .with_context(|| "Failed to save store while deleting staff".to_string())?;
-
-        Ok(())
+    fn check_conflicts(&self, changes: &ChangeSet) -> anyhow::Result<()> {
+        changes.check_conflicts(&self.data)
     }
 
-    fn delete_team(&mut self, team_id: &String) -> anyhow::Result<()> {
-        let teams_value = self
-            .get("teams")
-            .with_context(|| "Unable to read teams from store".to_string())?
-            .clone();
-        let mut teams_store = serde_json::from_value::<HashMap<String, Team>>(teams_value)
-            .with_context(|| "Failed to deserialize teams".to_string())?;
-
-        let removed_entry = teams_store.remove(team_id);
-        self.insert(String::from("teams"), serde_json::json!(teams_store))
-            .with_context(|| format!("Failed to delete team {}", team_id))?;
-
-        if let Some(removed_team) = removed_entry {
-            if removed_team.state == TeamState::Dispatched {
-                // Update active occurrence
-                let active_occurrence_value = self
-                    .get("active_occurrences")
-                    .with_context(|| "Unable to read active occurrences from store".to_string())?
-                    .clone();
-                let mut active_occurrence_store = serde_json::from_value::<
-                    HashMap<String, ActiveOccurrence>,
-                >(active_occurrence_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-                if let Some(active_occurrence) = active_occurrence_store
-                    .values_mut()
-                    .find(|active_occurrence| active_occurrence.team_ids.contains(&team_id))
-                {
-                    let team_idx = active_occurrence
-                        .team_ids
-                        .iter()
-                        .position(|id| id == team_id);
-                    active_occurrence.team_ids.swap_remove(team_idx.unwrap());
-                }
-
-                self.insert(
-                    String::from("active_occurrences"),
-                    serde_json::json!(active_occurrence_store),
-                )
-                .with_context(|| {
-                    format!(
-                        "Failed to update active occurrences with value {:?}",
-                        active_occurrence_store
-                    )
-                })?;
-            }
+    fn commit(&mut self, changes: &ChangeSet) -> anyhow::Result<()> {
+        if self.read_only {
+            anyhow::bail!("The store is being recovered and cannot be changed");
         }
 
-        self.save()
-            .with_context(|| "Failed to save store while deleting team".to_string())?;
+        changes.apply(&mut self.data);
+        changes.apply_archive(&mut self.archive);
+        self.dirty = true;
 
         Ok(())
     }
 
-    fn delete_vehicle(&mut self, vehicle_id: &String) -> anyhow::Result<()> {
-        let vehicles_value = self
-            .get("vehicles")
-            .with_context(|| "Unable to read vehicles from store".to_string())?
-            .clone();
-        let mut vehicle_store = serde_json::from_value::<HashMap<String, Vehicle>>(vehicles_value)
-            .with_context(|| "Failed to deserialize vehicles".to_string())?;
-
-        let removed_entry = vehicle_store.remove(vehicle_id);
-        self.insert(String::from("vehicles"), serde_json::json!(vehicle_store))
-            .with_context(|| format!("Failed to delete vehicle {}", vehicle_id))?;
-
-        if let Some(removed_vehicle) = removed_entry {
-            if removed_vehicle.state == VehicleState::Dispatched {
-                // Update active occurrence
-                let active_occurrence_value = self
-                    .get("active_occurrences")
-                    .with_context(|| "Unable to read active occurrences from store".to_string())?
-                    .clone();
-                let mut active_occurrence_store = serde_json::from_value::<
-                    HashMap<String, ActiveOccurrence>,
-                >(active_occurrence_value)
-                .with_context(|| "Failed to deserialize active occurrences".to_string())?;
-
-                if let Some(active_occurrence) = active_occurrence_store
-                    .values_mut()
-                    .find(|active_occurrence| active_occurrence.vehicle_ids.contains(&vehicle_id))
-                {
-                    let vehicle_idx = active_occurrence
-                        .vehicle_ids
-                        .iter()
-                        .position(|id| id == vehicle_id);
-                    active_occurrence
-                        .vehicle_ids
-                        .swap_remove(vehicle_idx.unwrap());
-                    active_occurrence.vehicle_assignment_map.remove(vehicle_id);
-                }
-
-                self.insert(
-                    String::from("active_occurrences"),
-                    serde_json::json!(active_occurrence_store),
-                )
-                .with_context(|| {
-                    format!(
-                        "Failed to update active occurrences with value {:?}",
-                        active_occurrence_store
-                    )
-                })?;
-            }
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if !self.dirty {
+            return Ok(());
         }
 
-        self.save()
-            .with_context(|| "Failed to save store while deleting vehicle".to_string())?;
-
-        Ok(())
-    }
-
-    fn set_staff_shift(
-        &mut self,
-        available_staff: Vec<String>,
-        team_allocations: HashMap<String, Vec<String>>,
-    ) -> anyhow::Result<()> {
-        let staff_value = self
-            .get("staff")
-            .with_context(|| "Unable to read staff from store".to_string())?
-            .clone();
-        let mut staff_store = serde_json::from_value::<HashMap<String, Staff>>(staff_value)
-            .with_context(|| "Failed to deserialize staff".to_string())?;
-
-        staff_store.iter_mut().for_each(|(_, staff)| {
-            if (staff.state == StaffState::Available) || (staff.state == StaffState::Unavailable) {
-                staff.state = if available_staff.contains(&staff.internal_id) {
-                    StaffState::Available
-                } else {
-                    StaffState::Unavailable
-                }
-            }
-        });
-
-        self.insert(String::from("staff"), serde_json::json!(staff_store))
-            .with_context(|| format!("Failed to update staff with value {:?}", staff_store))?;
-
-        let teams_value = self
-            .get("teams")
-            .map(|teams| teams.clone())
-            .unwrap_or_else(|| json!({}));
-        
-        let mut teams_store = serde_json::from_value::<HashMap<String, Team>>(teams_value)
-            .with_context(|| "Failed to deserialize teams".to_string())?;
-
-        teams_store.iter_mut().for_each(|(_, team)| {
-            if (team.state == TeamState::Available) || (team.state == TeamState::Unavailable) {
-                if team_allocations.contains_key(&team.internal_id) {
-                    team.state = TeamState::Available;
-                    team.member_ids = team_allocations.get(&team.internal_id).unwrap().clone();
-                } else {
-                    team.state = TeamState::Unavailable;
-                    team.member_ids = vec![];
-                }
-            }
-        });
-
-        self.insert(String::from("teams"), serde_json::json!(teams_store))
-            .with_context(|| format!("Failed to update teams with value {:?}", teams_store))?;
-
-        self.save()
-            .with_context(|| "Failed to save store while setting staff shift".to_string())?;
+        self.persisted_store
+            .insert(
+                String::from("active_occurrences"),
+                serde_json::json!(self.data.active_occurrences),
+            )
+            .with_context(|| "Failed to update active occurrences".to_string())?;
+        self.persisted_store
+            .insert(
+                String::from("occurrences"),
+                serde_json::json!(self.data.occurrences),
+            )
+            .with_context(|| "Failed to update occurrences".to_string())?;
+        self.persisted_store
+            .insert(String::from("staff"), serde_json::json!(self.data.staff))
+            .with_context(|| "Failed to update staff".to_string())?;
+        self.persisted_store
+            .insert(String::from("teams"), serde_json::json!(self.data.teams))
+            .with_context(|| "Failed to update teams".to_string())?;
+        self.persisted_store
+            .insert(
+                String::from("vehicles"),
+                serde_json::json!(self.data.vehicles),
+            )
+            .with_context(|| "Failed to update vehicles".to_string())?;
+        self.persisted_store
+            .insert(
+                String::from(CREW_REQUIREMENTS_KEY),
+                serde_json::json!(self.data.crew_requirements),
+            )
+            .with_context(|| "Failed to update crew requirements".to_string())?;
+        self.persisted_store
+            .insert(
+                String::from(SHIFT_TEMPLATES_KEY),
+                serde_json::json!(self.data.shift_templates),
+            )
+            .with_context(|| "Failed to update shift templates".to_string())?;
+        self.persisted_store
+            .insert(String::from(ARCHIVE_KEY), serde_json::json!(self.archive))
+            .with_context(|| "Failed to update archived occurrences".to_string())?;
+
+        self.persisted_store
+            .save()
+            .with_context(|| "Failed to save store".to_string())?;
+        self.dirty = false;
 
         Ok(())
     }
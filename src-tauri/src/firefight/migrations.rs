@@ -0,0 +1,388 @@
+use anyhow::Context;
+use serde_json::{json, Map, Value};
+
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+pub const COLLECTION_KEYS: [&str; 5] = [
+    "active_occurrences",
+    "occurrences",
+    "staff",
+    "teams",
+    "vehicles",
+];
+
+pub const ARCHIVE_KEY: &str = "archived_occurrences";
+
+// Added after the initial collections, so it is created by its own migration step
+pub const CREW_REQUIREMENTS_KEY: &str = "crew_requirements";
+pub const SHIFT_TEMPLATES_KEY: &str = "shift_templates";
+
+type Migration = fn(&mut Map<String, Value>) -> anyhow::Result<()>;
+
+// Applied in order, entry N upgrades a store from version N to N + 1.
+// Only ever append to this list, released steps must not change.
+const STORE_MIGRATIONS: &[Migration] = &[
+    initialize_collections,
+    fill_default_fields,
+    initialize_archive,
+    initialize_qualifications,
+    initialize_crew_requirements,
+    assign_crew_roles,
+    initialize_shift_templates,
+    initialize_leave_periods,
+    initialize_vehicle_timelines,
+    initialize_resource_transfers,
+    initialize_resource_releases,
+    initialize_dispatch_plans,
+];
+
+pub fn current_schema_version() -> u64 {
+    STORE_MIGRATIONS.len() as u64
+}
+
+pub fn schema_version(entries: &Map<String, Value>) -> anyhow::Result<u64> {
+    match entries.get(SCHEMA_VERSION_KEY) {
+        Some(version_value) => version_value
+            .as_u64()
+            .with_context(|| format!("Invalid store schema version: {}", version_value)),
+        None => Ok(0),
+    }
+}
+
+/// Upgrades the raw store entries in place, returns whether anything was migrated
+pub fn migrate_store(entries: &mut Map<String, Value>) -> anyhow::Result<bool> {
+    let schema_version = schema_version(entries)?;
+
+    if schema_version > current_schema_version() {
+        anyhow::bail!(
+            "Store schema version {} is newer than the supported version {}",
+            schema_version,
+            current_schema_version()
+        );
+    }
+
+    for (index, migration) in STORE_MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(schema_version as usize)
+    {
+        let next_version = index as u64 + 1;
+        println!("Migrating store to schema version {}", next_version);
+
+        migration(entries)
+            .with_context(|| format!("Failed to migrate store to version {}", next_version))?;
+        entries.insert(String::from(SCHEMA_VERSION_KEY), json!(next_version));
+    }
+
+    Ok(schema_version < current_schema_version())
+}
+
+fn collection_entries<'a>(
+    entries: &'a mut Map<String, Value>,
+    key: &str,
+) -> anyhow::Result<Vec<&'a mut Map<String, Value>>> {
+    entries
+        .get_mut(key)
+        .and_then(Value::as_object_mut)
+        .with_context(|| format!("Store collection {} is not an object", key))?
+        .values_mut()
+        .map(|entry| {
+            entry
+                .as_object_mut()
+                .with_context(|| format!("Store collection {} has an invalid entry", key))
+        })
+        .collect()
+}
+
+fn fill_default(entry: &mut Map<String, Value>, field: &str, default_value: Value) {
+    if !entry.contains_key(field) {
+        entry.insert(String::from(field), default_value);
+    }
+}
+
+// Migration steps
+
+fn initialize_collections(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for key in COLLECTION_KEYS {
+        match entries.get(key) {
+            Some(Value::Object(_)) => {}
+            Some(Value::Null) | None => {
+                entries.insert(String::from(key), json!({}));
+            }
+            Some(_) => anyhow::bail!("Store collection {} is not an object", key),
+        }
+    }
+
+    Ok(())
+}
+
+fn fill_default_fields(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for staff in collection_entries(entries, "staff")? {
+        fill_default(staff, "nationalId", json!(""));
+        fill_default(staff, "permission", json!("own"));
+        fill_default(staff, "rank", json!("unknown"));
+    }
+
+    for vehicle in collection_entries(entries, "vehicles")? {
+        fill_default(vehicle, "category", json!("unknown"));
+    }
+
+    for active_occurrence in collection_entries(entries, "active_occurrences")? {
+        fill_default(active_occurrence, "teamIds", json!([]));
+        fill_default(active_occurrence, "vehicleAssignmentMap", json!({}));
+    }
+
+    Ok(())
+}
+
+fn initialize_archive(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    match entries.get(ARCHIVE_KEY) {
+        Some(Value::Object(_)) => Ok(()),
+        Some(Value::Null) | None => {
+            entries.insert(String::from(ARCHIVE_KEY), json!({}));
+            Ok(())
+        }
+        Some(_) => anyhow::bail!("Store collection {} is not an object", ARCHIVE_KEY),
+    }
+}
+
+fn initialize_qualifications(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for staff in collection_entries(entries, "staff")? {
+        fill_default(staff, "qualifications", json!([]));
+    }
+
+    Ok(())
+}
+
+fn initialize_crew_requirements(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    match entries.get(CREW_REQUIREMENTS_KEY) {
+        Some(Value::Object(_)) => Ok(()),
+        Some(Value::Null) | None => {
+            entries.insert(String::from(CREW_REQUIREMENTS_KEY), json!({}));
+            Ok(())
+        }
+        Some(_) => anyhow::bail!(
+            "Store collection {} is not an object",
+            CREW_REQUIREMENTS_KEY
+        ),
+    }
+}
+
+fn assign_vehicle_crew_roles(active_occurrence: &mut Map<String, Value>) {
+    let vehicle_assignment_map = match active_occurrence
+        .get_mut("vehicleAssignmentMap")
+        .and_then(Value::as_object_mut)
+    {
+        Some(vehicle_assignment_map) => vehicle_assignment_map,
+        None => return,
+    };
+
+    for vehicle_crew in vehicle_assignment_map
+        .values_mut()
+        .filter_map(Value::as_array_mut)
+    {
+        for assignment in vehicle_crew.iter_mut() {
+            if let Value::String(staff_id) = assignment {
+                *assignment = json!({ "staffId": staff_id, "role": "crew" });
+            }
+        }
+    }
+}
+
+fn assign_crew_roles(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for active_occurrence in collection_entries(entries, "active_occurrences")? {
+        assign_vehicle_crew_roles(active_occurrence);
+    }
+
+    for archived_occurrence in collection_entries(entries, ARCHIVE_KEY)? {
+        if let Some(active_occurrence) = archived_occurrence
+            .get_mut("activeOccurrence")
+            .and_then(Value::as_object_mut)
+        {
+            assign_vehicle_crew_roles(active_occurrence);
+        }
+    }
+
+    Ok(())
+}
+
+fn initialize_shift_templates(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    match entries.get(SHIFT_TEMPLATES_KEY) {
+        Some(Value::Object(_)) => Ok(()),
+        Some(Value::Null) | None => {
+            entries.insert(String::from(SHIFT_TEMPLATES_KEY), json!({}));
+            Ok(())
+        }
+        Some(_) => anyhow::bail!("Store collection {} is not an object", SHIFT_TEMPLATES_KEY),
+    }
+}
+
+fn initialize_leave_periods(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for staff in collection_entries(entries, "staff")? {
+        fill_default(staff, "leavePeriods", json!([]));
+    }
+
+    Ok(())
+}
+
+fn initialize_vehicle_timelines(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for active_occurrence in collection_entries(entries, "active_occurrences")? {
+        fill_default(active_occurrence, "vehicleTimelines", json!({}));
+    }
+
+    for archived_occurrence in collection_entries(entries, ARCHIVE_KEY)? {
+        if let Some(active_occurrence) = archived_occurrence
+            .get_mut("activeOccurrence")
+            .and_then(Value::as_object_mut)
+        {
+            fill_default(active_occurrence, "vehicleTimelines", json!({}));
+        }
+    }
+
+    Ok(())
+}
+
+fn initialize_resource_transfers(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for active_occurrence in collection_entries(entries, "active_occurrences")? {
+        fill_default(active_occurrence, "resourceTransfers", json!([]));
+    }
+
+    for archived_occurrence in collection_entries(entries, ARCHIVE_KEY)? {
+        if let Some(active_occurrence) = archived_occurrence
+            .get_mut("activeOccurrence")
+            .and_then(Value::as_object_mut)
+        {
+            fill_default(active_occurrence, "resourceTransfers", json!([]));
+        }
+    }
+
+    Ok(())
+}
+
+fn initialize_resource_releases(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for active_occurrence in collection_entries(entries, "active_occurrences")? {
+        fill_default(active_occurrence, "resourceReleases", json!([]));
+    }
+
+    for archived_occurrence in collection_entries(entries, ARCHIVE_KEY)? {
+        if let Some(active_occurrence) = archived_occurrence
+            .get_mut("activeOccurrence")
+            .and_then(Value::as_object_mut)
+        {
+            fill_default(active_occurrence, "resourceReleases", json!([]));
+        }
+    }
+
+    Ok(())
+}
+
+fn initialize_dispatch_plans(entries: &mut Map<String, Value>) -> anyhow::Result<()> {
+    for occurrence in collection_entries(entries, "occurrences")? {
+        fill_default(occurrence, "dispatchPlan", json!([]));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_entries() -> Map<String, Value> {
+        json!({
+            "active_occurrences": {
+                "active": {
+                    "internalId": "active",
+                    "occurrenceId": "occurrence",
+                    "staffIds": ["staff"],
+                    "vehicleIds": ["vehicle"],
+                    "vehicleAssignmentMap": { "vehicle": ["staff"] }
+                }
+            },
+            "staff": {
+                "staff": {
+                    "internalId": "staff",
+                    "image": "",
+                    "label": "1",
+                    "name": "Staff",
+                    "state": "available"
+                }
+            },
+            "vehicles": null
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    #[test]
+    fn migrates_an_unversioned_store_to_the_current_version() {
+        let mut entries = legacy_entries();
+
+        assert!(migrate_store(&mut entries).unwrap());
+        assert_eq!(schema_version(&entries).unwrap(), current_schema_version());
+
+        for key in COLLECTION_KEYS
+            .iter()
+            .chain([ARCHIVE_KEY, CREW_REQUIREMENTS_KEY, SHIFT_TEMPLATES_KEY].iter())
+        {
+            assert!(entries[*key].is_object(), "{} was not initialized", key);
+        }
+
+        let staff = &entries["staff"]["staff"];
+        assert_eq!(staff["permission"], json!("own"));
+        assert_eq!(staff["qualifications"], json!([]));
+        assert_eq!(staff["leavePeriods"], json!([]));
+
+        let active_occurrence = &entries["active_occurrences"]["active"];
+        assert_eq!(
+            active_occurrence["vehicleAssignmentMap"]["vehicle"],
+            json!([{ "staffId": "staff", "role": "crew" }])
+        );
+        assert_eq!(active_occurrence["teamIds"], json!([]));
+        assert_eq!(active_occurrence["vehicleTimelines"], json!({}));
+        assert_eq!(active_occurrence["resourceReleases"], json!([]));
+    }
+
+    #[test]
+    fn migrates_a_partially_migrated_store_from_its_version() {
+        let mut entries = legacy_entries();
+        initialize_collections(&mut entries).unwrap();
+        initialize_archive(&mut entries).unwrap();
+        entries.insert(String::from(SCHEMA_VERSION_KEY), json!(5));
+
+        assert!(migrate_store(&mut entries).unwrap());
+        assert_eq!(schema_version(&entries).unwrap(), current_schema_version());
+
+        // Steps below version 5 already ran, so their defaults are not filled again
+        let staff = &entries["staff"]["staff"];
+        assert!(staff.get("permission").is_none());
+        assert!(staff.get("qualifications").is_none());
+        assert_eq!(staff["leavePeriods"], json!([]));
+        assert_eq!(
+            entries["active_occurrences"]["active"]["vehicleAssignmentMap"]["vehicle"],
+            json!([{ "staffId": "staff", "role": "crew" }])
+        );
+    }
+
+    #[test]
+    fn leaves_a_current_store_untouched() {
+        let mut entries = legacy_entries();
+        migrate_store(&mut entries).unwrap();
+        let migrated_entries = entries.clone();
+
+        assert!(!migrate_store(&mut entries).unwrap());
+        assert_eq!(entries, migrated_entries);
+    }
+
+    #[test]
+    fn refuses_stores_from_a_newer_version() {
+        let mut entries = legacy_entries();
+        entries.insert(
+            String::from(SCHEMA_VERSION_KEY),
+            json!(current_schema_version() + 1),
+        );
+
+        assert!(migrate_store(&mut entries).is_err());
+    }
+}
@@ -1,4 +1,25 @@
 pub mod audio;
+pub mod backup;
+pub mod bundle;
+pub mod consistency;
+pub mod crew;
+pub mod errors;
 pub mod events;
+#[cfg(test)]
+mod fixtures;
+pub mod history;
+pub mod images;
+pub mod journal;
+pub mod leave;
 pub mod local_store;
+pub mod migrations;
+pub mod operators;
+pub mod operations;
+pub mod qualifications;
+pub mod recommend;
+pub mod roster;
+pub mod shifts;
+pub mod sqlite_store;
+pub mod timeline;
+pub mod transaction;
 pub mod types;
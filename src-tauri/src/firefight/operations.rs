@@ -0,0 +1,1901 @@
+use std::collections::HashMap;
+
+use super::crew::{self, DispatchReport};
+use super::errors::StoreError;
+use super::leave;
+use super::types::{
+    ActiveOccurrence, ArchivedOccurrence, CrewRequirement, CrewRequirementTarget, CrewRole,
+    DataStore, LeavePeriod, LeaveStatus, Occurrence, Qualification, ResourceRelease,
+    ResourceTransfer, ShiftTemplate, Staff, StaffState, Team, TeamState, Vehicle, VehicleState,
+    VehicleStatus, VehicleStatusChange,
+};
+
+// Staged mutations, applied to a copy of the store inside a transaction
+
+fn set_vehicle_state(
+    data_store: &mut DataStore,
+    vehicle_id: &String,
+    state: VehicleState,
+) -> anyhow::Result<()> {
+    let vehicle =
+        data_store
+            .vehicles
+            .get_mut(vehicle_id)
+            .ok_or_else(|| StoreError::UnknownVehicle {
+                vehicle_id: vehicle_id.clone(),
+            })?;
+    vehicle.state = state;
+    Ok(())
+}
+
+fn set_staff_state(
+    data_store: &mut DataStore,
+    staff_id: &String,
+    state: StaffState,
+) -> anyhow::Result<()> {
+    let staff = data_store
+        .staff
+        .get_mut(staff_id)
+        .ok_or_else(|| StoreError::UnknownStaff {
+            staff_id: staff_id.clone(),
+        })?;
+    staff.state = state;
+    Ok(())
+}
+
+/// Sends a staff member back from an occurrence, straight onto a leave that started meanwhile
+fn release_staff(staff: &mut Staff) {
+    staff.state = StaffState::Available;
+    leave::settle_staff_state(staff, false);
+}
+
+fn set_team_state(
+    data_store: &mut DataStore,
+    team_id: &String,
+    state: TeamState,
+) -> anyhow::Result<()> {
+    let team = data_store
+        .teams
+        .get_mut(team_id)
+        .ok_or_else(|| StoreError::UnknownTeam {
+            team_id: team_id.clone(),
+        })?;
+    team.state = state;
+    Ok(())
+}
+
+/// Team members are dispatched along with their team
+fn include_team_members(data_store: &DataStore, active_occurrence: &mut ActiveOccurrence) {
+    for team_id in active_occurrence.team_ids.clone().iter() {
+        if let Some(team) = data_store.teams.get(team_id) {
+            push_missing(&mut active_occurrence.staff_ids, &team.member_ids);
+        }
+    }
+}
+
+/// Resources the occurrence shares with other active occurrences, one transfer per occurrence
+fn take_over_resources(
+    data_store: &DataStore,
+    active_occurrence: &ActiveOccurrence,
+    time: u128,
+) -> Vec<ResourceTransfer> {
+    data_store
+        .active_occurrences
+        .iter()
+        .filter(|(other_occurrence_id, _)| **other_occurrence_id != active_occurrence.internal_id)
+        .map(|(other_occurrence_id, other_occurrence)| ResourceTransfer {
+            from_active_occurrence_id: other_occurrence_id.clone(),
+            to_active_occurrence_id: active_occurrence.internal_id.clone(),
+            staff_ids: shared_ids(&other_occurrence.staff_ids, &active_occurrence.staff_ids),
+            team_ids: shared_ids(&other_occurrence.team_ids, &active_occurrence.team_ids),
+            vehicle_ids: shared_ids(
+                &other_occurrence.vehicle_ids,
+                &active_occurrence.vehicle_ids,
+            ),
+            time,
+        })
+        .filter(|transfer| !is_empty_transfer(transfer))
+        .collect()
+}
+
+/// Rejects references to entities that do not exist and crews outside the occurrence
+pub fn validate_active_occurrence(
+    data_store: &DataStore,
+    active_occurrence: &ActiveOccurrence,
+) -> Result<(), StoreError> {
+    if !data_store
+        .occurrences
+        .contains_key(&active_occurrence.occurrence_id)
+    {
+        return Err(StoreError::UnknownOccurrence {
+            occurrence_id: active_occurrence.occurrence_id.clone(),
+        });
+    }
+    if let Some(staff_id) = active_occurrence
+        .staff_ids
+        .iter()
+        .find(|staff_id| !data_store.staff.contains_key(*staff_id))
+    {
+        return Err(StoreError::UnknownStaff {
+            staff_id: staff_id.clone(),
+        });
+    }
+    if let Some(team_id) = active_occurrence
+        .team_ids
+        .iter()
+        .find(|team_id| !data_store.teams.contains_key(*team_id))
+    {
+        return Err(StoreError::UnknownTeam {
+            team_id: team_id.clone(),
+        });
+    }
+    if let Some(vehicle_id) = active_occurrence
+        .vehicle_ids
+        .iter()
+        .find(|vehicle_id| !data_store.vehicles.contains_key(*vehicle_id))
+    {
+        return Err(StoreError::UnknownVehicle {
+            vehicle_id: vehicle_id.clone(),
+        });
+    }
+
+    for (vehicle_id, vehicle_crew) in active_occurrence.vehicle_assignment_map.iter() {
+        if !active_occurrence.vehicle_ids.contains(vehicle_id) {
+            return Err(StoreError::UnassignedVehicle {
+                vehicle_id: vehicle_id.clone(),
+            });
+        }
+        if let Some(assignment) = vehicle_crew
+            .iter()
+            .find(|assignment| !active_occurrence.staff_ids.contains(&assignment.staff_id))
+        {
+            return Err(StoreError::UnassignedStaff {
+                vehicle_id: vehicle_id.clone(),
+                staff_id: assignment.staff_id.clone(),
+            });
+        }
+        // Crew members fill the remaining seats, a vehicle only has one chief and one driver
+        for role in [CrewRole::Chief, CrewRole::Driver] {
+            let role_count = vehicle_crew
+                .iter()
+                .filter(|assignment| assignment.role == role)
+                .count();
+            if role_count > 1 {
+                return Err(StoreError::DuplicateCrewRole {
+                    vehicle_id: vehicle_id.clone(),
+                    role,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn create_active_occurrence(
+    data_store: &mut DataStore,
+    mut occurrence: ActiveOccurrence,
+) -> anyhow::Result<DispatchReport> {
+    let now = std::time::UNIX_EPOCH.elapsed()?.as_millis();
+    validate_active_occurrence(data_store, &occurrence)?;
+    include_team_members(data_store, &mut occurrence);
+    let crew_warnings = crew::enforce_crew_requirements(data_store, &occurrence, None, now)?;
+
+    // Create active ocurrence
+    let active_occurrence_id = uuid::Uuid::new_v4().to_string();
+    occurrence.internal_id = active_occurrence_id.clone();
+
+    // Resources still on other active occurrences are taken over, the transfer is recorded on both
+    let transfers = take_over_resources(data_store, &occurrence, now);
+
+    // Every vehicle starts its timeline alerted
+    occurrence.vehicle_timelines = occurrence
+        .vehicle_ids
+        .iter()
+        .map(|vehicle_id| {
+            (
+                vehicle_id.clone(),
+                vec![VehicleStatusChange {
+                    status: VehicleStatus::Alerted,
+                    time: now,
+                }],
+            )
+        })
+        .collect();
+
+    // Update vehicles
+    for vehicle_id in occurrence.vehicle_ids.iter() {
+        set_vehicle_state(data_store, vehicle_id, VehicleState::Dispatched)?;
+    }
+
+    // Update staff
+    for staff_id in occurrence.staff_ids.iter() {
+        set_staff_state(data_store, staff_id, StaffState::Dispatched)?;
+    }
+
+    // Update teams
+    for team_id in occurrence.team_ids.iter() {
+        set_team_state(data_store, team_id, TeamState::Dispatched)?;
+    }
+
+    data_store
+        .active_occurrences
+        .insert(active_occurrence_id.clone(), occurrence);
+
+    for transfer in transfers.iter() {
+        move_resources(data_store, transfer)?;
+    }
+
+    Ok(DispatchReport {
+        active_occurrence_id,
+        crew_warnings,
+        transfers,
+    })
+}
+
+pub fn create_occurrence(
+    data_store: &mut DataStore,
+    mut occurrence: Occurrence,
+) -> anyhow::Result<String> {
+    let occurrence_id = uuid::Uuid::new_v4().to_string();
+    occurrence.internal_id = occurrence_id.clone();
+    data_store
+        .occurrences
+        .insert(occurrence_id.clone(), occurrence);
+
+    Ok(occurrence_id)
+}
+
+pub fn create_staff(data_store: &mut DataStore, mut staff: Staff) -> anyhow::Result<String> {
+    let staff_id = uuid::Uuid::new_v4().to_string();
+    staff.internal_id = staff_id.clone();
+    data_store.staff.insert(staff_id.clone(), staff);
+
+    Ok(staff_id)
+}
+
+pub fn create_team(data_store: &mut DataStore, mut team: Team) -> anyhow::Result<String> {
+    let team_id = uuid::Uuid::new_v4().to_string();
+    team.internal_id = team_id.clone();
+    data_store.teams.insert(team_id.clone(), team);
+
+    Ok(team_id)
+}
+
+pub fn create_vehicle(data_store: &mut DataStore, mut vehicle: Vehicle) -> anyhow::Result<String> {
+    let vehicle_id = uuid::Uuid::new_v4().to_string();
+    vehicle.internal_id = vehicle_id.clone();
+    data_store.vehicles.insert(vehicle_id.clone(), vehicle);
+
+    Ok(vehicle_id)
+}
+
+/// Vehicles only go back to Available once they report back at base. Vehicles dispatched before
+/// statuses were tracked have no timeline and are released as before.
+fn check_vehicles_at_base(
+    active_occurrence: &ActiveOccurrence,
+    vehicle_ids: &[String],
+) -> Result<(), StoreError> {
+    let vehicle_ids = vehicle_ids
+        .iter()
+        .filter(|vehicle_id| {
+            active_occurrence
+                .vehicle_status(vehicle_id)
+                .is_some_and(|status| status != VehicleStatus::AtBase)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if !vehicle_ids.is_empty() {
+        return Err(StoreError::VehiclesNotAtBase { vehicle_ids });
+    }
+
+    Ok(())
+}
+
+pub fn update_active_occurrence(
+    data_store: &mut DataStore,
+    active_occurrence_id: &String,
+    mut active_occurrence: ActiveOccurrence,
+) -> anyhow::Result<DispatchReport> {
+    let now = std::time::UNIX_EPOCH.elapsed()?.as_millis();
+    let previous_active_occurrence = data_store
+        .active_occurrences
+        .get(active_occurrence_id)
+        .cloned()
+        .ok_or_else(|| StoreError::UnknownActiveOccurrence {
+            active_occurrence_id: active_occurrence_id.clone(),
+        })?;
+    validate_active_occurrence(data_store, &active_occurrence)?;
+    active_occurrence.internal_id = active_occurrence_id.clone();
+
+    // Members leave with a team that is no longer dispatched, unless they sit on a vehicle
+    let crew_staff_ids = active_occurrence
+        .vehicle_assignment_map
+        .values()
+        .flatten()
+        .map(|assignment| assignment.staff_id.clone())
+        .collect::<Vec<_>>();
+    for team_id in previous_active_occurrence
+        .team_ids
+        .iter()
+        .filter(|team_id| !active_occurrence.team_ids.contains(team_id))
+    {
+        if let Some(team) = data_store.teams.get(team_id) {
+            active_occurrence.staff_ids.retain(|staff_id| {
+                !team.member_ids.contains(staff_id) || crew_staff_ids.contains(staff_id)
+            });
+        }
+    }
+    include_team_members(data_store, &mut active_occurrence);
+
+    let crew_warnings = crew::enforce_crew_requirements(
+        data_store,
+        &active_occurrence,
+        Some(&previous_active_occurrence),
+        now,
+    )?;
+    let transfers = take_over_resources(data_store, &active_occurrence, now);
+
+    // Timelines only change through reported statuses, vehicles joining the occurrence are alerted
+    active_occurrence.vehicle_timelines = previous_active_occurrence.vehicle_timelines.clone();
+    for vehicle_id in active_occurrence
+        .vehicle_ids
+        .iter()
+        .filter(|vehicle_id| !previous_active_occurrence.vehicle_ids.contains(vehicle_id))
+    {
+        active_occurrence
+            .vehicle_timelines
+            .entry(vehicle_id.clone())
+            .or_default()
+            .push(VehicleStatusChange {
+                status: VehicleStatus::Alerted,
+                time: now,
+            });
+    }
+
+    // Update vehicles
+    let removed_vehicle_ids = previous_active_occurrence
+        .vehicle_ids
+        .iter()
+        .filter(|vehicle_id| !active_occurrence.vehicle_ids.contains(vehicle_id))
+        .cloned()
+        .collect::<Vec<_>>();
+    check_vehicles_at_base(&previous_active_occurrence, &removed_vehicle_ids)?;
+    for vehicle_id in removed_vehicle_ids.iter() {
+        set_vehicle_state(data_store, vehicle_id, VehicleState::Available)?;
+    }
+
+    for vehicle_id in active_occurrence.vehicle_ids.iter().filter(|vehicle_id| {
+        active_occurrence.vehicle_status(vehicle_id) != Some(VehicleStatus::AtBase)
+    }) {
+        set_vehicle_state(data_store, vehicle_id, VehicleState::Dispatched)?;
+    }
+
+    // Update staff
+    for staff_id in previous_active_occurrence
+        .staff_ids
+        .iter()
+        .filter(|staff_id| !active_occurrence.staff_ids.contains(staff_id))
+    {
+        release_staff(get_staff_mut(data_store, staff_id)?);
+    }
+
+    for staff_id in active_occurrence.staff_ids.iter() {
+        set_staff_state(data_store, staff_id, StaffState::Dispatched)?;
+    }
+
+    // Update teams
+    for team_id in previous_active_occurrence
+        .team_ids
+        .iter()
+        .filter(|team_id| !active_occurrence.team_ids.contains(team_id))
+    {
+        set_team_state(data_store, team_id, TeamState::Available)?;
+    }
+
+    for team_id in active_occurrence.team_ids.iter() {
+        set_team_state(data_store, team_id, TeamState::Dispatched)?;
+    }
+
+    data_store
+        .active_occurrences
+        .insert(active_occurrence_id.clone(), active_occurrence);
+
+    for transfer in transfers.iter() {
+        move_resources(data_store, transfer)?;
+    }
+
+    Ok(DispatchReport {
+        active_occurrence_id: active_occurrence_id.clone(),
+        crew_warnings,
+        transfers,
+    })
+}
+
+pub fn update_occurrence(
+    data_store: &mut DataStore,
+    occurrence_id: &String,
+    occurrence: Occurrence,
+) -> anyhow::Result<Option<Occurrence>> {
+    Ok(data_store
+        .occurrences
+        .insert(occurrence_id.clone(), occurrence))
+}
+
+pub fn update_staff(
+    data_store: &mut DataStore,
+    staff_id: &String,
+    staff: Staff,
+) -> anyhow::Result<Option<Staff>> {
+    Ok(data_store.staff.insert(staff_id.clone(), staff))
+}
+
+pub fn update_team(
+    data_store: &mut DataStore,
+    team_id: &String,
+    team: Team,
+) -> anyhow::Result<Option<Team>> {
+    Ok(data_store.teams.insert(team_id.clone(), team))
+}
+
+pub fn update_vehicle(
+    data_store: &mut DataStore,
+    vehicle_id: &String,
+    vehicle: Vehicle,
+) -> anyhow::Result<Option<Vehicle>> {
+    Ok(data_store.vehicles.insert(vehicle_id.clone(), vehicle))
+}
+
+pub fn delete_active_occurrence(
+    data_store: &mut DataStore,
+    active_occurrence_id: &String,
+) -> anyhow::Result<()> {
+    if let Some(active_occurrence) = data_store.active_occurrences.get(active_occurrence_id) {
+        check_vehicles_at_base(active_occurrence, &active_occurrence.vehicle_ids)?;
+    }
+
+    if let Some(active_ocurrence) = data_store.active_occurrences.remove(active_occurrence_id) {
+        // Update vehicles
+        active_ocurrence.vehicle_ids.iter().for_each(|vehicle_id| {
+            if let Some(vehicle) = data_store.vehicles.get_mut(vehicle_id) {
+                vehicle.state = VehicleState::Available;
+            }
+        });
+
+        // Update staff
+        active_ocurrence.staff_ids.iter().for_each(|staff_id| {
+            if let Some(staff) = data_store.staff.get_mut(staff_id) {
+                release_staff(staff);
+            }
+        });
+
+        // Update teams
+        active_ocurrence.team_ids.iter().for_each(|team_id| {
+            if let Some(team) = data_store.teams.get_mut(team_id) {
+                team.state = TeamState::Available;
+            }
+        });
+    }
+
+    Ok(())
+}
+
+pub fn close_active_occurrence(
+    data_store: &mut DataStore,
+    active_occurrence_id: &String,
+    end_time: u128,
+) -> anyhow::Result<ArchivedOccurrence> {
+    let active_occurrence = data_store
+        .active_occurrences
+        .get(active_occurrence_id)
+        .cloned()
+        .ok_or_else(|| StoreError::UnknownActiveOccurrence {
+            active_occurrence_id: active_occurrence_id.clone(),
+        })?;
+
+    delete_active_occurrence(data_store, active_occurrence_id)?;
+
+    Ok(ArchivedOccurrence {
+        active_occurrence,
+        end_time,
+    })
+}
+
+/// Appends a status to the timeline of a vehicle, back at base makes the vehicle available again
+pub fn record_vehicle_status(
+    data_store: &mut DataStore,
+    active_occurrence_id: &String,
+    vehicle_id: &String,
+    status: VehicleStatus,
+    time: u128,
+) -> anyhow::Result<()> {
+    let active_occurrence = data_store
+        .active_occurrences
+        .get_mut(active_occurrence_id)
+        .ok_or_else(|| StoreError::UnknownActiveOccurrence {
+            active_occurrence_id: active_occurrence_id.clone(),
+        })?;
+    if !active_occurrence.vehicle_ids.contains(vehicle_id) {
+        return Err(StoreError::VehicleNotInOccurrence {
+            active_occurrence_id: active_occurrence_id.clone(),
+            vehicle_id: vehicle_id.clone(),
+        }
+        .into());
+    }
+
+    let timeline = active_occurrence
+        .vehicle_timelines
+        .entry(vehicle_id.clone())
+        .or_default();
+    // Alerted only opens a timeline and nothing follows back at base
+    let invalid_status = match timeline.last() {
+        Some(last_change) => {
+            status == VehicleStatus::Alerted
+                || status == last_change.status
+                || last_change.status == VehicleStatus::AtBase
+                || time < last_change.time
+        }
+        None => false,
+    };
+    if invalid_status {
+        return Err(StoreError::InvalidVehicleStatus {
+            vehicle_id: vehicle_id.clone(),
+            status,
+        }
+        .into());
+    }
+    timeline.push(VehicleStatusChange { status, time });
+
+    if status == VehicleStatus::AtBase {
+        set_vehicle_state(data_store, vehicle_id, VehicleState::Available)?;
+    }
+
+    Ok(())
+}
+
+fn shared_ids(ids: &[String], other_ids: &[String]) -> Vec<String> {
+    ids.iter()
+        .filter(|id| other_ids.contains(id))
+        .cloned()
+        .collect()
+}
+
+fn is_empty_transfer(transfer: &ResourceTransfer) -> bool {
+    transfer.staff_ids.is_empty() && transfer.team_ids.is_empty() && transfer.vehicle_ids.is_empty()
+}
+
+fn push_missing(ids: &mut Vec<String>, new_ids: &[String]) {
+    for id in new_ids.iter() {
+        if !ids.contains(id) {
+            ids.push(id.clone());
+        }
+    }
+}
+
+/// Moves exactly the listed resources and keeps the crews of both occurrences coherent
+fn move_resources(data_store: &mut DataStore, transfer: &ResourceTransfer) -> anyhow::Result<()> {
+    let source_occurrence = data_store
+        .active_occurrences
+        .get_mut(&transfer.from_active_occurrence_id)
+        .ok_or_else(|| StoreError::UnknownActiveOccurrence {
+            active_occurrence_id: transfer.from_active_occurrence_id.clone(),
+        })?;
+    source_occurrence
+        .staff_ids
+        .retain(|staff_id| !transfer.staff_ids.contains(staff_id));
+    source_occurrence
+        .team_ids
+        .retain(|team_id| !transfer.team_ids.contains(team_id));
+    source_occurrence
+        .vehicle_ids
+        .retain(|vehicle_id| !transfer.vehicle_ids.contains(vehicle_id));
+    let mut moved_crews = HashMap::new();
+    for vehicle_id in transfer.vehicle_ids.iter() {
+        if let Some(vehicle_crew) = source_occurrence.vehicle_assignment_map.remove(vehicle_id) {
+            moved_crews.insert(vehicle_id.clone(), vehicle_crew);
+        }
+    }
+    source_occurrence
+        .vehicle_assignment_map
+        .values_mut()
+        .for_each(|vehicle_crew| {
+            vehicle_crew.retain(|assignment| !transfer.staff_ids.contains(&assignment.staff_id))
+        });
+    source_occurrence.resource_transfers.push(transfer.clone());
+
+    let target_occurrence = data_store
+        .active_occurrences
+        .get_mut(&transfer.to_active_occurrence_id)
+        .ok_or_else(|| StoreError::UnknownActiveOccurrence {
+            active_occurrence_id: transfer.to_active_occurrence_id.clone(),
+        })?;
+    push_missing(&mut target_occurrence.staff_ids, &transfer.staff_ids);
+    push_missing(&mut target_occurrence.team_ids, &transfer.team_ids);
+    // Vehicles the target already lists keep the crew it gave them
+    for vehicle_id in transfer.vehicle_ids.iter() {
+        if target_occurrence.vehicle_ids.contains(vehicle_id) {
+            continue;
+        }
+
+        target_occurrence.vehicle_ids.push(vehicle_id.clone());
+        if let Some(mut vehicle_crew) = moved_crews.remove(vehicle_id) {
+            vehicle_crew.retain(|assignment| transfer.staff_ids.contains(&assignment.staff_id));
+            target_occurrence
+                .vehicle_assignment_map
+                .insert(vehicle_id.clone(), vehicle_crew);
+        }
+        target_occurrence
+            .vehicle_timelines
+            .entry(vehicle_id.clone())
+            .or_default()
+            .push(VehicleStatusChange {
+                status: VehicleStatus::Alerted,
+                time: transfer.time,
+            });
+    }
+    target_occurrence.resource_transfers.push(transfer.clone());
+
+    // Vehicles back at base are on their way again
+    for vehicle_id in transfer.vehicle_ids.iter() {
+        set_vehicle_state(data_store, vehicle_id, VehicleState::Dispatched)?;
+    }
+    for staff_id in transfer.staff_ids.iter() {
+        set_staff_state(data_store, staff_id, StaffState::Dispatched)?;
+    }
+    for team_id in transfer.team_ids.iter() {
+        set_team_state(data_store, team_id, TeamState::Dispatched)?;
+    }
+
+    Ok(())
+}
+
+/// Moves resources to another active occurrence, vehicles take their crew along and teams
+/// the members that are on the source occurrence
+pub fn transfer_resources(
+    data_store: &mut DataStore,
+    mut transfer: ResourceTransfer,
+) -> anyhow::Result<ResourceTransfer> {
+    let source_occurrence = data_store
+        .active_occurrences
+        .get(&transfer.from_active_occurrence_id)
+        .cloned()
+        .ok_or_else(|| StoreError::UnknownActiveOccurrence {
+            active_occurrence_id: transfer.from_active_occurrence_id.clone(),
+        })?;
+    if !data_store
+        .active_occurrences
+        .contains_key(&transfer.to_active_occurrence_id)
+    {
+        return Err(StoreError::UnknownActiveOccurrence {
+            active_occurrence_id: transfer.to_active_occurrence_id.clone(),
+        }
+        .into());
+    }
+    if transfer.from_active_occurrence_id == transfer.to_active_occurrence_id
+        || is_empty_transfer(&transfer)
+    {
+        return Err(StoreError::InvalidResourceTransfer {
+            from_active_occurrence_id: transfer.from_active_occurrence_id.clone(),
+            to_active_occurrence_id: transfer.to_active_occurrence_id.clone(),
+        }
+        .into());
+    }
+
+    if let Some(vehicle_id) = transfer
+        .vehicle_ids
+        .iter()
+        .find(|vehicle_id| !source_occurrence.vehicle_ids.contains(vehicle_id))
+    {
+        return Err(StoreError::VehicleNotInOccurrence {
+            active_occurrence_id: source_occurrence.internal_id.clone(),
+            vehicle_id: vehicle_id.clone(),
+        }
+        .into());
+    }
+    if let Some(staff_id) = transfer
+        .staff_ids
+        .iter()
+        .find(|staff_id| !source_occurrence.staff_ids.contains(staff_id))
+    {
+        return Err(StoreError::StaffNotInOccurrence {
+            active_occurrence_id: source_occurrence.internal_id.clone(),
+            staff_id: staff_id.clone(),
+        }
+        .into());
+    }
+    if let Some(team_id) = transfer
+        .team_ids
+        .iter()
+        .find(|team_id| !source_occurrence.team_ids.contains(team_id))
+    {
+        return Err(StoreError::TeamNotInOccurrence {
+            active_occurrence_id: source_occurrence.internal_id.clone(),
+            team_id: team_id.clone(),
+        }
+        .into());
+    }
+
+    let mut staff_ids = transfer.staff_ids.clone();
+    for vehicle_id in transfer.vehicle_ids.iter() {
+        if let Some(vehicle_crew) = source_occurrence.vehicle_assignment_map.get(vehicle_id) {
+            let crew_ids = vehicle_crew
+                .iter()
+                .map(|assignment| assignment.staff_id.clone())
+                .collect::<Vec<_>>();
+            push_missing(&mut staff_ids, &crew_ids);
+        }
+    }
+    for team_id in transfer.team_ids.iter() {
+        if let Some(team) = data_store.teams.get(team_id) {
+            push_missing(
+                &mut staff_ids,
+                &shared_ids(&team.member_ids, &source_occurrence.staff_ids),
+            );
+        }
+    }
+    transfer.staff_ids = staff_ids;
+
+    move_resources(data_store, &transfer)?;
+
+    Ok(transfer)
+}
+
+/// Sends vehicles with their crew, teams with their members and single staff back while the
+/// occurrence stays open
+pub fn release_resources(
+    data_store: &mut DataStore,
+    active_occurrence_id: &String,
+    mut release: ResourceRelease,
+) -> anyhow::Result<ResourceRelease> {
+    let active_occurrence = data_store
+        .active_occurrences
+        .get_mut(active_occurrence_id)
+        .ok_or_else(|| StoreError::UnknownActiveOccurrence {
+            active_occurrence_id: active_occurrence_id.clone(),
+        })?;
+    if release.vehicle_ids.is_empty() && release.team_ids.is_empty() && release.staff_ids.is_empty()
+    {
+        return Err(StoreError::EmptyResourceRelease {
+            active_occurrence_id: active_occurrence_id.clone(),
+        }
+        .into());
+    }
+    if let Some(vehicle_id) = release
+        .vehicle_ids
+        .iter()
+        .find(|vehicle_id| !active_occurrence.vehicle_ids.contains(vehicle_id))
+    {
+        return Err(StoreError::VehicleNotInOccurrence {
+            active_occurrence_id: active_occurrence_id.clone(),
+            vehicle_id: vehicle_id.clone(),
+        }
+        .into());
+    }
+    if let Some(staff_id) = release
+        .staff_ids
+        .iter()
+        .find(|staff_id| !active_occurrence.staff_ids.contains(staff_id))
+    {
+        return Err(StoreError::StaffNotInOccurrence {
+            active_occurrence_id: active_occurrence_id.clone(),
+            staff_id: staff_id.clone(),
+        }
+        .into());
+    }
+    if let Some(team_id) = release
+        .team_ids
+        .iter()
+        .find(|team_id| !active_occurrence.team_ids.contains(team_id))
+    {
+        return Err(StoreError::TeamNotInOccurrence {
+            active_occurrence_id: active_occurrence_id.clone(),
+            team_id: team_id.clone(),
+        }
+        .into());
+    }
+
+    // Members go back with their team and crews with their vehicles
+    for team_id in release.team_ids.iter() {
+        if let Some(team) = data_store.teams.get(team_id) {
+            push_missing(
+                &mut release.staff_ids,
+                &shared_ids(&team.member_ids, &active_occurrence.staff_ids),
+            );
+        }
+    }
+    for vehicle_id in release.vehicle_ids.iter() {
+        if let Some(vehicle_crew) = active_occurrence.vehicle_assignment_map.remove(vehicle_id) {
+            let crew_ids = vehicle_crew
+                .iter()
+                .map(|assignment| assignment.staff_id.clone())
+                .collect::<Vec<_>>();
+            push_missing(&mut release.staff_ids, &crew_ids);
+        }
+    }
+    active_occurrence
+        .vehicle_ids
+        .retain(|vehicle_id| !release.vehicle_ids.contains(vehicle_id));
+    active_occurrence
+        .staff_ids
+        .retain(|staff_id| !release.staff_ids.contains(staff_id));
+    active_occurrence
+        .team_ids
+        .retain(|team_id| !release.team_ids.contains(team_id));
+    active_occurrence
+        .vehicle_assignment_map
+        .values_mut()
+        .for_each(|vehicle_crew| {
+            vehicle_crew.retain(|assignment| !release.staff_ids.contains(&assignment.staff_id))
+        });
+    active_occurrence.resource_releases.push(release.clone());
+
+    for vehicle_id in release.vehicle_ids.iter() {
+        set_vehicle_state(data_store, vehicle_id, VehicleState::Available)?;
+    }
+    for team_id in release.team_ids.iter() {
+        set_team_state(data_store, team_id, TeamState::Available)?;
+    }
+    for staff_id in release.staff_ids.iter() {
+        release_staff(get_staff_mut(data_store, staff_id)?);
+    }
+
+    Ok(release)
+}
+
+pub fn delete_occurrence(data_store: &mut DataStore, occurrence_id: &String) -> anyhow::Result<()> {
+    data_store.occurrences.remove(occurrence_id);
+
+    Ok(())
+}
+
+pub fn delete_staff(data_store: &mut DataStore, staff_id: &String) -> anyhow::Result<()> {
+    data_store
+        .shift_templates
+        .values_mut()
+        .for_each(|shift_template| {
+            shift_template.available_staff.retain(|id| id != staff_id);
+            shift_template
+                .team_allocations
+                .values_mut()
+                .for_each(|member_ids| member_ids.retain(|id| id != staff_id));
+        });
+
+    if let Some(remove_staff) = data_store.staff.remove(staff_id) {
+        if remove_staff.state == StaffState::Dispatched {
+            // Update active occurrence
+            if let Some(active_occurrence) = data_store
+                .active_occurrences
+                .values_mut()
+                .find(|active_occurrence| active_occurrence.staff_ids.contains(staff_id))
+            {
+                active_occurrence.staff_ids.retain(|id| id != staff_id);
+                active_occurrence
+                    .vehicle_assignment_map
+                    .values_mut()
+                    .for_each(|vehicle_crew| {
+                        vehicle_crew.retain(|assignment| &assignment.staff_id != staff_id);
+                    });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn delete_team(data_store: &mut DataStore, team_id: &String) -> anyhow::Result<()> {
+    data_store
+        .shift_templates
+        .values_mut()
+        .for_each(|shift_template| {
+            shift_template.team_allocations.remove(team_id);
+        });
+
+    if let Some(removed_team) = data_store.teams.remove(team_id) {
+        if removed_team.state == TeamState::Dispatched {
+            // Update active occurrence
+            if let Some(active_occurrence) = data_store
+                .active_occurrences
+                .values_mut()
+                .find(|active_occurrence| active_occurrence.team_ids.contains(team_id))
+            {
+                active_occurrence.team_ids.retain(|id| id != team_id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn delete_vehicle(data_store: &mut DataStore, vehicle_id: &String) -> anyhow::Result<()> {
+    let vehicle_target = CrewRequirementTarget::Vehicle {
+        vehicle_id: vehicle_id.clone(),
+    };
+    data_store
+        .crew_requirements
+        .remove(&vehicle_target.requirement_id());
+
+    if let Some(removed_vehicle) = data_store.vehicles.remove(vehicle_id) {
+        if removed_vehicle.state == VehicleState::Dispatched {
+            // Update active occurrence
+            if let Some(active_occurrence) = data_store
+                .active_occurrences
+                .values_mut()
+                .find(|active_occurrence| active_occurrence.vehicle_ids.contains(vehicle_id))
+            {
+                active_occurrence.vehicle_ids.retain(|id| id != vehicle_id);
+                active_occurrence.vehicle_assignment_map.remove(vehicle_id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates staff without an internal id and replaces the others, returns the ids in order
+pub fn import_staff(
+    data_store: &mut DataStore,
+    staff_list: &[Staff],
+) -> anyhow::Result<Vec<String>> {
+    let mut staff_ids = vec![];
+    for staff in staff_list.iter() {
+        if staff.internal_id.is_empty() {
+            staff_ids.push(create_staff(data_store, staff.clone())?);
+            continue;
+        }
+
+        if !data_store.staff.contains_key(&staff.internal_id) {
+            anyhow::bail!("Failed to get staff with id: {}", staff.internal_id);
+        }
+        update_staff(data_store, &staff.internal_id, staff.clone())?;
+        staff_ids.push(staff.internal_id.clone());
+    }
+
+    Ok(staff_ids)
+}
+
+fn get_staff_mut<'a>(
+    data_store: &'a mut DataStore,
+    staff_id: &String,
+) -> Result<&'a mut Staff, StoreError> {
+    data_store
+        .staff
+        .get_mut(staff_id)
+        .ok_or_else(|| StoreError::UnknownStaff {
+            staff_id: staff_id.clone(),
+        })
+}
+
+fn validate_qualification(qualification: &Qualification) -> Result<(), StoreError> {
+    match qualification.expiry_time {
+        Some(expiry_time) if expiry_time < qualification.issue_time => {
+            Err(StoreError::InvalidQualificationPeriod {
+                qualification_id: qualification.internal_id.clone(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn add_staff_qualification(
+    data_store: &mut DataStore,
+    staff_id: &String,
+    mut qualification: Qualification,
+) -> anyhow::Result<String> {
+    let qualification_id = uuid::Uuid::new_v4().to_string();
+    qualification.internal_id = qualification_id.clone();
+    validate_qualification(&qualification)?;
+
+    get_staff_mut(data_store, staff_id)?
+        .qualifications
+        .push(qualification);
+
+    Ok(qualification_id)
+}
+
+pub fn update_staff_qualification(
+    data_store: &mut DataStore,
+    staff_id: &String,
+    qualification: Qualification,
+) -> anyhow::Result<Qualification> {
+    validate_qualification(&qualification)?;
+
+    let staff = get_staff_mut(data_store, staff_id)?;
+    let existing_qualification = staff
+        .qualifications
+        .iter_mut()
+        .find(|existing_qualification| {
+            existing_qualification.internal_id == qualification.internal_id
+        })
+        .ok_or_else(|| StoreError::UnknownQualification {
+            staff_id: staff_id.clone(),
+            qualification_id: qualification.internal_id.clone(),
+        })?;
+
+    Ok(std::mem::replace(existing_qualification, qualification))
+}
+
+pub fn remove_staff_qualification(
+    data_store: &mut DataStore,
+    staff_id: &String,
+    qualification_id: &String,
+) -> anyhow::Result<()> {
+    let staff = get_staff_mut(data_store, staff_id)?;
+    let qualification_count = staff.qualifications.len();
+    staff
+        .qualifications
+        .retain(|qualification| qualification.internal_id != *qualification_id);
+    if staff.qualifications.len() == qualification_count {
+        return Err(StoreError::UnknownQualification {
+            staff_id: staff_id.clone(),
+            qualification_id: qualification_id.clone(),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+fn validate_leave_period(leave_period: &LeavePeriod) -> Result<(), StoreError> {
+    if leave_period.end_time <= leave_period.start_time {
+        return Err(StoreError::InvalidLeavePeriod {
+            leave_period_id: leave_period.internal_id.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+pub fn add_staff_leave(
+    data_store: &mut DataStore,
+    staff_id: &String,
+    mut leave_period: LeavePeriod,
+) -> anyhow::Result<String> {
+    let leave_period_id = uuid::Uuid::new_v4().to_string();
+    leave_period.internal_id = leave_period_id.clone();
+    leave_period.status = LeaveStatus::Scheduled;
+    validate_leave_period(&leave_period)?;
+
+    get_staff_mut(data_store, staff_id)?
+        .leave_periods
+        .push(leave_period);
+
+    Ok(leave_period_id)
+}
+
+/// Replaces the dates and reason of a leave period, its status stays with the leave scheduler
+pub fn update_staff_leave(
+    data_store: &mut DataStore,
+    staff_id: &String,
+    mut leave_period: LeavePeriod,
+) -> anyhow::Result<LeavePeriod> {
+    validate_leave_period(&leave_period)?;
+
+    let staff = get_staff_mut(data_store, staff_id)?;
+    let existing_leave_period = staff
+        .leave_periods
+        .iter_mut()
+        .find(|existing_leave_period| existing_leave_period.internal_id == leave_period.internal_id)
+        .ok_or_else(|| StoreError::UnknownLeavePeriod {
+            staff_id: staff_id.clone(),
+            leave_period_id: leave_period.internal_id.clone(),
+        })?;
+    leave_period.status = existing_leave_period.status;
+
+    Ok(std::mem::replace(existing_leave_period, leave_period))
+}
+
+pub fn remove_staff_leave(
+    data_store: &mut DataStore,
+    staff_id: &String,
+    leave_period_id: &String,
+) -> anyhow::Result<()> {
+    let staff = get_staff_mut(data_store, staff_id)?;
+    let leave_period_index = staff
+        .leave_periods
+        .iter()
+        .position(|leave_period| leave_period.internal_id == *leave_period_id)
+        .ok_or_else(|| StoreError::UnknownLeavePeriod {
+            staff_id: staff_id.clone(),
+            leave_period_id: leave_period_id.clone(),
+        })?;
+
+    // Cancelling an ongoing leave releases the staff member right away
+    let removed_leave_period = staff.leave_periods.remove(leave_period_index);
+    leave::settle_staff_state(staff, removed_leave_period.status == LeaveStatus::Ongoing);
+
+    Ok(())
+}
+
+/// Creates or replaces the requirement of its target, returns the requirement id
+pub fn set_crew_requirement(
+    data_store: &mut DataStore,
+    mut crew_requirement: CrewRequirement,
+) -> anyhow::Result<String> {
+    if let CrewRequirementTarget::Vehicle { vehicle_id } = &crew_requirement.target {
+        if !data_store.vehicles.contains_key(vehicle_id) {
+            return Err(StoreError::UnknownVehicle {
+                vehicle_id: vehicle_id.clone(),
+            }
+            .into());
+        }
+    }
+
+    let crew_requirement_id = crew_requirement.target.requirement_id();
+    if let (Some(min_crew), Some(max_crew)) = (crew_requirement.min_crew, crew_requirement.max_crew)
+    {
+        if min_crew > max_crew {
+            return Err(StoreError::InvalidCrewRange {
+                crew_requirement_id,
+            }
+            .into());
+        }
+    }
+
+    crew_requirement.internal_id = crew_requirement_id.clone();
+    data_store
+        .crew_requirements
+        .insert(crew_requirement_id.clone(), crew_requirement);
+
+    Ok(crew_requirement_id)
+}
+
+pub fn delete_crew_requirement(
+    data_store: &mut DataStore,
+    crew_requirement_id: &String,
+) -> anyhow::Result<()> {
+    data_store.crew_requirements.remove(crew_requirement_id);
+
+    Ok(())
+}
+
+pub fn set_staff_shift(
+    data_store: &mut DataStore,
+    available_staff: &[String],
+    team_allocations: &HashMap<String, Vec<String>>,
+) -> anyhow::Result<()> {
+    data_store.staff.values_mut().for_each(|staff| {
+        // Staff on leave stay out until the leave scheduler releases them
+        if staff.is_on_leave() {
+            return;
+        }
+
+        if (staff.state == StaffState::Available) || (staff.state == StaffState::Unavailable) {
+            staff.state = if available_staff.contains(&staff.internal_id) {
+                StaffState::Available
+            } else {
+                StaffState::Unavailable
+            }
+        }
+    });
+
+    data_store.teams.values_mut().for_each(|team| {
+        if (team.state == TeamState::Available) || (team.state == TeamState::Unavailable) {
+            if let Some(member_ids) = team_allocations.get(&team.internal_id) {
+                team.state = TeamState::Available;
+                team.member_ids = member_ids.clone();
+            } else {
+                team.state = TeamState::Unavailable;
+                team.member_ids = vec![];
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Creates or replaces a shift template, returns the template id. Handovers that are already past
+/// at `time` count as applied, saving a template never rewrites the current shift.
+pub fn set_shift_template(
+    data_store: &mut DataStore,
+    mut shift_template: ShiftTemplate,
+    time: u128,
+) -> anyhow::Result<String> {
+    let shift_template_id = if shift_template.internal_id.is_empty() {
+        uuid::Uuid::new_v4().to_string()
+    } else if data_store
+        .shift_templates
+        .contains_key(&shift_template.internal_id)
+    {
+        shift_template.internal_id.clone()
+    } else {
+        return Err(StoreError::UnknownShiftTemplate {
+            shift_template_id: shift_template.internal_id.clone(),
+        }
+        .into());
+    };
+
+    if !shift_template
+        .schedule
+        .iter()
+        .all(|recurrence| recurrence.is_valid())
+    {
+        return Err(StoreError::InvalidShiftSchedule { shift_template_id }.into());
+    }
+    if let Some(staff_id) = shift_template
+        .available_staff
+        .iter()
+        .chain(shift_template.team_allocations.values().flatten())
+        .find(|staff_id| !data_store.staff.contains_key(*staff_id))
+    {
+        return Err(StoreError::UnknownStaff {
+            staff_id: staff_id.clone(),
+        }
+        .into());
+    }
+    if let Some(team_id) = shift_template
+        .team_allocations
+        .keys()
+        .find(|team_id| !data_store.teams.contains_key(*team_id))
+    {
+        return Err(StoreError::UnknownTeam {
+            team_id: team_id.clone(),
+        }
+        .into());
+    }
+
+    let previous_handover = data_store
+        .shift_templates
+        .get(&shift_template_id)
+        .and_then(|previous_template| previous_template.last_handover);
+    shift_template.last_handover = shift_template.latest_handover(time).max(previous_handover);
+    shift_template.internal_id = shift_template_id.clone();
+    data_store
+        .shift_templates
+        .insert(shift_template_id.clone(), shift_template);
+
+    Ok(shift_template_id)
+}
+
+pub fn delete_shift_template(
+    data_store: &mut DataStore,
+    shift_template_id: &String,
+) -> anyhow::Result<()> {
+    data_store.shift_templates.remove(shift_template_id);
+
+    Ok(())
+}
+
+/// Hands the station over to the roster of a template, through the same transitions as a manual shift
+pub fn apply_shift_template(
+    data_store: &mut DataStore,
+    shift_template_id: &String,
+    handover_time: u128,
+) -> anyhow::Result<()> {
+    let shift_template = data_store
+        .shift_templates
+        .get_mut(shift_template_id)
+        .ok_or_else(|| StoreError::UnknownShiftTemplate {
+            shift_template_id: shift_template_id.clone(),
+        })?;
+    shift_template.last_handover = Some(handover_time).max(shift_template.last_handover);
+    let available_staff = shift_template.available_staff.clone();
+    let team_allocations = shift_template.team_allocations.clone();
+
+    set_staff_shift(data_store, &available_staff, &team_allocations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::types::{CrewAssignment, LeaveReason, VehicleCategory};
+
+    fn store() -> DataStore {
+        fixtures::data_store(
+            vec![fixtures::occurrence("occurrence")],
+            vec![
+                fixtures::staff("staff-1"),
+                fixtures::staff("staff-2"),
+                fixtures::staff("staff-3"),
+            ],
+            vec![],
+            vec![
+                fixtures::vehicle("vehicle-1", VehicleCategory::FireFight, Some(5)),
+                fixtures::vehicle("vehicle-2", VehicleCategory::Ambulances, Some(3)),
+            ],
+        )
+    }
+
+    fn dispatch(data_store: &mut DataStore, vehicle_crews: &[(&str, &[&str])]) -> String {
+        let active_occurrence = fixtures::active_occurrence("", "occurrence", vehicle_crews);
+        create_active_occurrence(data_store, active_occurrence)
+            .unwrap()
+            .active_occurrence_id
+    }
+
+    fn store_error(error: anyhow::Error) -> StoreError {
+        error.downcast::<StoreError>().unwrap()
+    }
+
+    fn record(
+        data_store: &mut DataStore,
+        active_occurrence_id: &String,
+        vehicle_id: &str,
+        status: VehicleStatus,
+        time: u128,
+    ) -> anyhow::Result<()> {
+        record_vehicle_status(
+            data_store,
+            active_occurrence_id,
+            &vehicle_id.to_string(),
+            status,
+            time,
+        )
+    }
+
+    #[test]
+    fn dispatched_vehicles_start_their_timeline_alerted() {
+        let mut data_store = store();
+        let active_occurrence_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+
+        let active_occurrence = &data_store.active_occurrences[&active_occurrence_id];
+        assert_eq!(
+            active_occurrence.vehicle_status(&String::from("vehicle-1")),
+            Some(VehicleStatus::Alerted)
+        );
+        assert_eq!(
+            data_store.vehicles["vehicle-1"].state,
+            VehicleState::Dispatched
+        );
+    }
+
+    #[test]
+    fn vehicle_statuses_follow_each_other_until_back_at_base() {
+        let mut data_store = store();
+        let active_occurrence_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+        let now = data_store.active_occurrences[&active_occurrence_id].vehicle_timelines
+            ["vehicle-1"][0]
+            .time;
+
+        record(
+            &mut data_store,
+            &active_occurrence_id,
+            "vehicle-1",
+            VehicleStatus::Departed,
+            now,
+        )
+        .unwrap();
+        for (status, time) in [
+            (VehicleStatus::Departed, now + 1),
+            (VehicleStatus::Alerted, now + 1),
+            (VehicleStatus::OnScene, now - 1),
+        ] {
+            let error = record(
+                &mut data_store,
+                &active_occurrence_id,
+                "vehicle-1",
+                status,
+                time,
+            )
+            .unwrap_err();
+            assert!(matches!(
+                store_error(error),
+                StoreError::InvalidVehicleStatus { .. }
+            ));
+        }
+
+        record(
+            &mut data_store,
+            &active_occurrence_id,
+            "vehicle-1",
+            VehicleStatus::AtBase,
+            now + 2,
+        )
+        .unwrap();
+        assert_eq!(
+            data_store.vehicles["vehicle-1"].state,
+            VehicleState::Available
+        );
+
+        let error = record(
+            &mut data_store,
+            &active_occurrence_id,
+            "vehicle-1",
+            VehicleStatus::Returning,
+            now + 3,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            store_error(error),
+            StoreError::InvalidVehicleStatus { .. }
+        ));
+    }
+
+    #[test]
+    fn vehicle_statuses_are_only_recorded_for_vehicles_of_the_occurrence() {
+        let mut data_store = store();
+        let active_occurrence_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+
+        let error = record(
+            &mut data_store,
+            &active_occurrence_id,
+            "vehicle-2",
+            VehicleStatus::Departed,
+            0,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            store_error(error),
+            StoreError::VehicleNotInOccurrence { .. }
+        ));
+    }
+
+    #[test]
+    fn vehicles_leave_an_updated_occurrence_only_once_back_at_base() {
+        let mut data_store = store();
+        let active_occurrence_id = dispatch(
+            &mut data_store,
+            &[("vehicle-1", &["staff-1"]), ("vehicle-2", &["staff-2"])],
+        );
+        let without_vehicle_2 =
+            fixtures::active_occurrence("", "occurrence", &[("vehicle-1", &["staff-1"])]);
+
+        let error = update_active_occurrence(
+            &mut data_store,
+            &active_occurrence_id,
+            without_vehicle_2.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            store_error(error),
+            StoreError::VehiclesNotAtBase {
+                vehicle_ids: vec![String::from("vehicle-2")]
+            }
+        );
+
+        record(
+            &mut data_store,
+            &active_occurrence_id,
+            "vehicle-2",
+            VehicleStatus::AtBase,
+            u128::MAX,
+        )
+        .unwrap();
+        update_active_occurrence(&mut data_store, &active_occurrence_id, without_vehicle_2)
+            .unwrap();
+        assert_eq!(
+            data_store.vehicles["vehicle-2"].state,
+            VehicleState::Available
+        );
+        assert_eq!(
+            data_store.vehicles["vehicle-1"].state,
+            VehicleState::Dispatched
+        );
+    }
+
+    #[test]
+    fn occurrences_are_deleted_only_once_their_vehicles_are_back_at_base() {
+        let mut data_store = store();
+        let active_occurrence_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+
+        let error = delete_active_occurrence(&mut data_store, &active_occurrence_id).unwrap_err();
+        assert!(matches!(
+            store_error(error),
+            StoreError::VehiclesNotAtBase { .. }
+        ));
+
+        record(
+            &mut data_store,
+            &active_occurrence_id,
+            "vehicle-1",
+            VehicleStatus::AtBase,
+            u128::MAX,
+        )
+        .unwrap();
+        delete_active_occurrence(&mut data_store, &active_occurrence_id).unwrap();
+        assert!(data_store.active_occurrences.is_empty());
+        assert_eq!(data_store.staff["staff-1"].state, StaffState::Available);
+    }
+
+    #[test]
+    fn vehicles_without_a_timeline_are_released_as_before() {
+        let mut data_store = store();
+        let active_occurrence_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+        data_store
+            .active_occurrences
+            .get_mut(&active_occurrence_id)
+            .unwrap()
+            .vehicle_timelines
+            .clear();
+
+        let archived_occurrence =
+            close_active_occurrence(&mut data_store, &active_occurrence_id, 10).unwrap();
+        assert_eq!(archived_occurrence.end_time, 10);
+        assert_eq!(
+            data_store.vehicles["vehicle-1"].state,
+            VehicleState::Available
+        );
+    }
+
+    fn transfer(
+        from_active_occurrence_id: &str,
+        to_active_occurrence_id: &str,
+        vehicle_ids: &[&str],
+        staff_ids: &[&str],
+    ) -> ResourceTransfer {
+        ResourceTransfer {
+            from_active_occurrence_id: from_active_occurrence_id.to_string(),
+            to_active_occurrence_id: to_active_occurrence_id.to_string(),
+            staff_ids: staff_ids.iter().map(|id| id.to_string()).collect(),
+            team_ids: vec![],
+            vehicle_ids: vehicle_ids.iter().map(|id| id.to_string()).collect(),
+            time: 20,
+        }
+    }
+
+    #[test]
+    fn transferred_vehicles_take_their_crew_along() {
+        let mut data_store = store();
+        let from_id = dispatch(
+            &mut data_store,
+            &[("vehicle-1", &["staff-1", "staff-2"]), ("vehicle-2", &[])],
+        );
+        let to_id = dispatch(&mut data_store, &[]);
+
+        let applied_transfer = transfer_resources(
+            &mut data_store,
+            transfer(&from_id, &to_id, &["vehicle-1"], &[]),
+        )
+        .unwrap();
+        assert_eq!(applied_transfer.staff_ids, vec!["staff-1", "staff-2"]);
+
+        let source_occurrence = &data_store.active_occurrences[&from_id];
+        assert_eq!(source_occurrence.vehicle_ids, vec!["vehicle-2"]);
+        assert!(source_occurrence.staff_ids.is_empty());
+        assert_eq!(
+            source_occurrence.resource_transfers,
+            vec![applied_transfer.clone()]
+        );
+
+        let target_occurrence = &data_store.active_occurrences[&to_id];
+        assert_eq!(target_occurrence.vehicle_ids, vec!["vehicle-1"]);
+        assert_eq!(
+            target_occurrence.vehicle_assignment_map["vehicle-1"].len(),
+            2
+        );
+        assert_eq!(target_occurrence.resource_transfers, vec![applied_transfer]);
+        assert_eq!(
+            target_occurrence.vehicle_timelines["vehicle-1"].last(),
+            Some(&VehicleStatusChange {
+                status: VehicleStatus::Alerted,
+                time: 20
+            })
+        );
+        // The timeline stays with the source occurrence for its response times
+        assert!(source_occurrence
+            .vehicle_timelines
+            .contains_key("vehicle-1"));
+    }
+
+    #[test]
+    fn transferred_staff_leave_the_crews_of_the_source_occurrence() {
+        let mut data_store = store();
+        let from_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1", "staff-2"])]);
+        let to_id = dispatch(&mut data_store, &[]);
+
+        transfer_resources(
+            &mut data_store,
+            transfer(&from_id, &to_id, &[], &["staff-2"]),
+        )
+        .unwrap();
+
+        let source_occurrence = &data_store.active_occurrences[&from_id];
+        assert_eq!(source_occurrence.staff_ids, vec!["staff-1"]);
+        assert_eq!(
+            source_occurrence.vehicle_assignment_map["vehicle-1"].len(),
+            1
+        );
+        assert_eq!(
+            data_store.active_occurrences[&to_id].staff_ids,
+            vec!["staff-2"]
+        );
+        assert_eq!(data_store.staff["staff-2"].state, StaffState::Dispatched);
+    }
+
+    #[test]
+    fn invalid_transfers_are_refused() {
+        let mut data_store = store();
+        let from_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+        let to_id = dispatch(&mut data_store, &[]);
+
+        let error = transfer_resources(
+            &mut data_store,
+            transfer(&from_id, &from_id, &["vehicle-1"], &[]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            store_error(error),
+            StoreError::InvalidResourceTransfer { .. }
+        ));
+
+        let error =
+            transfer_resources(&mut data_store, transfer(&from_id, &to_id, &[], &[])).unwrap_err();
+        assert!(matches!(
+            store_error(error),
+            StoreError::InvalidResourceTransfer { .. }
+        ));
+
+        let error = transfer_resources(
+            &mut data_store,
+            transfer(&from_id, &to_id, &["vehicle-2"], &[]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            store_error(error),
+            StoreError::VehicleNotInOccurrence { .. }
+        ));
+    }
+
+    #[test]
+    fn dispatching_resources_of_another_occurrence_transfers_them() {
+        let mut data_store = store();
+        let from_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+
+        let active_occurrence =
+            fixtures::active_occurrence("", "occurrence", &[("vehicle-1", &["staff-1"])]);
+        let dispatch_report = create_active_occurrence(&mut data_store, active_occurrence).unwrap();
+
+        assert_eq!(dispatch_report.transfers.len(), 1);
+        assert_eq!(
+            dispatch_report.transfers[0].from_active_occurrence_id,
+            from_id
+        );
+        assert!(data_store.active_occurrences[&from_id]
+            .vehicle_ids
+            .is_empty());
+        assert_eq!(
+            data_store.active_occurrences[&dispatch_report.active_occurrence_id].vehicle_ids,
+            vec!["vehicle-1"]
+        );
+    }
+
+    fn release(vehicle_ids: &[&str], staff_ids: &[&str]) -> ResourceRelease {
+        ResourceRelease {
+            staff_ids: staff_ids.iter().map(|id| id.to_string()).collect(),
+            team_ids: vec![],
+            vehicle_ids: vehicle_ids.iter().map(|id| id.to_string()).collect(),
+            time: 30,
+        }
+    }
+
+    #[test]
+    fn released_vehicles_go_back_with_their_crew() {
+        let mut data_store = store();
+        let active_occurrence_id = dispatch(
+            &mut data_store,
+            &[
+                ("vehicle-1", &["staff-1", "staff-2"]),
+                ("vehicle-2", &["staff-3"]),
+            ],
+        );
+
+        let applied_release = release_resources(
+            &mut data_store,
+            &active_occurrence_id,
+            release(&["vehicle-1"], &[]),
+        )
+        .unwrap();
+        assert_eq!(applied_release.staff_ids, vec!["staff-1", "staff-2"]);
+
+        let active_occurrence = &data_store.active_occurrences[&active_occurrence_id];
+        assert_eq!(active_occurrence.vehicle_ids, vec!["vehicle-2"]);
+        assert_eq!(active_occurrence.staff_ids, vec!["staff-3"]);
+        assert!(!active_occurrence
+            .vehicle_assignment_map
+            .contains_key("vehicle-1"));
+        assert_eq!(active_occurrence.resource_releases, vec![applied_release]);
+        // Released resources still count as having served on the occurrence
+        assert!(active_occurrence
+            .involved_vehicle_ids()
+            .contains(&String::from("vehicle-1")));
+        assert!(active_occurrence
+            .involved_staff_ids()
+            .contains(&String::from("staff-2")));
+
+        assert_eq!(
+            data_store.vehicles["vehicle-1"].state,
+            VehicleState::Available
+        );
+        assert_eq!(data_store.staff["staff-1"].state, StaffState::Available);
+        assert_eq!(data_store.staff["staff-3"].state, StaffState::Dispatched);
+    }
+
+    #[test]
+    fn released_staff_leave_the_crews() {
+        let mut data_store = store();
+        let active_occurrence_id =
+            dispatch(&mut data_store, &[("vehicle-1", &["staff-1", "staff-2"])]);
+
+        release_resources(
+            &mut data_store,
+            &active_occurrence_id,
+            release(&[], &["staff-2"]),
+        )
+        .unwrap();
+
+        let active_occurrence = &data_store.active_occurrences[&active_occurrence_id];
+        assert_eq!(active_occurrence.vehicle_ids, vec!["vehicle-1"]);
+        assert_eq!(
+            active_occurrence.vehicle_assignment_map["vehicle-1"],
+            vec![CrewAssignment {
+                staff_id: String::from("staff-1"),
+                role: CrewRole::Crew,
+            }]
+        );
+        assert_eq!(data_store.staff["staff-2"].state, StaffState::Available);
+    }
+
+    fn start_sick_leave(data_store: &mut DataStore, staff_id: &str) {
+        data_store
+            .staff
+            .get_mut(staff_id)
+            .unwrap()
+            .leave_periods
+            .push(LeavePeriod {
+                internal_id: String::from("leave"),
+                reason: LeaveReason::Sick,
+                start_time: 0,
+                end_time: u128::MAX,
+                status: LeaveStatus::Ongoing,
+            });
+    }
+
+    #[test]
+    fn released_staff_go_on_the_leave_that_started_meanwhile() {
+        let mut data_store = store();
+        let active_occurrence_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+        start_sick_leave(&mut data_store, "staff-1");
+
+        release_resources(
+            &mut data_store,
+            &active_occurrence_id,
+            release(&[], &["staff-1"]),
+        )
+        .unwrap();
+        assert_eq!(data_store.staff["staff-1"].state, StaffState::SickLeave);
+    }
+
+    #[test]
+    fn staff_removed_from_an_occurrence_go_on_the_leave_that_started_meanwhile() {
+        let mut data_store = store();
+        let active_occurrence_id =
+            dispatch(&mut data_store, &[("vehicle-1", &["staff-1", "staff-2"])]);
+        start_sick_leave(&mut data_store, "staff-2");
+
+        let without_staff_2 =
+            fixtures::active_occurrence("", "occurrence", &[("vehicle-1", &["staff-1"])]);
+        update_active_occurrence(&mut data_store, &active_occurrence_id, without_staff_2).unwrap();
+        assert_eq!(data_store.staff["staff-1"].state, StaffState::Dispatched);
+        assert_eq!(data_store.staff["staff-2"].state, StaffState::SickLeave);
+    }
+
+    #[test]
+    fn staff_of_a_closed_occurrence_go_on_the_leave_that_started_meanwhile() {
+        let mut data_store = store();
+        let active_occurrence_id =
+            dispatch(&mut data_store, &[("vehicle-1", &["staff-1", "staff-2"])]);
+        start_sick_leave(&mut data_store, "staff-2");
+        record(
+            &mut data_store,
+            &active_occurrence_id,
+            "vehicle-1",
+            VehicleStatus::AtBase,
+            u128::MAX,
+        )
+        .unwrap();
+
+        close_active_occurrence(&mut data_store, &active_occurrence_id, 10).unwrap();
+        assert_eq!(data_store.staff["staff-1"].state, StaffState::Available);
+        assert_eq!(data_store.staff["staff-2"].state, StaffState::SickLeave);
+    }
+
+    #[test]
+    fn invalid_releases_are_refused() {
+        let mut data_store = store();
+        let active_occurrence_id = dispatch(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+
+        let error = release_resources(&mut data_store, &active_occurrence_id, release(&[], &[]))
+            .unwrap_err();
+        assert!(matches!(
+            store_error(error),
+            StoreError::EmptyResourceRelease { .. }
+        ));
+
+        let error = release_resources(
+            &mut data_store,
+            &active_occurrence_id,
+            release(&[], &["staff-2"]),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            store_error(error),
+            StoreError::StaffNotInOccurrence { .. }
+        ));
+    }
+
+    fn team_store() -> DataStore {
+        let mut data_store = store();
+        data_store.teams.insert(
+            String::from("team"),
+            fixtures::team("team", &["staff-2", "staff-3"]),
+        );
+        data_store
+    }
+
+    fn dispatch_team(data_store: &mut DataStore, vehicle_crews: &[(&str, &[&str])]) -> String {
+        let mut active_occurrence = fixtures::active_occurrence("", "occurrence", vehicle_crews);
+        active_occurrence.team_ids = vec![String::from("team")];
+        create_active_occurrence(data_store, active_occurrence)
+            .unwrap()
+            .active_occurrence_id
+    }
+
+    #[test]
+    fn teams_are_dispatched_with_their_members() {
+        let mut data_store = team_store();
+        let active_occurrence_id = dispatch_team(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+
+        let active_occurrence = &data_store.active_occurrences[&active_occurrence_id];
+        assert_eq!(
+            active_occurrence.staff_ids,
+            vec!["staff-1", "staff-2", "staff-3"]
+        );
+        assert_eq!(data_store.teams["team"].state, TeamState::Dispatched);
+        assert_eq!(data_store.staff["staff-3"].state, StaffState::Dispatched);
+    }
+
+    #[test]
+    fn members_leave_with_their_team_unless_they_crew_a_vehicle() {
+        let mut data_store = team_store();
+        let active_occurrence_id =
+            dispatch_team(&mut data_store, &[("vehicle-1", &["staff-1", "staff-2"])]);
+
+        let without_team = fixtures::active_occurrence(
+            "",
+            "occurrence",
+            &[("vehicle-1", &["staff-1", "staff-2"])],
+        );
+        update_active_occurrence(&mut data_store, &active_occurrence_id, without_team).unwrap();
+
+        let active_occurrence = &data_store.active_occurrences[&active_occurrence_id];
+        assert!(active_occurrence.team_ids.is_empty());
+        assert_eq!(active_occurrence.staff_ids, vec!["staff-1", "staff-2"]);
+        assert_eq!(data_store.teams["team"].state, TeamState::Available);
+        assert_eq!(data_store.staff["staff-2"].state, StaffState::Dispatched);
+        assert_eq!(data_store.staff["staff-3"].state, StaffState::Available);
+    }
+
+    #[test]
+    fn released_teams_take_their_members_back() {
+        let mut data_store = team_store();
+        let active_occurrence_id = dispatch_team(&mut data_store, &[("vehicle-1", &["staff-1"])]);
+
+        let applied_release = release_resources(
+            &mut data_store,
+            &active_occurrence_id,
+            ResourceRelease {
+                team_ids: vec![String::from("team")],
+                ..ResourceRelease::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(applied_release.staff_ids, vec!["staff-2", "staff-3"]);
+
+        assert_eq!(
+            data_store.active_occurrences[&active_occurrence_id].staff_ids,
+            vec!["staff-1"]
+        );
+        assert_eq!(data_store.teams["team"].state, TeamState::Available);
+        assert_eq!(data_store.staff["staff-2"].state, StaffState::Available);
+    }
+
+    #[test]
+    fn deleted_occurrences_free_their_teams() {
+        let mut data_store = team_store();
+        let active_occurrence_id = dispatch_team(&mut data_store, &[]);
+
+        delete_active_occurrence(&mut data_store, &active_occurrence_id).unwrap();
+        assert_eq!(data_store.teams["team"].state, TeamState::Available);
+        assert_eq!(data_store.staff["staff-3"].state, StaffState::Available);
+    }
+}
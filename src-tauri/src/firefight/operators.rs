@@ -0,0 +1,512 @@
+use anyhow::Context;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+};
+use tauri::AppHandle;
+
+use super::errors::StoreError;
+use super::types::{FirefightDataManager, Staff, StaffPermission};
+
+const CREDENTIALS_FILE: &str = "operators.json";
+
+/// What a command needs from the operator that calls it
+#[derive(Clone, Copy, Debug)]
+pub enum Access<'a> {
+    /// Dispatch board work, active occurrences, shifts and alerts
+    Dispatch,
+    /// Changes to a single staff record
+    EditStaff(&'a String),
+    /// Everything else that changes the station
+    Manage,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorSession {
+    pub staff_id: String,
+    pub login_time: u128,
+}
+
+#[derive(Default)]
+struct OperatorState {
+    /// PHC encoded password hashes by staff id
+    credentials: HashMap<String, String>,
+    session: Option<OperatorSession>,
+}
+
+/// Operator credentials and the current session. Kept apart from the DataStore so password
+/// hashes are never broadcast, journaled or included in backups and bundles.
+pub struct OperatorRegistry {
+    credentials_path: PathBuf,
+    state: Mutex<OperatorState>,
+}
+
+pub fn permits(permission: &StaffPermission, operator_id: &String, access: Access) -> bool {
+    match (permission, access) {
+        (StaffPermission::All, _) => true,
+        (StaffPermission::Shift, Access::Dispatch) => true,
+        (StaffPermission::Shift | StaffPermission::Own, Access::EditStaff(staff_id)) => {
+            staff_id == operator_id
+        }
+        _ => false,
+    }
+}
+
+fn hash_secret(secret: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(secret.as_bytes(), &salt)
+        .map(|password_hash| password_hash.to_string())
+        .map_err(|hash_error| anyhow::anyhow!("Failed to hash operator secret: {}", hash_error))
+}
+
+fn verify_secret(secret: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(password_hash) => Argon2::default()
+            .verify_password(secret.as_bytes(), &password_hash)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+pub fn open_registry(app_handle: &AppHandle) -> anyhow::Result<OperatorRegistry> {
+    let data_dir = app_handle
+        .path_resolver()
+        .app_local_data_dir()
+        .with_context(|| "Unable to resolve the app local data directory".to_string())?;
+    std::fs::create_dir_all(&data_dir)
+        .with_context(|| format!("Failed to create data directory {:?}", data_dir))?;
+
+    let credentials_path = data_dir.join(CREDENTIALS_FILE);
+    let credentials = match std::fs::read(&credentials_path) {
+        Ok(contents) => serde_json::from_slice(&contents).with_context(|| {
+            format!(
+                "Failed to parse operator credentials {:?}",
+                credentials_path
+            )
+        })?,
+        Err(read_error) if read_error.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+        Err(read_error) => {
+            return Err(read_error).with_context(|| {
+                format!("Failed to read operator credentials {:?}", credentials_path)
+            })
+        }
+    };
+
+    Ok(OperatorRegistry {
+        credentials_path,
+        state: Mutex::new(OperatorState {
+            credentials,
+            session: None,
+        }),
+    })
+}
+
+impl OperatorRegistry {
+    fn state(&self) -> anyhow::Result<MutexGuard<'_, OperatorState>> {
+        self.state
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Operator registry is poisoned"))
+    }
+
+    fn write_credentials(&self, credentials: &HashMap<String, String>) -> anyhow::Result<()> {
+        let partial_path = self.credentials_path.with_extension("partial");
+        std::fs::write(&partial_path, serde_json::to_vec(credentials)?)
+            .with_context(|| format!("Failed to write operator credentials {:?}", partial_path))?;
+        std::fs::rename(&partial_path, &self.credentials_path).with_context(|| {
+            format!(
+                "Failed to move operator credentials to {:?}",
+                self.credentials_path
+            )
+        })
+    }
+
+    /// Logs the staff member in, replacing any previous session
+    pub fn login(
+        &self,
+        store: &dyn FirefightDataManager,
+        staff_id: &String,
+        secret: &str,
+    ) -> anyhow::Result<OperatorSession> {
+        let mut state = self.state()?;
+        let password_hash = state
+            .credentials
+            .get(staff_id)
+            .ok_or(StoreError::InvalidCredentials)?;
+        if !verify_secret(secret, password_hash) {
+            return Err(StoreError::InvalidCredentials.into());
+        }
+
+        let staff = store
+            .get_staff(staff_id)
+            .map_err(|_| StoreError::InvalidCredentials)?;
+        if staff.permission == StaffPermission::None {
+            return Err(StoreError::PermissionDenied {
+                permission: staff.permission,
+            }
+            .into());
+        }
+
+        let session = OperatorSession {
+            staff_id: staff_id.clone(),
+            login_time: std::time::UNIX_EPOCH.elapsed()?.as_millis(),
+        };
+        state.session = Some(session.clone());
+        Ok(session)
+    }
+
+    pub fn logout(&self) -> anyhow::Result<()> {
+        self.state()?.session = None;
+        Ok(())
+    }
+
+    pub fn session(&self) -> anyhow::Result<Option<OperatorSession>> {
+        Ok(self.state()?.session.clone())
+    }
+
+    /// Staff record of the logged in operator, `None` while access control is not set up yet
+    fn operator(&self, store: &dyn FirefightDataManager) -> anyhow::Result<Option<Staff>> {
+        let mut state = self.state()?;
+        // Until the first credential is set the console stays open, as it was before logins
+        if state.credentials.is_empty() {
+            return Ok(None);
+        }
+
+        let operator_id = match &state.session {
+            Some(session) => session.staff_id.clone(),
+            None => return Err(StoreError::NotLoggedIn.into()),
+        };
+        match store.get_staff(&operator_id) {
+            Ok(staff) => Ok(Some(staff)),
+            Err(_) => {
+                // The operator was removed from the roster in the meantime
+                state.session = None;
+                Err(StoreError::NotLoggedIn.into())
+            }
+        }
+    }
+
+    /// Checked against the current permission of the operator, changes apply immediately
+    pub fn authorize(
+        &self,
+        store: &dyn FirefightDataManager,
+        access: Access,
+    ) -> anyhow::Result<()> {
+        let operator = match self.operator(store)? {
+            Some(operator) => operator,
+            None => return Ok(()),
+        };
+        if !permits(&operator.permission, &operator.internal_id, access) {
+            return Err(StoreError::PermissionDenied {
+                permission: operator.permission,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Operators editing their own record may only change how they are identified. Their
+    /// permission may not be raised, rank, state, qualifications and leave stay as stored since
+    /// those are managed through commands that need `Access::Manage`. Returns the record to write.
+    pub fn authorize_staff_update(
+        &self,
+        store: &dyn FirefightDataManager,
+        staff: Staff,
+    ) -> anyhow::Result<Staff> {
+        self.authorize(store, Access::EditStaff(&staff.internal_id))?;
+
+        let operator = match self.operator(store)? {
+            Some(operator) => operator,
+            None => return Ok(staff),
+        };
+        if operator.permission == StaffPermission::All {
+            return Ok(staff);
+        }
+
+        let previous_staff = store.get_staff(&staff.internal_id)?;
+        if staff.permission != previous_staff.permission {
+            return Err(StoreError::PermissionDenied {
+                permission: operator.permission,
+            }
+            .into());
+        }
+
+        Ok(Staff {
+            image: staff.image,
+            label: staff.label,
+            name: staff.name,
+            national_id: staff.national_id,
+            ..previous_staff
+        })
+    }
+
+    /// Operators set their own secret and managers anyone's. The first credential has to
+    /// belong to a manager, so access control never starts without someone able to manage it.
+    pub fn set_credential(
+        &self,
+        store: &dyn FirefightDataManager,
+        staff_id: &String,
+        secret: &str,
+    ) -> anyhow::Result<()> {
+        let staff = store.get_staff(staff_id)?;
+        if self.state()?.credentials.is_empty() {
+            if staff.permission != StaffPermission::All {
+                return Err(StoreError::PermissionDenied {
+                    permission: staff.permission,
+                }
+                .into());
+            }
+        } else {
+            self.authorize(store, Access::EditStaff(staff_id))?;
+        }
+
+        let password_hash = hash_secret(secret)?;
+        let mut state = self.state()?;
+        let mut credentials = state.credentials.clone();
+        credentials.insert(staff_id.clone(), password_hash);
+        self.write_credentials(&credentials)?;
+        state.credentials = credentials;
+
+        Ok(())
+    }
+
+    pub fn remove_credential(&self, staff_id: &String) -> anyhow::Result<()> {
+        let mut state = self.state()?;
+        if !state.credentials.contains_key(staff_id) {
+            return Ok(());
+        }
+
+        let mut credentials = state.credentials.clone();
+        credentials.remove(staff_id);
+        self.write_credentials(&credentials)?;
+        state.credentials = credentials;
+        if state
+            .session
+            .as_ref()
+            .is_some_and(|session| &session.staff_id == staff_id)
+        {
+            state.session = None;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures::{self, MemoryStore};
+    use crate::firefight::types::{StaffRank, StaffState};
+    use std::sync::OnceLock;
+
+    const SECRET: &str = "correct horse";
+
+    fn store() -> MemoryStore {
+        let mut manager = fixtures::staff("manager");
+        manager.permission = StaffPermission::All;
+        let mut dispatcher = fixtures::staff("dispatcher");
+        dispatcher.permission = StaffPermission::Shift;
+        let mut firefighter = fixtures::staff("firefighter");
+        firefighter.rank = StaffRank::Rank2;
+        let mut retired = fixtures::staff("retired");
+        retired.permission = StaffPermission::None;
+
+        MemoryStore::new(fixtures::data_store(
+            vec![],
+            vec![manager, dispatcher, firefighter, retired],
+            vec![],
+            vec![],
+        ))
+    }
+
+    fn registry() -> OperatorRegistry {
+        OperatorRegistry {
+            credentials_path: std::env::temp_dir()
+                .join(format!("operators-{}.json", uuid::Uuid::new_v4())),
+            state: Mutex::new(OperatorState::default()),
+        }
+    }
+
+    /// Registry where every listed staff member has `SECRET` set and `operator_id` is logged in
+    fn logged_in_registry(staff_ids: &[&str], operator_id: &str) -> OperatorRegistry {
+        // Hashing is slow on purpose, every test shares the same hash
+        static PASSWORD_HASH: OnceLock<String> = OnceLock::new();
+        let password_hash = PASSWORD_HASH.get_or_init(|| hash_secret(SECRET).unwrap());
+
+        let registry = registry();
+        let mut state = registry.state.lock().unwrap();
+        for staff_id in staff_ids {
+            state
+                .credentials
+                .insert(staff_id.to_string(), password_hash.clone());
+        }
+        state.session = Some(OperatorSession {
+            staff_id: String::from(operator_id),
+            login_time: 0,
+        });
+        drop(state);
+        registry
+    }
+
+    fn store_error(error: anyhow::Error) -> StoreError {
+        error.downcast::<StoreError>().unwrap()
+    }
+
+    #[test]
+    fn permissions_grant_access_by_level() {
+        let operator_id = String::from("operator");
+        let other_id = String::from("other");
+        let grants = |permission| {
+            [
+                Access::Dispatch,
+                Access::EditStaff(&operator_id),
+                Access::EditStaff(&other_id),
+                Access::Manage,
+            ]
+            .map(|access| permits(&permission, &operator_id, access))
+        };
+
+        assert_eq!(grants(StaffPermission::All), [true, true, true, true]);
+        assert_eq!(grants(StaffPermission::Shift), [true, true, false, false]);
+        assert_eq!(grants(StaffPermission::Own), [false, true, false, false]);
+        assert_eq!(grants(StaffPermission::None), [false, false, false, false]);
+    }
+
+    #[test]
+    fn the_console_stays_open_until_the_first_credential() {
+        let store = store();
+        let registry = registry();
+        registry.authorize(&store, Access::Manage).unwrap();
+
+        let refused = registry
+            .set_credential(&store, &String::from("dispatcher"), SECRET)
+            .unwrap_err();
+        assert!(matches!(
+            store_error(refused),
+            StoreError::PermissionDenied {
+                permission: StaffPermission::Shift
+            }
+        ));
+        registry.authorize(&store, Access::Manage).unwrap();
+
+        registry
+            .set_credential(&store, &String::from("manager"), SECRET)
+            .unwrap();
+        let refused = registry.authorize(&store, Access::Dispatch).unwrap_err();
+        assert!(matches!(store_error(refused), StoreError::NotLoggedIn));
+        std::fs::remove_file(&registry.credentials_path).unwrap();
+    }
+
+    #[test]
+    fn operators_log_in_with_their_secret() {
+        let store = store();
+        let registry = logged_in_registry(&["dispatcher", "retired"], "dispatcher");
+        registry.logout().unwrap();
+
+        let refused = registry
+            .login(&store, &String::from("dispatcher"), "wrong")
+            .unwrap_err();
+        assert!(matches!(
+            store_error(refused),
+            StoreError::InvalidCredentials
+        ));
+        let refused = registry
+            .login(&store, &String::from("retired"), SECRET)
+            .unwrap_err();
+        assert!(matches!(
+            store_error(refused),
+            StoreError::PermissionDenied { .. }
+        ));
+
+        let session = registry
+            .login(&store, &String::from("dispatcher"), SECRET)
+            .unwrap();
+        assert_eq!(session.staff_id, "dispatcher");
+        registry.authorize(&store, Access::Dispatch).unwrap();
+        assert!(registry.authorize(&store, Access::Manage).is_err());
+    }
+
+    #[test]
+    fn operators_editing_themselves_keep_their_privileged_fields() {
+        let store = store();
+        let registry = logged_in_registry(&["manager", "firefighter"], "firefighter");
+
+        let mut edited_staff = store.get_staff(&String::from("firefighter")).unwrap();
+        edited_staff.label = String::from("FF");
+        edited_staff.rank = StaffRank::Rank4;
+        edited_staff.state = StaffState::Unavailable;
+        let written_staff = registry
+            .authorize_staff_update(&store, edited_staff.clone())
+            .unwrap();
+        assert_eq!(written_staff.label, "FF");
+        assert_eq!(written_staff.rank, StaffRank::Rank2);
+        assert_eq!(written_staff.state, StaffState::Available);
+
+        edited_staff.permission = StaffPermission::All;
+        let refused = registry
+            .authorize_staff_update(&store, edited_staff)
+            .unwrap_err();
+        assert!(matches!(
+            store_error(refused),
+            StoreError::PermissionDenied {
+                permission: StaffPermission::Own
+            }
+        ));
+
+        let other_staff = store.get_staff(&String::from("dispatcher")).unwrap();
+        assert!(registry
+            .authorize_staff_update(&store, other_staff)
+            .is_err());
+    }
+
+    #[test]
+    fn managers_edit_staff_records_as_sent() {
+        let store = store();
+        let registry = logged_in_registry(&["manager"], "manager");
+
+        let mut edited_staff = store.get_staff(&String::from("firefighter")).unwrap();
+        edited_staff.rank = StaffRank::Rank4;
+        edited_staff.permission = StaffPermission::Shift;
+        let written_staff = registry
+            .authorize_staff_update(&store, edited_staff.clone())
+            .unwrap();
+        assert_eq!(written_staff, edited_staff);
+    }
+
+    #[test]
+    fn removing_a_credential_ends_its_session() {
+        let store = store();
+        let registry = logged_in_registry(&["manager", "dispatcher"], "dispatcher");
+
+        registry
+            .remove_credential(&String::from("manager"))
+            .unwrap();
+        assert!(registry.session().unwrap().is_some());
+
+        registry
+            .remove_credential(&String::from("dispatcher"))
+            .unwrap();
+        assert!(registry.session().unwrap().is_none());
+        // Without credentials left the console is open again
+        registry.authorize(&store, Access::Manage).unwrap();
+        std::fs::remove_file(&registry.credentials_path).unwrap();
+    }
+
+    #[test]
+    fn operators_removed_from_the_roster_are_logged_out() {
+        let mut store = store();
+        let registry = logged_in_registry(&["manager", "dispatcher"], "dispatcher");
+        store.data.staff.remove("dispatcher");
+
+        let refused = registry.authorize(&store, Access::Dispatch).unwrap_err();
+        assert!(matches!(store_error(refused), StoreError::NotLoggedIn));
+        assert!(registry.session().unwrap().is_none());
+    }
+}
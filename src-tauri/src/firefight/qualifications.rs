@@ -0,0 +1,219 @@
+use super::types::{DataStore, Qualification, QualificationKind};
+
+/// How long before its expiry a qualification starts being reported, one month
+pub const EXPIRY_WARNING_WINDOW: u128 = 30 * 24 * 60 * 60 * 1000;
+
+/// A qualification that expires within the warning window or already has
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualificationExpiry {
+    pub staff_id: String,
+    pub staff_label: String,
+    pub qualification: Qualification,
+    pub expired: bool,
+}
+
+/// Lists qualifications expiring before `time + window`, soonest first
+pub fn expiring_qualifications(
+    data_store: &DataStore,
+    time: u128,
+    window: u128,
+) -> Vec<QualificationExpiry> {
+    let mut expiring_qualifications = data_store
+        .staff
+        .values()
+        .flat_map(|staff| {
+            staff
+                .qualifications
+                .iter()
+                .filter_map(move |qualification| {
+                    let expiry_time = qualification.expiry_time?;
+                    if expiry_time >= time.saturating_add(window) {
+                        return None;
+                    }
+
+                    Some(QualificationExpiry {
+                        staff_id: staff.internal_id.clone(),
+                        staff_label: staff.label.clone(),
+                        qualification: qualification.clone(),
+                        expired: expiry_time <= time,
+                    })
+                })
+        })
+        .collect::<Vec<QualificationExpiry>>();
+
+    expiring_qualifications.sort_by(|expiry, other_expiry| {
+        expiry
+            .qualification
+            .expiry_time
+            .cmp(&other_expiry.qualification.expiry_time)
+            .then_with(|| expiry.staff_label.cmp(&other_expiry.staff_label))
+    });
+    expiring_qualifications
+}
+
+/// Staff holding a valid qualification of the given kind, for dispatch checks
+pub fn qualified_staff(data_store: &DataStore, kind: QualificationKind, time: u128) -> Vec<String> {
+    let mut staff_ids = data_store
+        .staff
+        .values()
+        .filter(|staff| staff.is_qualified(kind, time))
+        .map(|staff| staff.internal_id.clone())
+        .collect::<Vec<String>>();
+    staff_ids.sort();
+    staff_ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::types::Staff;
+
+    const TIME: u128 = 1_000;
+    const WINDOW: u128 = 100;
+
+    fn qualification(
+        qualification_id: &str,
+        kind: QualificationKind,
+        expiry_time: Option<u128>,
+    ) -> Qualification {
+        Qualification {
+            internal_id: qualification_id.to_string(),
+            kind,
+            issue_time: 0,
+            expiry_time,
+        }
+    }
+
+    fn qualified_member(staff_id: &str, qualifications: Vec<Qualification>) -> Staff {
+        let mut member = fixtures::staff(staff_id);
+        member.label = staff_id.to_string();
+        member.qualifications = qualifications;
+        member
+    }
+
+    fn store(staff: Vec<Staff>) -> DataStore {
+        fixtures::data_store(vec![], staff, vec![], vec![])
+    }
+
+    fn expiring(data_store: &DataStore) -> Vec<(String, bool)> {
+        expiring_qualifications(data_store, TIME, WINDOW)
+            .into_iter()
+            .map(|expiry| (expiry.qualification.internal_id, expiry.expired))
+            .collect()
+    }
+
+    #[test]
+    fn qualifications_expiring_within_the_window_are_listed_soonest_first() {
+        let data_store = store(vec![
+            qualified_member(
+                "a",
+                vec![
+                    qualification("later", QualificationKind::Driver, Some(TIME + 50)),
+                    qualification("outside", QualificationKind::Tas, Some(TIME + WINDOW)),
+                    qualification("permanent", QualificationKind::Diver, None),
+                ],
+            ),
+            qualified_member(
+                "b",
+                vec![qualification(
+                    "sooner",
+                    QualificationKind::Driver,
+                    Some(TIME + 10),
+                )],
+            ),
+        ]);
+
+        assert_eq!(
+            expiring(&data_store),
+            vec![
+                (String::from("sooner"), false),
+                (String::from("later"), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn qualifications_past_their_expiry_are_flagged_expired() {
+        let data_store = store(vec![qualified_member(
+            "a",
+            vec![
+                qualification("long-expired", QualificationKind::Driver, Some(10)),
+                qualification("expiring-now", QualificationKind::Tas, Some(TIME)),
+                qualification("still-valid", QualificationKind::Tat, Some(TIME + 1)),
+            ],
+        )]);
+
+        assert_eq!(
+            expiring(&data_store),
+            vec![
+                (String::from("long-expired"), true),
+                (String::from("expiring-now"), true),
+                (String::from("still-valid"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn equal_expiry_times_are_ordered_by_staff_label() {
+        let data_store = store(
+            ["c", "a", "b"]
+                .map(|staff_id| {
+                    qualified_member(
+                        staff_id,
+                        vec![qualification(
+                            staff_id,
+                            QualificationKind::Driver,
+                            Some(TIME + 10),
+                        )],
+                    )
+                })
+                .to_vec(),
+        );
+
+        let staff_labels = expiring_qualifications(&data_store, TIME, WINDOW)
+            .into_iter()
+            .map(|expiry| expiry.staff_label)
+            .collect::<Vec<String>>();
+        assert_eq!(staff_labels, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn only_staff_with_a_valid_qualification_are_qualified() {
+        let mut not_yet_issued = qualification("issued-later", QualificationKind::Driver, None);
+        not_yet_issued.issue_time = TIME + 1;
+        let data_store = store(vec![
+            qualified_member(
+                "valid",
+                vec![qualification(
+                    "valid",
+                    QualificationKind::Driver,
+                    Some(TIME + 1),
+                )],
+            ),
+            qualified_member(
+                "expired",
+                vec![qualification(
+                    "expired",
+                    QualificationKind::Driver,
+                    Some(TIME),
+                )],
+            ),
+            qualified_member("issued-later", vec![not_yet_issued]),
+            qualified_member(
+                "other-kind",
+                vec![qualification("other-kind", QualificationKind::Tas, None)],
+            ),
+            qualified_member(
+                "permanent",
+                vec![qualification("permanent", QualificationKind::Driver, None)],
+            ),
+        ]);
+
+        assert_eq!(
+            qualified_staff(&data_store, QualificationKind::Driver, TIME),
+            vec!["permanent", "valid"]
+        );
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+
+use super::crew::{self, CrewViolation};
+use super::errors::StoreError;
+use super::types::{
+    CrewAssignment, CrewRole, DataStore, DispatchPlanEntry, QualificationKind, Staff, StaffState,
+    Vehicle, VehicleState,
+};
+
+/// Vehicles and crews proposed from the dispatch plan of an occurrence, for operators to adjust
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DispatchRecommendation {
+    pub occurrence_id: String,
+    pub vehicle_ids: Vec<String>,
+    pub vehicle_assignment_map: HashMap<String, Vec<CrewAssignment>>,
+    pub staff_ids: Vec<String>,
+    /// Part of the plan that could not be covered by Available vehicles
+    pub missing_vehicles: Vec<DispatchPlanEntry>,
+    /// Unmet crew requirements of the proposed crews
+    pub crew_warnings: Vec<CrewViolation>,
+}
+
+fn fits_plan_entry(vehicle: &Vehicle, plan_entry: &DispatchPlanEntry) -> bool {
+    vehicle.state == VehicleState::Available
+        && vehicle.category == plan_entry.category
+        && match plan_entry.min_capacity {
+            Some(min_capacity) => vehicle
+                .capacity
+                .is_some_and(|capacity| capacity >= min_capacity),
+            None => true,
+        }
+}
+
+/// Seats to fill, the tighter of the requirement and the capacity but never below the minimum
+fn crew_seats(data_store: &DataStore, vehicle: &Vehicle) -> usize {
+    let requirement = crew::crew_requirement(data_store, vehicle);
+    let max_crew = requirement.and_then(|requirement| requirement.max_crew);
+    let min_crew = requirement
+        .and_then(|requirement| requirement.min_crew)
+        .unwrap_or_default();
+
+    let seats = match (max_crew, vehicle.capacity) {
+        (Some(max_crew), Some(capacity)) => max_crew.min(capacity),
+        (Some(seats), None) | (None, Some(seats)) => seats,
+        (None, None) => min_crew,
+    };
+    seats.max(min_crew) as usize
+}
+
+fn take_staff<'a>(
+    available_staff: &mut Vec<&'a Staff>,
+    is_suitable: impl Fn(&Staff) -> bool,
+) -> Option<&'a Staff> {
+    let index = available_staff
+        .iter()
+        .position(|member| is_suitable(member))?;
+    Some(available_staff.remove(index))
+}
+
+fn is_driver(member: &Staff, time: u128) -> bool {
+    member.is_qualified(QualificationKind::Driver, time)
+        || member.is_qualified(QualificationKind::HeavyDriver, time)
+}
+
+/// Chief first, then one member per required qualification, a driver and the remaining seats
+fn recommend_crew(
+    data_store: &DataStore,
+    vehicle: &Vehicle,
+    available_staff: &mut Vec<&Staff>,
+    time: u128,
+) -> Vec<CrewAssignment> {
+    let requirement = crew::crew_requirement(data_store, vehicle);
+    let seats = crew_seats(data_store, vehicle);
+    let mut vehicle_crew: Vec<(&Staff, CrewRole)> = vec![];
+
+    // The least senior member who holds the rank, senior officers stay free for larger occurrences
+    if let Some(chief_seniority) = requirement
+        .and_then(|requirement| requirement.chief_rank.as_ref())
+        .and_then(|chief_rank| chief_rank.seniority())
+    {
+        let chief = available_staff
+            .iter()
+            .enumerate()
+            .filter_map(|(index, member)| {
+                member
+                    .rank
+                    .seniority()
+                    .filter(|seniority| *seniority <= chief_seniority)
+                    .map(|seniority| (index, seniority))
+            })
+            .min_by_key(|(index, seniority)| (u8::MAX - seniority, *index));
+        if let Some((index, _)) = chief {
+            vehicle_crew.push((available_staff.remove(index), CrewRole::Chief));
+        }
+    }
+
+    // Members already in the crew count for one qualification each
+    let mut covering_members: Vec<&String> = vec![];
+    let qualifications = requirement
+        .map(|requirement| requirement.qualifications.as_slice())
+        .unwrap_or_default();
+    for kind in qualifications.iter() {
+        let covering_member = vehicle_crew.iter().find(|(member, _)| {
+            !covering_members.contains(&&member.internal_id) && member.is_qualified(*kind, time)
+        });
+        if let Some((member, _)) = covering_member {
+            covering_members.push(&member.internal_id);
+            continue;
+        }
+        if vehicle_crew.len() >= seats {
+            break;
+        }
+        if let Some(member) = take_staff(available_staff, |member| member.is_qualified(*kind, time))
+        {
+            covering_members.push(&member.internal_id);
+            vehicle_crew.push((member, CrewRole::Crew));
+        }
+    }
+
+    let driver_index = vehicle_crew
+        .iter()
+        .position(|(member, role)| *role == CrewRole::Crew && is_driver(member, time));
+    match driver_index {
+        Some(index) => vehicle_crew[index].1 = CrewRole::Driver,
+        None if vehicle_crew.len() < seats => {
+            if let Some(member) = take_staff(available_staff, |member| is_driver(member, time)) {
+                vehicle_crew.push((member, CrewRole::Driver));
+            }
+        }
+        None => {}
+    }
+
+    while vehicle_crew.len() < seats {
+        match take_staff(available_staff, |_| true) {
+            Some(member) => vehicle_crew.push((member, CrewRole::Crew)),
+            None => break,
+        }
+    }
+
+    vehicle_crew
+        .into_iter()
+        .map(|(member, role)| CrewAssignment {
+            staff_id: member.internal_id.clone(),
+            role,
+        })
+        .collect()
+}
+
+/// Proposes Available vehicles for each entry of the dispatch plan, smallest fitting ones first,
+/// and crews them with Available staff. Nothing is dispatched, operators review the proposal.
+pub fn recommend_dispatch(
+    data_store: &DataStore,
+    occurrence_id: &String,
+    time: u128,
+) -> Result<DispatchRecommendation, StoreError> {
+    let occurrence =
+        data_store
+            .occurrences
+            .get(occurrence_id)
+            .ok_or_else(|| StoreError::UnknownOccurrence {
+                occurrence_id: occurrence_id.clone(),
+            })?;
+
+    let mut available_staff = data_store
+        .staff
+        .values()
+        .filter(|member| member.state == StaffState::Available && !member.is_on_leave())
+        .collect::<Vec<&Staff>>();
+    available_staff.sort_by(|a, b| a.label.cmp(&b.label));
+
+    let mut recommendation = DispatchRecommendation {
+        occurrence_id: occurrence_id.clone(),
+        ..DispatchRecommendation::default()
+    };
+    for plan_entry in occurrence.dispatch_plan.iter() {
+        let mut candidates = data_store
+            .vehicles
+            .values()
+            .filter(|vehicle| {
+                fits_plan_entry(vehicle, plan_entry)
+                    && !recommendation.vehicle_ids.contains(&vehicle.internal_id)
+            })
+            .collect::<Vec<&Vehicle>>();
+        candidates.sort_by(|a, b| (a.capacity, &a.label).cmp(&(b.capacity, &b.label)));
+        candidates.truncate(plan_entry.count as usize);
+
+        if candidates.len() < plan_entry.count as usize {
+            recommendation.missing_vehicles.push(DispatchPlanEntry {
+                count: plan_entry.count - candidates.len() as u8,
+                ..plan_entry.clone()
+            });
+        }
+
+        for vehicle in candidates {
+            let vehicle_crew = recommend_crew(data_store, vehicle, &mut available_staff, time);
+            recommendation
+                .crew_warnings
+                .extend(crew::check_vehicle_crew(
+                    data_store,
+                    vehicle,
+                    &vehicle_crew,
+                    time,
+                ));
+            recommendation.staff_ids.extend(
+                vehicle_crew
+                    .iter()
+                    .map(|assignment| assignment.staff_id.clone()),
+            );
+            recommendation.vehicle_ids.push(vehicle.internal_id.clone());
+            recommendation
+                .vehicle_assignment_map
+                .insert(vehicle.internal_id.clone(), vehicle_crew);
+        }
+    }
+
+    Ok(recommendation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::types::{
+        CrewRequirement, CrewRequirementTarget, LeavePeriod, LeaveReason, LeaveStatus,
+        Qualification, StaffRank, VehicleCategory,
+    };
+
+    fn plan_entry(
+        category: VehicleCategory,
+        count: u8,
+        min_capacity: Option<u8>,
+    ) -> DispatchPlanEntry {
+        DispatchPlanEntry {
+            category,
+            count,
+            min_capacity,
+        }
+    }
+
+    fn store(dispatch_plan: Vec<DispatchPlanEntry>, staff: Vec<Staff>) -> DataStore {
+        let mut occurrence = fixtures::occurrence("occurrence");
+        occurrence.dispatch_plan = dispatch_plan;
+        let mut dispatched = fixtures::vehicle("dispatched", VehicleCategory::FireFight, Some(2));
+        dispatched.state = VehicleState::Dispatched;
+
+        fixtures::data_store(
+            vec![occurrence],
+            staff,
+            vec![],
+            vec![
+                fixtures::vehicle("large", VehicleCategory::FireFight, Some(6)),
+                fixtures::vehicle("small", VehicleCategory::FireFight, Some(3)),
+                fixtures::vehicle("ambulance", VehicleCategory::Ambulances, Some(3)),
+                dispatched,
+            ],
+        )
+    }
+
+    fn recommend(data_store: &DataStore) -> DispatchRecommendation {
+        recommend_dispatch(data_store, &String::from("occurrence"), 0).unwrap()
+    }
+
+    #[test]
+    fn proposes_the_smallest_available_vehicles_that_fit() {
+        let data_store = store(
+            vec![
+                plan_entry(VehicleCategory::FireFight, 1, None),
+                plan_entry(VehicleCategory::FireFight, 1, Some(4)),
+            ],
+            vec![],
+        );
+
+        let recommendation = recommend(&data_store);
+        assert_eq!(recommendation.vehicle_ids, vec!["small", "large"]);
+        assert!(recommendation.missing_vehicles.is_empty());
+    }
+
+    #[test]
+    fn reports_the_part_of_the_plan_left_uncovered() {
+        let data_store = store(
+            vec![
+                plan_entry(VehicleCategory::FireFight, 3, None),
+                plan_entry(VehicleCategory::Vessels, 1, None),
+            ],
+            vec![],
+        );
+
+        let recommendation = recommend(&data_store);
+        assert_eq!(recommendation.vehicle_ids, vec!["small", "large"]);
+        assert_eq!(
+            recommendation.missing_vehicles,
+            vec![
+                plan_entry(VehicleCategory::FireFight, 1, None),
+                plan_entry(VehicleCategory::Vessels, 1, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn crews_vehicles_with_a_chief_and_a_driver() {
+        let mut commander = fixtures::staff("a-commander");
+        commander.rank = StaffRank::Rank1;
+        let mut officer = fixtures::staff("b-officer");
+        officer.rank = StaffRank::Rank3;
+        let mut driver = fixtures::staff("c-driver");
+        driver.qualifications.push(Qualification {
+            internal_id: String::from("driver"),
+            kind: QualificationKind::Driver,
+            issue_time: 0,
+            expiry_time: None,
+        });
+        let mut data_store = store(
+            vec![plan_entry(VehicleCategory::Ambulances, 1, None)],
+            vec![commander, officer, driver, fixtures::staff("d-firefighter")],
+        );
+        let target = CrewRequirementTarget::Category {
+            category: VehicleCategory::Ambulances,
+        };
+        data_store.crew_requirements.insert(
+            target.requirement_id(),
+            CrewRequirement {
+                internal_id: target.requirement_id(),
+                target,
+                min_crew: Some(2),
+                max_crew: Some(3),
+                chief_rank: Some(StaffRank::Rank4),
+                qualifications: vec![],
+                blocking: false,
+            },
+        );
+
+        let recommendation = recommend(&data_store);
+        // The most senior officer stays free for larger occurrences
+        assert_eq!(
+            recommendation.vehicle_assignment_map["ambulance"],
+            vec![
+                CrewAssignment {
+                    staff_id: String::from("b-officer"),
+                    role: CrewRole::Chief,
+                },
+                CrewAssignment {
+                    staff_id: String::from("c-driver"),
+                    role: CrewRole::Driver,
+                },
+                CrewAssignment {
+                    staff_id: String::from("a-commander"),
+                    role: CrewRole::Crew,
+                },
+            ]
+        );
+        assert!(recommendation.crew_warnings.is_empty());
+    }
+
+    #[test]
+    fn only_proposes_available_staff_once() {
+        let mut on_leave = fixtures::staff("a");
+        on_leave.leave_periods.push(LeavePeriod {
+            internal_id: String::from("leave"),
+            reason: LeaveReason::Vacation,
+            start_time: 0,
+            end_time: u128::MAX,
+            status: LeaveStatus::Ongoing,
+        });
+        let mut dispatched = fixtures::staff("b");
+        dispatched.state = StaffState::Dispatched;
+        let data_store = store(
+            vec![plan_entry(VehicleCategory::FireFight, 2, None)],
+            vec![
+                on_leave,
+                dispatched,
+                fixtures::staff("c"),
+                fixtures::staff("d"),
+            ],
+        );
+
+        let recommendation = recommend(&data_store);
+        assert_eq!(recommendation.staff_ids, vec!["c", "d"]);
+        assert_eq!(recommendation.vehicle_assignment_map["small"].len(), 2);
+        assert!(recommendation.vehicle_assignment_map["large"].is_empty());
+    }
+
+    #[test]
+    fn unknown_occurrences_are_refused() {
+        let data_store = store(vec![], vec![]);
+
+        assert_eq!(
+            recommend_dispatch(&data_store, &String::from("unknown"), 0).unwrap_err(),
+            StoreError::UnknownOccurrence {
+                occurrence_id: String::from("unknown")
+            }
+        );
+    }
+}
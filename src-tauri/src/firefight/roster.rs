@@ -0,0 +1,458 @@
+use anyhow::Context;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::types::{DataStore, Staff, StaffPermission, StaffRank, StaffState};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StaffImportMode {
+    /// Every row is a new staff member, existing labels are rejected
+    Create,
+    UpsertByLabel,
+    UpsertByNationalId,
+}
+
+/// One row of the roster spreadsheet, enums are kept as text so bad values can be reported
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+struct StaffRecord {
+    label: String,
+    name: String,
+    #[serde(default)]
+    national_id: String,
+    #[serde(default)]
+    rank: String,
+    #[serde(default)]
+    permission: String,
+    #[serde(default)]
+    state: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffImportIssue {
+    pub line: u64,
+    pub label: String,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaffImportReport {
+    pub dry_run: bool,
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+    /// Any error prevents the whole import from being applied
+    pub errors: Vec<StaffImportIssue>,
+    pub warnings: Vec<StaffImportIssue>,
+}
+
+pub struct StaffImportEntry {
+    /// New staff have an empty internal id
+    pub staff: Staff,
+    pub previous_label: Option<String>,
+}
+
+pub struct StaffImportPlan {
+    pub report: StaffImportReport,
+    pub entries: Vec<StaffImportEntry>,
+}
+
+fn variant_name<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    serde_json::to_value(value)?
+        .as_str()
+        .map(String::from)
+        .with_context(|| "Failed to serialize variant name".to_string())
+}
+
+// Accepts the serialized camelCase names as well as capitalized ones from the spreadsheet
+fn parse_variant<T: DeserializeOwned>(value: &str) -> Option<T> {
+    let mut chars = value.trim().chars();
+    let first_char = chars.next()?;
+    let variant_name = first_char.to_lowercase().chain(chars).collect::<String>();
+    serde_json::from_value(Value::String(variant_name)).ok()
+}
+
+fn parse_rank(value: &str) -> Option<StaffRank> {
+    let rank_name = value.trim().replace(' ', "");
+    if rank_name.is_empty() {
+        return Some(StaffRank::Unknown);
+    }
+
+    // Plain numbers refer to the rank level
+    if rank_name
+        .chars()
+        .all(|rank_char| rank_char.is_ascii_digit())
+    {
+        return parse_variant(&format!("rank{}", rank_name));
+    }
+    parse_variant(&rank_name)
+}
+
+pub fn export_staff_csv(staff_list: &[Staff]) -> anyhow::Result<String> {
+    let mut sorted_staff = staff_list.iter().collect::<Vec<&Staff>>();
+    sorted_staff.sort_by(|a, b| a.label.cmp(&b.label));
+
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for staff in sorted_staff {
+        writer
+            .serialize(StaffRecord {
+                label: staff.label.clone(),
+                name: staff.name.clone(),
+                national_id: staff.national_id.clone(),
+                rank: variant_name(&staff.rank)?,
+                permission: variant_name(&staff.permission)?,
+                state: variant_name(&staff.state)?,
+            })
+            .with_context(|| format!("Failed to write staff {}", staff.label))?;
+    }
+
+    let csv_bytes = writer
+        .into_inner()
+        .with_context(|| "Failed to finish staff CSV".to_string())?;
+    String::from_utf8(csv_bytes).with_context(|| "Staff CSV is not valid UTF-8".to_string())
+}
+
+/// Validates a roster CSV against the current store without changing it
+pub fn plan_staff_import(
+    data_store: &DataStore,
+    csv_text: &str,
+    mode: StaffImportMode,
+) -> anyhow::Result<StaffImportPlan> {
+    let mut report = StaffImportReport::default();
+    let mut entries = vec![];
+
+    let staff_by_label = data_store
+        .staff
+        .values()
+        .map(|staff| (staff.label.clone(), staff))
+        .collect::<HashMap<String, &Staff>>();
+    let staff_by_national_id = data_store
+        .staff
+        .values()
+        .filter(|staff| !staff.national_id.is_empty())
+        .map(|staff| (staff.national_id.clone(), staff))
+        .collect::<HashMap<String, &Staff>>();
+
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(csv_text.as_bytes());
+    let headers = reader
+        .headers()
+        .with_context(|| "Failed to read staff CSV header".to_string())?
+        .clone();
+
+    let mut seen_labels = HashMap::new();
+    let mut seen_national_ids = HashMap::new();
+    for record in reader.records() {
+        let record = record.with_context(|| "Failed to read staff CSV".to_string())?;
+        let line = record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or(0);
+
+        let mut errors = vec![];
+        let mut warnings = vec![];
+        let staff_record = match record.deserialize::<StaffRecord>(Some(&headers)) {
+            Ok(staff_record) => staff_record,
+            Err(parse_error) => {
+                report.errors.push(StaffImportIssue {
+                    line,
+                    label: String::new(),
+                    message: parse_error.to_string(),
+                });
+                continue;
+            }
+        };
+
+        if staff_record.label.is_empty() {
+            errors.push(String::from("Label is required"));
+        } else if let Some(first_line) = seen_labels.insert(staff_record.label.clone(), line) {
+            errors.push(format!(
+                "Duplicate label, first seen on line {}",
+                first_line
+            ));
+        }
+        if !staff_record.national_id.is_empty() {
+            if let Some(first_line) =
+                seen_national_ids.insert(staff_record.national_id.clone(), line)
+            {
+                errors.push(format!(
+                    "Duplicate national id, first seen on line {}",
+                    first_line
+                ));
+            }
+        }
+
+        let rank = parse_rank(&staff_record.rank);
+        if rank.is_none() {
+            errors.push(format!("Unknown rank: {}", staff_record.rank));
+        }
+        let permission = if staff_record.permission.is_empty() {
+            Some(StaffPermission::Own)
+        } else {
+            parse_variant::<StaffPermission>(&staff_record.permission)
+        };
+        if permission.is_none() {
+            errors.push(format!("Unknown permission: {}", staff_record.permission));
+        }
+        let state = if staff_record.state.is_empty() {
+            None
+        } else {
+            match parse_variant::<StaffState>(&staff_record.state) {
+                Some(state) => Some(state),
+                None => {
+                    errors.push(format!("Unknown state: {}", staff_record.state));
+                    None
+                }
+            }
+        };
+
+        let existing_staff = match mode {
+            StaffImportMode::Create => {
+                if staff_by_label.contains_key(&staff_record.label) {
+                    errors.push(String::from(
+                        "A staff member with this label already exists",
+                    ));
+                }
+                None
+            }
+            StaffImportMode::UpsertByLabel => staff_by_label.get(&staff_record.label).copied(),
+            StaffImportMode::UpsertByNationalId => {
+                if staff_record.national_id.is_empty() {
+                    errors.push(String::from("National id is required to match staff"));
+                }
+                let existing_staff = staff_by_national_id.get(&staff_record.national_id).copied();
+                if let Some(label_owner) = staff_by_label.get(&staff_record.label) {
+                    if existing_staff.map(|staff| &staff.internal_id)
+                        != Some(&label_owner.internal_id)
+                    {
+                        errors.push(String::from(
+                            "Label already belongs to another staff member",
+                        ));
+                    }
+                }
+                existing_staff
+            }
+        };
+
+        // Exported rosters list dispatched staff as such, which only holds for staff already out
+        let is_dispatched =
+            existing_staff.is_some_and(|staff| staff.state == StaffState::Dispatched);
+        if state == Some(StaffState::Dispatched) && !is_dispatched {
+            errors.push(String::from(
+                "Staff can only be dispatched from an occurrence",
+            ));
+        }
+
+        if !errors.is_empty() {
+            report
+                .errors
+                .extend(errors.into_iter().map(|message| StaffImportIssue {
+                    line,
+                    label: staff_record.label.clone(),
+                    message,
+                }));
+            continue;
+        }
+
+        let mut staff = match existing_staff {
+            Some(existing_staff) => (*existing_staff).clone(),
+            None => Staff {
+                internal_id: String::new(),
+                image: String::new(),
+                label: String::new(),
+                name: String::new(),
+                national_id: String::new(),
+                permission: StaffPermission::Own,
+                rank: StaffRank::Unknown,
+                state: StaffState::Available,
+                qualifications: vec![],
+                leave_periods: vec![],
+            },
+        };
+        staff.label = staff_record.label.clone();
+        staff.name = staff_record.name;
+        staff.national_id = staff_record.national_id;
+        staff.rank = rank.unwrap_or(StaffRank::Unknown);
+        staff.permission = permission.unwrap_or(StaffPermission::Own);
+        if let Some(state) = state {
+            if is_dispatched && state != StaffState::Dispatched {
+                warnings.push(String::from(
+                    "Staff is dispatched, state was left unchanged",
+                ));
+            } else {
+                staff.state = state;
+            }
+        }
+
+        report
+            .warnings
+            .extend(warnings.into_iter().map(|message| StaffImportIssue {
+                line,
+                label: staff_record.label.clone(),
+                message,
+            }));
+        match existing_staff {
+            Some(existing_staff) if *existing_staff == staff => {
+                report.unchanged.push(staff.label.clone());
+            }
+            Some(existing_staff) => {
+                report.updated.push(staff.label.clone());
+                entries.push(StaffImportEntry {
+                    staff,
+                    previous_label: Some(existing_staff.label.clone()),
+                });
+            }
+            None => {
+                report.created.push(staff.label.clone());
+                entries.push(StaffImportEntry {
+                    staff,
+                    previous_label: None,
+                });
+            }
+        }
+    }
+
+    Ok(StaffImportPlan { report, entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+
+    fn store() -> DataStore {
+        let mut chief = fixtures::staff("chief");
+        chief.label = String::from("001");
+        chief.national_id = String::from("111");
+        chief.rank = StaffRank::Rank2;
+        let mut firefighter = fixtures::staff("firefighter");
+        firefighter.label = String::from("002");
+        firefighter.national_id = String::from("222");
+        firefighter.state = StaffState::Dispatched;
+
+        fixtures::data_store(vec![], vec![chief, firefighter], vec![], vec![])
+    }
+
+    fn plan(data_store: &DataStore, csv_text: &str, mode: StaffImportMode) -> StaffImportPlan {
+        plan_staff_import(data_store, csv_text, mode).unwrap()
+    }
+
+    fn messages(issues: &[StaffImportIssue]) -> Vec<(u64, &str)> {
+        issues
+            .iter()
+            .map(|issue| (issue.line, issue.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn ranks_are_read_from_numbers_and_names() {
+        assert_eq!(parse_rank("3"), Some(StaffRank::Rank3));
+        assert_eq!(parse_rank(" Rank 3 "), Some(StaffRank::Rank3));
+        assert_eq!(parse_rank("rank3"), Some(StaffRank::Rank3));
+        assert_eq!(parse_rank(""), Some(StaffRank::Unknown));
+        assert_eq!(parse_rank("12"), None);
+        assert_eq!(parse_rank("captain"), None);
+        assert_eq!(
+            parse_variant::<StaffState>("SickLeave"),
+            Some(StaffState::SickLeave)
+        );
+    }
+
+    #[test]
+    fn an_exported_roster_imports_unchanged() {
+        let data_store = store();
+        let staff_list = data_store.staff.values().cloned().collect::<Vec<Staff>>();
+        let csv_text = export_staff_csv(&staff_list).unwrap();
+        assert!(csv_text.starts_with("label,name,national_id,rank,permission,state\n001,"));
+
+        let import_plan = plan(&data_store, &csv_text, StaffImportMode::UpsertByLabel);
+        assert_eq!(import_plan.report.unchanged, vec!["001", "002"]);
+        assert!(import_plan.entries.is_empty());
+        assert!(import_plan.report.errors.is_empty());
+    }
+
+    #[test]
+    fn new_staff_get_defaults_for_missing_columns() {
+        let import_plan = plan(
+            &store(),
+            "label,name\n003,New Staff\n",
+            StaffImportMode::Create,
+        );
+
+        assert_eq!(import_plan.report.created, vec!["003"]);
+        let staff = &import_plan.entries[0].staff;
+        assert!(staff.internal_id.is_empty());
+        assert_eq!(staff.name, "New Staff");
+        assert_eq!(staff.rank, StaffRank::Unknown);
+        assert_eq!(staff.permission, StaffPermission::Own);
+        assert_eq!(staff.state, StaffState::Available);
+    }
+
+    #[test]
+    fn invalid_rows_are_reported_by_line() {
+        let csv_text = "label,name,national_id,rank,permission,state\n\
+            001,Existing,,,,\n\
+            003,Bad Rank,,captain,,\n\
+            004,Bad Permission,,,boss,\n\
+            005,Dispatched,,,,dispatched\n\
+            006,First,999,,,\n\
+            006,Second,999,,,\n";
+        let import_plan = plan(&store(), csv_text, StaffImportMode::Create);
+
+        assert_eq!(
+            messages(&import_plan.report.errors),
+            vec![
+                (2, "A staff member with this label already exists"),
+                (3, "Unknown rank: captain"),
+                (4, "Unknown permission: boss"),
+                (5, "Staff can only be dispatched from an occurrence"),
+                (7, "Duplicate label, first seen on line 6"),
+                (7, "Duplicate national id, first seen on line 6"),
+            ]
+        );
+        assert_eq!(import_plan.report.created, vec!["006"]);
+    }
+
+    #[test]
+    fn staff_are_matched_by_national_id() {
+        let csv_text = "label,name,national_id\n\
+            010,Renamed Chief,111\n\
+            002,Someone Else,333\n\
+            011,No Id,\n";
+        let import_plan = plan(&store(), csv_text, StaffImportMode::UpsertByNationalId);
+
+        assert_eq!(import_plan.report.updated, vec!["010"]);
+        assert_eq!(import_plan.entries[0].staff.internal_id, "chief");
+        assert_eq!(
+            import_plan.entries[0].previous_label.as_deref(),
+            Some("001")
+        );
+        assert_eq!(
+            messages(&import_plan.report.errors),
+            vec![
+                (3, "Label already belongs to another staff member"),
+                (4, "National id is required to match staff"),
+            ]
+        );
+    }
+
+    #[test]
+    fn dispatched_staff_keep_their_state() {
+        let import_plan = plan(
+            &store(),
+            "label,name,national_id,state\n002,Staff firefighter,222,unavailable\n",
+            StaffImportMode::UpsertByLabel,
+        );
+
+        assert_eq!(
+            messages(&import_plan.report.warnings),
+            vec![(2, "Staff is dispatched, state was left unchanged")]
+        );
+        assert_eq!(import_plan.report.unchanged, vec!["002"]);
+    }
+}
@@ -0,0 +1,294 @@
+use super::types::{DataStore, ShiftRecurrence, ShiftTemplate};
+
+/// How far ahead the shift calendar is listed by default, one week
+pub const CALENDAR_WINDOW: u128 = 7 * 24 * 60 * 60 * 1000;
+
+/// A single handover of a shift template
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftHandover {
+    pub shift_template_id: String,
+    pub shift_template_name: String,
+    pub handover_time: u128,
+}
+
+impl ShiftRecurrence {
+    pub fn is_valid(&self) -> bool {
+        self.repeat_interval != Some(0)
+            && self
+                .end_time
+                .map(|end_time| end_time > self.start_time)
+                .unwrap_or(true)
+    }
+
+    fn is_before_end(&self, time: u128) -> bool {
+        self.end_time
+            .map(|end_time| time < end_time)
+            .unwrap_or(true)
+    }
+
+    /// Latest handover at or before `time`
+    pub fn latest_handover(&self, time: u128) -> Option<u128> {
+        if time < self.start_time {
+            return None;
+        }
+
+        let handover_time = match self.repeat_interval {
+            Some(repeat_interval) if repeat_interval > 0 => {
+                let mut handover_time =
+                    self.start_time + (time - self.start_time) / repeat_interval * repeat_interval;
+                // Past the end of the recurrence, step back to its last handover
+                if let Some(end_time) = self.end_time {
+                    if handover_time >= end_time {
+                        let last_index =
+                            (end_time - 1).checked_sub(self.start_time)? / repeat_interval;
+                        handover_time = self.start_time + last_index * repeat_interval;
+                    }
+                }
+                handover_time
+            }
+            _ => self.start_time,
+        };
+
+        self.is_before_end(handover_time).then_some(handover_time)
+    }
+
+    /// Handovers in `[from_time, to_time)`, in order
+    pub fn handovers_between(&self, from_time: u128, to_time: u128) -> Vec<u128> {
+        let mut handover_times = vec![];
+        let mut handover_time = match self.repeat_interval {
+            Some(repeat_interval) if repeat_interval > 0 && from_time > self.start_time => {
+                // First handover at or after `from_time`
+                let elapsed = from_time - self.start_time;
+                self.start_time + elapsed.div_ceil(repeat_interval) * repeat_interval
+            }
+            _ => self.start_time,
+        };
+
+        while handover_time >= from_time
+            && handover_time < to_time
+            && self.is_before_end(handover_time)
+        {
+            handover_times.push(handover_time);
+            match self.repeat_interval {
+                Some(repeat_interval) if repeat_interval > 0 => handover_time += repeat_interval,
+                _ => break,
+            }
+        }
+
+        handover_times
+    }
+}
+
+impl ShiftTemplate {
+    /// Latest handover of any of its recurrences at or before `time`
+    pub fn latest_handover(&self, time: u128) -> Option<u128> {
+        self.schedule
+            .iter()
+            .filter_map(|recurrence| recurrence.latest_handover(time))
+            .max()
+    }
+}
+
+/// The most recent handover up to `time` when it was not applied yet. Only the latest one is
+/// returned, a roster that was already replaced by a later handover is never applied.
+pub fn due_handover<'a>(
+    shift_templates: impl IntoIterator<Item = &'a ShiftTemplate>,
+    time: u128,
+) -> Option<ShiftHandover> {
+    let (shift_template, handover_time) = shift_templates
+        .into_iter()
+        .filter_map(|shift_template| {
+            shift_template
+                .latest_handover(time)
+                .map(|handover_time| (shift_template, handover_time))
+        })
+        .max_by(
+            |(template, handover_time), (other_template, other_handover_time)| {
+                handover_time
+                    .cmp(other_handover_time)
+                    .then_with(|| other_template.internal_id.cmp(&template.internal_id))
+            },
+        )?;
+
+    if shift_template
+        .last_handover
+        .is_some_and(|last_handover| last_handover >= handover_time)
+    {
+        return None;
+    }
+
+    Some(ShiftHandover {
+        shift_template_id: shift_template.internal_id.clone(),
+        shift_template_name: shift_template.name.clone(),
+        handover_time,
+    })
+}
+
+/// Every handover in `[from_time, to_time)`, soonest first
+pub fn shift_calendar(
+    data_store: &DataStore,
+    from_time: u128,
+    to_time: u128,
+) -> Vec<ShiftHandover> {
+    let mut handovers = data_store
+        .shift_templates
+        .values()
+        .flat_map(|shift_template| {
+            shift_template
+                .schedule
+                .iter()
+                .flat_map(move |recurrence| recurrence.handovers_between(from_time, to_time))
+                .map(move |handover_time| ShiftHandover {
+                    shift_template_id: shift_template.internal_id.clone(),
+                    shift_template_name: shift_template.name.clone(),
+                    handover_time,
+                })
+        })
+        .collect::<Vec<ShiftHandover>>();
+
+    handovers.sort_by(|handover, other_handover| {
+        handover
+            .handover_time
+            .cmp(&other_handover.handover_time)
+            .then_with(|| {
+                handover
+                    .shift_template_name
+                    .cmp(&other_handover.shift_template_name)
+            })
+    });
+    handovers.dedup_by(|handover, other_handover| {
+        handover.handover_time == other_handover.handover_time
+            && handover.shift_template_id == other_handover.shift_template_id
+    });
+    handovers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recurrence(
+        start_time: u128,
+        repeat_interval: Option<u128>,
+        end_time: Option<u128>,
+    ) -> ShiftRecurrence {
+        ShiftRecurrence {
+            start_time,
+            repeat_interval,
+            end_time,
+        }
+    }
+
+    fn shift_template(shift_template_id: &str, schedule: Vec<ShiftRecurrence>) -> ShiftTemplate {
+        ShiftTemplate {
+            internal_id: shift_template_id.to_string(),
+            name: shift_template_id.to_string(),
+            available_staff: vec![],
+            team_allocations: Default::default(),
+            schedule,
+            last_handover: None,
+        }
+    }
+
+    #[test]
+    fn latest_handover_steps_back_from_the_end_time() {
+        let recurrence = recurrence(100, Some(10), Some(130));
+
+        assert_eq!(recurrence.latest_handover(99), None);
+        assert_eq!(recurrence.latest_handover(100), Some(100));
+        assert_eq!(recurrence.latest_handover(119), Some(110));
+        assert_eq!(recurrence.latest_handover(120), Some(120));
+        assert_eq!(recurrence.latest_handover(129), Some(120));
+        assert_eq!(recurrence.latest_handover(130), Some(120));
+        assert_eq!(recurrence.latest_handover(1_000), Some(120));
+    }
+
+    #[test]
+    fn latest_handover_excludes_a_handover_at_the_end_time() {
+        let recurrence = recurrence(100, Some(10), Some(120));
+
+        assert_eq!(recurrence.latest_handover(120), Some(110));
+        assert_eq!(recurrence.latest_handover(125), Some(110));
+    }
+
+    #[test]
+    fn single_handovers_happen_once() {
+        assert_eq!(
+            recurrence(100, None, None).latest_handover(1_000),
+            Some(100)
+        );
+        assert_eq!(
+            recurrence(100, None, Some(101)).latest_handover(1_000),
+            Some(100)
+        );
+        assert_eq!(
+            recurrence(100, None, Some(100)).latest_handover(1_000),
+            None
+        );
+
+        assert_eq!(
+            recurrence(100, None, None).handovers_between(100, 101),
+            vec![100]
+        );
+        assert!(recurrence(100, None, None)
+            .handovers_between(101, 1_000)
+            .is_empty());
+    }
+
+    #[test]
+    fn handovers_between_include_the_start_and_exclude_the_end() {
+        let recurrence = recurrence(100, Some(10), Some(130));
+
+        assert_eq!(recurrence.handovers_between(0, 1_000), vec![100, 110, 120]);
+        assert_eq!(recurrence.handovers_between(110, 120), vec![110]);
+        assert_eq!(recurrence.handovers_between(101, 130), vec![110, 120]);
+        assert!(recurrence.handovers_between(0, 100).is_empty());
+        assert!(recurrence.handovers_between(121, 1_000).is_empty());
+    }
+
+    #[test]
+    fn invalid_recurrences_are_detected() {
+        assert!(recurrence(100, Some(10), None).is_valid());
+        assert!(!recurrence(100, Some(0), None).is_valid());
+        assert!(!recurrence(100, Some(10), Some(100)).is_valid());
+    }
+
+    #[test]
+    fn only_the_latest_unapplied_handover_is_due() {
+        let mut data_store = DataStore::default();
+        for shift_template in [
+            shift_template("day", vec![recurrence(100, Some(20), None)]),
+            shift_template("night", vec![recurrence(110, Some(20), None)]),
+        ] {
+            data_store
+                .shift_templates
+                .insert(shift_template.internal_id.clone(), shift_template);
+        }
+
+        let handover = due_handover(data_store.shift_templates.values(), 135).unwrap();
+        assert_eq!(handover.shift_template_id, "night");
+        assert_eq!(handover.handover_time, 130);
+
+        data_store
+            .shift_templates
+            .get_mut("night")
+            .unwrap()
+            .last_handover = Some(130);
+        assert!(due_handover(data_store.shift_templates.values(), 135).is_none());
+
+        let calendar = shift_calendar(&data_store, 100, 140)
+            .into_iter()
+            .map(|handover| (handover.shift_template_id, handover.handover_time))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            calendar,
+            vec![
+                (String::from("day"), 100),
+                (String::from("night"), 110),
+                (String::from("day"), 120),
+                (String::from("night"), 130),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1722 @@
+use anyhow::Context;
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+use tauri::AppHandle;
+
+use super::backup::{self, BackupKind};
+use super::migrations;
+use super::transaction::{ChangeObserver, ChangeSet, EntityChanges};
+use super::types::{
+    ActiveOccurrence, ArchiveQuery, ArchivedOccurrence, CrewAssignment, CrewRequirement, DataStore,
+    FirefightDataManager, LeavePeriod, Occurrence, Qualification, ResourceRelease,
+    ResourceTransfer, ShiftTemplate, Staff, Team, Vehicle, VehicleStatusChange,
+};
+
+/// Ordered schema steps, the database `user_version` holds how many were applied
+const SCHEMA_MIGRATIONS: &[&str] = &["
+    CREATE TABLE occurrences (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        image TEXT NOT NULL,
+        name TEXT NOT NULL
+    );
+
+    CREATE TABLE staff (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        image TEXT NOT NULL,
+        label TEXT NOT NULL,
+        name TEXT NOT NULL,
+        national_id TEXT NOT NULL,
+        permission TEXT NOT NULL,
+        rank TEXT NOT NULL,
+        state TEXT NOT NULL
+    );
+
+    CREATE TABLE teams (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        label TEXT NOT NULL,
+        state TEXT NOT NULL
+    );
+
+    CREATE TABLE team_members (
+        team_id TEXT NOT NULL REFERENCES teams (internal_id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        staff_id TEXT NOT NULL,
+        PRIMARY KEY (team_id, position)
+    );
+
+    CREATE TABLE vehicles (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        capacity INTEGER,
+        category TEXT NOT NULL,
+        image TEXT NOT NULL,
+        label TEXT NOT NULL,
+        license_plate TEXT,
+        state TEXT NOT NULL
+    );
+
+    CREATE TABLE active_occurrences (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        address TEXT,
+        codu_number TEXT,
+        creation_time INTEGER,
+        description TEXT,
+        location TEXT,
+        occurrence_id TEXT NOT NULL,
+        reference_point TEXT,
+        vmer_siv INTEGER
+    );
+
+    CREATE TABLE active_occurrence_staff (
+        active_occurrence_id TEXT NOT NULL REFERENCES active_occurrences (internal_id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        staff_id TEXT NOT NULL,
+        PRIMARY KEY (active_occurrence_id, position)
+    );
+    CREATE INDEX active_occurrence_staff_by_staff ON active_occurrence_staff (staff_id);
+
+    CREATE TABLE active_occurrence_teams (
+        active_occurrence_id TEXT NOT NULL REFERENCES active_occurrences (internal_id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        team_id TEXT NOT NULL,
+        PRIMARY KEY (active_occurrence_id, position)
+    );
+    CREATE INDEX active_occurrence_teams_by_team ON active_occurrence_teams (team_id);
+
+    CREATE TABLE active_occurrence_vehicles (
+        active_occurrence_id TEXT NOT NULL REFERENCES active_occurrences (internal_id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        vehicle_id TEXT NOT NULL,
+        PRIMARY KEY (active_occurrence_id, position)
+    );
+    CREATE INDEX active_occurrence_vehicles_by_vehicle ON active_occurrence_vehicles (vehicle_id);
+
+    CREATE TABLE vehicle_assignments (
+        active_occurrence_id TEXT NOT NULL REFERENCES active_occurrences (internal_id) ON DELETE CASCADE,
+        vehicle_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        staff_id TEXT NOT NULL,
+        PRIMARY KEY (active_occurrence_id, vehicle_id, position)
+    );
+", "
+    CREATE TABLE archived_occurrences (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        occurrence_id TEXT NOT NULL,
+        start_time INTEGER NOT NULL,
+        end_time INTEGER NOT NULL,
+        record TEXT NOT NULL
+    );
+    CREATE INDEX archived_occurrences_by_end_time ON archived_occurrences (end_time);
+
+    CREATE TABLE archived_occurrence_staff (
+        archived_occurrence_id TEXT NOT NULL REFERENCES archived_occurrences (internal_id) ON DELETE CASCADE,
+        staff_id TEXT NOT NULL,
+        PRIMARY KEY (archived_occurrence_id, staff_id)
+    );
+    CREATE INDEX archived_occurrence_staff_by_staff ON archived_occurrence_staff (staff_id);
+
+    CREATE TABLE archived_occurrence_vehicles (
+        archived_occurrence_id TEXT NOT NULL REFERENCES archived_occurrences (internal_id) ON DELETE CASCADE,
+        vehicle_id TEXT NOT NULL,
+        PRIMARY KEY (archived_occurrence_id, vehicle_id)
+    );
+    CREATE INDEX archived_occurrence_vehicles_by_vehicle ON archived_occurrence_vehicles (vehicle_id);
+", "
+    CREATE TABLE staff_qualifications (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        staff_id TEXT NOT NULL REFERENCES staff (internal_id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        issue_time INTEGER NOT NULL,
+        expiry_time INTEGER
+    );
+    CREATE INDEX staff_qualifications_by_staff ON staff_qualifications (staff_id, position);
+", "
+    CREATE TABLE crew_requirements (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        record TEXT NOT NULL
+    );
+", "
+    ALTER TABLE vehicle_assignments ADD COLUMN role TEXT NOT NULL DEFAULT 'crew';
+", "
+    CREATE TABLE shift_templates (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        record TEXT NOT NULL
+    );
+", "
+    CREATE TABLE staff_leave_periods (
+        internal_id TEXT PRIMARY KEY NOT NULL,
+        staff_id TEXT NOT NULL REFERENCES staff (internal_id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        reason TEXT NOT NULL,
+        start_time INTEGER NOT NULL,
+        end_time INTEGER NOT NULL,
+        status TEXT NOT NULL
+    );
+    CREATE INDEX staff_leave_periods_by_staff ON staff_leave_periods (staff_id, position);
+", "
+    CREATE TABLE vehicle_status_changes (
+        active_occurrence_id TEXT NOT NULL REFERENCES active_occurrences (internal_id) ON DELETE CASCADE,
+        vehicle_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        time INTEGER NOT NULL,
+        PRIMARY KEY (active_occurrence_id, vehicle_id, position)
+    );
+", "
+    CREATE TABLE resource_transfers (
+        active_occurrence_id TEXT NOT NULL REFERENCES active_occurrences (internal_id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        record TEXT NOT NULL,
+        PRIMARY KEY (active_occurrence_id, position)
+    );
+", "
+    CREATE TABLE resource_releases (
+        active_occurrence_id TEXT NOT NULL REFERENCES active_occurrences (internal_id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        record TEXT NOT NULL,
+        PRIMARY KEY (active_occurrence_id, position)
+    );
+", "
+    ALTER TABLE occurrences ADD COLUMN dispatch_plan TEXT NOT NULL DEFAULT '[]';
+"];
+
+const ACTIVE_OCCURRENCE_COLUMNS: &str = "internal_id, address, codu_number, creation_time, description, location, occurrence_id, reference_point, vmer_siv";
+const OCCURRENCE_COLUMNS: &str = "internal_id, image, name, dispatch_plan";
+const STAFF_COLUMNS: &str = "internal_id, image, label, name, national_id, permission, rank, state";
+const TEAM_COLUMNS: &str = "internal_id, label, state";
+const VEHICLE_COLUMNS: &str = "internal_id, capacity, category, image, label, license_plate, state";
+
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+    observers: Vec<Arc<dyn ChangeObserver>>,
+}
+
+fn get_data_dir(app_handle: &AppHandle) -> anyhow::Result<PathBuf> {
+    let data_dir = app_handle
+        .path_resolver()
+        .app_local_data_dir()
+        .with_context(|| "Unable to resolve the app local data directory".to_string())?;
+    std::fs::create_dir_all(&data_dir)
+        .with_context(|| format!("Failed to create data directory {:?}", data_dir))?;
+
+    Ok(data_dir)
+}
+
+pub fn create_store(app_handle: AppHandle) -> anyhow::Result<SqliteStore> {
+    let data_dir = get_data_dir(&app_handle)?;
+
+    let mut connection = Connection::open(data_dir.join("data_store.db"))
+        .with_context(|| "Failed to open database".to_string())?;
+    connection
+        .pragma_update(None, "foreign_keys", true)
+        .with_context(|| "Failed to enable foreign keys".to_string())?;
+
+    let schema_version: i64 = connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .with_context(|| "Failed to read database schema version".to_string())?;
+
+    let transaction = connection.transaction()?;
+    migrate_schema(&transaction, schema_version)?;
+
+    // Keep a backup of the contents as they were before any schema change. They are read through
+    // the migrated schema, before it is committed, so the backup is a snapshot any backend restores.
+    if schema_version > 0 && (schema_version as usize) < SCHEMA_MIGRATIONS.len() {
+        let archive = query_archived_occurrence_list(&transaction, &ArchiveQuery::default())?
+            .into_iter()
+            .map(|archived_occurrence| {
+                (
+                    archived_occurrence.active_occurrence.internal_id.clone(),
+                    archived_occurrence,
+                )
+            })
+            .collect::<HashMap<String, ArchivedOccurrence>>();
+        backup::create_store_backup(
+            &app_handle,
+            &query_data_store(&transaction)?,
+            &archive,
+            BackupKind::PreMigration,
+        )?;
+    }
+
+    // First run, bring over whatever the json store had
+    let legacy_store_path = data_dir.join("data_store.dat");
+    if schema_version == 0 && legacy_store_path.exists() {
+        println!("Importing legacy store {:?}", legacy_store_path);
+        import_legacy_store(&transaction, &legacy_store_path)?;
+    }
+
+    transaction
+        .commit()
+        .with_context(|| "Failed to commit database initialization".to_string())?;
+
+    Ok(SqliteStore {
+        connection: Mutex::new(connection),
+        observers: vec![],
+    })
+}
+
+/// Moves the database aside and rebuilds it from a backup snapshot
+pub fn restore_backup(app_handle: AppHandle, name: &str) -> anyhow::Result<()> {
+    let snapshot = backup::read_backup(&app_handle, name)?;
+
+    let database_path = get_data_dir(&app_handle)?.join("data_store.db");
+    if database_path.exists() {
+        let replaced_path = database_path.with_extension(format!(
+            "replaced-{}",
+            std::time::UNIX_EPOCH.elapsed()?.as_millis()
+        ));
+        std::fs::rename(&database_path, &replaced_path)
+            .with_context(|| format!("Failed to move {:?} aside", database_path))?;
+    }
+
+    let mut store = create_store(app_handle)?;
+    store.restore_store(snapshot.data_store, snapshot.archive)
+}
+
+fn migrate_schema(connection: &Connection, schema_version: i64) -> anyhow::Result<()> {
+    for (index, migration) in SCHEMA_MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(schema_version as usize)
+    {
+        let next_version = index as i64 + 1;
+        connection
+            .execute_batch(migration)
+            .with_context(|| format!("Failed to migrate database to version {}", next_version))?;
+        connection.pragma_update(None, "user_version", next_version)?;
+    }
+
+    Ok(())
+}
+
+fn legacy_collection<T: DeserializeOwned>(
+    legacy_value: &serde_json::Value,
+    key: &str,
+) -> anyhow::Result<HashMap<String, T>> {
+    match legacy_value.get(key) {
+        Some(collection_value) => serde_json::from_value(collection_value.clone())
+            .with_context(|| format!("Failed to deserialize legacy {}", key)),
+        None => Ok(HashMap::new()),
+    }
+}
+
+fn import_legacy_store(connection: &Connection, legacy_store_path: &Path) -> anyhow::Result<()> {
+    let legacy_bytes = std::fs::read(legacy_store_path)
+        .with_context(|| format!("Failed to read legacy store {:?}", legacy_store_path))?;
+    let mut legacy_entries =
+        serde_json::from_slice::<serde_json::Map<String, serde_json::Value>>(&legacy_bytes)
+            .with_context(|| "Failed to parse legacy store".to_string())?;
+    migrations::migrate_store(&mut legacy_entries)?;
+    let legacy_value = serde_json::Value::Object(legacy_entries);
+
+    let data_store = DataStore {
+        active_occurrences: legacy_collection(&legacy_value, "active_occurrences")?,
+        occurrences: legacy_collection(&legacy_value, "occurrences")?,
+        staff: legacy_collection(&legacy_value, "staff")?,
+        teams: legacy_collection(&legacy_value, "teams")?,
+        vehicles: legacy_collection(&legacy_value, "vehicles")?,
+        crew_requirements: legacy_collection(&legacy_value, migrations::CREW_REQUIREMENTS_KEY)?,
+        shift_templates: legacy_collection(&legacy_value, migrations::SHIFT_TEMPLATES_KEY)?,
+    };
+
+    for occurrence in data_store.occurrences.values() {
+        write_occurrence(connection, occurrence)?;
+    }
+    for staff in data_store.staff.values() {
+        write_staff(connection, staff)?;
+    }
+    for team in data_store.teams.values() {
+        write_team(connection, team)?;
+    }
+    for vehicle in data_store.vehicles.values() {
+        write_vehicle(connection, vehicle)?;
+    }
+    for active_occurrence in data_store.active_occurrences.values() {
+        write_active_occurrence(connection, active_occurrence)?;
+    }
+    for crew_requirement in data_store.crew_requirements.values() {
+        write_crew_requirement(connection, crew_requirement)?;
+    }
+    for shift_template in data_store.shift_templates.values() {
+        write_shift_template(connection, shift_template)?;
+    }
+
+    let archive: HashMap<String, ArchivedOccurrence> =
+        legacy_collection(&legacy_value, migrations::ARCHIVE_KEY)?;
+    for archived_occurrence in archive.values() {
+        write_archived_occurrence(connection, archived_occurrence)?;
+    }
+    Ok(())
+}
+
+// Row mapping
+
+fn enum_to_sql<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    serde_json::to_value(value)?
+        .as_str()
+        .map(String::from)
+        .with_context(|| "Failed to serialize enumeration value".to_string())
+}
+
+fn enum_column<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    serde_json::from_value(serde_json::Value::String(value)).map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(error))
+    })
+}
+
+fn json_column<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    serde_json::from_str(&value).map_err(|error| {
+        rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(error))
+    })
+}
+
+fn active_occurrence_from_row(row: &Row) -> rusqlite::Result<ActiveOccurrence> {
+    let creation_time: Option<i64> = row.get(3)?;
+
+    Ok(ActiveOccurrence {
+        address: row.get(1)?,
+        codu_number: row.get(2)?,
+        creation_time: creation_time.map(|time| time as u128),
+        description: row.get(4)?,
+        internal_id: row.get(0)?,
+        location: row.get(5)?,
+        occurrence_id: row.get(6)?,
+        reference_point: row.get(7)?,
+        resource_releases: vec![],
+        resource_transfers: vec![],
+        staff_ids: vec![],
+        team_ids: vec![],
+        vehicle_assignment_map: HashMap::new(),
+        vehicle_ids: vec![],
+        vehicle_timelines: HashMap::new(),
+        vmer_siv: row.get(8)?,
+    })
+}
+
+fn occurrence_from_row(row: &Row) -> rusqlite::Result<Occurrence> {
+    Ok(Occurrence {
+        internal_id: row.get(0)?,
+        image: row.get(1)?,
+        name: row.get(2)?,
+        dispatch_plan: json_column(row, 3)?,
+    })
+}
+
+fn staff_from_row(row: &Row) -> rusqlite::Result<Staff> {
+    Ok(Staff {
+        internal_id: row.get(0)?,
+        image: row.get(1)?,
+        label: row.get(2)?,
+        name: row.get(3)?,
+        national_id: row.get(4)?,
+        permission: enum_column(row, 5)?,
+        rank: enum_column(row, 6)?,
+        state: enum_column(row, 7)?,
+        qualifications: vec![],
+        leave_periods: vec![],
+    })
+}
+
+fn leave_period_from_row(row: &Row) -> rusqlite::Result<LeavePeriod> {
+    Ok(LeavePeriod {
+        internal_id: row.get(0)?,
+        reason: enum_column(row, 1)?,
+        start_time: row.get::<_, i64>(2)? as u128,
+        end_time: row.get::<_, i64>(3)? as u128,
+        status: enum_column(row, 4)?,
+    })
+}
+
+fn qualification_from_row(row: &Row) -> rusqlite::Result<Qualification> {
+    let expiry_time: Option<i64> = row.get(3)?;
+
+    Ok(Qualification {
+        internal_id: row.get(0)?,
+        kind: enum_column(row, 1)?,
+        issue_time: row.get::<_, i64>(2)? as u128,
+        expiry_time: expiry_time.map(|time| time as u128),
+    })
+}
+
+fn team_from_row(row: &Row) -> rusqlite::Result<Team> {
+    Ok(Team {
+        internal_id: row.get(0)?,
+        label: row.get(1)?,
+        member_ids: vec![],
+        state: enum_column(row, 2)?,
+    })
+}
+
+fn vehicle_from_row(row: &Row) -> rusqlite::Result<Vehicle> {
+    Ok(Vehicle {
+        internal_id: row.get(0)?,
+        capacity: row.get(1)?,
+        category: enum_column(row, 2)?,
+        image: row.get(3)?,
+        label: row.get(4)?,
+        license_plate: row.get(5)?,
+        state: enum_column(row, 6)?,
+    })
+}
+
+// Queries
+
+fn query_id_list(
+    connection: &Connection,
+    sql: &str,
+    owner_id: &String,
+) -> anyhow::Result<Vec<String>> {
+    let mut statement = connection.prepare_cached(sql)?;
+    let id_list = statement
+        .query_map(params![owner_id], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(id_list)
+}
+
+fn load_active_occurrence_relations(
+    connection: &Connection,
+    active_occurrence: &mut ActiveOccurrence,
+) -> anyhow::Result<()> {
+    active_occurrence.staff_ids = query_id_list(
+        connection,
+        "SELECT staff_id FROM active_occurrence_staff WHERE active_occurrence_id = ?1 ORDER BY position",
+        &active_occurrence.internal_id,
+    )?;
+    active_occurrence.team_ids = query_id_list(
+        connection,
+        "SELECT team_id FROM active_occurrence_teams WHERE active_occurrence_id = ?1 ORDER BY position",
+        &active_occurrence.internal_id,
+    )?;
+    active_occurrence.vehicle_ids = query_id_list(
+        connection,
+        "SELECT vehicle_id FROM active_occurrence_vehicles WHERE active_occurrence_id = ?1 ORDER BY position",
+        &active_occurrence.internal_id,
+    )?;
+
+    let mut statement = connection.prepare_cached(
+        "SELECT vehicle_id, staff_id, role FROM vehicle_assignments WHERE active_occurrence_id = ?1 ORDER BY vehicle_id, position",
+    )?;
+    let assignments = statement
+        .query_map(params![active_occurrence.internal_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                CrewAssignment {
+                    staff_id: row.get(1)?,
+                    role: enum_column(row, 2)?,
+                },
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<(String, CrewAssignment)>>>()?;
+
+    active_occurrence.vehicle_assignment_map.clear();
+    assignments
+        .into_iter()
+        .for_each(|(vehicle_id, assignment)| {
+            active_occurrence
+                .vehicle_assignment_map
+                .entry(vehicle_id)
+                .or_default()
+                .push(assignment);
+        });
+
+    let mut statement = connection.prepare_cached(
+        "SELECT vehicle_id, status, time FROM vehicle_status_changes WHERE active_occurrence_id = ?1 ORDER BY vehicle_id, position",
+    )?;
+    let status_changes = statement
+        .query_map(params![active_occurrence.internal_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                VehicleStatusChange {
+                    status: enum_column(row, 1)?,
+                    time: row.get::<_, i64>(2)? as u128,
+                },
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<(String, VehicleStatusChange)>>>()?;
+
+    active_occurrence.vehicle_timelines.clear();
+    status_changes
+        .into_iter()
+        .for_each(|(vehicle_id, status_change)| {
+            active_occurrence
+                .vehicle_timelines
+                .entry(vehicle_id)
+                .or_default()
+                .push(status_change);
+        });
+
+    let mut statement = connection.prepare_cached(
+        "SELECT record FROM resource_transfers WHERE active_occurrence_id = ?1 ORDER BY position",
+    )?;
+    let records = statement
+        .query_map(params![active_occurrence.internal_id], |row| {
+            row.get::<_, String>(0)
+        })?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    active_occurrence.resource_transfers = records
+        .iter()
+        .map(|record| {
+            serde_json::from_str(record)
+                .with_context(|| "Failed to deserialize resource transfer".to_string())
+        })
+        .collect::<anyhow::Result<Vec<ResourceTransfer>>>()?;
+
+    let mut statement = connection.prepare_cached(
+        "SELECT record FROM resource_releases WHERE active_occurrence_id = ?1 ORDER BY position",
+    )?;
+    let records = statement
+        .query_map(params![active_occurrence.internal_id], |row| {
+            row.get::<_, String>(0)
+        })?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    active_occurrence.resource_releases = records
+        .iter()
+        .map(|record| {
+            serde_json::from_str(record)
+                .with_context(|| "Failed to deserialize resource release".to_string())
+        })
+        .collect::<anyhow::Result<Vec<ResourceRelease>>>()?;
+
+    Ok(())
+}
+
+fn query_active_occurrence(
+    connection: &Connection,
+    active_occurrence_id: &String,
+) -> anyhow::Result<Option<ActiveOccurrence>> {
+    let active_occurrence = connection
+        .query_row(
+            &format!(
+                "SELECT {} FROM active_occurrences WHERE internal_id = ?1",
+                ACTIVE_OCCURRENCE_COLUMNS
+            ),
+            params![active_occurrence_id],
+            active_occurrence_from_row,
+        )
+        .optional()
+        .with_context(|| format!("Failed to query active occurrence {}", active_occurrence_id))?;
+
+    match active_occurrence {
+        Some(mut active_occurrence) => {
+            load_active_occurrence_relations(connection, &mut active_occurrence)?;
+            Ok(Some(active_occurrence))
+        }
+        None => Ok(None),
+    }
+}
+
+fn query_active_occurrence_list(
+    connection: &Connection,
+    filter: &str,
+    filter_params: &[&dyn rusqlite::ToSql],
+) -> anyhow::Result<Vec<ActiveOccurrence>> {
+    let mut statement = connection.prepare(&format!(
+        "SELECT {} FROM active_occurrences {}",
+        ACTIVE_OCCURRENCE_COLUMNS, filter
+    ))?;
+    let mut active_occurrence_list = statement
+        .query_map(filter_params, active_occurrence_from_row)?
+        .collect::<rusqlite::Result<Vec<ActiveOccurrence>>>()
+        .with_context(|| "Failed to query active occurrences".to_string())?;
+
+    for active_occurrence in active_occurrence_list.iter_mut() {
+        load_active_occurrence_relations(connection, active_occurrence)?;
+    }
+    Ok(active_occurrence_list)
+}
+
+fn query_occurrence(
+    connection: &Connection,
+    occurrence_id: &String,
+) -> anyhow::Result<Option<Occurrence>> {
+    connection
+        .query_row(
+            &format!(
+                "SELECT {} FROM occurrences WHERE internal_id = ?1",
+                OCCURRENCE_COLUMNS
+            ),
+            params![occurrence_id],
+            occurrence_from_row,
+        )
+        .optional()
+        .with_context(|| format!("Failed to query occurrence {}", occurrence_id))
+}
+
+fn query_occurrence_list(connection: &Connection) -> anyhow::Result<Vec<Occurrence>> {
+    let mut statement =
+        connection.prepare(&format!("SELECT {} FROM occurrences", OCCURRENCE_COLUMNS))?;
+    let occurrence_list = statement
+        .query_map([], occurrence_from_row)?
+        .collect::<rusqlite::Result<Vec<Occurrence>>>()
+        .with_context(|| "Failed to query occurrences".to_string())?;
+    Ok(occurrence_list)
+}
+
+fn query_qualification_list(
+    connection: &Connection,
+    staff_id: &String,
+) -> anyhow::Result<Vec<Qualification>> {
+    let mut statement = connection.prepare_cached(
+        "SELECT internal_id, kind, issue_time, expiry_time FROM staff_qualifications WHERE staff_id = ?1 ORDER BY position",
+    )?;
+    let qualification_list = statement
+        .query_map(params![staff_id], qualification_from_row)?
+        .collect::<rusqlite::Result<Vec<Qualification>>>()
+        .with_context(|| format!("Failed to query qualifications of staff {}", staff_id))?;
+    Ok(qualification_list)
+}
+
+fn query_leave_period_list(
+    connection: &Connection,
+    staff_id: &String,
+) -> anyhow::Result<Vec<LeavePeriod>> {
+    let mut statement = connection.prepare_cached(
+        "SELECT internal_id, reason, start_time, end_time, status FROM staff_leave_periods WHERE staff_id = ?1 ORDER BY position",
+    )?;
+    let leave_period_list = statement
+        .query_map(params![staff_id], leave_period_from_row)?
+        .collect::<rusqlite::Result<Vec<LeavePeriod>>>()
+        .with_context(|| format!("Failed to query leave periods of staff {}", staff_id))?;
+    Ok(leave_period_list)
+}
+
+fn query_staff(connection: &Connection, staff_id: &String) -> anyhow::Result<Option<Staff>> {
+    let staff = connection
+        .query_row(
+            &format!("SELECT {} FROM staff WHERE internal_id = ?1", STAFF_COLUMNS),
+            params![staff_id],
+            staff_from_row,
+        )
+        .optional()
+        .with_context(|| format!("Failed to query staff {}", staff_id))?;
+
+    match staff {
+        Some(mut staff) => {
+            staff.qualifications = query_qualification_list(connection, &staff.internal_id)?;
+            staff.leave_periods = query_leave_period_list(connection, &staff.internal_id)?;
+            Ok(Some(staff))
+        }
+        None => Ok(None),
+    }
+}
+
+fn query_staff_list(connection: &Connection) -> anyhow::Result<Vec<Staff>> {
+    let mut statement = connection.prepare(&format!("SELECT {} FROM staff", STAFF_COLUMNS))?;
+    let mut staff_list = statement
+        .query_map([], staff_from_row)?
+        .collect::<rusqlite::Result<Vec<Staff>>>()
+        .with_context(|| "Failed to query staff".to_string())?;
+
+    for staff in staff_list.iter_mut() {
+        staff.qualifications = query_qualification_list(connection, &staff.internal_id)?;
+        staff.leave_periods = query_leave_period_list(connection, &staff.internal_id)?;
+    }
+    Ok(staff_list)
+}
+
+fn query_team(connection: &Connection, team_id: &String) -> anyhow::Result<Option<Team>> {
+    let team = connection
+        .query_row(
+            &format!("SELECT {} FROM teams WHERE internal_id = ?1", TEAM_COLUMNS),
+            params![team_id],
+            team_from_row,
+        )
+        .optional()
+        .with_context(|| format!("Failed to query team {}", team_id))?;
+
+    match team {
+        Some(mut team) => {
+            team.member_ids = query_id_list(
+                connection,
+                "SELECT staff_id FROM team_members WHERE team_id = ?1 ORDER BY position",
+                &team.internal_id,
+            )?;
+            Ok(Some(team))
+        }
+        None => Ok(None),
+    }
+}
+
+fn query_team_list(connection: &Connection) -> anyhow::Result<Vec<Team>> {
+    let mut statement = connection.prepare(&format!("SELECT {} FROM teams", TEAM_COLUMNS))?;
+    let mut team_list = statement
+        .query_map([], team_from_row)?
+        .collect::<rusqlite::Result<Vec<Team>>>()
+        .with_context(|| "Failed to query teams".to_string())?;
+
+    for team in team_list.iter_mut() {
+        team.member_ids = query_id_list(
+            connection,
+            "SELECT staff_id FROM team_members WHERE team_id = ?1 ORDER BY position",
+            &team.internal_id,
+        )?;
+    }
+    Ok(team_list)
+}
+
+fn query_vehicle(connection: &Connection, vehicle_id: &String) -> anyhow::Result<Option<Vehicle>> {
+    connection
+        .query_row(
+            &format!(
+                "SELECT {} FROM vehicles WHERE internal_id = ?1",
+                VEHICLE_COLUMNS
+            ),
+            params![vehicle_id],
+            vehicle_from_row,
+        )
+        .optional()
+        .with_context(|| format!("Failed to query vehicle {}", vehicle_id))
+}
+
+fn query_vehicle_list(connection: &Connection) -> anyhow::Result<Vec<Vehicle>> {
+    let mut statement = connection.prepare(&format!("SELECT {} FROM vehicles", VEHICLE_COLUMNS))?;
+    let vehicle_list = statement
+        .query_map([], vehicle_from_row)?
+        .collect::<rusqlite::Result<Vec<Vehicle>>>()
+        .with_context(|| "Failed to query vehicles".to_string())?;
+    Ok(vehicle_list)
+}
+
+fn query_crew_requirement_list(connection: &Connection) -> anyhow::Result<Vec<CrewRequirement>> {
+    let mut statement = connection.prepare("SELECT record FROM crew_requirements")?;
+    let records = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .with_context(|| "Failed to query crew requirements".to_string())?;
+
+    records
+        .iter()
+        .map(|record| {
+            serde_json::from_str(record)
+                .with_context(|| "Failed to deserialize crew requirement".to_string())
+        })
+        .collect()
+}
+
+fn query_shift_template_list(connection: &Connection) -> anyhow::Result<Vec<ShiftTemplate>> {
+    let mut statement = connection.prepare("SELECT record FROM shift_templates")?;
+    let records = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .with_context(|| "Failed to query shift templates".to_string())?;
+
+    records
+        .iter()
+        .map(|record| {
+            serde_json::from_str(record)
+                .with_context(|| "Failed to deserialize shift template".to_string())
+        })
+        .collect()
+}
+
+fn query_record<T: DeserializeOwned>(
+    connection: &Connection,
+    table: &str,
+    entity_id: &String,
+) -> anyhow::Result<Option<T>> {
+    let record = connection
+        .query_row(
+            &format!("SELECT record FROM {} WHERE internal_id = ?1", table),
+            params![entity_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .with_context(|| format!("Failed to query {} {}", table, entity_id))?;
+
+    match record {
+        Some(record) => Ok(Some(serde_json::from_str(&record).with_context(|| {
+            format!("Failed to deserialize {} {}", table, entity_id)
+        })?)),
+        None => Ok(None),
+    }
+}
+
+fn query_crew_requirement(
+    connection: &Connection,
+    crew_requirement_id: &String,
+) -> anyhow::Result<Option<CrewRequirement>> {
+    query_record(connection, "crew_requirements", crew_requirement_id)
+}
+
+fn query_shift_template(
+    connection: &Connection,
+    shift_template_id: &String,
+) -> anyhow::Result<Option<ShiftTemplate>> {
+    query_record(connection, "shift_templates", shift_template_id)
+}
+
+fn query_touched<T>(
+    connection: &Connection,
+    changes: &EntityChanges<T>,
+    query: fn(&Connection, &String) -> anyhow::Result<Option<T>>,
+) -> anyhow::Result<HashMap<String, T>> {
+    let mut entities = HashMap::new();
+    for entity_id in changes.keys() {
+        if let Some(entity) = query(connection, entity_id)? {
+            entities.insert(entity_id.clone(), entity);
+        }
+    }
+
+    Ok(entities)
+}
+
+/// Only the rows a change set touches, enough to check it for conflicts
+fn query_touched_store(connection: &Connection, changes: &ChangeSet) -> anyhow::Result<DataStore> {
+    Ok(DataStore {
+        active_occurrences: query_touched(
+            connection,
+            &changes.active_occurrences,
+            query_active_occurrence,
+        )?,
+        occurrences: query_touched(connection, &changes.occurrences, query_occurrence)?,
+        staff: query_touched(connection, &changes.staff, query_staff)?,
+        teams: query_touched(connection, &changes.teams, query_team)?,
+        vehicles: query_touched(connection, &changes.vehicles, query_vehicle)?,
+        crew_requirements: query_touched(
+            connection,
+            &changes.crew_requirements,
+            query_crew_requirement,
+        )?,
+        shift_templates: query_touched(connection, &changes.shift_templates, query_shift_template)?,
+    })
+}
+
+fn query_data_store(connection: &Connection) -> anyhow::Result<DataStore> {
+    Ok(DataStore {
+        active_occurrences: query_active_occurrence_list(connection, "", &[])?
+            .into_iter()
+            .map(|active_occurrence| (active_occurrence.internal_id.clone(), active_occurrence))
+            .collect(),
+        occurrences: query_occurrence_list(connection)?
+            .into_iter()
+            .map(|occurrence| (occurrence.internal_id.clone(), occurrence))
+            .collect(),
+        staff: query_staff_list(connection)?
+            .into_iter()
+            .map(|staff| (staff.internal_id.clone(), staff))
+            .collect(),
+        teams: query_team_list(connection)?
+            .into_iter()
+            .map(|team| (team.internal_id.clone(), team))
+            .collect(),
+        vehicles: query_vehicle_list(connection)?
+            .into_iter()
+            .map(|vehicle| (vehicle.internal_id.clone(), vehicle))
+            .collect(),
+        crew_requirements: query_crew_requirement_list(connection)?
+            .into_iter()
+            .map(|crew_requirement| (crew_requirement.internal_id.clone(), crew_requirement))
+            .collect(),
+        shift_templates: query_shift_template_list(connection)?
+            .into_iter()
+            .map(|shift_template| (shift_template.internal_id.clone(), shift_template))
+            .collect(),
+    })
+}
+
+fn query_archived_occurrence(
+    connection: &Connection,
+    archived_occurrence_id: &String,
+) -> anyhow::Result<Option<ArchivedOccurrence>> {
+    let record = connection
+        .query_row(
+            "SELECT record FROM archived_occurrences WHERE internal_id = ?1",
+            params![archived_occurrence_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .with_context(|| {
+            format!(
+                "Failed to query archived occurrence {}",
+                archived_occurrence_id
+            )
+        })?;
+
+    match record {
+        Some(record) => Ok(Some(serde_json::from_str(&record).with_context(|| {
+            format!(
+                "Failed to deserialize archived occurrence {}",
+                archived_occurrence_id
+            )
+        })?)),
+        None => Ok(None),
+    }
+}
+
+fn query_archived_occurrence_list(
+    connection: &Connection,
+    query: &ArchiveQuery,
+) -> anyhow::Result<Vec<ArchivedOccurrence>> {
+    let mut statement = connection.prepare(
+        "SELECT record FROM archived_occurrences
+         WHERE (?1 IS NULL OR end_time >= ?1)
+            AND (?2 IS NULL OR start_time <= ?2)
+            AND (?3 IS NULL OR occurrence_id = ?3)
+            AND (?4 IS NULL OR EXISTS (SELECT 1 FROM archived_occurrence_staff WHERE archived_occurrence_id = internal_id AND staff_id = ?4))
+            AND (?5 IS NULL OR EXISTS (SELECT 1 FROM archived_occurrence_vehicles WHERE archived_occurrence_id = internal_id AND vehicle_id = ?5))
+         ORDER BY end_time DESC",
+    )?;
+    let records = statement
+        .query_map(
+            params![
+                query.from_time.map(|time| time as i64),
+                query.to_time.map(|time| time as i64),
+                query.occurrence_id,
+                query.staff_id,
+                query.vehicle_id,
+            ],
+            |row| row.get::<_, String>(0),
+        )?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .with_context(|| "Failed to query archived occurrences".to_string())?;
+
+    records
+        .iter()
+        .map(|record| {
+            serde_json::from_str(record)
+                .with_context(|| "Failed to deserialize archived occurrence".to_string())
+        })
+        .collect()
+}
+
+// Writes
+
+fn write_id_list(
+    connection: &Connection,
+    table: &str,
+    owner_column: &str,
+    id_column: &str,
+    owner_id: &String,
+    id_list: &[String],
+) -> anyhow::Result<()> {
+    connection.execute(
+        &format!("DELETE FROM {} WHERE {} = ?1", table, owner_column),
+        params![owner_id],
+    )?;
+
+    let mut statement = connection.prepare_cached(&format!(
+        "INSERT INTO {} ({}, position, {}) VALUES (?1, ?2, ?3)",
+        table, owner_column, id_column
+    ))?;
+    for (position, id) in id_list.iter().enumerate() {
+        statement.execute(params![owner_id, position as i64, id])?;
+    }
+
+    Ok(())
+}
+
+fn write_active_occurrence(
+    connection: &Connection,
+    active_occurrence: &ActiveOccurrence,
+) -> anyhow::Result<()> {
+    connection
+        .execute(
+            "INSERT INTO active_occurrences (internal_id, address, codu_number, creation_time, description, location, occurrence_id, reference_point, vmer_siv)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT (internal_id) DO UPDATE SET
+                address = excluded.address,
+                codu_number = excluded.codu_number,
+                creation_time = excluded.creation_time,
+                description = excluded.description,
+                location = excluded.location,
+                occurrence_id = excluded.occurrence_id,
+                reference_point = excluded.reference_point,
+                vmer_siv = excluded.vmer_siv",
+            params![
+                active_occurrence.internal_id,
+                active_occurrence.address,
+                active_occurrence.codu_number,
+                active_occurrence.creation_time.map(|time| time as i64),
+                active_occurrence.description,
+                active_occurrence.location,
+                active_occurrence.occurrence_id,
+                active_occurrence.reference_point,
+                active_occurrence.vmer_siv,
+            ],
+        )
+        .with_context(|| format!("Failed to write active occurrence {}", active_occurrence.internal_id))?;
+
+    write_id_list(
+        connection,
+        "active_occurrence_staff",
+        "active_occurrence_id",
+        "staff_id",
+        &active_occurrence.internal_id,
+        &active_occurrence.staff_ids,
+    )?;
+    write_id_list(
+        connection,
+        "active_occurrence_teams",
+        "active_occurrence_id",
+        "team_id",
+        &active_occurrence.internal_id,
+        &active_occurrence.team_ids,
+    )?;
+    write_id_list(
+        connection,
+        "active_occurrence_vehicles",
+        "active_occurrence_id",
+        "vehicle_id",
+        &active_occurrence.internal_id,
+        &active_occurrence.vehicle_ids,
+    )?;
+
+    connection.execute(
+        "DELETE FROM vehicle_assignments WHERE active_occurrence_id = ?1",
+        params![active_occurrence.internal_id],
+    )?;
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO vehicle_assignments (active_occurrence_id, vehicle_id, position, staff_id, role) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (vehicle_id, vehicle_crew) in active_occurrence.vehicle_assignment_map.iter() {
+        for (position, assignment) in vehicle_crew.iter().enumerate() {
+            statement.execute(params![
+                active_occurrence.internal_id,
+                vehicle_id,
+                position as i64,
+                assignment.staff_id,
+                enum_to_sql(&assignment.role)?
+            ])?;
+        }
+    }
+
+    connection.execute(
+        "DELETE FROM vehicle_status_changes WHERE active_occurrence_id = ?1",
+        params![active_occurrence.internal_id],
+    )?;
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO vehicle_status_changes (active_occurrence_id, vehicle_id, position, status, time) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (vehicle_id, vehicle_timeline) in active_occurrence.vehicle_timelines.iter() {
+        for (position, status_change) in vehicle_timeline.iter().enumerate() {
+            statement.execute(params![
+                active_occurrence.internal_id,
+                vehicle_id,
+                position as i64,
+                enum_to_sql(&status_change.status)?,
+                status_change.time as i64
+            ])?;
+        }
+    }
+
+    connection.execute(
+        "DELETE FROM resource_transfers WHERE active_occurrence_id = ?1",
+        params![active_occurrence.internal_id],
+    )?;
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO resource_transfers (active_occurrence_id, position, record) VALUES (?1, ?2, ?3)",
+    )?;
+    for (position, resource_transfer) in active_occurrence.resource_transfers.iter().enumerate() {
+        statement.execute(params![
+            active_occurrence.internal_id,
+            position as i64,
+            serde_json::to_string(resource_transfer)?
+        ])?;
+    }
+
+    connection.execute(
+        "DELETE FROM resource_releases WHERE active_occurrence_id = ?1",
+        params![active_occurrence.internal_id],
+    )?;
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO resource_releases (active_occurrence_id, position, record) VALUES (?1, ?2, ?3)",
+    )?;
+    for (position, resource_release) in active_occurrence.resource_releases.iter().enumerate() {
+        statement.execute(params![
+            active_occurrence.internal_id,
+            position as i64,
+            serde_json::to_string(resource_release)?
+        ])?;
+    }
+
+    Ok(())
+}
+
+fn write_occurrence(connection: &Connection, occurrence: &Occurrence) -> anyhow::Result<()> {
+    connection
+        .execute(
+            "INSERT INTO occurrences (internal_id, image, name, dispatch_plan) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (internal_id) DO UPDATE SET image = excluded.image, name = excluded.name,
+             dispatch_plan = excluded.dispatch_plan",
+            params![
+                occurrence.internal_id,
+                occurrence.image,
+                occurrence.name,
+                serde_json::to_string(&occurrence.dispatch_plan)?
+            ],
+        )
+        .with_context(|| format!("Failed to write occurrence {}", occurrence.internal_id))?;
+    Ok(())
+}
+
+fn write_staff(connection: &Connection, staff: &Staff) -> anyhow::Result<()> {
+    connection
+        .execute(
+            "INSERT INTO staff (internal_id, image, label, name, national_id, permission, rank, state)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (internal_id) DO UPDATE SET
+                image = excluded.image,
+                label = excluded.label,
+                name = excluded.name,
+                national_id = excluded.national_id,
+                permission = excluded.permission,
+                rank = excluded.rank,
+                state = excluded.state",
+            params![
+                staff.internal_id,
+                staff.image,
+                staff.label,
+                staff.name,
+                staff.national_id,
+                enum_to_sql(&staff.permission)?,
+                enum_to_sql(&staff.rank)?,
+                enum_to_sql(&staff.state)?,
+            ],
+        )
+        .with_context(|| format!("Failed to write staff {}", staff.internal_id))?;
+
+    connection.execute(
+        "DELETE FROM staff_qualifications WHERE staff_id = ?1",
+        params![staff.internal_id],
+    )?;
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO staff_qualifications (internal_id, staff_id, position, kind, issue_time, expiry_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    for (position, qualification) in staff.qualifications.iter().enumerate() {
+        statement.execute(params![
+            qualification.internal_id,
+            staff.internal_id,
+            position as i64,
+            enum_to_sql(&qualification.kind)?,
+            qualification.issue_time as i64,
+            qualification.expiry_time.map(|time| time as i64),
+        ])?;
+    }
+
+    connection.execute(
+        "DELETE FROM staff_leave_periods WHERE staff_id = ?1",
+        params![staff.internal_id],
+    )?;
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO staff_leave_periods (internal_id, staff_id, position, reason, start_time, end_time, status) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for (position, leave_period) in staff.leave_periods.iter().enumerate() {
+        statement.execute(params![
+            leave_period.internal_id,
+            staff.internal_id,
+            position as i64,
+            enum_to_sql(&leave_period.reason)?,
+            leave_period.start_time as i64,
+            leave_period.end_time as i64,
+            enum_to_sql(&leave_period.status)?,
+        ])?;
+    }
+
+    Ok(())
+}
+
+fn write_team(connection: &Connection, team: &Team) -> anyhow::Result<()> {
+    connection
+        .execute(
+            "INSERT INTO teams (internal_id, label, state) VALUES (?1, ?2, ?3)
+             ON CONFLICT (internal_id) DO UPDATE SET label = excluded.label, state = excluded.state",
+            params![team.internal_id, team.label, enum_to_sql(&team.state)?],
+        )
+        .with_context(|| format!("Failed to write team {}", team.internal_id))?;
+
+    write_id_list(
+        connection,
+        "team_members",
+        "team_id",
+        "staff_id",
+        &team.internal_id,
+        &team.member_ids,
+    )
+}
+
+fn write_vehicle(connection: &Connection, vehicle: &Vehicle) -> anyhow::Result<()> {
+    connection
+        .execute(
+            "INSERT INTO vehicles (internal_id, capacity, category, image, label, license_plate, state)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (internal_id) DO UPDATE SET
+                capacity = excluded.capacity,
+                category = excluded.category,
+                image = excluded.image,
+                label = excluded.label,
+                license_plate = excluded.license_plate,
+                state = excluded.state",
+            params![
+                vehicle.internal_id,
+                vehicle.capacity,
+                enum_to_sql(&vehicle.category)?,
+                vehicle.image,
+                vehicle.label,
+                vehicle.license_plate,
+                enum_to_sql(&vehicle.state)?,
+            ],
+        )
+        .with_context(|| format!("Failed to write vehicle {}", vehicle.internal_id))?;
+    Ok(())
+}
+
+fn write_crew_requirement(
+    connection: &Connection,
+    crew_requirement: &CrewRequirement,
+) -> anyhow::Result<()> {
+    connection
+        .execute(
+            "INSERT INTO crew_requirements (internal_id, record) VALUES (?1, ?2)
+             ON CONFLICT (internal_id) DO UPDATE SET record = excluded.record",
+            params![
+                crew_requirement.internal_id,
+                serde_json::to_string(crew_requirement)?
+            ],
+        )
+        .with_context(|| {
+            format!(
+                "Failed to write crew requirement {}",
+                crew_requirement.internal_id
+            )
+        })?;
+    Ok(())
+}
+
+fn write_shift_template(
+    connection: &Connection,
+    shift_template: &ShiftTemplate,
+) -> anyhow::Result<()> {
+    connection
+        .execute(
+            "INSERT INTO shift_templates (internal_id, record) VALUES (?1, ?2)
+             ON CONFLICT (internal_id) DO UPDATE SET record = excluded.record",
+            params![
+                shift_template.internal_id,
+                serde_json::to_string(shift_template)?
+            ],
+        )
+        .with_context(|| {
+            format!(
+                "Failed to write shift template {}",
+                shift_template.internal_id
+            )
+        })?;
+    Ok(())
+}
+
+fn write_archived_occurrence(
+    connection: &Connection,
+    archived_occurrence: &ArchivedOccurrence,
+) -> anyhow::Result<()> {
+    let active_occurrence = &archived_occurrence.active_occurrence;
+    let start_time = active_occurrence
+        .creation_time
+        .unwrap_or(archived_occurrence.end_time);
+    let record = serde_json::to_string(archived_occurrence)?;
+
+    connection
+        .execute(
+            "INSERT INTO archived_occurrences (internal_id, occurrence_id, start_time, end_time, record)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (internal_id) DO UPDATE SET
+                occurrence_id = excluded.occurrence_id,
+                start_time = excluded.start_time,
+                end_time = excluded.end_time,
+                record = excluded.record",
+            params![
+                active_occurrence.internal_id,
+                active_occurrence.occurrence_id,
+                start_time as i64,
+                archived_occurrence.end_time as i64,
+                record,
+            ],
+        )
+        .with_context(|| {
+            format!(
+                "Failed to write archived occurrence {}",
+                active_occurrence.internal_id
+            )
+        })?;
+
+    connection.execute(
+        "DELETE FROM archived_occurrence_staff WHERE archived_occurrence_id = ?1",
+        params![active_occurrence.internal_id],
+    )?;
+    let mut statement = connection.prepare_cached(
+        "INSERT OR IGNORE INTO archived_occurrence_staff (archived_occurrence_id, staff_id) VALUES (?1, ?2)",
+    )?;
+    for staff_id in active_occurrence.involved_staff_ids().iter() {
+        statement.execute(params![active_occurrence.internal_id, staff_id])?;
+    }
+
+    connection.execute(
+        "DELETE FROM archived_occurrence_vehicles WHERE archived_occurrence_id = ?1",
+        params![active_occurrence.internal_id],
+    )?;
+    let mut statement = connection.prepare_cached(
+        "INSERT OR IGNORE INTO archived_occurrence_vehicles (archived_occurrence_id, vehicle_id) VALUES (?1, ?2)",
+    )?;
+    for vehicle_id in active_occurrence.involved_vehicle_ids().iter() {
+        statement.execute(params![active_occurrence.internal_id, vehicle_id])?;
+    }
+
+    Ok(())
+}
+
+fn commit_collection<T>(
+    connection: &Connection,
+    table: &str,
+    changes: &EntityChanges<T>,
+    write: fn(&Connection, &T) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    for (entity_id, change) in changes.iter() {
+        match &change.after {
+            Some(entity) => write(connection, entity)?,
+            None => {
+                connection
+                    .execute(
+                        &format!("DELETE FROM {} WHERE internal_id = ?1", table),
+                        params![entity_id],
+                    )
+                    .with_context(|| format!("Failed to delete {} {}", table, entity_id))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl SqliteStore {
+    fn connection(&self) -> anyhow::Result<MutexGuard<'_, Connection>> {
+        self.connection
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Database connection is poisoned"))
+    }
+
+    fn connection_mut(&mut self) -> anyhow::Result<&mut Connection> {
+        self.connection
+            .get_mut()
+            .map_err(|_| anyhow::anyhow!("Database connection is poisoned"))
+    }
+}
+
+impl FirefightDataManager for SqliteStore {
+    fn get_active_occurrence(&self, occurrence_id: &String) -> anyhow::Result<ActiveOccurrence> {
+        query_active_occurrence(&*self.connection()?, occurrence_id)?
+            .with_context(|| format!("No active occurrence found with id: {}", occurrence_id))
+    }
+
+    fn get_active_occurrence_by_staff(
+        &self,
+        staff_id: &String,
+    ) -> anyhow::Result<ActiveOccurrence> {
+        let connection = self.connection()?;
+        let active_occurrence_id = connection
+            .query_row(
+                "SELECT active_occurrence_id FROM active_occurrence_staff WHERE staff_id = ?1 LIMIT 1",
+                params![staff_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .with_context(|| format!("No active occurrence found with staff id: {}", staff_id))?;
+
+        query_active_occurrence(&connection, &active_occurrence_id)?
+            .with_context(|| format!("No active occurrence found with staff id: {}", staff_id))
+    }
+
+    fn get_active_occurrence_by_vehicle(
+        &self,
+        vehicle_id: &String,
+    ) -> anyhow::Result<ActiveOccurrence> {
+        let connection = self.connection()?;
+        let active_occurrence_id = connection
+            .query_row(
+                "SELECT active_occurrence_id FROM active_occurrence_vehicles WHERE vehicle_id = ?1 LIMIT 1",
+                params![vehicle_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .with_context(|| {
+                format!("No active occurrence found with vehicle id: {}", vehicle_id)
+            })?;
+
+        query_active_occurrence(&connection, &active_occurrence_id)?
+            .with_context(|| format!("No active occurrence found with vehicle id: {}", vehicle_id))
+    }
+
+    fn get_active_occurrence_list(&self) -> anyhow::Result<Vec<ActiveOccurrence>> {
+        query_active_occurrence_list(&*self.connection()?, "", &[])
+    }
+
+    fn get_active_occurrence_list_by_occurrence(
+        &self,
+        occurrence_id: &String,
+    ) -> anyhow::Result<Vec<ActiveOccurrence>> {
+        query_active_occurrence_list(
+            &*self.connection()?,
+            "WHERE occurrence_id = ?1",
+            &[occurrence_id],
+        )
+    }
+
+    fn get_occurrence(&self, occurrence_id: &String) -> anyhow::Result<Occurrence> {
+        query_occurrence(&*self.connection()?, occurrence_id)?
+            .with_context(|| format!("No occurrence found with id: {}", occurrence_id))
+    }
+
+    fn get_occurrence_name(&self, occurrence_id: &String) -> anyhow::Result<String> {
+        self.connection()?
+            .query_row(
+                "SELECT name FROM occurrences WHERE internal_id = ?1",
+                params![occurrence_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .with_context(|| format!("No occurrence found with id: {}", occurrence_id))
+    }
+
+    fn get_occurrence_list(&self) -> anyhow::Result<Vec<Occurrence>> {
+        query_occurrence_list(&*self.connection()?)
+    }
+
+    fn get_staff(&self, staff_id: &String) -> anyhow::Result<Staff> {
+        query_staff(&*self.connection()?, staff_id)?
+            .with_context(|| format!("No staff found with id: {}", staff_id))
+    }
+
+    fn get_staff_label(&self, staff_id: &String) -> anyhow::Result<String> {
+        self.connection()?
+            .query_row(
+                "SELECT label FROM staff WHERE internal_id = ?1",
+                params![staff_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .with_context(|| format!("No staff found with id: {}", staff_id))
+    }
+
+    fn get_staff_list(&self) -> anyhow::Result<Vec<Staff>> {
+        query_staff_list(&*self.connection()?)
+    }
+
+    fn get_team(&self, team_id: &String) -> anyhow::Result<Team> {
+        query_team(&*self.connection()?, team_id)?
+            .with_context(|| format!("No team found with id: {}", team_id))
+    }
+
+    fn get_team_label(&self, team_id: &String) -> anyhow::Result<String> {
+        self.connection()?
+            .query_row(
+                "SELECT label FROM teams WHERE internal_id = ?1",
+                params![team_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .with_context(|| format!("No teams found with id: {}", team_id))
+    }
+
+    fn get_team_list(&self) -> anyhow::Result<Vec<Team>> {
+        query_team_list(&*self.connection()?)
+    }
+
+    fn get_vehicle(&self, vehicle_id: &String) -> anyhow::Result<Vehicle> {
+        query_vehicle(&*self.connection()?, vehicle_id)?
+            .with_context(|| format!("No vehicle found with id: {}", vehicle_id))
+    }
+
+    fn get_vehicle_capacity(&self, vehicle_id: &String) -> anyhow::Result<Option<u8>> {
+        self.connection()?
+            .query_row(
+                "SELECT capacity FROM vehicles WHERE internal_id = ?1",
+                params![vehicle_id],
+                |row| row.get::<_, Option<u8>>(0),
+            )
+            .optional()?
+            .with_context(|| format!("No vehicle found with id: {}", vehicle_id))
+    }
+
+    fn get_vehicle_label(&self, vehicle_id: &String) -> anyhow::Result<String> {
+        self.connection()?
+            .query_row(
+                "SELECT label FROM vehicles WHERE internal_id = ?1",
+                params![vehicle_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .with_context(|| format!("No vehicle found with id: {}", vehicle_id))
+    }
+
+    fn get_vehicle_list(&self) -> anyhow::Result<Vec<Vehicle>> {
+        query_vehicle_list(&*self.connection()?)
+    }
+
+    fn get_shift_template_list(&self) -> anyhow::Result<Vec<ShiftTemplate>> {
+        query_shift_template_list(&*self.connection()?)
+    }
+
+    fn get_data_store(&self) -> anyhow::Result<DataStore> {
+        query_data_store(&*self.connection()?)
+    }
+
+    fn get_archived_occurrence(
+        &self,
+        archived_occurrence_id: &String,
+    ) -> anyhow::Result<ArchivedOccurrence> {
+        query_archived_occurrence(&*self.connection()?, archived_occurrence_id)?.with_context(
+            || {
+                format!(
+                    "No archived occurrence found with id: {}",
+                    archived_occurrence_id
+                )
+            },
+        )
+    }
+
+    fn get_archived_occurrence_list(
+        &self,
+        query: &ArchiveQuery,
+    ) -> anyhow::Result<Vec<ArchivedOccurrence>> {
+        query_archived_occurrence_list(&*self.connection()?, query)
+    }
+
+    fn add_observer(&mut self, observer: Arc<dyn ChangeObserver>) {
+        self.observers.push(observer);
+    }
+
+    fn observers(&self) -> &[Arc<dyn ChangeObserver>] {
+        &self.observers
+    }
+
+    // Reading the whole database would take a query per staff member and active occurrence
+    fn check_conflicts(&self, changes: &ChangeSet) -> anyhow::Result<()> {
+        changes.check_conflicts(&query_touched_store(&*self.connection()?, changes)?)
+    }
+
+    fn commit(&mut self, changes: &ChangeSet) -> anyhow::Result<()> {
+        let connection = self.connection_mut()?;
+        let transaction = connection.transaction()?;
+
+        commit_collection(
+            &transaction,
+            "occurrences",
+            &changes.occurrences,
+            write_occurrence,
+        )?;
+        commit_collection(&transaction, "staff", &changes.staff, write_staff)?;
+        commit_collection(&transaction, "teams", &changes.teams, write_team)?;
+        commit_collection(&transaction, "vehicles", &changes.vehicles, write_vehicle)?;
+        commit_collection(
+            &transaction,
+            "crew_requirements",
+            &changes.crew_requirements,
+            write_crew_requirement,
+        )?;
+        commit_collection(
+            &transaction,
+            "shift_templates",
+            &changes.shift_templates,
+            write_shift_template,
+        )?;
+        commit_collection(
+            &transaction,
+            "active_occurrences",
+            &changes.active_occurrences,
+            write_active_occurrence,
+        )?;
+        commit_collection(
+            &transaction,
+            "archived_occurrences",
+            &changes.archived_occurrences,
+            write_archived_occurrence,
+        )?;
+
+        transaction
+            .commit()
+            .with_context(|| "Failed to save store".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::transaction::StoreOperation;
+    use crate::firefight::types::{StaffState, VehicleCategory};
+
+    fn store() -> SqliteStore {
+        let connection = Connection::open_in_memory().unwrap();
+        migrate_schema(&connection, 0).unwrap();
+        let mut store = SqliteStore {
+            connection: Mutex::new(connection),
+            observers: vec![],
+        };
+        store
+            .restore_store(
+                fixtures::data_store(
+                    vec![fixtures::occurrence("occurrence")],
+                    vec![fixtures::staff("staff-1"), fixtures::staff("staff-2")],
+                    vec![fixtures::team("team", &["staff-1"])],
+                    vec![fixtures::vehicle(
+                        "vehicle",
+                        VehicleCategory::FireFight,
+                        Some(5),
+                    )],
+                ),
+                HashMap::new(),
+            )
+            .unwrap();
+        store
+    }
+
+    fn set_state(store: &mut SqliteStore, staff_id: &str, state: StaffState) -> ChangeSet {
+        store
+            .transaction(StoreOperation::UpdateStaff, &mut |data_store| {
+                data_store.staff.get_mut(staff_id).unwrap().state = state.clone();
+                Ok(())
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn transactions_commit_what_they_staged() {
+        let mut store = store();
+        let changes = set_state(&mut store, "staff-1", StaffState::Unavailable);
+
+        assert_eq!(changes.staff.len(), 1);
+        assert!(changes.teams.is_empty());
+        assert_eq!(
+            store.get_staff(&String::from("staff-1")).unwrap().state,
+            StaffState::Unavailable
+        );
+        assert_eq!(store.get_data_store().unwrap().staff.len(), 2);
+    }
+
+    #[test]
+    fn conflicts_are_checked_against_the_touched_rows() {
+        let mut store = store();
+        let changes = set_state(&mut store, "staff-1", StaffState::Unavailable);
+        set_state(&mut store, "staff-2", StaffState::Unavailable);
+
+        // Other rows changed since, the change itself can still be reverted
+        store
+            .replay(StoreOperation::Undo, &changes.inverse())
+            .unwrap();
+        assert_eq!(
+            store.get_staff(&String::from("staff-1")).unwrap().state,
+            StaffState::Available
+        );
+
+        assert!(store
+            .replay(StoreOperation::Undo, &changes.inverse())
+            .is_err());
+        store.replay(StoreOperation::Redo, &changes).unwrap();
+        assert!(store.replay(StoreOperation::Redo, &changes).is_err());
+    }
+}
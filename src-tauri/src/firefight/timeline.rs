@@ -0,0 +1,76 @@
+use super::types::{ActiveOccurrence, VehicleStatus, VehicleStatusChange};
+
+/// Response figures of a vehicle in milliseconds, `None` until the statuses involved are reported
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VehicleResponseTimes {
+    pub vehicle_id: String,
+    /// Alerted until departed
+    pub turnout_time: Option<u128>,
+    /// Departed until on scene
+    pub travel_time: Option<u128>,
+    /// Alerted until on scene
+    pub response_time: Option<u128>,
+    /// On scene until the next status, leaving for hospital or base
+    pub on_scene_time: Option<u128>,
+    /// Alerted until back at base
+    pub total_time: Option<u128>,
+}
+
+fn first_time(timeline: &[VehicleStatusChange], status: VehicleStatus) -> Option<u128> {
+    timeline
+        .iter()
+        .find(|status_change| status_change.status == status)
+        .map(|status_change| status_change.time)
+}
+
+fn elapsed(from_time: Option<u128>, to_time: Option<u128>) -> Option<u128> {
+    to_time?.checked_sub(from_time?)
+}
+
+fn vehicle_response_times(
+    vehicle_id: &String,
+    timeline: &[VehicleStatusChange],
+) -> VehicleResponseTimes {
+    let alerted_time = first_time(timeline, VehicleStatus::Alerted);
+    let departed_time = first_time(timeline, VehicleStatus::Departed);
+    let on_scene_time = first_time(timeline, VehicleStatus::OnScene);
+    let left_scene_time = timeline
+        .iter()
+        .skip_while(|status_change| status_change.status != VehicleStatus::OnScene)
+        .nth(1)
+        .map(|status_change| status_change.time);
+
+    VehicleResponseTimes {
+        vehicle_id: vehicle_id.clone(),
+        turnout_time: elapsed(alerted_time, departed_time),
+        travel_time: elapsed(departed_time, on_scene_time),
+        response_time: elapsed(alerted_time, on_scene_time),
+        on_scene_time: elapsed(on_scene_time, left_scene_time),
+        total_time: elapsed(alerted_time, first_time(timeline, VehicleStatus::AtBase)),
+    }
+}
+
+/// Response figures of every vehicle with a timeline, in dispatch order
+pub fn response_times(active_occurrence: &ActiveOccurrence) -> Vec<VehicleResponseTimes> {
+    let mut vehicle_ids = active_occurrence.vehicle_ids.clone();
+    // Vehicles moved to another occurrence keep their timeline here
+    let mut moved_vehicle_ids = active_occurrence
+        .vehicle_timelines
+        .keys()
+        .filter(|vehicle_id| !active_occurrence.vehicle_ids.contains(vehicle_id))
+        .cloned()
+        .collect::<Vec<_>>();
+    moved_vehicle_ids.sort();
+    vehicle_ids.extend(moved_vehicle_ids);
+
+    vehicle_ids
+        .iter()
+        .filter_map(|vehicle_id| {
+            active_occurrence
+                .vehicle_timelines
+                .get(vehicle_id)
+                .map(|timeline| vehicle_response_times(vehicle_id, timeline))
+        })
+        .collect()
+}
@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+
+use super::types::{
+    ActiveOccurrence, ArchivedOccurrence, CrewRequirement, DataStore, Occurrence, ShiftTemplate,
+    Staff, Team, Vehicle,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StoreOperation {
+    CreateActiveOccurrence,
+    CreateOccurrence,
+    CreateStaff,
+    CreateTeam,
+    CreateVehicle,
+    UpdateActiveOccurrence,
+    UpdateOccurrence,
+    UpdateStaff,
+    UpdateTeam,
+    UpdateVehicle,
+    DeleteActiveOccurrence,
+    DeleteOccurrence,
+    DeleteStaff,
+    DeleteTeam,
+    DeleteVehicle,
+    CloseActiveOccurrence,
+    RecordVehicleStatus,
+    TransferResources,
+    ReleaseResources,
+    SetStaffShift,
+    ImportStaff,
+    AddStaffQualification,
+    UpdateStaffQualification,
+    RemoveStaffQualification,
+    AddStaffLeave,
+    UpdateStaffLeave,
+    RemoveStaffLeave,
+    ApplyStaffLeave,
+    SetCrewRequirement,
+    DeleteCrewRequirement,
+    SetShiftTemplate,
+    DeleteShiftTemplate,
+    ApplyShiftTemplate,
+    RepairStore,
+    ExternalizeImages,
+    RestoreBackup,
+    Undo,
+    Redo,
+}
+
+impl StoreOperation {
+    /// Dispatch board operations that can be reverted from the undo history. Undo and redo only
+    /// need dispatch access, so operations that need more stay out of it.
+    pub fn is_undoable(&self) -> bool {
+        matches!(
+            self,
+            StoreOperation::CreateActiveOccurrence
+                | StoreOperation::UpdateActiveOccurrence
+                | StoreOperation::DeleteActiveOccurrence
+                | StoreOperation::CloseActiveOccurrence
+                | StoreOperation::RecordVehicleStatus
+                | StoreOperation::TransferResources
+                | StoreOperation::ReleaseResources
+                | StoreOperation::SetStaffShift
+                | StoreOperation::ApplyShiftTemplate
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntityKind {
+    ActiveOccurrence,
+    ArchivedOccurrence,
+    CrewRequirement,
+    Occurrence,
+    ShiftTemplate,
+    Staff,
+    Team,
+    Vehicle,
+}
+
+/// Notified after every successful commit, in registration order
+pub trait ChangeObserver: Send + Sync {
+    fn on_commit(&self, operation: StoreOperation, changes: &ChangeSet) -> anyhow::Result<()>;
+}
+
+/// State of a single entity around a commit, `None` when it does not exist on that side
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityChange<T> {
+    pub before: Option<T>,
+    pub after: Option<T>,
+}
+
+pub type EntityChanges<T> = HashMap<String, EntityChange<T>>;
+
+/// Every entity touched by a transaction, keyed by internal id
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSet {
+    pub active_occurrences: EntityChanges<ActiveOccurrence>,
+    pub occurrences: EntityChanges<Occurrence>,
+    pub staff: EntityChanges<Staff>,
+    pub teams: EntityChanges<Team>,
+    pub vehicles: EntityChanges<Vehicle>,
+    #[serde(default)]
+    pub crew_requirements: EntityChanges<CrewRequirement>,
+    #[serde(default)]
+    pub shift_templates: EntityChanges<ShiftTemplate>,
+    // Not part of the live DataStore, only written by operations that archive
+    #[serde(default)]
+    pub archived_occurrences: EntityChanges<ArchivedOccurrence>,
+}
+
+pub fn diff_collection<T: Clone + PartialEq>(
+    before: &HashMap<String, T>,
+    after: &HashMap<String, T>,
+) -> EntityChanges<T> {
+    let mut changes = EntityChanges::new();
+
+    for (entity_id, before_entity) in before.iter() {
+        let after_entity = after.get(entity_id);
+        if after_entity != Some(before_entity) {
+            changes.insert(
+                entity_id.clone(),
+                EntityChange {
+                    before: Some(before_entity.clone()),
+                    after: after_entity.cloned(),
+                },
+            );
+        }
+    }
+
+    for (entity_id, after_entity) in after.iter() {
+        if !before.contains_key(entity_id) {
+            changes.insert(
+                entity_id.clone(),
+                EntityChange {
+                    before: None,
+                    after: Some(after_entity.clone()),
+                },
+            );
+        }
+    }
+
+    changes
+}
+
+fn apply_collection<T: Clone>(changes: &EntityChanges<T>, collection: &mut HashMap<String, T>) {
+    for (entity_id, change) in changes.iter() {
+        match &change.after {
+            Some(after_entity) => {
+                collection.insert(entity_id.clone(), after_entity.clone());
+            }
+            None => {
+                collection.remove(entity_id);
+            }
+        }
+    }
+}
+
+fn invert_collection<T: Clone>(changes: &EntityChanges<T>) -> EntityChanges<T> {
+    changes
+        .iter()
+        .map(|(entity_id, change)| {
+            (
+                entity_id.clone(),
+                EntityChange {
+                    before: change.after.clone(),
+                    after: change.before.clone(),
+                },
+            )
+        })
+        .collect()
+}
+
+fn check_collection<T: PartialEq>(
+    entity_kind: EntityKind,
+    changes: &EntityChanges<T>,
+    collection: &HashMap<String, T>,
+) -> anyhow::Result<()> {
+    for (entity_id, change) in changes.iter() {
+        if collection.get(entity_id) != change.before.as_ref() {
+            anyhow::bail!(
+                "{:?} {} was changed in the meantime",
+                entity_kind,
+                entity_id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+impl ChangeSet {
+    pub fn diff(before: &DataStore, after: &DataStore) -> ChangeSet {
+        ChangeSet {
+            active_occurrences: diff_collection(
+                &before.active_occurrences,
+                &after.active_occurrences,
+            ),
+            occurrences: diff_collection(&before.occurrences, &after.occurrences),
+            staff: diff_collection(&before.staff, &after.staff),
+            teams: diff_collection(&before.teams, &after.teams),
+            vehicles: diff_collection(&before.vehicles, &after.vehicles),
+            crew_requirements: diff_collection(&before.crew_requirements, &after.crew_requirements),
+            shift_templates: diff_collection(&before.shift_templates, &after.shift_templates),
+            archived_occurrences: EntityChanges::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active_occurrences.is_empty()
+            && self.occurrences.is_empty()
+            && self.staff.is_empty()
+            && self.teams.is_empty()
+            && self.vehicles.is_empty()
+            && self.crew_requirements.is_empty()
+            && self.shift_templates.is_empty()
+            && self.archived_occurrences.is_empty()
+    }
+
+    pub fn apply(&self, data_store: &mut DataStore) {
+        apply_collection(&self.active_occurrences, &mut data_store.active_occurrences);
+        apply_collection(&self.occurrences, &mut data_store.occurrences);
+        apply_collection(&self.staff, &mut data_store.staff);
+        apply_collection(&self.teams, &mut data_store.teams);
+        apply_collection(&self.vehicles, &mut data_store.vehicles);
+        apply_collection(&self.crew_requirements, &mut data_store.crew_requirements);
+        apply_collection(&self.shift_templates, &mut data_store.shift_templates);
+    }
+
+    pub fn inverse(&self) -> ChangeSet {
+        ChangeSet {
+            active_occurrences: invert_collection(&self.active_occurrences),
+            occurrences: invert_collection(&self.occurrences),
+            staff: invert_collection(&self.staff),
+            teams: invert_collection(&self.teams),
+            vehicles: invert_collection(&self.vehicles),
+            crew_requirements: invert_collection(&self.crew_requirements),
+            shift_templates: invert_collection(&self.shift_templates),
+            archived_occurrences: invert_collection(&self.archived_occurrences),
+        }
+    }
+
+    /// Fails when a live entity no longer holds the state this change set starts from
+    pub fn check_conflicts(&self, data_store: &DataStore) -> anyhow::Result<()> {
+        check_collection(
+            EntityKind::ActiveOccurrence,
+            &self.active_occurrences,
+            &data_store.active_occurrences,
+        )?;
+        check_collection(
+            EntityKind::Occurrence,
+            &self.occurrences,
+            &data_store.occurrences,
+        )?;
+        check_collection(EntityKind::Staff, &self.staff, &data_store.staff)?;
+        check_collection(EntityKind::Team, &self.teams, &data_store.teams)?;
+        check_collection(EntityKind::Vehicle, &self.vehicles, &data_store.vehicles)?;
+        check_collection(
+            EntityKind::CrewRequirement,
+            &self.crew_requirements,
+            &data_store.crew_requirements,
+        )?;
+        check_collection(
+            EntityKind::ShiftTemplate,
+            &self.shift_templates,
+            &data_store.shift_templates,
+        )
+    }
+
+    pub fn apply_archive(&self, archive: &mut HashMap<String, ArchivedOccurrence>) {
+        apply_collection(&self.archived_occurrences, archive);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firefight::fixtures;
+    use crate::firefight::types::{StaffState, VehicleCategory};
+
+    fn before_store() -> DataStore {
+        fixtures::data_store(
+            vec![fixtures::occurrence("occurrence")],
+            vec![fixtures::staff("kept"), fixtures::staff("removed")],
+            vec![],
+            vec![fixtures::vehicle(
+                "vehicle",
+                VehicleCategory::FireFight,
+                Some(5),
+            )],
+        )
+    }
+
+    fn after_store() -> DataStore {
+        let mut after = before_store();
+        after.staff.remove("removed");
+        after
+            .staff
+            .insert(String::from("added"), fixtures::staff("added"));
+        after.vehicles.get_mut("vehicle").unwrap().capacity = Some(6);
+        after
+    }
+
+    #[test]
+    fn diff_records_created_updated_and_deleted_entities() {
+        let changes = ChangeSet::diff(&before_store(), &after_store());
+
+        assert_eq!(changes.staff.len(), 2);
+        assert_eq!(changes.staff["removed"].after, None);
+        assert_eq!(changes.staff["added"].before, None);
+        assert_eq!(
+            changes.vehicles["vehicle"]
+                .before
+                .as_ref()
+                .unwrap()
+                .capacity,
+            Some(5)
+        );
+        assert_eq!(
+            changes.vehicles["vehicle"].after.as_ref().unwrap().capacity,
+            Some(6)
+        );
+        assert!(changes.occurrences.is_empty());
+        assert!(!changes.staff.contains_key("kept"));
+    }
+
+    #[test]
+    fn diff_of_equal_stores_is_empty() {
+        assert!(ChangeSet::diff(&before_store(), &before_store()).is_empty());
+    }
+
+    #[test]
+    fn apply_turns_the_before_store_into_the_after_store() {
+        let changes = ChangeSet::diff(&before_store(), &after_store());
+        let mut data_store = before_store();
+        changes.apply(&mut data_store);
+
+        assert!(ChangeSet::diff(&data_store, &after_store()).is_empty());
+    }
+
+    #[test]
+    fn inverse_reverts_an_applied_change_set() {
+        let changes = ChangeSet::diff(&before_store(), &after_store());
+        let mut data_store = after_store();
+        changes.inverse().apply(&mut data_store);
+
+        assert!(ChangeSet::diff(&data_store, &before_store()).is_empty());
+        assert_eq!(changes.inverse().inverse(), changes);
+    }
+
+    #[test]
+    fn check_conflicts_accepts_the_store_the_changes_start_from() {
+        let changes = ChangeSet::diff(&before_store(), &after_store());
+
+        assert!(changes.check_conflicts(&before_store()).is_ok());
+        assert!(changes.inverse().check_conflicts(&after_store()).is_ok());
+    }
+
+    #[test]
+    fn check_conflicts_refuses_entities_changed_in_the_meantime() {
+        let changes = ChangeSet::diff(&before_store(), &after_store());
+
+        let mut changed_store = before_store();
+        changed_store.vehicles.get_mut("vehicle").unwrap().capacity = Some(4);
+        assert!(changes.check_conflicts(&changed_store).is_err());
+
+        let mut created_store = before_store();
+        created_store
+            .staff
+            .insert(String::from("added"), fixtures::staff("added"));
+        assert!(changes.check_conflicts(&created_store).is_err());
+
+        // Entities the change set does not touch can change freely
+        let mut unrelated_store = before_store();
+        unrelated_store.staff.get_mut("kept").unwrap().state = StaffState::Unavailable;
+        assert!(changes.check_conflicts(&unrelated_store).is_ok());
+    }
+}
@@ -1,11 +1,33 @@
-use std::collections::HashMap;
+use anyhow::Context;
+use std::{collections::HashMap, sync::Arc};
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+use super::consistency::{self, ConsistencyReport};
+use super::crew::DispatchReport;
+use super::leave;
+use super::operations;
+use super::transaction::{
+    diff_collection, ChangeObserver, ChangeSet, EntityChange, StoreOperation,
+};
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Occurrence {
     pub internal_id: String,
     pub image: String,
     pub name: String,
+    /// Vehicles usually sent to this kind of occurrence, proposed when dispatching it
+    #[serde(default)]
+    pub dispatch_plan: Vec<DispatchPlanEntry>,
+}
+
+/// Number of vehicles of a category in a dispatch plan, such as one VUCI for an urban fire
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DispatchPlanEntry {
+    pub category: VehicleCategory,
+    pub count: u8,
+    /// Only vehicles seating at least this many are proposed
+    pub min_capacity: Option<u8>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -23,6 +45,24 @@ pub enum StaffRank {
     Unknown,
 }
 
+impl StaffRank {
+    /// Lower is more senior, `None` when the rank is not known
+    pub fn seniority(&self) -> Option<u8> {
+        match self {
+            StaffRank::Rank0 => Some(0),
+            StaffRank::Rank1 => Some(1),
+            StaffRank::Rank2 => Some(2),
+            StaffRank::Rank3 => Some(3),
+            StaffRank::Rank4 => Some(4),
+            StaffRank::Rank5 => Some(5),
+            StaffRank::Rank6 => Some(6),
+            StaffRank::Rank7 => Some(7),
+            StaffRank::Rank8 => Some(8),
+            StaffRank::Unknown => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum StaffState {
@@ -54,7 +94,76 @@ fn default_national_id() -> String {
     String::from("")
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QualificationKind {
+    Driver,
+    HeavyDriver,
+    Tas,
+    Tat,
+    Diver,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Qualification {
+    pub internal_id: String,
+    pub kind: QualificationKind,
+    pub issue_time: u128,
+    pub expiry_time: Option<u128>,
+}
+
+impl Qualification {
+    /// Qualifications without an expiry time never lapse
+    pub fn is_valid_at(&self, time: u128) -> bool {
+        match self.expiry_time {
+            Some(expiry_time) => self.issue_time <= time && time < expiry_time,
+            None => self.issue_time <= time,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LeaveReason {
+    Sick,
+    Vacation,
+    Training,
+    Other,
+}
+
+impl LeaveReason {
+    /// State staff are kept in while the leave lasts
+    pub fn staff_state(&self) -> StaffState {
+        match self {
+            LeaveReason::Sick => StaffState::SickLeave,
+            _ => StaffState::Unavailable,
+        }
+    }
+}
+
+/// Progress of a leave period, only ever moved forward by the leave scheduler
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LeaveStatus {
+    #[default]
+    Scheduled,
+    Ongoing,
+    Ended,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeavePeriod {
+    pub internal_id: String,
+    pub reason: LeaveReason,
+    pub start_time: u128,
+    pub end_time: u128,
+    #[serde(default)]
+    pub status: LeaveStatus,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Staff {
     pub internal_id: String,
@@ -68,6 +177,25 @@ pub struct Staff {
     #[serde(default = "default_rank")]
     pub rank: StaffRank,
     pub state: StaffState,
+    #[serde(default)]
+    pub qualifications: Vec<Qualification>,
+    #[serde(default)]
+    pub leave_periods: Vec<LeavePeriod>,
+}
+
+impl Staff {
+    /// Staff with an ongoing leave are left out of shifts until it ends
+    pub fn is_on_leave(&self) -> bool {
+        self.leave_periods
+            .iter()
+            .any(|leave_period| leave_period.status == LeaveStatus::Ongoing)
+    }
+
+    pub fn is_qualified(&self, kind: QualificationKind, time: u128) -> bool {
+        self.qualifications
+            .iter()
+            .any(|qualification| qualification.kind == kind && qualification.is_valid_at(time))
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -112,7 +240,7 @@ fn default_category() -> VehicleCategory {
     VehicleCategory::Unknown
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Vehicle {
     pub internal_id: String,
@@ -125,11 +253,163 @@ pub struct Vehicle {
     pub state: VehicleState,
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum CrewRequirementTarget {
+    Category { category: VehicleCategory },
+    Vehicle { vehicle_id: String },
+}
+
+impl CrewRequirementTarget {
+    /// A target has at most one requirement, so its id is derived from the target
+    pub fn requirement_id(&self) -> String {
+        match self {
+            CrewRequirementTarget::Category { category } => format!("category:{:?}", category),
+            CrewRequirementTarget::Vehicle { vehicle_id } => format!("vehicle:{}", vehicle_id),
+        }
+    }
+}
+
+/// Crew a vehicle needs to go out, a vehicle requirement replaces the one of its category
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrewRequirement {
+    pub internal_id: String,
+    pub target: CrewRequirementTarget,
+    pub min_crew: Option<u8>,
+    pub max_crew: Option<u8>,
+    /// Someone at or above this rank has to be part of the crew to command it
+    pub chief_rank: Option<StaffRank>,
+    /// One crew member per entry, repeat a kind to require several
+    #[serde(default)]
+    pub qualifications: Vec<QualificationKind>,
+    /// Unmet requirements block the dispatch instead of only warning about it
+    pub blocking: bool,
+}
+
+/// Handovers of a shift template, every `repeat_interval` milliseconds from `start_time`
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftRecurrence {
+    pub start_time: u128,
+    /// A single handover when not set
+    pub repeat_interval: Option<u128>,
+    /// No handovers from this time on
+    pub end_time: Option<u128>,
+}
+
+/// Named roster, such as a day, night or piquete shift, applied at each scheduled handover
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftTemplate {
+    pub internal_id: String,
+    pub name: String,
+    pub available_staff: Vec<String>,
+    /// Member ids by team, teams left out become unavailable
+    #[serde(default)]
+    pub team_allocations: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub schedule: Vec<ShiftRecurrence>,
+    /// Latest handover already applied, earlier ones are never applied again
+    #[serde(default)]
+    pub last_handover: Option<u128>,
+}
+
 fn default_team_ids() -> Vec<String> {
     vec![]
 }
 
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CrewRole {
+    Chief,
+    Driver,
+    Crew,
+}
+
+fn default_crew_role() -> CrewRole {
+    CrewRole::Crew
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", from = "CrewAssignmentValue")]
+pub struct CrewAssignment {
+    pub staff_id: String,
+    pub role: CrewRole,
+}
+
+/// Crews used to be plain lists of staff ids, those members keep the regular crew role
+#[derive(serde::Deserialize)]
+#[serde(untagged, rename_all_fields = "camelCase")]
+enum CrewAssignmentValue {
+    StaffId(String),
+    Assignment {
+        staff_id: String,
+        #[serde(default = "default_crew_role")]
+        role: CrewRole,
+    },
+}
+
+impl From<CrewAssignmentValue> for CrewAssignment {
+    fn from(value: CrewAssignmentValue) -> Self {
+        match value {
+            CrewAssignmentValue::StaffId(staff_id) => CrewAssignment {
+                staff_id,
+                role: CrewRole::Crew,
+            },
+            CrewAssignmentValue::Assignment { staff_id, role } => CrewAssignment { staff_id, role },
+        }
+    }
+}
+
+/// Operational status reported by a vehicle during an occurrence, in the usual order
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VehicleStatus {
+    Alerted,
+    Departed,
+    OnScene,
+    TransportingToHospital,
+    AtHospital,
+    Returning,
+    AtBase,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VehicleStatusChange {
+    pub status: VehicleStatus,
+    pub time: u128,
+}
+
+/// Resources sent back before the occurrence closes
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceRelease {
+    pub staff_ids: Vec<String>,
+    #[serde(default)]
+    pub team_ids: Vec<String>,
+    pub vehicle_ids: Vec<String>,
+    pub time: u128,
+}
+
+/// Resources moved from one active occurrence to another, recorded on both
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTransfer {
+    pub from_active_occurrence_id: String,
+    pub to_active_occurrence_id: String,
+    pub staff_ids: Vec<String>,
+    pub team_ids: Vec<String>,
+    pub vehicle_ids: Vec<String>,
+    pub time: u128,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ActiveOccurrence {
     pub address: Option<String>,
@@ -140,15 +420,105 @@ pub struct ActiveOccurrence {
     pub location: Option<String>,
     pub occurrence_id: String,
     pub reference_point: Option<String>,
+    /// Resources released while the occurrence went on, oldest first
+    #[serde(default)]
+    pub resource_releases: Vec<ResourceRelease>,
+    /// Resources taken from or given to other occurrences, oldest first
+    #[serde(default)]
+    pub resource_transfers: Vec<ResourceTransfer>,
     pub staff_ids: Vec<String>,
     #[serde(default="default_team_ids")]
     pub team_ids: Vec<String>,
     #[serde(default)]
-    pub vehicle_assignment_map: HashMap<String, Vec<String>>,
+    pub vehicle_assignment_map: HashMap<String, Vec<CrewAssignment>>,
     pub vehicle_ids: Vec<String>,
+    /// Status changes by vehicle, oldest first. Kept for vehicles that left the occurrence.
+    #[serde(default)]
+    pub vehicle_timelines: HashMap<String, Vec<VehicleStatusChange>>,
     pub vmer_siv: Option<bool>,
 }
 
+impl ActiveOccurrence {
+    /// Latest status of a vehicle, `None` for vehicles dispatched before statuses were tracked
+    pub fn vehicle_status(&self, vehicle_id: &String) -> Option<VehicleStatus> {
+        self.vehicle_timelines
+            .get(vehicle_id)
+            .and_then(|timeline| timeline.last())
+            .map(|status_change| status_change.status)
+    }
+
+    /// Staff that served on the occurrence, released staff included
+    pub fn involved_staff_ids(&self) -> Vec<String> {
+        let mut staff_ids = self.staff_ids.clone();
+        self.resource_releases.iter().for_each(|release| {
+            staff_ids.extend(release.staff_ids.iter().cloned());
+        });
+        staff_ids
+    }
+
+    /// Vehicles that served on the occurrence, released vehicles included
+    pub fn involved_vehicle_ids(&self) -> Vec<String> {
+        let mut vehicle_ids = self.vehicle_ids.clone();
+        self.resource_releases.iter().for_each(|release| {
+            vehicle_ids.extend(release.vehicle_ids.iter().cloned());
+        });
+        vehicle_ids
+    }
+
+    /// Crew of every vehicle by role, in dispatch order
+    pub fn vehicle_crews(&self) -> Vec<VehicleCrew> {
+        self.vehicle_ids
+            .iter()
+            .map(|vehicle_id| {
+                let vehicle_crew = self
+                    .vehicle_assignment_map
+                    .get(vehicle_id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                VehicleCrew::from_assignments(vehicle_id, vehicle_crew)
+            })
+            .collect()
+    }
+}
+
+/// Who commands, drives and crews a vehicle
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VehicleCrew {
+    pub vehicle_id: String,
+    pub chief_id: Option<String>,
+    pub driver_id: Option<String>,
+    pub crew_ids: Vec<String>,
+}
+
+impl VehicleCrew {
+    /// Only the first chief and driver keep their role, anyone else counts as crew
+    pub fn from_assignments(vehicle_id: &String, vehicle_crew: &[CrewAssignment]) -> Self {
+        let mut crew = VehicleCrew {
+            vehicle_id: vehicle_id.clone(),
+            chief_id: None,
+            driver_id: None,
+            crew_ids: vec![],
+        };
+        for assignment in vehicle_crew.iter() {
+            let role_slot = match assignment.role {
+                CrewRole::Chief => &mut crew.chief_id,
+                CrewRole::Driver => &mut crew.driver_id,
+                CrewRole::Crew => {
+                    crew.crew_ids.push(assignment.staff_id.clone());
+                    continue;
+                }
+            };
+            match role_slot {
+                Some(_) => crew.crew_ids.push(assignment.staff_id.clone()),
+                None => *role_slot = Some(assignment.staff_id.clone()),
+            }
+        }
+
+        crew
+    }
+}
+
 pub fn default_teams() -> HashMap<String, Team> {
     HashMap::new()
 }
@@ -162,6 +532,65 @@ pub struct DataStore {
     #[serde(default="default_teams")]
     pub teams: HashMap<String, Team>,
     pub vehicles: HashMap<String, Vehicle>,
+    #[serde(default)]
+    pub crew_requirements: HashMap<String, CrewRequirement>,
+    #[serde(default)]
+    pub shift_templates: HashMap<String, ShiftTemplate>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedOccurrence {
+    pub active_occurrence: ActiveOccurrence,
+    pub end_time: u128,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveQuery {
+    pub from_time: Option<u128>,
+    pub to_time: Option<u128>,
+    pub occurrence_id: Option<String>,
+    pub staff_id: Option<String>,
+    pub vehicle_id: Option<String>,
+}
+
+impl ArchiveQuery {
+    /// Time bounds select occurrences that were open at some point within them
+    pub fn matches(&self, archived_occurrence: &ArchivedOccurrence) -> bool {
+        let active_occurrence = &archived_occurrence.active_occurrence;
+        let start_time = active_occurrence
+            .creation_time
+            .unwrap_or(archived_occurrence.end_time);
+
+        if let Some(from_time) = self.from_time {
+            if archived_occurrence.end_time < from_time {
+                return false;
+            }
+        }
+        if let Some(to_time) = self.to_time {
+            if start_time > to_time {
+                return false;
+            }
+        }
+        if let Some(occurrence_id) = &self.occurrence_id {
+            if active_occurrence.occurrence_id != *occurrence_id {
+                return false;
+            }
+        }
+        if let Some(staff_id) = &self.staff_id {
+            if !active_occurrence.involved_staff_ids().contains(staff_id) {
+                return false;
+            }
+        }
+        if let Some(vehicle_id) = &self.vehicle_id {
+            if !active_occurrence.involved_vehicle_ids().contains(vehicle_id) {
+                return false;
+            }
+        }
+
+        true
+    }
 }
 
 pub trait FirefightDataManager {
@@ -190,36 +619,556 @@ pub trait FirefightDataManager {
     fn get_vehicle_capacity(&self, vehicle_id: &String) -> anyhow::Result<Option<u8>>;
     fn get_vehicle_label(&self, vehicle_id: &String) -> anyhow::Result<String>;
     fn get_vehicle_list(&self) -> anyhow::Result<Vec<Vehicle>>;
+    fn get_shift_template_list(&self) -> anyhow::Result<Vec<ShiftTemplate>>;
+    fn get_data_store(&self) -> anyhow::Result<DataStore>;
+    fn get_archived_occurrence(
+        &self,
+        archived_occurrence_id: &String,
+    ) -> anyhow::Result<ArchivedOccurrence>;
+    /// Most recently closed first
+    fn get_archived_occurrence_list(
+        &self,
+        query: &ArchiveQuery,
+    ) -> anyhow::Result<Vec<ArchivedOccurrence>>;
+
+    fn add_observer(&mut self, observer: Arc<dyn ChangeObserver>);
+    fn observers(&self) -> &[Arc<dyn ChangeObserver>];
+
+    /// Writes an already staged change set to the backend as a single unit
+    fn commit(&mut self, changes: &ChangeSet) -> anyhow::Result<()>;
+
+    /// Runs an operation on a working copy of the store and returns what it would change. The
+    /// store is read once, the copy it was staged from is kept to diff against.
+    fn stage_changes(
+        &self,
+        stage: &mut dyn FnMut(&mut DataStore) -> anyhow::Result<()>,
+    ) -> anyhow::Result<ChangeSet> {
+        let live_store = self.get_data_store()?;
+        let mut staged_store = live_store.clone();
+        stage(&mut staged_store)?;
+
+        Ok(ChangeSet::diff(&live_store, &staged_store))
+    }
+
+    /// Fails when an entity the change set touches no longer holds the state it starts from
+    fn check_conflicts(&self, changes: &ChangeSet) -> anyhow::Result<()> {
+        changes.check_conflicts(&self.get_data_store()?)
+    }
+
+    fn commit_operation(
+        &mut self,
+        operation: StoreOperation,
+        changes: &ChangeSet,
+    ) -> anyhow::Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        self.commit(changes)
+            .with_context(|| format!("Failed to commit {:?}", operation))?;
+
+        // The commit already happened, observers can only report their own failures
+        for observer in self.observers() {
+            if let Err(observer_error) = observer.on_commit(operation, changes) {
+                println!("Change observer failed: {:?}", observer_error);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Commits a previously recorded change set, refusing when any entity it touches changed since
+    fn replay(&mut self, operation: StoreOperation, changes: &ChangeSet) -> anyhow::Result<()> {
+        self.check_conflicts(changes)?;
+        for (archived_occurrence_id, change) in changes.archived_occurrences.iter() {
+            let archived_occurrence = self.get_archived_occurrence(archived_occurrence_id).ok();
+            if archived_occurrence.as_ref() != change.before.as_ref() {
+                anyhow::bail!(
+                    "ArchivedOccurrence {} was changed in the meantime",
+                    archived_occurrence_id
+                );
+            }
+        }
 
-    fn create_active_occurrence(&mut self, occurrence: ActiveOccurrence) -> anyhow::Result<String>;
-    fn create_occurrence(&mut self, occurrence: Occurrence) -> anyhow::Result<String>;
-    fn create_staff(&mut self, staff: Staff) -> anyhow::Result<String>;
-    fn create_team(&mut self, team: Team) -> anyhow::Result<String>;
-    fn create_vehicle(&mut self, vehicle: Vehicle) -> anyhow::Result<String>;
+        self.commit_operation(operation, changes)
+    }
+
+    /// Stages an operation on a copy of the store and commits the resulting changes together,
+    /// nothing is written when staging fails
+    fn transaction(
+        &mut self,
+        operation: StoreOperation,
+        stage: &mut dyn FnMut(&mut DataStore) -> anyhow::Result<()>,
+    ) -> anyhow::Result<ChangeSet> {
+        let changes = self.stage_changes(stage)?;
+        self.commit_operation(operation, &changes)?;
+
+        Ok(changes)
+    }
+
+    fn create_active_occurrence(
+        &mut self,
+        occurrence: ActiveOccurrence,
+    ) -> anyhow::Result<DispatchReport> {
+        let mut dispatch_report = DispatchReport::default();
+        self.transaction(StoreOperation::CreateActiveOccurrence, &mut |data_store| {
+            dispatch_report = operations::create_active_occurrence(data_store, occurrence.clone())?;
+            Ok(())
+        })?;
+        Ok(dispatch_report)
+    }
+
+    fn create_occurrence(&mut self, occurrence: Occurrence) -> anyhow::Result<String> {
+        let mut occurrence_id = String::new();
+        self.transaction(StoreOperation::CreateOccurrence, &mut |data_store| {
+            occurrence_id = operations::create_occurrence(data_store, occurrence.clone())?;
+            Ok(())
+        })?;
+        Ok(occurrence_id)
+    }
+
+    fn create_staff(&mut self, staff: Staff) -> anyhow::Result<String> {
+        let mut staff_id = String::new();
+        self.transaction(StoreOperation::CreateStaff, &mut |data_store| {
+            staff_id = operations::create_staff(data_store, staff.clone())?;
+            Ok(())
+        })?;
+        Ok(staff_id)
+    }
+
+    fn create_team(&mut self, team: Team) -> anyhow::Result<String> {
+        let mut team_id = String::new();
+        self.transaction(StoreOperation::CreateTeam, &mut |data_store| {
+            team_id = operations::create_team(data_store, team.clone())?;
+            Ok(())
+        })?;
+        Ok(team_id)
+    }
+
+    fn create_vehicle(&mut self, vehicle: Vehicle) -> anyhow::Result<String> {
+        let mut vehicle_id = String::new();
+        self.transaction(StoreOperation::CreateVehicle, &mut |data_store| {
+            vehicle_id = operations::create_vehicle(data_store, vehicle.clone())?;
+            Ok(())
+        })?;
+        Ok(vehicle_id)
+    }
 
     fn update_active_occurrence(
         &mut self,
         active_occurrence_id: &String,
         active_occurrence: ActiveOccurrence,
-    ) -> anyhow::Result<Option<ActiveOccurrence>>;
+    ) -> anyhow::Result<DispatchReport> {
+        let mut dispatch_report = DispatchReport::default();
+        self.transaction(StoreOperation::UpdateActiveOccurrence, &mut |data_store| {
+            dispatch_report = operations::update_active_occurrence(
+                data_store,
+                active_occurrence_id,
+                active_occurrence.clone(),
+            )?;
+            Ok(())
+        })?;
+        Ok(dispatch_report)
+    }
+
     fn update_occurrence(
         &mut self,
         occurrence_id: &String,
         occurrence: Occurrence,
-    ) -> anyhow::Result<Option<Occurrence>>;
-    fn update_staff(&mut self, staff_id: &String, staff: Staff) -> anyhow::Result<Option<Staff>>;
-    fn update_team(&mut self, team_id: &String, team: Team) -> anyhow::Result<Option<Team>>;
+    ) -> anyhow::Result<Option<Occurrence>> {
+        let mut previous_occurrence = None;
+        self.transaction(StoreOperation::UpdateOccurrence, &mut |data_store| {
+            previous_occurrence =
+                operations::update_occurrence(data_store, occurrence_id, occurrence.clone())?;
+            Ok(())
+        })?;
+        Ok(previous_occurrence)
+    }
+
+    fn update_staff(&mut self, staff_id: &String, staff: Staff) -> anyhow::Result<Option<Staff>> {
+        let mut previous_staff = None;
+        self.transaction(StoreOperation::UpdateStaff, &mut |data_store| {
+            previous_staff = operations::update_staff(data_store, staff_id, staff.clone())?;
+            Ok(())
+        })?;
+        Ok(previous_staff)
+    }
+
+    fn update_team(&mut self, team_id: &String, team: Team) -> anyhow::Result<Option<Team>> {
+        let mut previous_team = None;
+        self.transaction(StoreOperation::UpdateTeam, &mut |data_store| {
+            previous_team = operations::update_team(data_store, team_id, team.clone())?;
+            Ok(())
+        })?;
+        Ok(previous_team)
+    }
+
     fn update_vehicle(
         &mut self,
         vehicle_id: &String,
         vehicle: Vehicle,
-    ) -> anyhow::Result<Option<Vehicle>>;
+    ) -> anyhow::Result<Option<Vehicle>> {
+        let mut previous_vehicle = None;
+        self.transaction(StoreOperation::UpdateVehicle, &mut |data_store| {
+            previous_vehicle = operations::update_vehicle(data_store, vehicle_id, vehicle.clone())?;
+            Ok(())
+        })?;
+        Ok(previous_vehicle)
+    }
+
+    fn delete_active_occurrence(&mut self, active_occurrence_id: &String) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::DeleteActiveOccurrence, &mut |data_store| {
+            operations::delete_active_occurrence(data_store, active_occurrence_id)
+        })?;
+        Ok(())
+    }
+
+    fn delete_occurrence(&mut self, occurrence_id: &String) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::DeleteOccurrence, &mut |data_store| {
+            operations::delete_occurrence(data_store, occurrence_id)
+        })?;
+        Ok(())
+    }
+
+    fn delete_staff(&mut self, staff_id: &String) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::DeleteStaff, &mut |data_store| {
+            operations::delete_staff(data_store, staff_id)
+        })?;
+        Ok(())
+    }
+
+    fn delete_team(&mut self, team_id: &String) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::DeleteTeam, &mut |data_store| {
+            operations::delete_team(data_store, team_id)
+        })?;
+        Ok(())
+    }
+
+    fn delete_vehicle(&mut self, vehicle_id: &String) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::DeleteVehicle, &mut |data_store| {
+            operations::delete_vehicle(data_store, vehicle_id)
+        })?;
+        Ok(())
+    }
+
+    /// Frees the resources of an active occurrence and moves it into the archive
+    fn close_active_occurrence(
+        &mut self,
+        active_occurrence_id: &String,
+        end_time: u128,
+    ) -> anyhow::Result<ArchivedOccurrence> {
+        let mut archived_occurrence = None;
+        let mut changes = self.stage_changes(&mut |data_store| {
+            archived_occurrence = Some(operations::close_active_occurrence(
+                data_store,
+                active_occurrence_id,
+                end_time,
+            )?);
+            Ok(())
+        })?;
+        let archived_occurrence = archived_occurrence.with_context(|| {
+            format!(
+                "No active occurrence found with id: {}",
+                active_occurrence_id
+            )
+        })?;
+
+        changes.archived_occurrences.insert(
+            active_occurrence_id.clone(),
+            EntityChange {
+                before: None,
+                after: Some(archived_occurrence.clone()),
+            },
+        );
+        self.commit_operation(StoreOperation::CloseActiveOccurrence, &changes)?;
+
+        Ok(archived_occurrence)
+    }
+
+    fn record_vehicle_status(
+        &mut self,
+        active_occurrence_id: &String,
+        vehicle_id: &String,
+        status: VehicleStatus,
+        time: u128,
+    ) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::RecordVehicleStatus, &mut |data_store| {
+            operations::record_vehicle_status(
+                data_store,
+                active_occurrence_id,
+                vehicle_id,
+                status,
+                time,
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Frees vehicles with their crews, teams with their members and single staff while the
+    /// occurrence stays open
+    fn release_resources(
+        &mut self,
+        active_occurrence_id: &String,
+        release: &ResourceRelease,
+    ) -> anyhow::Result<ResourceRelease> {
+        let mut applied_release = None;
+        self.transaction(StoreOperation::ReleaseResources, &mut |data_store| {
+            applied_release = Some(operations::release_resources(
+                data_store,
+                active_occurrence_id,
+                release.clone(),
+            )?);
+            Ok(())
+        })?;
+        applied_release.with_context(|| {
+            format!(
+                "No active occurrence found with id: {}",
+                active_occurrence_id
+            )
+        })
+    }
+
+    /// Moves resources between active occurrences, vehicles take their crews and teams their members
+    fn transfer_resources(
+        &mut self,
+        transfer: &ResourceTransfer,
+    ) -> anyhow::Result<ResourceTransfer> {
+        let mut applied_transfer = None;
+        self.transaction(StoreOperation::TransferResources, &mut |data_store| {
+            applied_transfer = Some(operations::transfer_resources(
+                data_store,
+                transfer.clone(),
+            )?);
+            Ok(())
+        })?;
+        applied_transfer.with_context(|| {
+            format!(
+                "No active occurrence found with id: {}",
+                transfer.from_active_occurrence_id
+            )
+        })
+    }
+
+    /// Replaces the whole store and archive, as one commit that observers see entity by entity
+    fn restore_store(
+        &mut self,
+        data_store: DataStore,
+        archive: HashMap<String, ArchivedOccurrence>,
+    ) -> anyhow::Result<()> {
+        let mut changes = self.stage_changes(&mut |staged_store| {
+            *staged_store = data_store.clone();
+            Ok(())
+        })?;
+
+        let current_archive = self
+            .get_archived_occurrence_list(&ArchiveQuery::default())?
+            .into_iter()
+            .map(|archived_occurrence| {
+                (
+                    archived_occurrence.active_occurrence.internal_id.clone(),
+                    archived_occurrence,
+                )
+            })
+            .collect::<HashMap<String, ArchivedOccurrence>>();
+        changes.archived_occurrences = diff_collection(&current_archive, &archive);
+
+        self.commit_operation(StoreOperation::RestoreBackup, &changes)
+    }
+
+    fn set_staff_shift(
+        &mut self,
+        available_staff: Vec<String>,
+        team_allocations: HashMap<String, Vec<String>>,
+    ) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::SetStaffShift, &mut |data_store| {
+            operations::set_staff_shift(data_store, &available_staff, &team_allocations)
+        })?;
+        Ok(())
+    }
+
+    /// Applies a roster import in a single transaction, returns the staff ids in order
+    fn import_staff(&mut self, staff_list: Vec<Staff>) -> anyhow::Result<Vec<String>> {
+        let mut staff_ids = vec![];
+        self.transaction(StoreOperation::ImportStaff, &mut |data_store| {
+            staff_ids = operations::import_staff(data_store, &staff_list)?;
+            Ok(())
+        })?;
+        Ok(staff_ids)
+    }
+
+    fn add_staff_qualification(
+        &mut self,
+        staff_id: &String,
+        qualification: Qualification,
+    ) -> anyhow::Result<String> {
+        let mut qualification_id = String::new();
+        self.transaction(StoreOperation::AddStaffQualification, &mut |data_store| {
+            qualification_id =
+                operations::add_staff_qualification(data_store, staff_id, qualification.clone())?;
+            Ok(())
+        })?;
+        Ok(qualification_id)
+    }
 
-    fn delete_active_occurrence(&mut self, active_occurrence_id: &String) -> anyhow::Result<()>;
-    fn delete_occurrence(&mut self, occurrence_id: &String) -> anyhow::Result<()>;
-    fn delete_staff(&mut self, staff_id: &String) -> anyhow::Result<()>;
-    fn delete_team(&mut self, team_id: &String) -> anyhow::Result<()>;
-    fn delete_vehicle(&mut self, vehicle_id: &String) -> anyhow::Result<()>;
+    fn update_staff_qualification(
+        &mut self,
+        staff_id: &String,
+        qualification: Qualification,
+    ) -> anyhow::Result<Option<Qualification>> {
+        let mut previous_qualification = None;
+        self.transaction(
+            StoreOperation::UpdateStaffQualification,
+            &mut |data_store| {
+                previous_qualification = Some(operations::update_staff_qualification(
+                    data_store,
+                    staff_id,
+                    qualification.clone(),
+                )?);
+                Ok(())
+            },
+        )?;
+        Ok(previous_qualification)
+    }
+
+    fn remove_staff_qualification(
+        &mut self,
+        staff_id: &String,
+        qualification_id: &String,
+    ) -> anyhow::Result<()> {
+        self.transaction(
+            StoreOperation::RemoveStaffQualification,
+            &mut |data_store| {
+                operations::remove_staff_qualification(data_store, staff_id, qualification_id)
+            },
+        )?;
+        Ok(())
+    }
+
+    fn add_staff_leave(
+        &mut self,
+        staff_id: &String,
+        leave_period: LeavePeriod,
+    ) -> anyhow::Result<String> {
+        let mut leave_period_id = String::new();
+        self.transaction(StoreOperation::AddStaffLeave, &mut |data_store| {
+            leave_period_id =
+                operations::add_staff_leave(data_store, staff_id, leave_period.clone())?;
+            Ok(())
+        })?;
+        Ok(leave_period_id)
+    }
+
+    fn update_staff_leave(
+        &mut self,
+        staff_id: &String,
+        leave_period: LeavePeriod,
+    ) -> anyhow::Result<Option<LeavePeriod>> {
+        let mut previous_leave_period = None;
+        self.transaction(StoreOperation::UpdateStaffLeave, &mut |data_store| {
+            previous_leave_period = Some(operations::update_staff_leave(
+                data_store,
+                staff_id,
+                leave_period.clone(),
+            )?);
+            Ok(())
+        })?;
+        Ok(previous_leave_period)
+    }
+
+    fn remove_staff_leave(
+        &mut self,
+        staff_id: &String,
+        leave_period_id: &String,
+    ) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::RemoveStaffLeave, &mut |data_store| {
+            operations::remove_staff_leave(data_store, staff_id, leave_period_id)
+        })?;
+        Ok(())
+    }
 
-    fn set_staff_shift(&mut self, available_staff: Vec<String>, team_allocations: HashMap<String, Vec<String>>) -> anyhow::Result<()>;
+    /// Moves staff into and out of their leave states as leave periods start and end
+    fn apply_staff_leave(&mut self, time: u128) -> anyhow::Result<()> {
+        // Most runs have nothing to do, the whole store is only staged when some staff member moves
+        if !leave::has_due_transitions(&self.get_staff_list()?, time) {
+            return Ok(());
+        }
+
+        self.transaction(StoreOperation::ApplyStaffLeave, &mut |data_store| {
+            leave::apply_leave_transitions(data_store, time);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    fn set_crew_requirement(
+        &mut self,
+        crew_requirement: CrewRequirement,
+    ) -> anyhow::Result<String> {
+        let mut crew_requirement_id = String::new();
+        self.transaction(StoreOperation::SetCrewRequirement, &mut |data_store| {
+            crew_requirement_id =
+                operations::set_crew_requirement(data_store, crew_requirement.clone())?;
+            Ok(())
+        })?;
+        Ok(crew_requirement_id)
+    }
+
+    fn delete_crew_requirement(&mut self, crew_requirement_id: &String) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::DeleteCrewRequirement, &mut |data_store| {
+            operations::delete_crew_requirement(data_store, crew_requirement_id)
+        })?;
+        Ok(())
+    }
+
+    /// Creates or replaces a shift template, returns the template id
+    fn set_shift_template(
+        &mut self,
+        shift_template: ShiftTemplate,
+        time: u128,
+    ) -> anyhow::Result<String> {
+        let mut shift_template_id = String::new();
+        self.transaction(StoreOperation::SetShiftTemplate, &mut |data_store| {
+            shift_template_id =
+                operations::set_shift_template(data_store, shift_template.clone(), time)?;
+            Ok(())
+        })?;
+        Ok(shift_template_id)
+    }
+
+    fn delete_shift_template(&mut self, shift_template_id: &String) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::DeleteShiftTemplate, &mut |data_store| {
+            operations::delete_shift_template(data_store, shift_template_id)
+        })?;
+        Ok(())
+    }
+
+    /// Applies the roster of a template as its handover at `handover_time`
+    fn apply_shift_template(
+        &mut self,
+        shift_template_id: &String,
+        handover_time: u128,
+    ) -> anyhow::Result<()> {
+        self.transaction(StoreOperation::ApplyShiftTemplate, &mut |data_store| {
+            operations::apply_shift_template(data_store, shift_template_id, handover_time)
+        })?;
+        Ok(())
+    }
+
+    /// Fixes every repairable inconsistency in a single transaction and reports what changed
+    fn repair_store(&mut self) -> anyhow::Result<ConsistencyReport> {
+        let mut report = ConsistencyReport::default();
+        self.transaction(StoreOperation::RepairStore, &mut |data_store| {
+            report = consistency::repair_store(data_store);
+            Ok(())
+        })?;
+        report.repaired = true;
+        Ok(report)
+    }
+
+    /// Persists pending writes, for backends that do not write through on every change
+    fn flush(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
+
+pub type FirefightStore = Box<dyn FirefightDataManager + Send + Sync>;
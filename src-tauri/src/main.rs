@@ -5,7 +5,32 @@ mod commands;
 mod firefight;
 mod polly;
 
-use tauri::{async_runtime::Mutex, Manager};
+use firefight::{
+    backup::{self, BackupKind},
+    events::{self, EventBroadcaster},
+    history::UndoHistory,
+    images, local_store, operators, qualifications, shifts, sqlite_store,
+    types::FirefightStore,
+};
+use std::{sync::Arc, time::Duration};
+use tauri::{
+    async_runtime::Mutex,
+    http::{Request, Response, ResponseBuilder},
+    AppHandle, Manager,
+};
+
+// Set to "sqlite" to keep the data in an embedded database instead of data_store.dat
+const STORE_BACKEND_VARIABLE: &str = "FIREFIGHT_STORE_BACKEND";
+// How often pending store writes are persisted to disk
+const STORE_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+// How often a rotating backup of the store is taken
+const BACKUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// How often staff qualifications are checked for upcoming expiry
+const QUALIFICATION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// How often leave periods are checked for their start and end
+const STAFF_LEAVE_INTERVAL: Duration = Duration::from_secs(60);
+// How often the shift calendar is checked for a due handover
+const SHIFT_HANDOVER_INTERVAL: Duration = Duration::from_secs(30);
 
 #[cfg(dev)]
 #[tauri::command]
@@ -18,6 +43,160 @@ fn get_environment() -> String {
     String::from("production")
 }
 
+fn flush_store(app_handle: &AppHandle) {
+    let store = app_handle.state::<Mutex<FirefightStore>>();
+    if let Err(flush_error) = store.blocking_lock().flush() {
+        println!("Failed to flush store: {:?}", flush_error);
+    }
+}
+
+fn serve_image(
+    app_handle: &AppHandle,
+    request: &Request,
+) -> Result<Response, Box<dyn std::error::Error>> {
+    let image = match images::parse_image_request(request.uri()) {
+        Some((reference, size)) => images::read_image(app_handle, &reference, size),
+        None => Err(anyhow::anyhow!("Invalid image request: {}", request.uri())),
+    };
+
+    match image {
+        // Content never changes for a reference, so webviews can keep it forever
+        Ok(image_bytes) => ResponseBuilder::new()
+            .mimetype(images::image_mimetype(&image_bytes))
+            .header("Cache-Control", "max-age=31536000, immutable")
+            .body(image_bytes),
+        Err(image_error) => ResponseBuilder::new()
+            .status(404)
+            .body(image_error.to_string().into_bytes()),
+    }
+}
+
+fn backup_store(app_handle: &AppHandle) {
+    let store = app_handle.state::<Mutex<FirefightStore>>();
+    let store = store.blocking_lock();
+    if let Err(backup_error) =
+        backup::create_backup(app_handle, store.as_ref(), BackupKind::Periodic)
+    {
+        println!("Failed to back up store: {:?}", backup_error);
+    }
+}
+
+fn check_qualifications(app_handle: &AppHandle) -> anyhow::Result<()> {
+    let store = app_handle.state::<Mutex<FirefightStore>>();
+    let data_store = store.blocking_lock().get_data_store()?;
+
+    let now = std::time::UNIX_EPOCH.elapsed()?.as_millis();
+    let expiring_qualifications = qualifications::expiring_qualifications(
+        &data_store,
+        now,
+        qualifications::EXPIRY_WARNING_WINDOW,
+    );
+    if !expiring_qualifications.is_empty() {
+        app_handle.emit_all(events::QUALIFICATIONS_EXPIRING, expiring_qualifications)?;
+    }
+
+    Ok(())
+}
+
+fn apply_staff_leave(app_handle: &AppHandle) -> anyhow::Result<()> {
+    let store = app_handle.state::<Mutex<FirefightStore>>();
+    let now = std::time::UNIX_EPOCH.elapsed()?.as_millis();
+    store.blocking_lock().apply_staff_leave(now)
+}
+
+fn apply_shift_handover(app_handle: &AppHandle) -> anyhow::Result<()> {
+    let store = app_handle.state::<Mutex<FirefightStore>>();
+    let handover = {
+        let mut store = store.blocking_lock();
+        let now = std::time::UNIX_EPOCH.elapsed()?.as_millis();
+        let handover = match shifts::due_handover(&store.get_shift_template_list()?, now) {
+            Some(handover) => handover,
+            None => return Ok(()),
+        };
+        store.apply_shift_template(&handover.shift_template_id, handover.handover_time)?;
+        handover
+    };
+    println!(
+        "Shift handover to {} at {}",
+        handover.shift_template_name, handover.handover_time
+    );
+
+    // Announced with the store unlocked, so the dispatch board is not held up by the audio
+    app_handle.emit_all(events::SHIFT_HANDOVER, &handover)?;
+    tauri::async_runtime::block_on(commands::announce_shift_handover(app_handle, &handover))
+}
+
+// Keeps the app alive long enough to explain why it cannot start, then exits
+fn report_store_failure(app_handle: AppHandle, load_error: anyhow::Error) -> FirefightStore {
+    println!("Failed to load store: {:?}", load_error);
+
+    let recovery_store = local_store::create_recovery_store(app_handle.clone());
+    let message = format!(
+        "The data store could not be loaded and no readable backup was found: {}",
+        load_error
+    );
+    tauri::api::dialog::MessageDialogBuilder::new("Data store recovery", message)
+        .kind(tauri::api::dialog::MessageDialogKind::Error)
+        .show(move |_| app_handle.exit(1));
+
+    Box::new(recovery_store)
+}
+
+// Runs the app on an empty read-only store and offers to restore the latest readable backup
+fn offer_store_recovery(
+    app_handle: AppHandle,
+    load_error: anyhow::Error,
+    restore_backup: fn(&AppHandle, &str) -> anyhow::Result<()>,
+) -> FirefightStore {
+    let backup = match backup::find_latest_valid_backup(&app_handle) {
+        Some(backup) => backup,
+        None => return report_store_failure(app_handle, load_error),
+    };
+    println!(
+        "Failed to load store, offering backup {}: {:?}",
+        backup.name, load_error
+    );
+
+    let recovery_store = local_store::create_recovery_store(app_handle.clone());
+    let message = format!(
+        "The data store could not be loaded: {}\n\nRestore the backup from {}? The current file will be kept aside.",
+        load_error, backup.name
+    );
+    tauri::api::dialog::ask(
+        None::<&tauri::Window>,
+        "Data store recovery",
+        message,
+        move |restore| {
+            if !restore {
+                app_handle.exit(1);
+                return;
+            }
+
+            match restore_backup(&app_handle, &backup.name) {
+                Ok(()) => app_handle.restart(),
+                Err(restore_error) => {
+                    println!(
+                        "Failed to restore backup {}: {:?}",
+                        backup.name, restore_error
+                    );
+                    app_handle.exit(1);
+                }
+            }
+        },
+    );
+
+    Box::new(recovery_store)
+}
+
+fn restore_local_backup(app_handle: &AppHandle, name: &str) -> anyhow::Result<()> {
+    let store_path = local_store::get_store_path(app_handle);
+    backup::restore_backup_file(app_handle, name, &store_path)
+}
+
+fn restore_sqlite_backup(app_handle: &AppHandle, name: &str) -> anyhow::Result<()> {
+    sqlite_store::restore_backup(app_handle.clone(), name)
+}
+
 fn main() {
     tauri::Builder::default()
         .on_window_event(|event| {
@@ -26,14 +205,85 @@ fn main() {
 
                 if window.label() == "main" {
                     println!("Main window destroyed, exiting...");
+                    flush_store(&window.app_handle());
                     std::process::exit(0);
                 }
             }
         })
+        .register_uri_scheme_protocol(images::IMAGE_PROTOCOL, serve_image)
         .setup(|app| {
-            let store = firefight::local_store::create_store(app.app_handle());
+            let mut store: FirefightStore = match std::env::var(STORE_BACKEND_VARIABLE).as_deref() {
+                Ok("sqlite") => match sqlite_store::create_store(app.app_handle()) {
+                    Ok(sqlite_store) => Box::new(sqlite_store),
+                    Err(load_error) => {
+                        offer_store_recovery(app.app_handle(), load_error, restore_sqlite_backup)
+                    }
+                },
+                _ => match local_store::create_store(app.app_handle()) {
+                    Ok(local_store) => Box::new(local_store),
+                    Err(load_error) => {
+                        offer_store_recovery(app.app_handle(), load_error, restore_local_backup)
+                    }
+                },
+            };
+            if let Err(image_error) = images::externalize_images(&app.app_handle(), store.as_mut())
+            {
+                println!("Failed to move inline images to disk: {:?}", image_error);
+            }
+
+            let operator_registry = Arc::new(operators::open_registry(&app.app_handle())?);
+            app.manage(operator_registry.clone());
+
+            let audit_journal =
+                firefight::journal::open_journal(&app.app_handle(), operator_registry)?;
+            store.add_observer(Arc::new(audit_journal));
+
+            let undo_history = Arc::new(UndoHistory::default());
+            store.add_observer(undo_history.clone());
+            app.manage(undo_history);
+
+            let event_broadcaster = Arc::new(EventBroadcaster::new(app.app_handle()));
+            store.add_observer(event_broadcaster.clone());
+            app.manage(event_broadcaster);
+
             app.manage(Mutex::new(store));
 
+            let app_handle = app.app_handle();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(STORE_FLUSH_INTERVAL);
+                flush_store(&app_handle);
+            });
+
+            let app_handle = app.app_handle();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(BACKUP_INTERVAL);
+                backup_store(&app_handle);
+            });
+
+            let app_handle = app.app_handle();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(QUALIFICATION_CHECK_INTERVAL);
+                if let Err(check_error) = check_qualifications(&app_handle) {
+                    println!("Failed to check qualifications: {:?}", check_error);
+                }
+            });
+
+            let app_handle = app.app_handle();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(STAFF_LEAVE_INTERVAL);
+                if let Err(leave_error) = apply_staff_leave(&app_handle) {
+                    println!("Failed to apply staff leave: {:?}", leave_error);
+                }
+            });
+
+            let app_handle = app.app_handle();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(SHIFT_HANDOVER_INTERVAL);
+                if let Err(handover_error) = apply_shift_handover(&app_handle) {
+                    println!("Failed to apply shift handover: {:?}", handover_error);
+                }
+            });
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -41,6 +291,12 @@ fn main() {
             commands::get_version,
             commands::alert,
             commands::get_store,
+            commands::login,
+            commands::logout,
+            commands::get_operator,
+            commands::set_operator_secret,
+            commands::remove_operator_secret,
+            commands::resync,
             commands::create_active_occurrence,
             commands::create_occurrence,
             commands::create_staff,
@@ -52,11 +308,43 @@ fn main() {
             commands::update_team,
             commands::update_vehicle,
             commands::set_staff_shift,
+            commands::set_shift_template,
+            commands::delete_shift_template,
+            commands::apply_shift_template,
+            commands::get_shift_calendar,
+            commands::add_staff_qualification,
+            commands::update_staff_qualification,
+            commands::remove_staff_qualification,
+            commands::add_staff_leave,
+            commands::update_staff_leave,
+            commands::remove_staff_leave,
+            commands::get_expiring_qualifications,
+            commands::get_qualified_staff,
+            commands::recommend_dispatch,
+            commands::set_crew_requirement,
+            commands::delete_crew_requirement,
+            commands::undo,
+            commands::redo,
             commands::delete_active_occurrence,
+            commands::close_active_occurrence,
+            commands::get_archived_occurrence,
+            commands::get_archived_occurrence_crews,
+            commands::record_vehicle_status,
+            commands::transfer_resources,
+            commands::release_resources,
+            commands::get_vehicle_response_times,
+            commands::get_archived_occurrence_list,
             commands::delete_occurrence,
             commands::delete_staff,
             commands::delete_team,
             commands::delete_vehicle,
+            commands::export_staff_csv,
+            commands::import_staff_csv,
+            commands::export_station_bundle,
+            commands::import_station_bundle,
+            commands::verify_store,
+            commands::list_backups,
+            commands::restore_backup,
             commands::clear_audio_cache,
             commands::rebuild_audio_cache,
             commands::open_fvp,
@@ -6,6 +6,7 @@ import staffSample from '../../_assets/staff_sample.jpg'
 import check from '../../_assets/check-solid.svg'
 import { getCardClassForStates } from '../../_utils/cardStyle'
 import { shortenName } from '../../_utils/shortenName'
+import { imageUrl } from '../../_utils/imageUrl'
 import { defaultToNbSp } from '../../_utils/defaultToNbsp'
 import { useLanguageStore } from '../../_state/lang'
 
@@ -30,7 +31,7 @@ export const StaffCard: FunctionComponent<StaffCardProps> = (props) => {
 	const buttonClassName = getCardClassForStates(props.state, props.selected || false)
 
 	const clickHandler = props.disabled || !props.onClick ? undefined : props.onClick.bind(null, props.internalId)
-	const imgSource = props.image?.trim() || staffSample
+	const imgSource = imageUrl(props.image, 'card') || staffSample
 
 	const onFocus: FocusEventHandler<HTMLButtonElement> = (evt) => {
 		evt.target.scrollIntoView({ behavior: 'smooth', block: 'nearest' })
@@ -8,6 +8,7 @@ import { staffRankToInsignia } from '../../_utils/staffRankToInsignia'
 import { useLanguageStore } from '../../_state/lang'
 
 type StaffTagProps = {
+  caption?: string
   disabled?: boolean
   index: number
   internalId: string
@@ -44,7 +45,7 @@ export const StaffTag: FunctionComponent<StaffTagProps> = (props) => {
 				<label className="flex-1 text-primary truncate text-right">{defaultToNbSp(props.label)}</label>
 			</div>
 
-			<label className={stateClassName}>{defaultToNbSp(staffStateToShortLocale(props.state, languageData))}</label>
+			<label className={stateClassName}>{defaultToNbSp(props.caption ?? staffStateToShortLocale(props.state, languageData))}</label>
 		</div>
 	)
 }
@@ -5,6 +5,7 @@ import { vehicleStateToLocale } from '../../_utils/vehicleStateToLocale'
 import vehicleSample from '../../_assets/vehicle_sample.jpg'
 import check from '../../_assets/check-solid.svg'
 import { getCardClassForStates } from '../../_utils/cardStyle'
+import { imageUrl } from '../../_utils/imageUrl'
 import { useLanguageStore } from '../../_state/lang'
 
 type VehicleCardProps = {
@@ -27,7 +28,7 @@ export const VehicleCard: FunctionComponent<VehicleCardProps> = (props) => {
 	const className = getCardClassForStates(props.state, props.selected || false)
 
 	const clickHandler = props.disabled || !props.onClick ? undefined : props.onClick.bind(null, props.internalId)
-	const imgSource = props.image?.trim() || vehicleSample
+	const imgSource = imageUrl(props.image, 'card') || vehicleSample
 
 	const onFocus: FocusEventHandler<HTMLButtonElement> = (evt) => {
 		evt.target.scrollIntoView({ behavior: 'smooth', block: 'nearest' })
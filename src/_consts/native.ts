@@ -8,6 +8,64 @@ export type OccurrenceInfo = {
 	vmerSiv: boolean
 }
 
+export enum CrewRole {
+	Chief = 'chief',
+	Driver = 'driver',
+	Crew = 'crew'
+}
+
+export type CrewAssignment = {
+	staffId: string
+	role: CrewRole
+}
+
+export type VehicleCrew = {
+	vehicleId: string
+	chiefId?: string
+	driverId?: string
+	crewIds: string[]
+}
+
+export enum VehicleStatus {
+	Alerted = 'alerted',
+	Departed = 'departed',
+	OnScene = 'onScene',
+	TransportingToHospital = 'transportingToHospital',
+	AtHospital = 'atHospital',
+	Returning = 'returning',
+	AtBase = 'atBase'
+}
+
+export type VehicleStatusChange = {
+	status: VehicleStatus
+	time: number
+}
+
+export type VehicleResponseTimes = {
+	vehicleId: string
+	turnoutTime?: number
+	travelTime?: number
+	responseTime?: number
+	onSceneTime?: number
+	totalTime?: number
+}
+
+export type ResourceRelease = {
+	staffIds: string[]
+	teamIds: string[]
+	vehicleIds: string[]
+	time: number
+}
+
+export type ResourceTransfer = {
+	fromActiveOccurrenceId: string
+	toActiveOccurrenceId: string
+	staffIds: string[]
+	teamIds: string[]
+	vehicleIds: string[]
+	time: number
+}
+
 export type ActiveOccurrence = {
 	address?: string
 	creationTime?: number
@@ -16,17 +74,115 @@ export type ActiveOccurrence = {
 	location?: string
 	coduNumber?: string
 	occurrenceId: string
-	vehicleAssignmentMap: Record<string, string[]>
+	vehicleAssignmentMap: Record<string, CrewAssignment[]>
 	vehicleIds: string[]
+	vehicleTimelines: Record<string, VehicleStatusChange[]>
 	referencePoint?: string
+	resourceReleases: ResourceRelease[]
+	resourceTransfers: ResourceTransfer[]
 	staffIds: string[]
+	teamIds: string[]
 	vmerSiv?: boolean
 }
 
+export type ArchivedOccurrence = {
+	activeOccurrence: ActiveOccurrence
+	endTime: number
+}
+
+export type ArchiveQuery = {
+	fromTime?: number
+	toTime?: number
+	occurrenceId?: string
+	staffId?: string
+	vehicleId?: string
+}
+
+export type BackupInfo = {
+	name: string
+	kind: 'periodic' | 'preMigration' | 'preRepair' | 'preRestore'
+	creationTime: number
+	size: number
+}
+
+export type StaffImportMode = 'create' | 'upsertByLabel' | 'upsertByNationalId'
+
+export type StaffImportIssue = {
+	line: number
+	label: string
+	message: string
+}
+
+export type StaffImportReport = {
+	dryRun: boolean
+	created: string[]
+	updated: string[]
+	unchanged: string[]
+	errors: StaffImportIssue[]
+	warnings: StaffImportIssue[]
+}
+
+export type BundleManifest = {
+	formatVersion: number
+	appVersion: string
+	schemaVersion: number
+	creationTime: number
+}
+
+export type StoreError =
+	| { kind: 'unknownActiveOccurrence'; activeOccurrenceId: string }
+	| { kind: 'unknownOccurrence'; occurrenceId: string }
+	| { kind: 'unknownStaff'; staffId: string }
+	| { kind: 'unknownTeam'; teamId: string }
+	| { kind: 'unknownVehicle'; vehicleId: string }
+	| { kind: 'unassignedVehicle'; vehicleId: string }
+	| { kind: 'unassignedStaff'; vehicleId: string; staffId: string }
+	| { kind: 'duplicateCrewRole'; vehicleId: string; role: CrewRole }
+	| { kind: 'unknownQualification'; staffId: string; qualificationId: string }
+	| { kind: 'invalidQualificationPeriod'; qualificationId: string }
+	| { kind: 'unknownLeavePeriod'; staffId: string; leavePeriodId: string }
+	| { kind: 'invalidLeavePeriod'; leavePeriodId: string }
+	| { kind: 'invalidCrewRange'; crewRequirementId: string }
+	| { kind: 'unmetCrewRequirements'; violations: CrewViolation[] }
+	| { kind: 'vehicleNotInOccurrence'; activeOccurrenceId: string; vehicleId: string }
+	| { kind: 'staffNotInOccurrence'; activeOccurrenceId: string; staffId: string }
+	| { kind: 'teamNotInOccurrence'; activeOccurrenceId: string; teamId: string }
+	| { kind: 'emptyResourceRelease'; activeOccurrenceId: string }
+	| { kind: 'invalidResourceTransfer'; fromActiveOccurrenceId: string; toActiveOccurrenceId: string }
+	| { kind: 'invalidVehicleStatus'; vehicleId: string; status: VehicleStatus }
+	| { kind: 'vehiclesNotAtBase'; vehicleIds: string[] }
+	| { kind: 'unknownShiftTemplate'; shiftTemplateId: string }
+	| { kind: 'invalidShiftSchedule'; shiftTemplateId: string }
+	| { kind: 'notLoggedIn' }
+	| { kind: 'permissionDenied'; permission: StaffPermission }
+	| { kind: 'invalidCredentials' }
+
+/** Rejection value of every command, typed store errors or a plain message for anything else */
+export type CommandError = StoreError | string
+
+export type Inconsistency = {
+	entityKind: 'activeOccurrence' | 'archivedOccurrence' | 'crewRequirement' | 'occurrence' | 'shiftTemplate' | 'staff' | 'team' | 'vehicle'
+	entityId: string
+	message: string
+	repair?: string
+}
+
+export type ConsistencyReport = {
+	repaired: boolean
+	inconsistencies: Record<string, Inconsistency[]>
+}
+
+export type DispatchPlanEntry = {
+	category: VehicleCategory
+	count: number
+	minCapacity?: number
+}
+
 export type Occurrence = {
 	internalId: string
 	image: string
 	name: string
+	dispatchPlan: DispatchPlanEntry[]
 }
 
 export enum VehicleState {
@@ -35,6 +191,16 @@ export enum VehicleState {
 	Unavailable = 'unavailable'
 }
 
+export enum VehicleCategory {
+	Ambulances = 'ambulances',
+	Command = 'command',
+	FireFight = 'fireFight',
+	Support = 'support',
+	Trailers = 'trailers',
+	Vessels = 'vessels',
+	Unknown = 'unknown'
+}
+
 export type Vehicle = {
 	internalId: string
 	capacity?: number
@@ -72,6 +238,54 @@ export enum StaffPermission {
 	None = 'none'
 }
 
+export enum QualificationKind {
+	Driver = 'driver',
+	HeavyDriver = 'heavyDriver',
+	Tas = 'tas',
+	Tat = 'tat',
+	Diver = 'diver'
+}
+
+export type Qualification = {
+	internalId: string
+	kind: QualificationKind
+	issueTime: number
+	expiryTime?: number
+}
+
+export type QualificationExpiry = {
+	staffId: string
+	staffLabel: string
+	qualification: Qualification
+	expired: boolean
+}
+
+export enum LeaveReason {
+	Sick = 'sick',
+	Vacation = 'vacation',
+	Training = 'training',
+	Other = 'other'
+}
+
+export enum LeaveStatus {
+	Scheduled = 'scheduled',
+	Ongoing = 'ongoing',
+	Ended = 'ended'
+}
+
+export type LeavePeriod = {
+	internalId: string
+	reason: LeaveReason
+	startTime: number
+	endTime: number
+	status: LeaveStatus
+}
+
+export type OperatorSession = {
+	staffId: string
+	loginTime: number
+}
+
 export type Staff = {
 	internalId: string
 	image: string
@@ -81,6 +295,8 @@ export type Staff = {
 	permission: StaffPermission
 	rank: StaffRank
 	state: StaffState
+	qualifications: Qualification[]
+	leavePeriods: LeavePeriod[]
 }
 
 export enum TeamState {
@@ -95,4 +311,66 @@ export type Team = {
 	label: string
 	memberIds: string[]
 	state: TeamState
-}
\ No newline at end of file
+}
+export type CrewRequirementTarget =
+	| { kind: 'category'; category: VehicleCategory }
+	| { kind: 'vehicle'; vehicleId: string }
+
+export type CrewRequirement = {
+	internalId: string
+	target: CrewRequirementTarget
+	minCrew?: number
+	maxCrew?: number
+	chiefRank?: StaffRank
+	qualifications: QualificationKind[]
+	blocking: boolean
+}
+
+export type CrewProblem =
+	| { kind: 'tooFewCrew'; crewCount: number; minCrew: number }
+	| { kind: 'tooManyCrew'; crewCount: number; maxCrew: number }
+	| { kind: 'missingChief'; chiefRank: StaffRank }
+	| { kind: 'missingQualifications'; qualifications: QualificationKind[] }
+
+export type CrewViolation = {
+	vehicleId: string
+	requirementId?: string
+	problem: CrewProblem
+	blocking: boolean
+}
+
+export type DispatchReport = {
+	activeOccurrenceId: string
+	crewWarnings: CrewViolation[]
+	transfers: ResourceTransfer[]
+}
+
+export type DispatchRecommendation = {
+	occurrenceId: string
+	vehicleIds: string[]
+	vehicleAssignmentMap: Record<string, CrewAssignment[]>
+	staffIds: string[]
+	missingVehicles: DispatchPlanEntry[]
+	crewWarnings: CrewViolation[]
+}
+
+export type ShiftRecurrence = {
+	startTime: number
+	repeatInterval?: number
+	endTime?: number
+}
+
+export type ShiftTemplate = {
+	internalId: string
+	name: string
+	availableStaff: string[]
+	teamAllocations: Record<string, string[]>
+	schedule: ShiftRecurrence[]
+	lastHandover?: number
+}
+
+export type ShiftHandover = {
+	shiftTemplateId: string
+	shiftTemplateName: string
+	handoverTime: number
+}
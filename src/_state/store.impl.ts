@@ -1,11 +1,12 @@
 import { invoke } from '@tauri-apps/api'
 import { type Subject, catchError, delayWhen, filter, from, of, switchMap, take, tap } from 'rxjs'
 
-export const bindCreator$ = <T>(
+export const bindCreator$ = <T, R = unknown>(
 	inCreate$: Subject<T>,
 	outLoading$: Subject<boolean>,
 	remoteCall: string,
-	remoteField: string
+	remoteField: string,
+	outResult$?: Subject<R>
 ) => {
 	inCreate$
 		.pipe(
@@ -21,16 +22,20 @@ export const bindCreator$ = <T>(
 						outLoading$.next(false)
 						return of(null)
 					}))))
-		.subscribe(() => {
+		.subscribe((result) => {
 			outLoading$.next(false)
+			if (result !== null) {
+				outResult$?.next(result as R)
+			}
 		})
 }
 
-export const bindUpdater$ = <T extends { internalId: string }>(
+export const bindUpdater$ = <T extends { internalId: string }, R = unknown>(
 	inUpdate$: Subject<T>,
 	outLoading$: Subject<boolean>,
 	remoteCall: string,
-	remoteField: string
+	remoteField: string,
+	outResult$?: Subject<R>
 ) => {
 	inUpdate$
 		.pipe(
@@ -46,8 +51,11 @@ export const bindUpdater$ = <T extends { internalId: string }>(
 						outLoading$.next(false)
 						return of(null)
 					}))))
-		.subscribe(() => {
+		.subscribe((result) => {
 			outLoading$.next(false)
+			if (result !== null) {
+				outResult$?.next(result as R)
+			}
 		})
 }
 
@@ -1,7 +1,7 @@
 import { invoke } from '@tauri-apps/api/tauri'
 import { BehaviorSubject, ReplaySubject, Subject, distinctUntilChanged, map } from 'rxjs'
 import { listen } from '@tauri-apps/api/event'
-import { Team, type ActiveOccurrence, type Occurrence, type Staff, type Vehicle } from '../_consts/native'
+import { Team, type ActiveOccurrence, type ArchiveQuery, type ArchivedOccurrence, type BackupInfo, type BundleManifest, type ConsistencyReport, type CrewRequirement, type DispatchRecommendation, type DispatchReport, type LeavePeriod, type Occurrence, type OperatorSession, type Qualification, type QualificationExpiry, type QualificationKind, type ResourceRelease, type ResourceTransfer, type ShiftHandover, type ShiftTemplate, type Staff, type StaffImportMode, type StaffImportReport, type Vehicle, type VehicleCrew, type VehicleResponseTimes, type VehicleStatus } from '../_consts/native'
 import { bindBulkUpdater$, bindCreator$, bindDeleter$, bindUpdater$ } from './store.impl'
 
 export type State = {
@@ -10,28 +10,104 @@ export type State = {
   staff: Record<string, Staff>
   teams: Record<string, Team>
   vehicles: Record<string, Vehicle>
+  crewRequirements: Record<string, CrewRequirement>
+  shiftTemplates: Record<string, ShiftTemplate>
 }
 
 export const store$ = new ReplaySubject<State>(1)
 
-invoke('get_store')
-	.then((state) => {
-		store$.next(state as State)
-	})
-	.catch((err) => {
-		console.error('Failed to load state', err)
+type StoreSnapshot = { revision: number; dataStore: State }
+type EntitiesChanged<T> = { revision: number; operation: string; entities: T[] }
+type EntitiesDeleted = { revision: number; operation: string; entityIds: string[] }
+type ShiftChanged = { revision: number; staff: Staff[]; teams: Team[] }
+
+// Events are applied on top of the last snapshot, a missed revision triggers a resync
+let currentState: State | undefined
+let currentRevision = 0
+let resyncing = false
+
+const resync = () => {
+	if (resyncing) return
+	resyncing = true
+	invoke<StoreSnapshot>('resync')
+		.then(({ revision, dataStore }) => {
+			currentState = dataStore
+			currentRevision = revision
+			store$.next(dataStore)
+		})
+		.catch((err) => {
+			console.error('Failed to load state', err)
+		})
+		.finally(() => {
+			resyncing = false
+		})
+}
+
+const applyEvent = (revision: number, update: (state: State) => State) => {
+	if (!currentState || resyncing || revision <= currentRevision) return
+	if (revision !== currentRevision + 1) {
+		console.warn('Missed state revisions, resyncing', { currentRevision, revision })
+		resync()
+		return
+	}
+
+	currentRevision = revision
+	currentState = update(currentState)
+	store$.next(currentState)
+}
+
+const withEntities = <T extends { internalId: string }>(collection: Record<string, T>, entities: T[]) =>
+	entities.reduce((next, entity) => ({ ...next, [entity.internalId]: entity }), collection)
+
+const withoutEntities = <T>(collection: Record<string, T>, entityIds: string[]) =>
+	Object.fromEntries(Object.entries(collection).filter(([entityId]) => !entityIds.includes(entityId)))
+
+const bindCollectionEvents = <K extends keyof State>(eventPrefix: string, key: K) => {
+	const onChanged = ({ payload }: { payload: EntitiesChanged<State[K][string]> }) => {
+		applyEvent(payload.revision, (state) => ({ ...state, [key]: withEntities(state[key], payload.entities) }))
+	}
+	listen(`firefight://${eventPrefix}_created`, onChanged)
+	listen(`firefight://${eventPrefix}_updated`, onChanged)
+	listen<EntitiesDeleted>(`firefight://${eventPrefix}_deleted`, ({ payload }) => {
+		applyEvent(payload.revision, (state) => ({ ...state, [key]: withoutEntities(state[key], payload.entityIds) }))
 	})
+}
 
-listen('firefight://state_updated', ({ payload }) => {
-	console.trace('Updating state', { payload })
-	store$.next(payload as State)
+bindCollectionEvents('active_occurrence', 'activeOccurrences')
+bindCollectionEvents('occurrence', 'occurrences')
+bindCollectionEvents('staff', 'staff')
+bindCollectionEvents('team', 'teams')
+bindCollectionEvents('vehicle', 'vehicles')
+bindCollectionEvents('crew_requirement', 'crewRequirements')
+bindCollectionEvents('shift_template', 'shiftTemplates')
+listen<ShiftChanged>('firefight://shift_changed', ({ payload }) => {
+	applyEvent(payload.revision, (state) => ({
+		...state,
+		staff: withEntities(state.staff, payload.staff),
+		teams: withEntities(state.teams, payload.teams)
+	}))
 })
 
+// The archive is not kept in the state, views listing it refetch when it changes
+export const archiveChanged$ = new Subject<void>()
+const onArchiveChanged = ({ payload }: { payload: { revision: number } }) => {
+	applyEvent(payload.revision, (state) => state)
+	archiveChanged$.next()
+}
+listen('firefight://archived_occurrence_created', onArchiveChanged)
+listen('firefight://archived_occurrence_updated', onArchiveChanged)
+listen('firefight://archived_occurrence_deleted', onArchiveChanged)
+
+resync()
+
 export const updatingState$ = new BehaviorSubject<boolean>(false)
 
+// Crew requirements that were not met but did not block the dispatch
+export const dispatchReport$ = new Subject<DispatchReport>()
+
 export const createActiveOccurrence$ = new Subject<ActiveOccurrence>()
 export const activeOccurrenceCreated$ = new Subject<string>()
-bindCreator$(createActiveOccurrence$, updatingState$, 'create_active_occurrence', 'activeOccurrence')
+bindCreator$(createActiveOccurrence$, updatingState$, 'create_active_occurrence', 'activeOccurrence', dispatchReport$)
 export const createOccurrence$ = new Subject<Occurrence>()
 export const occurrenceCreated$ = new Subject<string>()
 bindCreator$(createOccurrence$, updatingState$, 'create_occurrence', 'occurrence')
@@ -46,7 +122,7 @@ export const vehicleCreated$ = new Subject<string>()
 bindCreator$(createVehicle$, updatingState$, 'create_vehicle', 'vehicle')
 
 export const updateActiveOccurrence$ = new Subject<ActiveOccurrence>()
-bindUpdater$(updateActiveOccurrence$, updatingState$, 'update_active_occurrence', 'activeOccurrence')
+bindUpdater$(updateActiveOccurrence$, updatingState$, 'update_active_occurrence', 'activeOccurrence', dispatchReport$)
 export const updateOccurrence$ = new Subject<Occurrence>()
 bindUpdater$(updateOccurrence$, updatingState$, 'update_occurrence', 'occurrence')
 export const updateStaff$ = new Subject<Staff>()
@@ -61,6 +137,8 @@ bindBulkUpdater$(updateShift$, updatingState$, 'set_staff_shift', 'availableStaf
 
 export const deleteActiveOccurrence$ = new Subject<string>()
 bindDeleter$(deleteActiveOccurrence$, updatingState$, 'delete_active_occurrence', 'activeOccurrenceId')
+export const closeActiveOccurrence$ = new Subject<string>()
+bindDeleter$(closeActiveOccurrence$, updatingState$, 'close_active_occurrence', 'activeOccurrenceId')
 export const deleteOccurrence$ = new Subject<string>()
 bindDeleter$(deleteOccurrence$, updatingState$, 'delete_occurrence', 'occurrenceId')
 export const deleteStaff$ = new Subject<string>()
@@ -70,6 +148,112 @@ bindDeleter$(deleteTeam$, updatingState$, 'delete_team', 'teamId')
 export const deleteVehicle$ = new Subject<string>()
 bindDeleter$(deleteVehicle$, updatingState$, 'delete_vehicle', 'vehicleId')
 
+// Both resolve to the operation that was reverted or reapplied
+export const undo = async () => await invoke<string>('undo')
+export const redo = async () => await invoke<string>('redo')
+
+export const getArchivedOccurrences = async (query: ArchiveQuery) =>
+	await invoke<ArchivedOccurrence[]>('get_archived_occurrence_list', { query })
+
+export const getArchivedOccurrenceCrews = async (archivedOccurrenceId: string) =>
+	await invoke<VehicleCrew[]>('get_archived_occurrence_crews', { archivedOccurrenceId })
+
+export const recordVehicleStatus = async (activeOccurrenceId: string, vehicleId: string, status: VehicleStatus, time?: number) =>
+	await invoke('record_vehicle_status', { activeOccurrenceId, vehicleId, status, time })
+export const releaseResources = async (activeOccurrenceId: string, release: ResourceRelease) =>
+	await invoke<ResourceRelease>('release_resources', { activeOccurrenceId, release })
+export const transferResources = async (transfer: ResourceTransfer) =>
+	await invoke<ResourceTransfer>('transfer_resources', { transfer })
+export const getVehicleResponseTimes = async (activeOccurrenceId: string) =>
+	await invoke<VehicleResponseTimes[]>('get_vehicle_response_times', { activeOccurrenceId })
+
+export const exportStaffCsv = async () => await invoke<string>('export_staff_csv')
+export const importStaffCsv = async (csvText: string, mode: StaffImportMode, dryRun: boolean) =>
+	await invoke<StaffImportReport>('import_staff_csv', { csvText, mode, dryRun })
+
+export const exportStationBundle = async (targetPath: string, settings: unknown) =>
+	await invoke<BundleManifest>('export_station_bundle', { targetPath, settings })
+export const importStationBundle = async (sourcePath: string) =>
+	await invoke<unknown>('import_station_bundle', { sourcePath })
+
+export const verifyStore = async (repair: boolean) =>
+	await invoke<ConsistencyReport>('verify_store', { repair })
+
+export const addStaffQualification = async (staffId: string, qualification: Qualification) =>
+	await invoke<string>('add_staff_qualification', { staffId, qualification })
+export const updateStaffQualification = async (staffId: string, qualification: Qualification) =>
+	await invoke('update_staff_qualification', { staffId, qualification })
+export const removeStaffQualification = async (staffId: string, qualificationId: string) =>
+	await invoke('remove_staff_qualification', { staffId, qualificationId })
+export const getExpiringQualifications = async () =>
+	await invoke<QualificationExpiry[]>('get_expiring_qualifications')
+export const getQualifiedStaff = async (kind: QualificationKind) =>
+	await invoke<string[]>('get_qualified_staff', { kind })
+export const recommendDispatch = async (occurrenceId: string) =>
+	await invoke<DispatchRecommendation>('recommend_dispatch', { occurrenceId })
+
+export const addStaffLeave = async (staffId: string, leavePeriod: LeavePeriod) =>
+	await invoke<string>('add_staff_leave', { staffId, leavePeriod })
+export const updateStaffLeave = async (staffId: string, leavePeriod: LeavePeriod) =>
+	await invoke('update_staff_leave', { staffId, leavePeriod })
+export const removeStaffLeave = async (staffId: string, leavePeriodId: string) =>
+	await invoke('remove_staff_leave', { staffId, leavePeriodId })
+
+// Checked hourly by the backend, only sent while something is about to expire
+export const qualificationsExpiring$ = new Subject<QualificationExpiry[]>()
+listen<QualificationExpiry[]>('firefight://qualifications_expiring', ({ payload }) => {
+	qualificationsExpiring$.next(payload)
+})
+
+export const setCrewRequirement = async (crewRequirement: CrewRequirement) =>
+	await invoke<string>('set_crew_requirement', { crewRequirement })
+export const deleteCrewRequirement = async (crewRequirementId: string) =>
+	await invoke('delete_crew_requirement', { crewRequirementId })
+
+export const setShiftTemplate = async (shiftTemplate: ShiftTemplate) =>
+	await invoke<string>('set_shift_template', { shiftTemplate })
+export const deleteShiftTemplate = async (shiftTemplateId: string) =>
+	await invoke('delete_shift_template', { shiftTemplateId })
+export const applyShiftTemplate = async (shiftTemplateId: string) =>
+	await invoke('apply_shift_template', { shiftTemplateId })
+export const getShiftCalendar = async (fromTime?: number, toTime?: number) =>
+	await invoke<ShiftHandover[]>('get_shift_calendar', { fromTime, toTime })
+
+// Sent by the backend scheduler when a template takes over at its handover time
+export const shiftHandover$ = new Subject<ShiftHandover>()
+listen<ShiftHandover>('firefight://shift_handover', ({ payload }) => {
+	shiftHandover$.next(payload)
+})
+
+// Explicit transfers and resources a new occurrence took over from another one
+export const resourcesTransferred$ = new Subject<ResourceTransfer>()
+listen<ResourceTransfer>('firefight://resources_transferred', ({ payload }) => {
+	resourcesTransferred$.next(payload)
+})
+
+export const setOperatorSecret = async (staffId: string, secret: string) =>
+	await invoke('set_operator_secret', { staffId, secret })
+export const removeOperatorSecret = async (staffId: string) =>
+	await invoke('remove_operator_secret', { staffId })
+export const login = async (staffId: string, secret: string) =>
+	await invoke<OperatorSession>('login', { staffId, secret })
+export const logout = async () => await invoke('logout')
+
+// Shared by every window, the backend announces each login and logout
+export const operator$ = new BehaviorSubject<OperatorSession | null>(null)
+listen<OperatorSession | null>('firefight://operator_changed', ({ payload }) => {
+	operator$.next(payload)
+})
+invoke<OperatorSession | null>('get_operator')
+	.then((session) => {
+		operator$.next(session)
+	})
+	.catch(console.error)
+
+export const listBackups = async () => await invoke<BackupInfo[]>('list_backups')
+export const restoreBackup = async (backupName: string) =>
+	await invoke('restore_backup', { backupName })
+
 export const activeOccurrences$ = store$.pipe(
 	map((state) => state.activeOccurrences),
 	distinctUntilChanged()
@@ -90,3 +274,7 @@ export const vehicles$ = store$.pipe(
 	map((state) => state.vehicles),
 	distinctUntilChanged()
 )
+export const shiftTemplates$ = store$.pipe(
+	map((state) => state.shiftTemplates),
+	distinctUntilChanged()
+)
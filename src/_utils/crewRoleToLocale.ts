@@ -0,0 +1,15 @@
+import { CrewRole } from '../_consts/native'
+import { LanguagePack } from '../_state/lang'
+
+export const crewRoleToLocale = (role: CrewRole, languageData: LanguagePack): string => {
+	switch (role) {
+	case CrewRole.Chief:
+		return languageData['crew_role.chief']
+	case CrewRole.Driver:
+		return languageData['crew_role.driver']
+	case CrewRole.Crew:
+		return languageData['crew_role.crew']
+	default:
+		return ''
+	}
+}
@@ -0,0 +1,13 @@
+import { convertFileSrc } from '@tauri-apps/api/tauri'
+
+export type ImageSize = 'original' | 'card' | 'tag'
+
+// Stored images are references served by the backend, data URLs are previews not yet saved
+export const imageUrl = (image: string | undefined, size: ImageSize): string | undefined => {
+	const value = image?.trim()
+	if (!value || value.startsWith('data:')) {
+		return value
+	}
+
+	return convertFileSrc(`${value}_${size}`, 'firefight-image')
+}
@@ -1,6 +1,7 @@
 import { invoke } from '@tauri-apps/api'
+import { type CrewAssignment } from '../_consts/native'
 
-export const sendOccurrenceAlert = (occurrenceId: string, vehicleAssignmentMap: Record<string, string[]>) => {
+export const sendOccurrenceAlert = (occurrenceId: string, vehicleAssignmentMap: Record<string, CrewAssignment[]>) => {
 	invoke('alert', { occurrenceId, vehicleAssignmentMap })
 		.catch(console.error)
 }
\ No newline at end of file
@@ -0,0 +1,22 @@
+import { VehicleCategory } from '../_consts/native'
+import { LanguagePack } from '../_state/lang'
+
+export const vehicleCategoryToLocale = (category: VehicleCategory, languageData: LanguagePack): string => {
+	switch (category) {
+	case VehicleCategory.Ambulances:
+		return languageData['vehicle_category.ambulances']
+	case VehicleCategory.Command:
+		return languageData['vehicle_category.command']
+	case VehicleCategory.FireFight:
+		return languageData['vehicle_category.fireFight']
+	case VehicleCategory.Support:
+		return languageData['vehicle_category.support']
+	case VehicleCategory.Trailers:
+		return languageData['vehicle_category.trailers']
+	case VehicleCategory.Vessels:
+		return languageData['vehicle_category.vessels']
+	case VehicleCategory.Unknown:
+	default:
+		return languageData['terms.not_defined']
+	}
+}
@@ -0,0 +1,82 @@
+import { type FunctionComponent } from 'react'
+import { Button } from '../../_components/Button'
+import { type DispatchPlanEntry, VehicleCategory } from '../../_consts/native'
+import { useLanguageStore } from '../../_state/lang'
+import { vehicleCategoryToLocale } from '../../_utils/vehicleCategoryToLocale'
+
+const categoryOptions = [
+	VehicleCategory.FireFight,
+	VehicleCategory.Ambulances,
+	VehicleCategory.Command,
+	VehicleCategory.Support,
+	VehicleCategory.Trailers,
+	VehicleCategory.Vessels,
+	VehicleCategory.Unknown
+]
+
+type DispatchPlanEntryRowProps = {
+  entry: DispatchPlanEntry
+  index: number
+  onChange: (index: number, entry: DispatchPlanEntry) => void
+  onRemove: (index: number) => void
+}
+
+export const DispatchPlanEntryRow: FunctionComponent<DispatchPlanEntryRowProps> = ({
+	entry,
+	index,
+	onChange,
+	onRemove
+}) => {
+	const { languageData } = useLanguageStore()
+
+	const onCategoryChange = (e: React.ChangeEvent<HTMLSelectElement>) => {
+		onChange(index, { ...entry, category: e.target.value as VehicleCategory })
+	}
+
+	const onCountChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+		const count = parseInt(e.target.value, 10)
+		onChange(index, { ...entry, count: isNaN(count) ? 1 : Math.max(1, count) })
+	}
+
+	const onMinCapacityChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+		const minCapacity = parseInt(e.target.value, 10)
+		onChange(index, { ...entry, minCapacity: isNaN(minCapacity) ? undefined : minCapacity })
+	}
+
+	const onRemoveClick = () => {
+		onRemove(index)
+	}
+
+	return (
+		<div className="flex flex-row items-center mt-2 space-x-2">
+			<select
+				className="flex-1 bg-background text-action p-2 rounded border border-[#000]/50"
+				onChange={onCategoryChange}
+				value={entry.category}
+			>
+				{categoryOptions.map((category) => (
+					<option key={category} value={category}>
+						{vehicleCategoryToLocale(category, languageData)}
+					</option>
+				))}
+			</select>
+			<input
+				className="w-20 bg-background text-action p-2 rounded border border-[#000]/50"
+				min={1}
+				onChange={onCountChange}
+				placeholder={languageData['manage_occurrences.dispatch_plan.count']}
+				type="number"
+				value={entry.count}
+			/>
+			<input
+				className="w-40 bg-background text-action p-2 rounded border border-[#000]/50"
+				min={0}
+				onChange={onMinCapacityChange}
+				placeholder={languageData['manage_occurrences.dispatch_plan.min_capacity']}
+				type="number"
+				value={entry.minCapacity ?? ''}
+			/>
+			<Button onClick={onRemoveClick}>{languageData['terms.remove']}</Button>
+		</div>
+	)
+}
@@ -0,0 +1,25 @@
+import { CustomCellRendererProps } from 'ag-grid-react'
+import { FunctionComponent } from 'react'
+import { Button } from '../../_components/Button'
+import { Occurrence } from '../../_consts/native'
+import { useLanguageStore } from '../../_state/lang'
+
+export type OccurrenceGridContext = {
+  onEdit: (occurrenceId: string) => void
+}
+
+export const EditOccurrenceButton: FunctionComponent<CustomCellRendererProps<Occurrence, unknown, OccurrenceGridContext>> = (
+	props
+) => {
+	const { languageData } = useLanguageStore()
+
+	const onClick = () => {
+		props.context.onEdit(props.data!.internalId)
+	}
+
+	return (
+		<Button className="min-h-0 h-full px-0 py-0" onClick={onClick}>
+			{languageData['manage_occurrences.dispatch_plan']}
+		</Button>
+	)
+}
@@ -1,32 +1,62 @@
-import { type FunctionComponent, useState } from 'react'
+import { type FunctionComponent, useEffect, useState } from 'react'
+import { useObservable } from 'react-use'
 import { Button } from '../../_components/Button'
 import { useEscapeKey } from '../../_utils/useEscapeKey'
-import { Occurrence } from '../../_consts/native'
-import { createOccurrence$ } from '../../_state/store'
+import { type DispatchPlanEntry, Occurrence, VehicleCategory } from '../../_consts/native'
+import { createOccurrence$, occurrences$, updateOccurrence$ } from '../../_state/store'
 import { useLanguageStore } from '../../_state/lang'
+import { DispatchPlanEntryRow } from './DispatchPlanEntryRow'
 
 type OcurrencePanelProps = {
+  internalId?: string
   onClose: () => void
 }
 
-export const OcurrenceTypePanel: FunctionComponent<OcurrencePanelProps> = ({ onClose }) => {
+export const OcurrenceTypePanel: FunctionComponent<OcurrencePanelProps> = ({ internalId, onClose }) => {
 	const { languageData } = useLanguageStore()
+	const occurrenceMap = useObservable(occurrences$, {})
 
 	const [name, setName] = useState('')
 	const onNameChange = (e: React.ChangeEvent<HTMLInputElement>) => {
 		setName(e.target.value)
 	}
 
+	const [dispatchPlan, setDispatchPlan] = useState<DispatchPlanEntry[]>([])
+	const onAddPlanEntry = () => {
+		setDispatchPlan([...dispatchPlan, { category: VehicleCategory.FireFight, count: 1 }])
+	}
+	const onChangePlanEntry = (index: number, entry: DispatchPlanEntry) => {
+		setDispatchPlan(dispatchPlan.map((planEntry, planIndex) => (planIndex === index ? entry : planEntry)))
+	}
+	const onRemovePlanEntry = (index: number) => {
+		setDispatchPlan(dispatchPlan.filter((_, planIndex) => planIndex !== index))
+	}
+
+	useEffect(() => {
+		const occurrence = internalId ? occurrenceMap[internalId] : undefined
+		if (!occurrence) {
+			return
+		}
+
+		setName(occurrence.name)
+		setDispatchPlan(occurrence.dispatchPlan ?? [])
+	}, [internalId])
+
 	const canSave = Boolean(name.trim())
 
 	const onSave = () => {
 		const ocurrence = {
-			internalId: '',
-			image: '',
-			name
+			internalId: internalId ?? '',
+			image: (internalId && occurrenceMap[internalId]?.image) || '',
+			name,
+			dispatchPlan
 		} satisfies Occurrence
 
-		createOccurrence$.next(ocurrence)
+		if (internalId) {
+			updateOccurrence$.next(ocurrence)
+		} else {
+			createOccurrence$.next(ocurrence)
+		}
 		onClose()
 	}
 
@@ -37,7 +67,11 @@ export const OcurrenceTypePanel: FunctionComponent<OcurrencePanelProps> = ({ onC
 			<div className="absolute top-0 left-0 w-full h-full backdrop-blur-md" />
 
 			<div className="flex flex-col bg-[#000] text-primary p-5 rounded-xl z-10 w-full max-w-2xl max-h-[calc(100vh-50px)] overflow-y-auto">
-				<div className="text-2xl font-extrabold">{languageData['manage_occurrences.new_occurrence']}</div>
+				<div className="text-2xl font-extrabold">
+					{internalId
+						? languageData['manage_occurrences.edit_occurrence']
+						: languageData['manage_occurrences.new_occurrence']}
+				</div>
 				<label className="mt-5 text-action">{languageData['terms.name']}</label>
 				<input
 					className="bg-background text-action mt-1 p-2 rounded border border-[#000]/50"
@@ -46,10 +80,27 @@ export const OcurrenceTypePanel: FunctionComponent<OcurrencePanelProps> = ({ onC
 					value={name}
 				/>
 
+				<label className="mt-5 text-action">{languageData['manage_occurrences.dispatch_plan']}</label>
+				{dispatchPlan.length === 0 && (
+					<div className="mt-1 text-action/75">{languageData['manage_occurrences.dispatch_plan.empty']}</div>
+				)}
+				{dispatchPlan.map((entry, index) => (
+					<DispatchPlanEntryRow
+						key={index}
+						entry={entry}
+						index={index}
+						onChange={onChangePlanEntry}
+						onRemove={onRemovePlanEntry}
+					/>
+				))}
+				<div className="flex flex-row mt-2">
+					<Button onClick={onAddPlanEntry}>{languageData['manage_occurrences.dispatch_plan.add']}</Button>
+				</div>
+
 				<div className="flex flex-row justify-center mt-10 space-x-5">
 					<Button onClick={onClose}>{languageData['terms.cancel']}</Button>
 					<Button disabled={!canSave} onClick={onSave}>
-						{languageData['terms.create']}
+						{internalId ? languageData['terms.save'] : languageData['terms.create']}
 					</Button>
 				</div>
 			</div>
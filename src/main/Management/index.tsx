@@ -17,6 +17,7 @@ import { Button } from '../../_components/Button'
 import { Modal } from '../../_components/Modal'
 import { useLanguageStore } from '../../_state/lang'
 import { DeleteOccurrenceButton } from './DeleteOcurrenceButton'
+import { EditOccurrenceButton, type OccurrenceGridContext } from './EditOccurrenceButton'
 import { OcurrenceTypePanel } from './OcurrenceTypePanel'
 
 ModuleRegistry.registerModules([ClientSideRowModelModule])
@@ -26,6 +27,15 @@ const getRowId: GetRowIdFunc<Occurrence> = (row) => row.data.internalId
 const occurrenceColumnDefs: Array<ColDef> = [
 	{ field: 'internalId', headerName: 'Id Interno', width: 300, resizable: false, editable: false, sortable: false },
 	{ field: 'name', headerName: 'Nome', flex: 1, resizable: true, editable: true, sort: 'desc' },
+	{
+		field: '',
+		cellClass: 'p-0 pr-[5px]',
+		cellRenderer: EditOccurrenceButton,
+		sortable: false,
+		resizable: false,
+		editable: false,
+		width: 180
+	},
 	{
 		field: '',
 		cellClass: 'p-0 pr-[5px]',
@@ -44,9 +54,23 @@ export const Management = () => {
 	const rowData = useMemo(() => Object.values(ocurrences), [ocurrences])
 
 	const [showForm, setShowForm] = useState(false)
-	const onCreate = setShowForm.bind(null, true)
+	const [editedId, setEditedId] = useState<string>()
+	const onCreate = () => {
+		setEditedId(undefined)
+		setShowForm(true)
+	}
 	const onCloseForm = setShowForm.bind(null, false)
 
+	const gridContext = useMemo<OccurrenceGridContext>(
+		() => ({
+			onEdit: (occurrenceId: string) => {
+				setEditedId(occurrenceId)
+				setShowForm(true)
+			}
+		}),
+		[]
+	)
+
 	const onChange = useCallback((evt: CellValueChangedEvent<Occurrence>) => {
 		updateOccurrence$.next(evt.data)
 	}, [])
@@ -62,6 +86,7 @@ export const Management = () => {
 				<AgGridReact
 					animateRows={false}
 					columnDefs={occurrenceColumnDefs}
+					context={gridContext}
 					getRowId={getRowId}
 					onCellValueChanged={onChange}
 					rowData={rowData}
@@ -70,7 +95,7 @@ export const Management = () => {
 
 			{showForm && (
 				<Modal>
-					<OcurrenceTypePanel onClose={onCloseForm} />
+					<OcurrenceTypePanel internalId={editedId} onClose={onCloseForm} />
 				</Modal>
 			)}
 		</div>
@@ -3,9 +3,10 @@ import { useObservable } from 'react-use'
 import { Header, HeaderSection } from '../../../../_components/Header'
 import { Button } from '../../../../_components/Button'
 import { occurrences$ } from '../../../../_state/store'
-import { ActiveOccurrence, Staff, StaffState, Vehicle, VehicleState } from '../../../../_consts/native'
+import { ActiveOccurrence, CrewAssignment, CrewRole, Staff, StaffState, Vehicle, VehicleState } from '../../../../_consts/native'
 import { sendOccurrenceAlert } from '../../../../_utils/sendAlert'
 import { useLanguageStore } from '../../../../_state/lang'
+import { crewRoleToLocale } from '../../../../_utils/crewRoleToLocale'
 
 type ConfirmOccurrenceProps = {
   activeOccurrence?: ActiveOccurrence
@@ -16,7 +17,7 @@ type ConfirmOccurrenceProps = {
   onPrevious: () => void
   staff: Record<string, Staff>
   staffIds: string[]
-  vehicleAssignmentMap: Record<string, string[]>
+  vehicleAssignmentMap: Record<string, CrewAssignment[]>
   vehicleIds: string[]
   vehicles: Record<string, Vehicle>
 }
@@ -96,6 +97,19 @@ export const ConfirmOccurrence: FunctionComponent<ConfirmOccurrenceProps> = ({
 			</p>
 
 			{hasUnavailableStaff && <p className="text-warning">{languageData['occurrence_wizard.staff_warning']}</p>}
+
+			{vehicleIds.map((vehicleId) => (
+				<p key={vehicleId}>
+					{vehicles[vehicleId]?.label}:&nbsp;
+					{[CrewRole.Chief, CrewRole.Driver]
+						.map((role) => {
+							const assignment = vehicleAssignmentMap[vehicleId]?.find((assignment) => assignment.role === role)
+							return assignment && `${crewRoleToLocale(role, languageData)} ${staff[assignment.staffId]?.label}`
+						})
+						.filter(Boolean)
+						.join(', ')}
+				</p>
+			))}
 		</div>
 	)
 }
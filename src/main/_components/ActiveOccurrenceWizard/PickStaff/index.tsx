@@ -3,17 +3,18 @@ import { Scrollable } from '../../../../_components/Scrollable'
 import { Header, HeaderSection } from '../../../../_components/Header'
 import { Button } from '../../../../_components/Button'
 import { staffSortByOccurrenceState } from '../../../../_utils/staffSort'
-import { Staff, StaffState } from '../../../../_consts/native'
+import { CrewAssignment, CrewRole, Staff, StaffState } from '../../../../_consts/native'
 import { TagGrid } from '../../../../_components/TagGrid'
 import { StaffTag } from '../../../../_components/StaffTag'
 import { useLanguageStore } from '../../../../_state/lang'
+import { crewRoleToLocale } from '../../../../_utils/crewRoleToLocale'
 
 type PickStaffProps = {
   alreadySelectedStaffIds: string[]
   capacity?: number
-  initialValue: string[]
+  initialValue: CrewAssignment[]
   onCancel: () => void
-  onNext: (vehicleId: string, staff: string[]) => void
+  onNext: (vehicleId: string, staff: CrewAssignment[]) => void
   onPrevious: () => void
   staff: Record<string, Staff>
   vehicleId: string
@@ -43,19 +44,31 @@ export const PickStaff: FunctionComponent<PickStaffProps> = ({
 		return entries
 	}, [alreadySelectedStaffIds, staff])
 
-	const [selected, setSelected] = useState<string[]>(initialValue)
+	const [selected, setSelected] = useState<CrewAssignment[]>(initialValue)
 	useEffect(() => setSelected(initialValue), [initialValue])
 
-	const onSelect = (vehicleId: string) => {
+	const [activeRole, setActiveRole] = useState(CrewRole.Crew)
+
+	const onSelect = (staffId: string) => {
 		setSelected((prevSelected) => {
-			const foundIdx = prevSelected.indexOf(vehicleId)
-			if (foundIdx >= 0) {
-				const nextSelected = prevSelected.slice()
-				nextSelected.splice(foundIdx, 1)
-				return nextSelected
+			const found = prevSelected.find((assignment) => assignment.staffId === staffId)
+			if (found?.role === activeRole) {
+				return prevSelected.filter((assignment) => assignment.staffId !== staffId)
+			}
+
+			// A vehicle has a single chief and driver, whoever held the role goes back to the crew
+			const nextSelected = prevSelected.map((assignment) =>
+				activeRole !== CrewRole.Crew && assignment.role === activeRole
+					? { ...assignment, role: CrewRole.Crew }
+					: assignment
+			)
+			if (found) {
+				return nextSelected.map((assignment) =>
+					assignment.staffId === staffId ? { ...assignment, role: activeRole } : assignment
+				)
 			}
 
-			return [...prevSelected, vehicleId]
+			return [...nextSelected, { staffId, role: activeRole }]
 		})
 	}
 
@@ -71,6 +84,15 @@ export const PickStaff: FunctionComponent<PickStaffProps> = ({
 					{capacity && ` (${selected.length}/${capacity})`}
 				</label>
 
+				<HeaderSection>
+					<label>{languageData['occurrence_wizard.assign_as']}</label>
+					{[CrewRole.Chief, CrewRole.Driver, CrewRole.Crew].map((role) => (
+						<Button key={role} active={activeRole === role} onClick={setActiveRole.bind(null, role)}>
+							{crewRoleToLocale(role, languageData)}
+						</Button>
+					))}
+				</HeaderSection>
+
 				<HeaderSection>
 					{onPrevious && <Button onClick={onPrevious}>{languageData['terms.back']}</Button>}
 					<Button onClick={onNext.bind(null, vehicleId, selected)}>{languageData['terms.next']}</Button>
@@ -79,18 +101,23 @@ export const PickStaff: FunctionComponent<PickStaffProps> = ({
 
 			<Scrollable>
 				<TagGrid>
-					{sortedStaff.map((staff, index) => (
-						<StaffTag
-							key={staff.internalId}
-							index={index}
-							label={staff.label}
-							internalId={staff.internalId}
-							onClick={onSelect}
-							rank={staff.rank}
-							selected={selected.includes(staff.internalId)}
-							state={staff.state}
-						/>
-					))}
+					{sortedStaff.map((staff, index) => {
+						const assignment = selected.find(({ staffId }) => staffId === staff.internalId)
+
+						return (
+							<StaffTag
+								key={staff.internalId}
+								caption={assignment && crewRoleToLocale(assignment.role, languageData)}
+								index={index}
+								label={staff.label}
+								internalId={staff.internalId}
+								onClick={onSelect}
+								rank={staff.rank}
+								selected={assignment !== undefined}
+								state={staff.state}
+							/>
+						)
+					})}
 				</TagGrid>
 			</Scrollable>
 		</div>
@@ -0,0 +1,69 @@
+import { type FunctionComponent, useMemo, useState, useEffect } from 'react'
+import { Scrollable } from '../../../../_components/Scrollable'
+import { Header, HeaderSection } from '../../../../_components/Header'
+import { Button } from '../../../../_components/Button'
+import { CardGrid } from '../../../../_components/CardGrid'
+import { TeamCard } from '../../../../_components/TeamCard'
+import { teamSortByOcurrenceState } from '../../../../_utils/teamSort'
+import { Team, TeamState } from '../../../../_consts/native'
+import { useLanguageStore } from '../../../../_state/lang'
+
+type PickTeamsProps = {
+  initialValue: string[]
+  onCancel: () => void
+  onNext: (value: string[]) => void
+  onPrevious: () => void
+  teams: Record<string, Team>
+}
+
+export const PickTeams: FunctionComponent<PickTeamsProps> = ({ initialValue, onCancel, onPrevious, onNext, teams }) => {
+	const { languageData } = useLanguageStore()
+
+	// Members of the picked teams are dispatched with them
+	const sortedTeams = useMemo(() => {
+		const entries = Object.values(teams).filter(
+			(team) => team.state === TeamState.Available || team.state === TeamState.Dispatched
+		)
+		entries.sort(teamSortByOcurrenceState)
+		return entries
+	}, [teams])
+
+	const [selected, setSelected] = useState<string[]>(initialValue)
+	useEffect(() => setSelected(initialValue), [initialValue])
+
+	const onSelect = (teamId: string) => {
+		setSelected((prevSelected) =>
+			prevSelected.includes(teamId) ? prevSelected.filter((id) => id !== teamId) : [...prevSelected, teamId]
+		)
+	}
+
+	return (
+		<div className="w-full text-action flex flex-col overflow-hidden">
+			<Header className="px-0 pt-0 mb-5">
+				<HeaderSection>
+					<Button onClick={onCancel}>{languageData['terms.cancel']}</Button>
+				</HeaderSection>
+
+				<HeaderSection>
+					<Button onClick={onPrevious}>{languageData['terms.back']}</Button>
+					<Button onClick={onNext.bind(null, selected)}>{languageData['terms.next']}</Button>
+				</HeaderSection>
+			</Header>
+
+			<Scrollable>
+				<CardGrid small>
+					{sortedTeams.map((team) => (
+						<TeamCard
+							key={team.internalId}
+							internalId={team.internalId}
+							label={team.label}
+							onClick={onSelect}
+							selected={selected.includes(team.internalId)}
+							state={team.state}
+						/>
+					))}
+				</CardGrid>
+			</Scrollable>
+		</div>
+	)
+}
@@ -10,6 +10,7 @@ import { useLanguageStore } from '../../../../_state/lang'
 
 type PickVehiclesProps = {
   initialValue: string[]
+  notice?: string
   onCancel: () => void
   onNext: (value: string[]) => void
   onPrevious?: () => void
@@ -18,6 +19,7 @@ type PickVehiclesProps = {
 
 export const PickVehicles: FunctionComponent<PickVehiclesProps> = ({
 	initialValue,
+	notice,
 	onCancel,
 	onPrevious,
 	onNext,
@@ -62,6 +64,8 @@ export const PickVehicles: FunctionComponent<PickVehiclesProps> = ({
 				</HeaderSection>
 			</Header>
 
+			{notice && <div className="mb-5 text-lg font-bold">{notice}</div>}
+
 			<Scrollable>
 				<TagGrid>
 					{sortedVehicles.map((vehicle, index) => (
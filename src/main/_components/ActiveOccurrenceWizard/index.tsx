@@ -3,16 +3,20 @@ import { useObservable } from 'react-use'
 import {
   activeOccurrences$,
   createActiveOccurrence$,
+  recommendDispatch,
   staff$,
+  teams$,
   updateActiveOccurrence$,
   vehicles$
 } from '../../../_state/store'
 import { FullscreenOverlay } from '../../../_components/FullScreenOverlay'
 import { useEscapeKey } from '../../../_utils/useEscapeKey'
-import { OccurrenceInfo, Vehicle } from '../../../_consts/native'
+import { useLanguageStore } from '../../../_state/lang'
+import { CrewAssignment, OccurrenceInfo, Vehicle } from '../../../_consts/native'
 import { PickOccurrence } from './PickOccurrence'
 import { PickVehicles } from './PickVehicles'
 import { PickStaff } from './PickStaff'
+import { PickTeams } from './PickTeams'
 import { ConfirmOccurrence } from './ConfirmOccurrence'
 import { InformationPanel } from './InformationPanel'
 
@@ -21,6 +25,7 @@ enum Section {
   Occurrence,
   Information,
   Staff,
+  Teams,
   Vehicles
 }
 
@@ -45,16 +50,21 @@ const getVehicleWithCapacityIdx = (
   return nextIdx
 }
 
+const getAssignedStaffIds = (vehicleAssignmentMap: Record<string, CrewAssignment[]>): string[] =>
+  Array.from(new Set(Object.values(vehicleAssignmentMap).flat().map(({ staffId }) => staffId)))
+
 type ActiveOccurrenceWizardProps = {
   internalId?: string
   onClose: () => void
 }
 
 export const ActiveOccurrenceWizard: FunctionComponent<ActiveOccurrenceWizardProps> = ({ internalId, onClose }) => {
+  const { languageData } = useLanguageStore()
   const [activeSection, setActiveSection] = useState(internalId ? Section.Vehicles : Section.Information)
 
   const staffMap = useObservable(staff$, {})
   const vehicleMap = useObservable(vehicles$, {})
+  const teamMap = useObservable(teams$, {})
 
   const [ocurrenceInfo, setOccurrenceInfo] = useState<OccurrenceInfo>({
     address: '',
@@ -67,7 +77,8 @@ export const ActiveOccurrenceWizard: FunctionComponent<ActiveOccurrenceWizardPro
   const [occurrenceId, setOccurrenceId] = useState('')
   const [vehicleIds, setVehicleIds] = useState<string[]>([])
   const [staffIds, setStaffIds] = useState<string[]>([])
-  const [vehicleAssignmentMap, setVehicleAssignmentMap] = useState<Record<string, string[]>>({})
+  const [teamIds, setTeamIds] = useState<string[]>([])
+  const [vehicleAssignmentMap, setVehicleAssignmentMap] = useState<Record<string, CrewAssignment[]>>({})
 
   // Initial Values
   const activeOccurrenceMap = useObservable(activeOccurrences$, {})
@@ -78,6 +89,7 @@ export const ActiveOccurrenceWizard: FunctionComponent<ActiveOccurrenceWizardPro
       setVehicleAssignmentMap(activeOccurrence.vehicleAssignmentMap)
       setVehicleIds(activeOccurrence.vehicleIds)
       setStaffIds(activeOccurrence.staffIds)
+      setTeamIds(activeOccurrence.teamIds ?? [])
     }
   }, [activeOccurrence])
 
@@ -89,10 +101,32 @@ export const ActiveOccurrenceWizard: FunctionComponent<ActiveOccurrenceWizardPro
   }, [])
 
   const onOccurrencePrev = useCallback(setActiveSection.bind(null, Section.Information), [])
-  const onOccurrenceNext = useCallback((occurrenceId: string) => {
-    setOccurrenceId(occurrenceId)
-    setActiveSection(Section.Vehicles)
-  }, [])
+  const [vehiclesNotice, setVehiclesNotice] = useState<string>()
+  const onOccurrenceNext = useCallback(
+    (nextOccurrenceId: string) => {
+      setOccurrenceId(nextOccurrenceId)
+      setActiveSection(Section.Vehicles)
+
+      // Start from the dispatch plan of the occurrence type, unless the operator already picked vehicles for it
+      if (nextOccurrenceId === occurrenceId && vehicleIds.length > 0) {
+        return
+      }
+
+      recommendDispatch(nextOccurrenceId)
+        .then((recommendation) => {
+          setVehicleIds(recommendation.vehicleIds)
+          setVehicleAssignmentMap(recommendation.vehicleAssignmentMap)
+          setStaffIds(recommendation.staffIds)
+          setVehiclesNotice(
+            recommendation.missingVehicles.length > 0
+              ? languageData['dispatch_recommendation.missing_vehicles']
+              : undefined
+          )
+        })
+        .catch(console.error)
+    },
+    [occurrenceId, vehicleIds, languageData]
+  )
 
   const onVehiclesPrev = useCallback(setActiveSection.bind(null, Section.Occurrence), [])
   const onVehiclesNext = useCallback(
@@ -110,36 +144,42 @@ export const ActiveOccurrenceWizard: FunctionComponent<ActiveOccurrenceWizardPro
 
       setVehicleIds(vehicleIds)
 
-      const nextVehicleAssignmentMap = vehicleIds.reduce((acc: Record<string, string[]>, vehicleId) => {
+      const nextVehicleAssignmentMap = vehicleIds.reduce((acc: Record<string, CrewAssignment[]>, vehicleId) => {
         acc[vehicleId] = vehicleAssignmentMap[vehicleId] ?? []
         return acc
       }, {})
       setVehicleAssignmentMap(nextVehicleAssignmentMap)
 
-      const nextStaffIds = Array.from(new Set(Object.values<string[]>(nextVehicleAssignmentMap!).flat()))
+      const nextStaffIds = getAssignedStaffIds(nextVehicleAssignmentMap)
       setStaffIds(nextStaffIds)
 
       setVehicleIdx(nextVehicleIdx)
-      setActiveSection(Section.Staff)
+      setActiveSection(Section.Teams)
     },
     [vehicleMap, vehicleAssignmentMap]
   )
 
+  const onTeamsPrev = useCallback(setActiveSection.bind(null, Section.Vehicles), [])
+  const onTeamsNext = useCallback((teamIds: string[]) => {
+    setTeamIds(teamIds)
+    setActiveSection(Section.Staff)
+  }, [])
+
   const onStaffPrev = useCallback(() => {
     const nextVehicleIdx = getVehicleWithCapacityIdx(vehicleMap, vehicleIds, vehicleIdx - 1, -1)
     if (nextVehicleIdx >= 0) {
       setVehicleIdx(nextVehicleIdx)
     } else {
-      setActiveSection(Section.Vehicles)
+      setActiveSection(Section.Teams)
     }
   }, [vehicleIdx])
 
   const onStaffNext = useCallback(
-    (vehicleId: string, staff: string[]) => {
+    (vehicleId: string, staff: CrewAssignment[]) => {
       const nextVehicleAssignmentMap = { ...vehicleAssignmentMap, [vehicleId]: staff }
       setVehicleAssignmentMap(nextVehicleAssignmentMap)
 
-      const nextStaffIds = Array.from(new Set(Object.values<string[]>(nextVehicleAssignmentMap!).flat()))
+      const nextStaffIds = getAssignedStaffIds(nextVehicleAssignmentMap)
       setStaffIds(nextStaffIds)
 
       const nextVehicleIdx = getVehicleWithCapacityIdx(vehicleMap, vehicleIds, vehicleIdx + 1, +1)
@@ -161,6 +201,7 @@ export const ActiveOccurrenceWizard: FunctionComponent<ActiveOccurrenceWizardPro
       internalId: internalId ?? '',
       occurrenceId,
       staffIds,
+      teamIds,
       vehicleAssignmentMap,
       vehicleIds
     })
@@ -173,6 +214,7 @@ export const ActiveOccurrenceWizard: FunctionComponent<ActiveOccurrenceWizardPro
     .fill(null)
     .map((_, idx) => vehicleAssignmentMap[vehicleIds[idx]])
     .flat()
+    .map(({ staffId }) => staffId)
 
   return (
     <FullscreenOverlay className="flex flex-col justify-center items-center">
@@ -195,6 +237,7 @@ export const ActiveOccurrenceWizard: FunctionComponent<ActiveOccurrenceWizardPro
         {activeSection === Section.Vehicles && (
           <PickVehicles
             initialValue={vehicleIds}
+            notice={vehiclesNotice}
             onCancel={onClose}
             onNext={onVehiclesNext}
             onPrevious={internalId ? undefined : onVehiclesPrev}
@@ -202,6 +245,16 @@ export const ActiveOccurrenceWizard: FunctionComponent<ActiveOccurrenceWizardPro
           />
         )}
 
+        {activeSection === Section.Teams && (
+          <PickTeams
+            initialValue={teamIds}
+            onCancel={onClose}
+            onNext={onTeamsNext}
+            onPrevious={onTeamsPrev}
+            teams={teamMap}
+          />
+        )}
+
         {activeSection === Section.Staff && (
           <PickStaff
             alreadySelectedStaffIds={selectedThusFar}
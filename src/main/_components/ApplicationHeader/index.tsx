@@ -1,9 +1,11 @@
 import { useObservable } from 'react-use'
-import { type MouseEventHandler } from 'react'
+import { type MouseEventHandler, useState } from 'react'
 import { Button } from '../../../_components/Button'
 import { View, activeView$, changeView$, openSettings$ } from '../../../_state/view'
 import { Header, HeaderSection } from '../../../_components/Header'
 import { useLanguageStore } from '../../../_state/lang'
+import { logout, operator$, staff$ } from '../../../_state/store'
+import { LoginPanel } from '../LoginPanel'
 
 const onOverview = () => {
 	changeView$.next(View.Overview)
@@ -25,6 +27,10 @@ const onManagement = () => {
 	changeView$.next(View.Management)
 }
 
+const onLogout = () => {
+	logout().catch(console.error)
+}
+
 const onSettings: MouseEventHandler = ({ clientX, clientY }) => {
 	openSettings$.next({ left: clientX, top: clientY })
 }
@@ -32,6 +38,18 @@ const onSettings: MouseEventHandler = ({ clientX, clientY }) => {
 export const ApplicationHeader = () => {
 	const { languageData } = useLanguageStore()
 	const viewMode = useObservable(activeView$)
+	const operator = useObservable(operator$)
+	const staffMap = useObservable(staff$, {})
+	const operatorStaff = operator ? staffMap[operator.staffId] : undefined
+
+	const [showLogin, setShowLogin] = useState(false)
+	const onLogin = () => {
+		setShowLogin(true)
+	}
+
+	const onCloseLogin = () => {
+		setShowLogin(false)
+	}
 
 	return (
 		<Header className="bg-backgroundEmphasis">
@@ -53,7 +71,16 @@ export const ApplicationHeader = () => {
 			<HeaderSection>
 				<Button onClick={onManagement}>{languageData['header.management']}</Button>
 				<Button onClick={onSettings}>{languageData['header.settings']}</Button>
+				{operator ? (
+					<Button onClick={onLogout}>
+						{operatorStaff?.label} - {languageData['header.logout']}
+					</Button>
+				) : (
+					<Button onClick={onLogin}>{languageData['header.login']}</Button>
+				)}
 			</HeaderSection>
+
+			{showLogin && <LoginPanel onClose={onCloseLogin} />}
 		</Header>
 	)
 }
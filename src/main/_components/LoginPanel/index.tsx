@@ -0,0 +1,89 @@
+import { type FunctionComponent, useMemo, useState } from 'react'
+import { useObservable } from 'react-use'
+import { FullscreenOverlay } from '../../../_components/FullScreenOverlay'
+import { Button } from '../../../_components/Button'
+import { StaffPermission } from '../../../_consts/native'
+import { login, staff$ } from '../../../_state/store'
+import { useEscapeKey } from '../../../_utils/useEscapeKey'
+import { staffSortByLabel } from '../../../_utils/staffSort'
+import { useLanguageStore } from '../../../_state/lang'
+
+type LoginPanelProps = {
+  onClose: () => void
+}
+
+export const LoginPanel: FunctionComponent<LoginPanelProps> = ({ onClose }) => {
+	const { languageData } = useLanguageStore()
+	const staffMap = useObservable(staff$, {})
+
+	const operators = useMemo(() => {
+		const entries = Object.values(staffMap).filter((staff) => staff.permission !== StaffPermission.None)
+		entries.sort(staffSortByLabel)
+		return entries
+	}, [staffMap])
+
+	const [staffId, setStaffId] = useState('')
+	const onStaffIdChange = (e: React.ChangeEvent<HTMLSelectElement>) => {
+		setStaffId(e.target.value)
+	}
+
+	const [secret, setSecret] = useState('')
+	const onSecretChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+		setSecret(e.target.value)
+	}
+
+	const [failed, setFailed] = useState(false)
+
+	const onLogin = () => {
+		login(staffId, secret)
+			.then(onClose)
+			.catch((err) => {
+				console.error(err)
+				setSecret('')
+				setFailed(true)
+			})
+	}
+
+	useEscapeKey(onClose)
+
+	return (
+		<FullscreenOverlay className="flex flex-col justify-center items-center">
+			<div className="absolute top-0 left-0 w-full h-full backdrop-blur-sm" />
+
+			<div className="flex flex-col bg-[#000] rounded-xl z-10 w-full max-w-md p-5 pb-10">
+				<div className="text-action text-2xl font-extrabold">{languageData['login.title']}</div>
+
+				<label className="mt-5 text-action">{languageData['login.staff']}</label>
+				<select
+					className="bg-background text-action mt-1 p-2 rounded border border-[#000]/50"
+					onChange={onStaffIdChange}
+					value={staffId}
+				>
+					<option value="" />
+					{operators.map((staff) => (
+						<option key={staff.internalId} value={staff.internalId}>
+							{staff.label} - {staff.name}
+						</option>
+					))}
+				</select>
+
+				<label className="mt-5 text-action">{languageData['login.secret']}</label>
+				<input
+					className="bg-background text-action mt-1 p-2 rounded border border-[#000]/50"
+					onChange={onSecretChange}
+					type="password"
+					value={secret}
+				/>
+
+				{failed && <p className="mt-5 text-warning">{languageData['login.invalid_credentials']}</p>}
+
+				<div className="flex flex-row justify-center flex-wrap gap-4 mt-10">
+					<Button onClick={onClose}>{languageData['terms.cancel']}</Button>
+					<Button disabled={!staffId || !secret} onClick={onLogin}>
+						{languageData['header.login']}
+					</Button>
+				</div>
+			</div>
+		</FullscreenOverlay>
+	)
+}
@@ -0,0 +1,18 @@
+import { type FunctionComponent } from 'react'
+import { Button } from '../../../_components/Button'
+import { useLanguageStore } from '../../../_state/lang'
+
+type ReleaseButtonProps = {
+  onRelease: (resourceId: string) => void
+  resourceId: string
+}
+
+export const ReleaseButton: FunctionComponent<ReleaseButtonProps> = ({ onRelease, resourceId }) => {
+	const { languageData } = useLanguageStore()
+
+	const onClick = () => {
+		onRelease(resourceId)
+	}
+
+	return <Button onClick={onClick}>{languageData['occurrence_details.release']}</Button>
+}
@@ -0,0 +1,22 @@
+import classNames from 'classnames'
+import { type FunctionComponent } from 'react'
+import { Button } from '../../../_components/Button'
+
+type ResourceToggleProps = {
+  label: string
+  onToggle: (resourceId: string) => void
+  resourceId: string
+  selected: boolean
+}
+
+export const ResourceToggle: FunctionComponent<ResourceToggleProps> = ({ label, onToggle, resourceId, selected }) => {
+	const onClick = () => {
+		onToggle(resourceId)
+	}
+
+	return (
+		<Button className={classNames({ 'ring-2 ring-actionHighlight': selected })} onClick={onClick}>
+			{label}
+		</Button>
+	)
+}
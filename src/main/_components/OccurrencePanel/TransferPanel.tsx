@@ -0,0 +1,157 @@
+import { type FunctionComponent, useMemo, useState } from 'react'
+import { useObservable } from 'react-use'
+import { FullscreenOverlay } from '../../../_components/FullScreenOverlay'
+import { Button } from '../../../_components/Button'
+import { activeOccurrences$, occurrences$, staff$, teams$, transferResources, vehicles$ } from '../../../_state/store'
+import { useEscapeKey } from '../../../_utils/useEscapeKey'
+import { staffSortByLabel } from '../../../_utils/staffSort'
+import { vehicleSortByLabel } from '../../../_utils/vehicleSort'
+import { useLanguageStore } from '../../../_state/lang'
+import { ResourceToggle } from './ResourceToggle'
+
+type TransferPanelProps = {
+  activeOccurrenceId: string
+  onClose: () => void
+}
+
+const toggleId = (ids: string[], id: string) => (ids.includes(id) ? ids.filter((entry) => entry !== id) : [...ids, id])
+
+export const TransferPanel: FunctionComponent<TransferPanelProps> = ({ activeOccurrenceId, onClose }) => {
+	const { languageData } = useLanguageStore()
+	const activeOccurrenceMap = useObservable(activeOccurrences$, {})
+	const occurrenceMap = useObservable(occurrences$, {})
+	const staffMap = useObservable(staff$, {})
+	const teamMap = useObservable(teams$, {})
+	const vehicleMap = useObservable(vehicles$, {})
+
+	const activeOccurrence = activeOccurrenceMap[activeOccurrenceId]
+	const targetOccurrences = Object.values(activeOccurrenceMap).filter(
+		(entry) => entry.internalId !== activeOccurrenceId
+	)
+
+	const sortedVehicles = useMemo(() => {
+		const entrySet = (activeOccurrence?.vehicleIds ?? []).map((id) => vehicleMap[id]).filter(Boolean)
+		entrySet.sort(vehicleSortByLabel)
+		return entrySet
+	}, [activeOccurrence, vehicleMap])
+
+	const sortedStaff = useMemo(() => {
+		const entrySet = (activeOccurrence?.staffIds ?? []).map((id) => staffMap[id]).filter(Boolean)
+		entrySet.sort(staffSortByLabel)
+		return entrySet
+	}, [activeOccurrence, staffMap])
+
+	const teams = (activeOccurrence?.teamIds ?? []).map((id) => teamMap[id]).filter(Boolean)
+
+	const [targetId, setTargetId] = useState('')
+	const onTargetIdChange = (e: React.ChangeEvent<HTMLSelectElement>) => {
+		setTargetId(e.target.value)
+	}
+
+	const [vehicleIds, setVehicleIds] = useState<string[]>([])
+	const onToggleVehicle = (vehicleId: string) => {
+		setVehicleIds(toggleId(vehicleIds, vehicleId))
+	}
+
+	const [staffIds, setStaffIds] = useState<string[]>([])
+	const onToggleStaff = (staffId: string) => {
+		setStaffIds(toggleId(staffIds, staffId))
+	}
+
+	const [teamIds, setTeamIds] = useState<string[]>([])
+	const onToggleTeam = (teamId: string) => {
+		setTeamIds(toggleId(teamIds, teamId))
+	}
+
+	const onConfirm = () => {
+		// Crews go along with their vehicles and members with their teams
+		transferResources({
+			fromActiveOccurrenceId: activeOccurrenceId,
+			toActiveOccurrenceId: targetId,
+			staffIds,
+			teamIds,
+			vehicleIds,
+			time: 0
+		})
+			.then(onClose)
+			.catch(console.error)
+	}
+
+	useEscapeKey(onClose)
+
+	const isEmpty = vehicleIds.length === 0 && staffIds.length === 0 && teamIds.length === 0
+
+	return (
+		<FullscreenOverlay className="flex flex-col justify-center items-center">
+			<div className="absolute top-0 left-0 w-full h-full backdrop-blur-sm" />
+
+			<div className="flex flex-col bg-[#000] rounded-xl z-10 w-full max-w-3xl p-5 pb-10">
+				<div className="text-action text-2xl font-extrabold">{languageData['transfer.title']}</div>
+
+				<label className="mt-5 text-action">{languageData['transfer.target']}</label>
+				<select
+					className="bg-background text-action mt-1 p-2 rounded border border-[#000]/50"
+					onChange={onTargetIdChange}
+					value={targetId}
+				>
+					<option value="" />
+					{targetOccurrences.map((entry) => (
+						<option key={entry.internalId} value={entry.internalId}>
+							{occurrenceMap[entry.occurrenceId]?.name} {entry.address ? `- ${entry.address}` : ''}
+						</option>
+					))}
+				</select>
+
+				<label className="mt-5 text-action">{languageData['terms.vehicles']}</label>
+				<div className="flex flex-row flex-wrap gap-2 mt-1">
+					{sortedVehicles.map((vehicle) => (
+						<ResourceToggle
+							key={vehicle.internalId}
+							label={vehicle.label}
+							onToggle={onToggleVehicle}
+							resourceId={vehicle.internalId}
+							selected={vehicleIds.includes(vehicle.internalId)}
+						/>
+					))}
+				</div>
+
+				{teams.length > 0 && (
+					<>
+						<label className="mt-5 text-action">{languageData['terms.teams']}</label>
+						<div className="flex flex-row flex-wrap gap-2 mt-1">
+							{teams.map((team) => (
+								<ResourceToggle
+									key={team.internalId}
+									label={team.label}
+									onToggle={onToggleTeam}
+									resourceId={team.internalId}
+									selected={teamIds.includes(team.internalId)}
+								/>
+							))}
+						</div>
+					</>
+				)}
+
+				<label className="mt-5 text-action">{languageData['terms.staff']}</label>
+				<div className="flex flex-row flex-wrap gap-2 mt-1">
+					{sortedStaff.map((staff) => (
+						<ResourceToggle
+							key={staff.internalId}
+							label={staff.label}
+							onToggle={onToggleStaff}
+							resourceId={staff.internalId}
+							selected={staffIds.includes(staff.internalId)}
+						/>
+					))}
+				</div>
+
+				<div className="flex flex-row justify-center flex-wrap gap-4 mt-10">
+					<Button onClick={onClose}>{languageData['terms.cancel']}</Button>
+					<Button disabled={!targetId || isEmpty} onClick={onConfirm}>
+						{languageData['transfer.confirm']}
+					</Button>
+				</div>
+			</div>
+		</FullscreenOverlay>
+	)
+}
@@ -0,0 +1,19 @@
+import { type FunctionComponent } from 'react'
+import { Button } from '../../../_components/Button'
+import { type VehicleStatus } from '../../../_consts/native'
+import { useLanguageStore } from '../../../_state/lang'
+
+type VehicleStatusButtonProps = {
+  onRecord: (status: VehicleStatus) => void
+  status: VehicleStatus
+}
+
+export const VehicleStatusButton: FunctionComponent<VehicleStatusButtonProps> = ({ onRecord, status }) => {
+	const { languageData } = useLanguageStore()
+
+	const onClick = () => {
+		onRecord(status)
+	}
+
+	return <Button onClick={onClick}>{languageData[`vehicle_status.${status}`]}</Button>
+}
@@ -0,0 +1,54 @@
+import { type FunctionComponent } from 'react'
+import { VehicleStatus, type VehicleStatusChange } from '../../../_consts/native'
+import { recordVehicleStatus } from '../../../_state/store'
+import { useLanguageStore } from '../../../_state/lang'
+import { VehicleStatusButton } from './VehicleStatusButton'
+
+// Alerted is recorded by the backend when the vehicle is dispatched
+const reportedStatuses = [
+	VehicleStatus.Departed,
+	VehicleStatus.OnScene,
+	VehicleStatus.TransportingToHospital,
+	VehicleStatus.AtHospital,
+	VehicleStatus.Returning,
+	VehicleStatus.AtBase
+]
+
+type VehicleStatusControlProps = {
+  activeOccurrenceId: string
+  timeline: VehicleStatusChange[]
+  vehicleId: string
+}
+
+export const VehicleStatusControl: FunctionComponent<VehicleStatusControlProps> = ({
+	activeOccurrenceId,
+	timeline,
+	vehicleId
+}) => {
+	const { languageData } = useLanguageStore()
+	const lastChange = timeline[timeline.length - 1]
+
+	const onRecord = (status: VehicleStatus) => {
+		recordVehicleStatus(activeOccurrenceId, vehicleId, status).catch(console.error)
+	}
+
+	return (
+		<div className="flex flex-col gap-2">
+			{lastChange && (
+				<div className="font-extrabold">
+					{languageData[`vehicle_status.${lastChange.status}`]} - {new Date(lastChange.time).toLocaleTimeString()}
+				</div>
+			)}
+
+			{lastChange?.status !== VehicleStatus.AtBase && (
+				<div className="flex flex-row flex-wrap gap-2">
+					{reportedStatuses
+						.filter((status) => status !== lastChange?.status)
+						.map((status) => (
+							<VehicleStatusButton key={status} onRecord={onRecord} status={status} />
+						))}
+				</div>
+			)}
+		</div>
+	)
+}
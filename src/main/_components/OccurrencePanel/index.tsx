@@ -1,6 +1,14 @@
 import { type FunctionComponent, useMemo, useState } from 'react'
 import { useObservable } from 'react-use'
-import { activeOccurrences$, deleteActiveOccurrence$, occurrences$, staff$, vehicles$ } from '../../../_state/store'
+import {
+	activeOccurrences$,
+	closeActiveOccurrence$,
+	occurrences$,
+	releaseResources,
+	staff$,
+	teams$,
+	vehicles$
+} from '../../../_state/store'
 import { Button } from '../../../_components/Button'
 import { Scrollable } from '../../../_components/Scrollable'
 import { staffSortByLabel } from '../../../_utils/staffSort'
@@ -8,12 +16,16 @@ import { vehicleSortByLabel } from '../../../_utils/vehicleSort'
 import { CardGrid } from '../../../_components/CardGrid'
 import { VehicleCard } from '../../../_components/VehicleCard'
 import { StaffCard } from '../../../_components/StaffCard'
+import { TeamCard } from '../../../_components/TeamCard'
 import { Modal } from '../../../_components/Modal'
 import { ActiveOccurrenceWizard } from '../ActiveOccurrenceWizard'
 import { Header, HeaderSection } from '../../../_components/Header'
 import { sendOccurrenceAlert } from '../../../_utils/sendAlert'
 import { ConfirmationPanel } from '../ConfirmationPanel'
 import { useLanguageStore } from '../../../_state/lang'
+import { ReleaseButton } from './ReleaseButton'
+import { TransferPanel } from './TransferPanel'
+import { VehicleStatusControl } from './VehicleStatusControl'
 
 type OccurrencePanelProps = {
   internalId: string
@@ -25,12 +37,14 @@ export const OccurrencePanel: FunctionComponent<OccurrencePanelProps> = ({ inter
 	const activeOccurrenceMap = useObservable(activeOccurrences$, {})
 	const staffMap = useObservable(staff$, {})
 	const vehicleMap = useObservable(vehicles$, {})
+	const teamMap = useObservable(teams$, {})
 	const occurrenceMap = useObservable(occurrences$, {})
 
 	const activeOccurrence = activeOccurrenceMap[internalId] ?? {}
 	const occurrence = occurrenceMap[activeOccurrence.occurrenceId] ?? {}
 	const staffIds = activeOccurrence.staffIds ?? []
 	const vehicleIds = activeOccurrence.vehicleIds ?? []
+	const teams = (activeOccurrence.teamIds ?? []).map((id) => teamMap[id]).filter(Boolean)
 
 	const sortedVehicles = useMemo(() => {
 		// TODO Remove filter once entries removed correctly from ocurrences
@@ -50,16 +64,28 @@ export const OccurrencePanel: FunctionComponent<OccurrencePanelProps> = ({ inter
 		sendOccurrenceAlert(activeOccurrence.occurrenceId, activeOccurrence.vehicleAssignmentMap)
 	}
 
+	// Vehicles go back with their crew, the occurrence stays open with the rest
+	const onReleaseVehicle = (vehicleId: string) => {
+		releaseResources(internalId, { staffIds: [], teamIds: [], vehicleIds: [vehicleId], time: 0 }).catch(console.error)
+	}
+	const onReleaseTeam = (teamId: string) => {
+		releaseResources(internalId, { staffIds: [], teamIds: [teamId], vehicleIds: [], time: 0 }).catch(console.error)
+	}
+	const onReleaseStaff = (staffId: string) => {
+		releaseResources(internalId, { staffIds: [staffId], teamIds: [], vehicleIds: [], time: 0 }).catch(console.error)
+	}
+
 	const [showDeleteConfirmation, setShowDeleteConfirmation] = useState(false)
 	const onDelete = setShowDeleteConfirmation.bind(null, true)
 	const onCancelDeletion = setShowDeleteConfirmation.bind(null, false)
 
 	const onConfirmDeletion = () => {
-		deleteActiveOccurrence$.next(internalId)
+		closeActiveOccurrence$.next(internalId)
 		onClose()
 	}
 
 	const [showCreateOccurrence, setShowCreateOccurrence] = useState(false)
+	const [showTransfer, setShowTransfer] = useState(false)
 
 	return (
 		<div className="absolute top-0 left-0 flex flex-col w-full h-full z-10 select-none bg-background text-primary pb-5">
@@ -73,6 +99,9 @@ export const OccurrencePanel: FunctionComponent<OccurrencePanelProps> = ({ inter
 					<Button onClick={setShowCreateOccurrence.bind(null, true)}>
 						{languageData['occurrence_details.change_occurrence']}
 					</Button>
+					<Button onClick={setShowTransfer.bind(null, true)}>
+						{languageData['occurrence_details.transfer_resources']}
+					</Button>
 					<Button onClick={onDelete}>{languageData['occurrence_details.close_occurrence']}</Button>
 				</HeaderSection>
 			</Header>
@@ -118,32 +147,77 @@ export const OccurrencePanel: FunctionComponent<OccurrencePanelProps> = ({ inter
 
 				<CardGrid small>
 					{sortedVehicles.map((vehicle) => (
-						<VehicleCard
-							key={vehicle.internalId}
-							label={vehicle.label}
-							image={vehicle.image}
-							internalId={vehicle.internalId}
-							small
-							state={vehicle.state}
-						/>
+						<div key={vehicle.internalId} className="flex flex-col gap-2">
+							<VehicleCard
+								label={vehicle.label}
+								image={vehicle.image}
+								internalId={vehicle.internalId}
+								small
+								state={vehicle.state}
+							/>
+							<VehicleStatusControl
+								activeOccurrenceId={internalId}
+								timeline={activeOccurrence.vehicleTimelines?.[vehicle.internalId] ?? []}
+								vehicleId={vehicle.internalId}
+							/>
+							<ReleaseButton onRelease={onReleaseVehicle} resourceId={vehicle.internalId} />
+						</div>
 					))}
 				</CardGrid>
 
+				{teams.length > 0 && (
+					<>
+						<h2 className="px-5 my-5 text-actionHighlight font-extrabold text-2xl">{languageData['terms.teams']}</h2>
+
+						<CardGrid small>
+							{teams.map((team) => (
+								<div key={team.internalId} className="flex flex-col gap-2">
+									<TeamCard internalId={team.internalId} label={team.label} state={team.state} />
+									<ReleaseButton onRelease={onReleaseTeam} resourceId={team.internalId} />
+								</div>
+							))}
+						</CardGrid>
+					</>
+				)}
+
 				<h2 className="px-5 my-5 text-actionHighlight font-extrabold text-2xl">{languageData['terms.staff']}</h2>
 
 				<CardGrid small>
 					{sortedStaff.map((staff) => (
-						<StaffCard
-							key={staff.internalId}
-							label={staff.label}
-							image={staff.image}
-							internalId={staff.internalId}
-							name={staff.name}
-							small
-							state={staff.state}
-						/>
+						<div key={staff.internalId} className="flex flex-col gap-2">
+							<StaffCard
+								label={staff.label}
+								image={staff.image}
+								internalId={staff.internalId}
+								name={staff.name}
+								small
+								state={staff.state}
+							/>
+							<ReleaseButton onRelease={onReleaseStaff} resourceId={staff.internalId} />
+						</div>
 					))}
 				</CardGrid>
+
+				{(activeOccurrence.resourceReleases ?? []).length > 0 && (
+					<>
+						<h2 className="px-5 my-5 text-actionHighlight font-extrabold text-2xl">
+							{languageData['occurrence_details.released']}
+						</h2>
+
+						{activeOccurrence.resourceReleases.map((release) => (
+							<div key={release.time} className="flex flex-row gap-2 px-5">
+								<div className="font-extrabold">{new Date(release.time).toLocaleTimeString()}</div>
+								{[
+									...release.vehicleIds.map((vehicleId) => vehicleMap[vehicleId]?.label),
+									...(release.teamIds ?? []).map((teamId) => teamMap[teamId]?.label),
+									...release.staffIds.map((staffId) => staffMap[staffId]?.label)
+								]
+									.filter(Boolean)
+									.join(', ')}
+							</div>
+						))}
+					</>
+				)}
 			</Scrollable>
 
 			{showCreateOccurrence && (
@@ -152,6 +226,8 @@ export const OccurrencePanel: FunctionComponent<OccurrencePanelProps> = ({ inter
 				</Modal>
 			)}
 
+			{showTransfer && <TransferPanel activeOccurrenceId={internalId} onClose={setShowTransfer.bind(null, false)} />}
+
 			{showDeleteConfirmation && (
 				<Modal>
 					<ConfirmationPanel
@@ -0,0 +1,16 @@
+import { type FunctionComponent } from 'react'
+import { Button } from '../../../_components/Button'
+import { type ShiftTemplate } from '../../../_consts/native'
+
+type ShiftTemplateButtonProps = {
+  onApply: (shiftTemplateId: string) => void
+  shiftTemplate: ShiftTemplate
+}
+
+export const ShiftTemplateButton: FunctionComponent<ShiftTemplateButtonProps> = ({ onApply, shiftTemplate }) => {
+	const onClick = () => {
+		onApply(shiftTemplate.internalId)
+	}
+
+	return <Button onClick={onClick}>{shiftTemplate.name}</Button>
+}
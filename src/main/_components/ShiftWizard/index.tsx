@@ -3,7 +3,7 @@ import { useObservable } from 'react-use'
 import { Scrollable } from '../../../_components/Scrollable'
 import { Header, HeaderSection } from '../../../_components/Header'
 import { Button } from '../../../_components/Button'
-import { staff$, updateShift$ } from '../../../_state/store'
+import { applyShiftTemplate, shiftTemplates$, staff$, updateShift$ } from '../../../_state/store'
 import { staffSortByLabel } from '../../../_utils/staffSort'
 import { FullscreenOverlay } from '../../../_components/FullScreenOverlay'
 import { useEscapeKey } from '../../../_utils/useEscapeKey'
@@ -11,6 +11,7 @@ import { TagGrid } from '../../../_components/TagGrid'
 import { StaffTag } from '../../../_components/StaffTag'
 import { StaffState } from '../../../_consts/native'
 import { useLanguageStore } from '../../../_state/lang'
+import { ShiftTemplateButton } from './ShiftTemplateButton'
 
 type ShiftWizardProps = {
   onClose: () => void
@@ -19,6 +20,13 @@ type ShiftWizardProps = {
 export const ShiftWizard: FunctionComponent<ShiftWizardProps> = ({ onClose }) => {
 	const { languageData } = useLanguageStore()
 	const staff = useObservable(staff$, {})
+	const shiftTemplates = useObservable(shiftTemplates$, {})
+
+	const sortedShiftTemplates = useMemo(() => {
+		const entries = Object.values(shiftTemplates)
+		entries.sort((a, b) => a.name.localeCompare(b.name))
+		return entries
+	}, [shiftTemplates])
 
 	const sortedStaff = useMemo(() => {
 		const entries = Object.values(staff)
@@ -52,6 +60,13 @@ export const ShiftWizard: FunctionComponent<ShiftWizardProps> = ({ onClose }) =>
 		onClose()
 	}
 
+	// Applied as a handover right away, ahead of the template schedule
+	const onApplyShiftTemplate = (shiftTemplateId: string) => {
+		applyShiftTemplate(shiftTemplateId)
+			.then(onClose)
+			.catch(console.error)
+	}
+
 	const onSelectShiftStaff = () => {
 		const shiftStaffIds = sortedStaff
 			.filter((staff) => staff.state === StaffState.Dispatched || staff.state === StaffState.Available)
@@ -70,6 +85,13 @@ export const ShiftWizard: FunctionComponent<ShiftWizardProps> = ({ onClose }) =>
 					<Header className="px-0 pt-0 mb-5">
 						<HeaderSection>
 							<Button onClick={onClose}>{languageData['terms.cancel']}</Button>
+							{sortedShiftTemplates.map((shiftTemplate) => (
+								<ShiftTemplateButton
+									key={shiftTemplate.internalId}
+									onApply={onApplyShiftTemplate}
+									shiftTemplate={shiftTemplate}
+								/>
+							))}
 						</HeaderSection>
 
 						<HeaderSection>
@@ -1,9 +1,10 @@
 import { type FunctionComponent, useEffect, useMemo, useState } from 'react'
 import { useObservable } from 'react-use'
 import { type Staff, StaffState, StaffRank, StaffPermission } from '../../../_consts/native'
-import { createStaff$, deleteStaff$, staff$, updateStaff$ } from '../../../_state/store'
+import { createStaff$, deleteStaff$, setOperatorSecret, staff$, updateStaff$ } from '../../../_state/store'
 import { Button } from '../../../_components/Button'
 import { useEscapeKey } from '../../../_utils/useEscapeKey'
+import { imageUrl } from '../../../_utils/imageUrl'
 import { staffStateToLocale } from '../../../_utils/staffStateToLocale'
 import { staffRankToLocale } from '../../../_utils/staffRankToLocale'
 import { staffPermissionToLocale } from '../../../_utils/permissionToLocale'
@@ -48,6 +49,11 @@ export const StaffPanel: FunctionComponent<StaffPanelProps> = ({ internalId, onC
 		setStaffPermission(e.target.value as StaffPermission)
 	}
 
+	const [operatorSecret, setOperatorSecretValue] = useState('')
+	const onOperatorSecretChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+		setOperatorSecretValue(e.target.value)
+	}
+
 	const [staffImage, setStaffImage] = useState('')
 	const onStaffImageChange = (e: React.ChangeEvent<HTMLInputElement>) => {
 		const file = e.target.files?.[0]
@@ -88,12 +94,19 @@ export const StaffPanel: FunctionComponent<StaffPanelProps> = ({ internalId, onC
 			nationalId: staffNationalId,
 			permission: staffPermission,
 			rank: staffRank,
-			state: staffState
+			state: staffState,
+			// Edited through their own commands, kept as they are
+			qualifications: (internalId && staffMap[internalId]?.qualifications) || [],
+			leavePeriods: (internalId && staffMap[internalId]?.leavePeriods) || []
 		} satisfies Staff
 
 		const tg$ = internalId ? updateStaff$ : createStaff$
 		tg$.next(staff)
 
+		if (internalId && operatorSecret) {
+			setOperatorSecret(internalId, operatorSecret).catch(console.error)
+		}
+
 		onClose()
 	}
 
@@ -206,7 +219,7 @@ export const StaffPanel: FunctionComponent<StaffPanelProps> = ({ internalId, onC
 				<label className="mt-5 text-action">{languageData['manage_staff.form.image']}</label>
 				{staffImage && (
 					<div className="flex flex-row justify-center">
-						<img className="max-h-[150px] p-2 rounded border border-[#000]/50" src={staffImage} />
+						<img className="max-h-[150px] p-2 rounded border border-[#000]/50" src={imageUrl(staffImage, 'card')} />
 					</div>
 				)}
 				<input
@@ -229,6 +242,19 @@ export const StaffPanel: FunctionComponent<StaffPanelProps> = ({ internalId, onC
 					))}
 				</select>
 
+				{internalId && (
+					<>
+						<label className="mt-5 text-action">{languageData['manage_staff.form.operator_secret']}</label>
+						<input
+							autoComplete="new-password"
+							className="bg-background text-action mt-1 p-2 rounded border border-[#000]/50"
+							onChange={onOperatorSecretChange}
+							type="password"
+							value={operatorSecret}
+						/>
+					</>
+				)}
+
 				<div className="flex flex-row justify-between mt-10">
 					<div className="space-x-5">
 						{internalId && (
@@ -4,6 +4,7 @@ import { type Vehicle, VehicleState } from '../../../_consts/native'
 import { createVehicle$, deleteVehicle$, updateVehicle$, vehicles$ } from '../../../_state/store'
 import { Button } from '../../../_components/Button'
 import { useEscapeKey } from '../../../_utils/useEscapeKey'
+import { imageUrl } from '../../../_utils/imageUrl'
 import { vehicleStateToLocale } from '../../../_utils/vehicleStateToLocale'
 import { useLanguageStore } from '../../../_state/lang'
 
@@ -148,7 +149,7 @@ export const VehiclePanel: FunctionComponent<VehiclePanelProps> = ({ internalId,
 				<label className="mt-5 text-action">{languageData['manage_vehicles.form.image']}</label>
 				{vehicleImage && (
 					<div className="flex flex-row justify-center">
-						<img className="max-h-[150px] p-2 rounded border border-[#000]/50" src={vehicleImage} />
+						<img className="max-h-[150px] p-2 rounded border border-[#000]/50" src={imageUrl(vehicleImage, 'card')} />
 					</div>
 				)}
 				<input